@@ -0,0 +1,106 @@
+//! Frames of a chart of 500 candles with studies, panes and drawings
+//!
+//! `crosshair` moves the mouse over the chart, which only draws the top layer
+//! over the kept base layer. `pan` drags the chart, which draws the base layer
+//! again every frame, as every frame was drawn before the layers.
+
+use std::hint::black_box;
+
+use aim_chart::{
+    Chart, CompanyInfo, MouseType, UiData,
+    indicator::{Macd, Rsi},
+};
+use aim_data::{Candlestick, OHLCData};
+use chrono::{DateTime, Duration};
+use criterion::{Criterion, criterion_group, criterion_main};
+
+const CANDLES: i64 = 500;
+const DRAWINGS: i32 = 40;
+
+fn hover(x: i32, y: i32) -> UiData {
+    UiData {
+        width: 1600,
+        height: 900,
+        position_x: x,
+        press_x: x,
+        position_y: y,
+        press_y: y,
+        is_release: true,
+        ..UiData::default()
+    }
+}
+
+fn chart() -> Chart {
+    let start = DateTime::from_timestamp(1_704_067_200, 0).unwrap();
+    let bars: Vec<Candlestick> = (0..CANDLES)
+        .map(|i| {
+            let close = 100.0 + 10.0 * (i as f64 / 9.0).sin() + i as f64 * 0.1;
+            Candlestick {
+                timestamp: start + Duration::days(i),
+                open: close - 1.0,
+                high: close + 2.0,
+                low: close - 2.5,
+                close,
+                volume: 1000 + i * 37 % 500,
+            }
+        })
+        .collect();
+    let mut chart = Chart::new_default(
+        "VNINDEX".to_string(),
+        "1D".to_string(),
+        OHLCData::from_candlesticks("VNINDEX", &bars),
+        CompanyInfo::default(),
+    );
+    chart.add_indicator(Box::new(Rsi::new(14)));
+    chart.add_indicator(Box::new(Macd::new(12, 26, 9)));
+
+    // Trend lines drawn with the mouse across the price pane
+    chart.render_plot(hover(800, 300));
+    for i in 0..DRAWINGS {
+        let press = UiData {
+            mouse_type: MouseType::Line,
+            is_release: false,
+            ..hover(40 + i * 35, 100 + i * 7 % 200)
+        };
+        chart.render_plot(press.clone());
+        let drag = UiData {
+            position_x: press.press_x + 200,
+            position_y: press.press_y + 60,
+            ..press
+        };
+        chart.render_plot(drag.clone());
+        chart.render_plot(UiData {
+            mouse_type: MouseType::Move,
+            is_release: true,
+            ..drag
+        });
+    }
+    assert_eq!(chart.all_draw_data.len(), DRAWINGS as usize);
+    chart
+}
+
+fn frames(c: &mut Criterion) {
+    let mut chart = chart();
+    let mut frame = 0;
+    c.bench_function("crosshair", |b| {
+        b.iter(|| {
+            frame += 1;
+            black_box(chart.render_plot(hover(100 + frame * 7 % 1200, 300)));
+        })
+    });
+
+    let mut chart = self::chart();
+    c.bench_function("pan", |b| {
+        b.iter(|| {
+            frame += 1;
+            black_box(chart.render_plot(UiData {
+                move_x: frame % 2 * 3 + 1,
+                is_release: false,
+                ..hover(800, 300)
+            }));
+        })
+    });
+}
+
+criterion_group!(benches, frames);
+criterion_main!(benches);
@@ -0,0 +1,526 @@
+//! Alerts on the quotes of the price board
+//!
+//! An [`Alert`] watches a symbol for a [`Condition`]. The [`AlertEngine`] is
+//! fed every snapshot of the board as [`Quote`]s and fires an [`AlertEvent`]
+//! when a condition starts to hold. It is armed again once the condition no
+//! longer holds, so a price hovering around a level does not fire on every
+//! poll.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    CandleData, Chart,
+    chart::vn_offset,
+    draw::DrawType,
+    indicator::{Indicator, Rsi},
+};
+
+/// Most events kept in the trigger history
+pub const DEFAULT_EVENT_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Condition {
+    /// The price crosses `level`, from either side
+    PriceCross { level: f32 },
+    /// The change from the reference price reaches `percent`, a rise when
+    /// positive and a fall when negative
+    ChangePercent { percent: f32 },
+    /// The volume of the session reaches `ratio` times the average volume of
+    /// the last `days` sessions
+    VolumeSpike { days: usize, ratio: f32 },
+    /// The price comes within `tolerance` percent of a horizontal line or a
+    /// trendline drawn on the chart of the symbol, or crosses one
+    TouchDrawing { tolerance: f32 },
+    /// The RSI of the daily closes, the price standing for the close of the
+    /// session, goes above or below `threshold`
+    Rsi {
+        period: usize,
+        threshold: f32,
+        above: bool,
+    },
+}
+
+impl Condition {
+    /// Whether the condition reads the daily candles before the session
+    pub fn needs_history(&self) -> bool {
+        matches!(self, Condition::VolumeSpike { .. } | Condition::Rsi { .. })
+    }
+
+    /// Whether the condition reads the drawings of the chart
+    pub fn needs_drawings(&self) -> bool {
+        matches!(self, Condition::TouchDrawing { .. })
+    }
+
+    /// Short label for the list of alerts
+    pub fn describe(&self) -> String {
+        match self {
+            Condition::PriceCross { level } => format!("Giá cắt {level:.2}"),
+            Condition::ChangePercent { percent } => format!("Thay đổi {percent:+.2}%"),
+            Condition::VolumeSpike { days, ratio } => {
+                format!("KL gấp {ratio:.1} lần TB {days} phiên")
+            }
+            Condition::TouchDrawing { tolerance } => format!("Chạm đường vẽ ±{tolerance:.2}%"),
+            Condition::Rsi {
+                period,
+                threshold,
+                above,
+            } => format!(
+                "RSI{period} {} {threshold:.0}",
+                if *above { ">" } else { "<" }
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    pub id: u64,
+    /// Upper-case symbol
+    pub symbol: String,
+    pub condition: Condition,
+    pub enabled: bool,
+    pub created: DateTime<Utc>,
+}
+
+/// Price of a symbol on the board
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    pub symbol: String,
+    pub price: f32,
+    /// Change from the reference price, in percent
+    pub change_percent: f32,
+    /// Volume matched in the session so far
+    pub volume: f64,
+}
+
+/// A condition that started to hold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertEvent {
+    pub alert_id: u64,
+    pub symbol: String,
+    pub time: DateTime<Utc>,
+    pub price: f32,
+    pub message: String,
+}
+
+/// What the conditions of a symbol read besides its quote
+#[derive(Debug, Clone, Default)]
+pub struct SymbolContext {
+    /// Daily candles, the ones of the session being quoted are left out
+    pub daily: Vec<CandleData>,
+    /// Prices of the horizontal lines and trendlines drawn on the chart
+    pub levels: Vec<f32>,
+}
+
+impl SymbolContext {
+    pub fn new(mut daily: Vec<CandleData>, levels: Vec<f32>, now: DateTime<Utc>) -> Self {
+        let session_start = session_start(now);
+        daily.retain(|candle| candle.time < session_start);
+        Self { daily, levels }
+    }
+}
+
+/// Start of the trading day of `now`, midnight in Vietnam
+fn session_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    let day = now.with_timezone(&vn_offset()).date_naive();
+    day.and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(vn_offset()).single())
+        .map_or(now, |midnight| midnight.with_timezone(&Utc))
+}
+
+/// Prices at `time` of the horizontal lines and trendlines drawn on `chart`,
+/// trendlines run on past their second point
+pub fn drawing_levels(chart: &Chart, time: DateTime<Utc>) -> Vec<f32> {
+    let x = chart.drawing_scale.index_at(time.timestamp_millis());
+    chart
+        .all_draw_data
+        .iter()
+        .filter_map(|object| match &object.draw_type {
+            DrawType::HorizontalLine(price) => Some(*price),
+            DrawType::Line(((x1, y1), (x2, y2))) if x1 != x2 => {
+                Some(y1 + (y2 - y1) * (x - x1) / (x2 - x1))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// RSI of the closes of `daily` then `price`
+fn live_rsi(daily: &[CandleData], price: f32, period: usize, now: DateTime<Utc>) -> Option<f32> {
+    let mut rsi = Rsi::new(period);
+    for candle in daily {
+        rsi.next(candle);
+    }
+    let session = CandleData {
+        num: daily.len() as f32,
+        time: now,
+        open: price,
+        high: price,
+        low: price,
+        close: price,
+        volume: 0.0,
+    };
+    rsi.next(&session)[0]
+}
+
+/// Run-time state of an alert, not saved
+#[derive(Debug, Clone, Copy, Default)]
+struct State {
+    /// Price of the last quote
+    price: Option<f32>,
+    /// The condition held at the last quote
+    holding: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AlertEngine {
+    alerts: Vec<Alert>,
+    states: HashMap<u64, State>,
+}
+
+impl AlertEngine {
+    pub fn new(alerts: Vec<Alert>) -> Self {
+        Self {
+            alerts,
+            states: HashMap::new(),
+        }
+    }
+
+    pub fn alerts(&self) -> &[Alert] {
+        &self.alerts
+    }
+
+    /// Watch `symbol` for `condition`, the id of the new alert is returned
+    pub fn add(&mut self, symbol: &str, condition: Condition, now: DateTime<Utc>) -> u64 {
+        let id = self
+            .alerts
+            .iter()
+            .map(|alert| alert.id + 1)
+            .max()
+            .unwrap_or(1);
+        self.alerts.push(Alert {
+            id,
+            symbol: symbol.trim().to_uppercase(),
+            condition,
+            enabled: true,
+            created: now,
+        });
+        id
+    }
+
+    pub fn remove(&mut self, id: u64) -> bool {
+        self.states.remove(&id);
+        let count = self.alerts.len();
+        self.alerts.retain(|alert| alert.id != id);
+        self.alerts.len() != count
+    }
+
+    /// Pause or resume an alert, it is armed again when resumed
+    pub fn set_enabled(&mut self, id: u64, enabled: bool) -> bool {
+        let Some(alert) = self.alerts.iter_mut().find(|alert| alert.id == id) else {
+            return false;
+        };
+        alert.enabled = enabled;
+        self.states.remove(&id);
+        true
+    }
+
+    /// Enabled alerts
+    pub fn active(&self) -> impl Iterator<Item = &Alert> {
+        self.alerts.iter().filter(|alert| alert.enabled)
+    }
+
+    /// Test the enabled alerts on a snapshot of the board
+    ///
+    /// `contexts` holds the daily candles and the drawn levels of the symbols
+    /// whose conditions need them, by symbol. A symbol missing from `quotes`
+    /// keeps the state of its alerts.
+    pub fn evaluate(
+        &mut self,
+        quotes: &[Quote],
+        contexts: &HashMap<String, SymbolContext>,
+        now: DateTime<Utc>,
+    ) -> Vec<AlertEvent> {
+        let quotes: HashMap<&str, &Quote> = quotes
+            .iter()
+            .map(|quote| (quote.symbol.as_str(), quote))
+            .collect();
+        let mut events = Vec::new();
+        for alert in self.alerts.iter().filter(|alert| alert.enabled) {
+            let Some(quote) = quotes.get(alert.symbol.as_str()) else {
+                continue;
+            };
+            let state = self.states.entry(alert.id).or_default();
+            let context = contexts.get(&alert.symbol);
+            let message = test(&alert.condition, quote, state.price, context, now);
+            let holding = message.is_some();
+            if let Some(message) = message.filter(|_| !state.holding) {
+                events.push(AlertEvent {
+                    alert_id: alert.id,
+                    symbol: alert.symbol.clone(),
+                    time: now,
+                    price: quote.price,
+                    message: format!("{} {message}", alert.symbol),
+                });
+            }
+            // A crossing is over with the quote it happened on
+            state.holding = holding && !matches!(alert.condition, Condition::PriceCross { .. });
+            state.price = Some(quote.price);
+        }
+        events
+    }
+}
+
+/// What happened when `condition` holds for `quote`, `previous` is the price
+/// of the last quote
+fn test(
+    condition: &Condition,
+    quote: &Quote,
+    previous: Option<f32>,
+    context: Option<&SymbolContext>,
+    now: DateTime<Utc>,
+) -> Option<String> {
+    let price = quote.price;
+    let crossed = |level: f32| {
+        previous.is_some_and(|previous| {
+            (previous < level && price >= level) || (previous > level && price <= level)
+        })
+    };
+    match condition {
+        Condition::PriceCross { level } => {
+            let direction = if price >= *level { "lên" } else { "xuống" };
+            crossed(*level).then(|| format!("cắt {direction} {level:.2}, giá {price:.2}"))
+        }
+        Condition::ChangePercent { percent } => {
+            let change = quote.change_percent;
+            let reached = if *percent >= 0.0 {
+                change >= *percent
+            } else {
+                change <= *percent
+            };
+            let direction = if change >= 0.0 { "tăng" } else { "giảm" };
+            reached.then(|| format!("{direction} {:.2}%, giá {price:.2}", change.abs()))
+        }
+        Condition::VolumeSpike { days, ratio } => {
+            let daily = &context?.daily;
+            let days = (*days).max(1);
+            if daily.len() < days {
+                return None;
+            }
+            let average = daily[daily.len() - days..]
+                .iter()
+                .map(|candle| candle.volume as f64)
+                .sum::<f64>()
+                / days as f64;
+            let times = quote.volume / average;
+            (average > 0.0 && times >= *ratio as f64)
+                .then(|| format!("khối lượng gấp {times:.1} lần TB {days} phiên"))
+        }
+        Condition::TouchDrawing { tolerance } => {
+            let level = context?.levels.iter().copied().find(|&level| {
+                (price - level).abs() <= level.abs() * tolerance / 100.0 || crossed(level)
+            })?;
+            Some(format!("chạm đường vẽ {level:.2}, giá {price:.2}"))
+        }
+        Condition::Rsi {
+            period,
+            threshold,
+            above,
+        } => {
+            let rsi = live_rsi(&context?.daily, price, *period, now)?;
+            let reached = if *above {
+                rsi > *threshold
+            } else {
+                rsi < *threshold
+            };
+            let side = if *above { "trên" } else { "dưới" };
+            reached.then(|| format!("RSI{period} {rsi:.1} {side} {threshold:.0}"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aim_data::{Candlestick, OHLCData};
+    use chrono::Duration;
+
+    use super::*;
+    use crate::{CompanyInfo, draw::DrawObject, test_support::candles_from, time_scale::TimeScale};
+
+    fn now() -> DateTime<Utc> {
+        // 2024-03-01 10:00 in Vietnam
+        DateTime::from_timestamp(1_709_262_000, 0).unwrap()
+    }
+
+    fn quote(price: f32) -> Quote {
+        Quote {
+            symbol: "HPG".to_string(),
+            price,
+            change_percent: 0.0,
+            volume: 0.0,
+        }
+    }
+
+    /// Daily candles up to yesterday closing at `closes`
+    fn daily(closes: &[f32], volume: f32) -> Vec<CandleData> {
+        let start = now() - Duration::days(closes.len() as i64);
+        let bars: Vec<_> = closes
+            .iter()
+            .map(|&close| (close, close, close, volume))
+            .collect();
+        candles_from(start, &bars)
+    }
+
+    fn run(
+        engine: &mut AlertEngine,
+        quotes: &[Quote],
+        contexts: &HashMap<String, SymbolContext>,
+    ) -> usize {
+        engine.evaluate(quotes, contexts, now()).len()
+    }
+
+    #[test]
+    fn test_price_cross_fires_on_each_crossing() {
+        let mut engine = AlertEngine::default();
+        engine.add("hpg", Condition::PriceCross { level: 25.0 }, now());
+        let none = HashMap::new();
+        let fired: Vec<_> = [24.5, 24.8, 25.1, 25.3, 24.9, 24.7]
+            .iter()
+            .map(|&price| run(&mut engine, &[quote(price)], &none))
+            .collect();
+        assert_eq!(fired, [0, 0, 1, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_change_percent_is_armed_again_when_it_stops_holding() {
+        let mut engine = AlertEngine::default();
+        engine.add("HPG", Condition::ChangePercent { percent: -3.0 }, now());
+        let none = HashMap::new();
+        let fired: Vec<_> = [-1.0, -3.5, -4.0, -2.0, -3.2]
+            .iter()
+            .map(|&change| {
+                let quote = Quote {
+                    change_percent: change,
+                    ..quote(25.0)
+                };
+                run(&mut engine, &[quote], &none)
+            })
+            .collect();
+        assert_eq!(fired, [0, 1, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_volume_spike_against_the_average_of_the_last_sessions() {
+        let mut engine = AlertEngine::default();
+        engine.add(
+            "HPG",
+            Condition::VolumeSpike {
+                days: 5,
+                ratio: 2.0,
+            },
+            now(),
+        );
+        let contexts = HashMap::from([(
+            "HPG".to_string(),
+            SymbolContext::new(daily(&[25.0; 10], 1000.0), Vec::new(), now()),
+        )]);
+        let volume = |volume| Quote {
+            volume,
+            ..quote(25.0)
+        };
+        assert_eq!(run(&mut engine, &[volume(1500.0)], &contexts), 0);
+        assert_eq!(run(&mut engine, &[volume(2100.0)], &contexts), 1);
+    }
+
+    #[test]
+    fn test_session_candles_are_left_out_of_the_history() {
+        let mut candles = daily(&[25.0; 3], 1000.0);
+        candles.push(CandleData {
+            time: now() - Duration::hours(1),
+            ..candles[2].clone()
+        });
+        assert_eq!(
+            SymbolContext::new(candles, Vec::new(), now()).daily.len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_rsi_reads_the_price_as_the_last_close() {
+        let closes: Vec<f32> = (0..20).map(|i| 20.0 + i as f32 * 0.5).collect();
+        let mut engine = AlertEngine::default();
+        engine.add(
+            "HPG",
+            Condition::Rsi {
+                period: 14,
+                threshold: 70.0,
+                above: true,
+            },
+            now(),
+        );
+        let contexts = HashMap::from([(
+            "HPG".to_string(),
+            SymbolContext::new(daily(&closes, 1000.0), Vec::new(), now()),
+        )]);
+        assert_eq!(run(&mut engine, &[quote(30.0)], &contexts), 1);
+        // A fall from the last close of 29.5 takes the RSI under 70
+        assert_eq!(run(&mut engine, &[quote(24.0)], &contexts), 0);
+        assert_eq!(run(&mut engine, &[quote(30.0)], &contexts), 1);
+    }
+
+    #[test]
+    fn test_touch_drawn_levels_extends_trendlines() {
+        let days: Vec<_> = (0..10)
+            .map(|day| now() - Duration::days(10 - day))
+            .collect();
+        let bars: Vec<Candlestick> = days
+            .iter()
+            .map(|&timestamp| Candlestick {
+                timestamp,
+                open: 20.0,
+                high: 20.0,
+                low: 20.0,
+                close: 20.0,
+                volume: 100,
+            })
+            .collect();
+        let mut chart = Chart::new_default(
+            "VNINDEX".to_string(),
+            "1D".to_string(),
+            OHLCData::from_candlesticks("VNINDEX", &bars),
+            CompanyInfo::default(),
+        );
+        chart.drawing_scale = TimeScale::new(days, Duration::days(1));
+        for draw_type in [
+            DrawType::HorizontalLine(30.0),
+            DrawType::Line(((0.0, 20.0), (5.0, 22.5))),
+            DrawType::VerticalLine(3.0),
+        ] {
+            chart.all_draw_data.push(DrawObject {
+                draw_type,
+                ..Default::default()
+            });
+        }
+        // `now` is a day after the last candle, at index 10
+        let levels = drawing_levels(&chart, now());
+        assert_eq!(levels.len(), 2);
+        assert!((levels[1] - 25.0).abs() < 1e-4);
+
+        let mut engine = AlertEngine::default();
+        engine.add("HPG", Condition::TouchDrawing { tolerance: 0.5 }, now());
+        let contexts = HashMap::from([(
+            "HPG".to_string(),
+            SymbolContext {
+                daily: Vec::new(),
+                levels,
+            },
+        )]);
+        assert_eq!(run(&mut engine, &[quote(25.5)], &contexts), 0);
+        assert_eq!(run(&mut engine, &[quote(25.1)], &contexts), 1);
+        assert_eq!(run(&mut engine, &[quote(25.0)], &contexts), 0);
+    }
+}
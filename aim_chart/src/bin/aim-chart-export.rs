@@ -0,0 +1,326 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use aim_chart::{Chart, CompanyInfo, indicator, vn_offset};
+use aim_data::Timeframe;
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use plotters::backend::{BitMapBackend, SVGBackend};
+
+const USAGE: &str = "\
+Usage: aim-chart-export [OPTIONS] <SYMBOL>
+
+Options:
+  --timeframe <TF>        Timeframe of the candles, e.g. 15m, 1H, 1D, 1W [default: 1D]
+  --from <YYYY-MM-DD>     First day of the chart [default: a year before --to]
+  --to <YYYY-MM-DD>       Last day of the chart [default: today]
+  --indicators <LIST>     Studies in place of the default moving averages, comma separated
+                          kinds with their parameters in order, e.g. sma:50,rsi,macd:12:26:9
+  --size <WxH>            Image size in pixels [default: 1600x900]
+  -o, --output <FILE>     Image to write, SVG for a .svg file and PNG otherwise
+                          [default: <SYMBOL>_<TF>.png]
+  -h, --help              Print this help
+
+Quotes come from VCI_BASE_URL, which can point at aim-mock-server.";
+
+/// What the command line asks for
+enum Command {
+    Export(Config),
+    Help,
+}
+
+struct Config {
+    symbol: String,
+    timeframe: String,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    indicators: Option<Vec<Box<dyn indicator::Indicator>>>,
+    size: (u32, u32),
+    output: Option<PathBuf>,
+}
+
+fn parse_date(name: &str, value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| format!("{name} must be YYYY-MM-DD"))
+}
+
+fn parse_size(value: &str) -> Result<(u32, u32), String> {
+    value
+        .split_once(['x', 'X'])
+        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+        .filter(|&(w, h)| w > 0 && h > 0)
+        .ok_or("--size must look like 1600x900".to_string())
+}
+
+/// Studies of `list`, e.g. `sma:50,rsi,macd:12:26:9`
+fn parse_indicators(list: &str) -> Result<Vec<Box<dyn indicator::Indicator>>, String> {
+    let mut indicators = Vec::new();
+    for spec in list
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+    {
+        let mut parts = spec.split(':');
+        let kind = parts.next().unwrap_or_default().to_lowercase();
+        let mut study = indicator::create(&kind).ok_or(format!(
+            "unknown indicator {kind}, one of: {}",
+            indicator::KINDS.join(", ")
+        ))?;
+        let params = study.params();
+        for (i, value) in parts.enumerate() {
+            let param = params
+                .get(i)
+                .ok_or(format!("{kind} takes {} parameters", params.len()))?;
+            let value = value
+                .parse()
+                .map_err(|_| format!("{kind}: {} must be a number", param.name))?;
+            study.set_param(param.name, value);
+        }
+        indicators.push(study);
+    }
+    Ok(indicators)
+}
+
+/// Parse the arguments following the program name
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut config = Config {
+        symbol: String::new(),
+        timeframe: "1D".to_string(),
+        from: None,
+        to: None,
+        indicators: None,
+        size: (1600, 900),
+        output: None,
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{name} needs a value"));
+        match arg.as_str() {
+            "--timeframe" => {
+                config.timeframe = value("--timeframe")?;
+                if Timeframe::parse(&config.timeframe).is_none() {
+                    return Err(format!("unknown timeframe {}", config.timeframe));
+                }
+            }
+            "--from" => config.from = Some(parse_date("--from", &value("--from")?)?),
+            "--to" => config.to = Some(parse_date("--to", &value("--to")?)?),
+            "--indicators" => config.indicators = Some(parse_indicators(&value("--indicators")?)?),
+            "--size" => config.size = parse_size(&value("--size")?)?,
+            "-o" | "--output" => config.output = Some(PathBuf::from(value("--output")?)),
+            "-h" | "--help" => return Ok(Command::Help),
+            other if other.starts_with('-') => {
+                return Err(format!("unknown option {other}\n\n{USAGE}"));
+            }
+            symbol if config.symbol.is_empty() => config.symbol = symbol.to_uppercase(),
+            other => return Err(format!("unexpected argument {other}\n\n{USAGE}")),
+        }
+    }
+    if config.symbol.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    Ok(Command::Export(config))
+}
+
+/// Start of `day` in Vietnam time
+fn day_start(day: NaiveDate) -> DateTime<Utc> {
+    day.and_hms_opt(0, 0, 0)
+        .and_then(|time| time.and_local_timezone(vn_offset()).single())
+        .expect("midnight exists in Vietnam time")
+        .with_timezone(&Utc)
+}
+
+async fn export(config: Config) -> Result<PathBuf, String> {
+    let timeframe = Timeframe::parse(&config.timeframe).unwrap_or(Timeframe::DAY);
+    let to = config
+        .to
+        .unwrap_or_else(|| Utc::now().with_timezone(&vn_offset()).date_naive());
+    let from = config.from.unwrap_or(to - Days::new(365));
+    if from > to {
+        return Err("--from is after --to".to_string());
+    }
+
+    let provider = aim_data::default_provider();
+    let symbol = config.symbol.as_str();
+    let quote = provider
+        .get_quote(
+            &[symbol],
+            timeframe.api_interval(),
+            Some(day_start(from)),
+            Some(day_start(to + Days::new(1))),
+        )
+        .await
+        .map_err(|e| format!("Failed to fetch {symbol}: {e}"))?
+        .0
+        .into_iter()
+        .find(|data| data.symbol.eq_ignore_ascii_case(symbol))
+        .filter(|data| !data.c.is_empty())
+        .ok_or(format!(
+            "No {} bars of {symbol} from {from} to {to}",
+            config.timeframe
+        ))?;
+    let company_info = provider
+        .get_company_info(symbol, "Y")
+        .await
+        .ok()
+        .and_then(|info| info.latest_ratio().map(CompanyInfo::from))
+        .unwrap_or_default();
+
+    let mut chart = Chart::new_default(
+        symbol.to_string(),
+        config.timeframe.clone(),
+        quote,
+        company_info,
+    );
+    if let Some(indicators) = config.indicators {
+        while chart.remove_indicator(0).is_some() {}
+        for study in indicators {
+            chart.add_indicator(study);
+        }
+    }
+    chart.fit_view();
+
+    let output = config
+        .output
+        .unwrap_or_else(|| PathBuf::from(format!("{symbol}_{}.png", config.timeframe)));
+    let svg = output
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"));
+    let rendered = if svg {
+        chart
+            .render_to(SVGBackend::new(&output, config.size))
+            .map_err(|e| e.to_string())
+    } else {
+        chart
+            .render_to(BitMapBackend::new(&output, config.size))
+            .map_err(|e| e.to_string())
+    };
+    match rendered {
+        Ok(true) => Ok(output),
+        Ok(false) => Err(format!("{symbol} has no candles to draw")),
+        Err(e) => Err(format!("Failed to write {}: {e}", output.display())),
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let config = match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Export(config)) => config,
+        Ok(Command::Help) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match export(config).await {
+        Ok(output) => {
+            println!("Wrote {}", output.display());
+            ExitCode::SUCCESS
+        }
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn export_config(args: &[&str]) -> Config {
+        match parse(args) {
+            Ok(Command::Export(config)) => config,
+            Ok(Command::Help) => panic!("{args:?} asks for the help"),
+            Err(message) => panic!("{args:?}: {message}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1600x900"), Ok((1600, 900)));
+        assert_eq!(parse_size("800X600"), Ok((800, 600)));
+        for size in ["1600", "0x900", "1600x", "-1x900", "wide x tall"] {
+            assert!(parse_size(size).is_err(), "{size}");
+        }
+    }
+
+    #[test]
+    fn test_parse_indicators() {
+        let studies = parse_indicators("sma:50, rsi,,macd:12:26:9").unwrap();
+        let kinds: Vec<_> = studies.iter().map(|study| study.kind()).collect();
+        assert_eq!(kinds, ["sma", "rsi", "macd"]);
+        assert_eq!(studies[0].params()[0].value, 50.0);
+        let macd: Vec<_> = studies[2].params().iter().map(|p| p.value).collect();
+        assert_eq!(macd, [12.0, 26.0, 9.0]);
+        assert!(parse_indicators("").unwrap().is_empty());
+
+        assert!(parse_indicators("SMA:20").is_ok());
+        assert!(
+            parse_indicators("vwap2")
+                .unwrap_err()
+                .contains("unknown indicator")
+        );
+        assert!(
+            parse_indicators("sma:fifty")
+                .unwrap_err()
+                .contains("number")
+        );
+        assert!(
+            parse_indicators("sma:20:5")
+                .unwrap_err()
+                .contains("parameters")
+        );
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let config = export_config(&["vnm"]);
+        assert_eq!(config.symbol, "VNM");
+        assert_eq!(config.timeframe, "1D");
+        assert_eq!(config.size, (1600, 900));
+        assert!(config.from.is_none() && config.to.is_none());
+        assert!(config.indicators.is_none() && config.output.is_none());
+
+        let config = export_config(&[
+            "--timeframe",
+            "15m",
+            "--from",
+            "2024-01-02",
+            "--to",
+            "2024-03-29",
+            "--indicators",
+            "ema:9,rsi",
+            "--size",
+            "800x600",
+            "-o",
+            "vnm.svg",
+            "VNM",
+        ]);
+        assert_eq!(config.timeframe, "15m");
+        assert_eq!(config.from, NaiveDate::from_ymd_opt(2024, 1, 2));
+        assert_eq!(config.to, NaiveDate::from_ymd_opt(2024, 3, 29));
+        assert_eq!(config.indicators.map(|studies| studies.len()), Some(2));
+        assert_eq!(config.size, (800, 600));
+        assert_eq!(config.output, Some(PathBuf::from("vnm.svg")));
+
+        assert!(matches!(parse(&["-h"]), Ok(Command::Help)));
+        assert!(matches!(parse(&["VNM", "--help"]), Ok(Command::Help)));
+        for args in [
+            &[][..],
+            &["--timeframe", "7x", "VNM"],
+            &["--from", "02/01/2024", "VNM"],
+            &["--size"],
+            &["--verbose", "VNM"],
+            &["VNM", "FPT"],
+        ] {
+            assert!(parse(args).is_err(), "{args:?}");
+        }
+    }
+}
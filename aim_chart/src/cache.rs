@@ -1,8 +1,34 @@
 use crate::{
     CandleData, Chart, CompanyInfo, UiData,
     chart::{BackupChartOffset, ChartData},
-    draw::DrawObject,
+    compare::Comparison,
+    draw::{DrawFormat, DrawObject},
+    grid::{ChartGrid, GridCell, GridLayout, Links},
+    history::{Edit, History},
+    indicator::{self, IndicatorInstance},
+    pane::{Pane, PaneKind, PaneLayout},
+    price_scale::ScaleMode,
+    style::ChartStyle,
+    time_scale::TimeScale,
 };
+use aim_data::Timeframe;
+use chrono::DateTime;
+use plotters::style::RGBColor;
+
+/// Version of the chart cache [`Chart::write_to_bytes`] writes. Every older
+/// version is still read by [`Chart::read_version_from_bytes`]:
+/// 1. the first format, with drawings at (candle index, price)
+/// 2. time frame after the name
+/// 3. indicators
+/// 4. pane layout
+/// 5. line style and width of the drawings, the moving drawing left out
+/// 6. drawing history
+/// 7. drawings at (time, price) anchors
+/// 8. price scale mode
+/// 9. chart grid after the charts
+/// 10. comparisons
+/// 11. chart style, box size and source candles
+pub const CACHE_VERSION: u32 = 11;
 
 impl Chart {
     // Manual serialization: write Chart to bytes
@@ -13,6 +39,11 @@ impl Chart {
         buf.extend_from_slice(&name_len.to_le_bytes());
         buf.extend_from_slice(name_bytes);
 
+        // time_frame: String
+        let time_frame_bytes = self.time_frame.as_bytes();
+        buf.extend_from_slice(&(time_frame_bytes.len() as u32).to_le_bytes());
+        buf.extend_from_slice(time_frame_bytes);
+
         // company_info: CompanyInfo
         buf.extend_from_slice(&self.company_info.roe.to_le_bytes());
         buf.extend_from_slice(&self.company_info.roa.to_le_bytes());
@@ -31,16 +62,15 @@ impl Chart {
         self.chart_data.write_to_bytes(buf);
 
         // current_draw_data: DrawObject
-        self.current_draw_data.write_to_bytes(buf);
-
-        // moving_object: DrawObject
-        self.moving_object.write_to_bytes(buf);
+        // Drawings are written as (time, price) anchors
+        let scale = &self.drawing_scale;
+        self.current_draw_data.write_to_bytes(buf, scale);
 
         // all_draw_data: Vec<DrawObject>
         let draw_count = self.all_draw_data.len() as u32;
         buf.extend_from_slice(&draw_count.to_le_bytes());
         for _draw in &self.all_draw_data {
-            _draw.write_to_bytes(buf);
+            _draw.write_to_bytes(buf, scale);
         }
 
         // backup_points: Vec<Point>
@@ -71,10 +101,53 @@ impl Chart {
         // delta: (f32, f32)
         buf.extend_from_slice(&self.delta.0.to_le_bytes());
         buf.extend_from_slice(&self.delta.1.to_le_bytes());
+
+        // indicators: Vec<IndicatorInstance>
+        buf.extend_from_slice(&(self.indicators.len() as u32).to_le_bytes());
+        for indicator in &self.indicators {
+            indicator.write_to_bytes(buf);
+        }
+
+        // layout: PaneLayout
+        self.layout.write_to_bytes(buf);
+
+        // history: History
+        self.history.write_to_bytes(buf, scale);
+
+        // scale_mode: ScaleMode
+        buf.push(self.scale_mode as u8);
+
+        // comparisons: Vec<Comparison>
+        buf.extend_from_slice(&(self.comparisons.len() as u32).to_le_bytes());
+        for comparison in &self.comparisons {
+            comparison.write_to_bytes(buf);
+        }
+
+        // style: ChartStyle, box_size: f32
+        buf.push(self.style as u8);
+        buf.extend_from_slice(&self.box_size.to_le_bytes());
+
+        // source_data: CandleDataVec, left out when the candles are shown as
+        // they are
+        let source: &[CandleData] = if self.style.is_derived() {
+            &self.source_data
+        } else {
+            &[]
+        };
+        buf.extend_from_slice(&(source.len() as u32).to_le_bytes());
+        for candle in source {
+            candle.write_to_bytes(buf);
+        }
     }
 
     // Manual deserialization: read Chart from bytes
     pub fn read_from_bytes(data: &[u8]) -> Option<(Self, usize)> {
+        Self::read_version_from_bytes(data, CACHE_VERSION)
+    }
+
+    /// Read a chart written with cache `version`, what older versions lack
+    /// gets the defaults of a new chart
+    pub fn read_version_from_bytes(data: &[u8], version: u32) -> Option<(Self, usize)> {
         let mut pos = 0;
         // stock_name: String
         if data.len() < pos + 4 {
@@ -89,6 +162,14 @@ impl Chart {
         let stock_name = String::from_utf8_lossy(&data[pos..pos + name_len]).to_string();
         pos += name_len;
 
+        // time_frame: String, since version 2
+        let mut time_frame = None;
+        if version >= 2 {
+            let (label, used) = read_str(&data[pos..])?;
+            time_frame = Some(label);
+            pos += used;
+        }
+
         // company_info: CompanyInfo
         if data.len() < pos + 8 * 5 {
             return None;
@@ -128,6 +209,9 @@ impl Chart {
             }
         }
 
+        // Charts showed the bars of the quote API as they came before version 2
+        let time_frame = time_frame.unwrap_or_else(|| base_time_frame(&candle_data).to_string());
+
         // chart_data: ChartData
         let chart_data = match ChartData::read_from_bytes(&data[pos..]) {
             Some((chart_data, used)) => {
@@ -138,18 +222,23 @@ impl Chart {
         };
 
         // current_draw_data: DrawObject
-        let (current_draw_data, used) = match DrawObject::read_from_bytes(&data[pos..]) {
+        // Drawings are projected on the candles read above
+        let bar_duration = Timeframe::parse(&time_frame)
+            .unwrap_or(Timeframe::DAY)
+            .bar_duration();
+        let drawing_scale = TimeScale::of(&candle_data, bar_duration);
+        let format = DrawFormat::of_version(version, &drawing_scale);
+        let (current_draw_data, used) = match DrawObject::read_as(&data[pos..], format) {
             Some((obj, used)) => (obj, used),
             None => return None,
         };
         pos += used;
 
-        // moving_object: DrawObject
-        let (moving_object, used) = match DrawObject::read_from_bytes(&data[pos..]) {
-            Some((obj, used)) => (obj, used),
-            None => return None,
-        };
-        pos += used;
+        // moving_object: DrawObject, until version 5
+        if version < 5 {
+            let (_, used) = DrawObject::read_as(&data[pos..], format)?;
+            pos += used;
+        }
 
         // all_draw_data: Vec<DrawObject>
         if data.len() < pos + 4 {
@@ -160,7 +249,7 @@ impl Chart {
         pos += 4;
         let mut all_draw_data = Vec::with_capacity(draw_count);
         for _ in 0..draw_count {
-            if let Some((draw, used)) = DrawObject::read_from_bytes(&data[pos..]) {
+            if let Some((draw, used)) = DrawObject::read_as(&data[pos..], format) {
                 all_draw_data.push(draw);
                 pos += used;
             } else {
@@ -238,19 +327,116 @@ impl Chart {
         pos += 4;
         let delta = (dx, dy);
 
+        // indicators: Vec<IndicatorInstance>, since version 3
+        let mut indicators = Chart::default_indicators();
+        if version >= 3 {
+            if data.len() < pos + 4 {
+                return None;
+            }
+            let indicator_count =
+                u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
+                    as usize;
+            pos += 4;
+            indicators = Vec::with_capacity(indicator_count);
+            for _ in 0..indicator_count {
+                let (indicator, used) = IndicatorInstance::read_from_bytes(&data[pos..])?;
+                indicators.push(indicator);
+                pos += used;
+            }
+        }
+
+        // layout: PaneLayout, since version 4
+        let layout = if version >= 4 {
+            let (layout, used) = PaneLayout::read_from_bytes(&data[pos..])?;
+            pos += used;
+            layout
+        } else {
+            let mut layout = PaneLayout::default();
+            layout.sync(&indicators);
+            layout
+        };
+
+        // history: History, since version 6
+        let mut history = History::default();
+        if version >= 6 {
+            let used;
+            (history, used) = History::read_as(&data[pos..], format)?;
+            pos += used;
+        }
+
+        // scale_mode: ScaleMode, since version 8
+        let mut scale_mode = ScaleMode::default();
+        if version >= 8 {
+            scale_mode = ScaleMode::from_u8(*data.get(pos)?);
+            pos += 1;
+        }
+
+        // comparisons: Vec<Comparison>, since version 10
+        let mut comparisons = Vec::new();
+        if version >= 10 {
+            let comparison_count =
+                u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+            pos += 4;
+            for _ in 0..comparison_count {
+                let (comparison, used) = Comparison::read_from_bytes(&data[pos..])?;
+                comparisons.push(comparison);
+                pos += used;
+            }
+        }
+
+        // style: ChartStyle, box_size: f32, since version 11
+        let mut style = ChartStyle::default();
+        let mut box_size = 0.0;
+        let mut source_data = Vec::new();
+        if version >= 11 {
+            style = ChartStyle::from_u8(*data.get(pos)?);
+            pos += 1;
+            box_size = f32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?);
+            pos += 4;
+
+            // source_data: CandleDataVec
+            let source_count =
+                u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+            pos += 4;
+            for _ in 0..source_count {
+                let (candle, used) = CandleData::read_from_bytes(&data[pos..])?;
+                source_data.push(candle);
+                pos += used;
+            }
+        }
+        if !style.is_derived() {
+            source_data = candle_data.clone();
+        }
+
         Some((
             Self {
                 stock_name,
+                time_frame,
                 company_info,
                 candle_data,
+                source_data,
+                style,
+                box_size,
+                history_exhausted: false,
+                partial_page: None,
                 chart_data,
                 current_draw_data,
-                moving_object,
                 all_draw_data,
+                selection: Default::default(),
+                history,
+                drawing_scale,
+                indicators,
+                layout,
+                scale_mode,
+                series: Default::default(),
+                comparisons,
                 backup_points,
                 backup_chart_offset,
                 is_in_object,
+                text_edit: None,
+                placing_point: None,
                 delta,
+                layers: Default::default(),
             },
             pos,
         ))
@@ -384,3 +570,335 @@ impl ChartData {
         // Color and type can be added if needed
     }
 }
+
+// Manual serialization for IndicatorInstance: the kind, the parameters and the
+// display settings, the values are computed again from the candles
+impl IndicatorInstance {
+    pub fn write_to_bytes(&self, buf: &mut Vec<u8>) {
+        write_str(buf, self.indicator.kind());
+        let params = self.indicator.params();
+        buf.extend_from_slice(&(params.len() as u32).to_le_bytes());
+        for param in params {
+            write_str(buf, param.name);
+            buf.extend_from_slice(&param.value.to_le_bytes());
+        }
+        buf.push(self.visible as u8);
+        buf.extend_from_slice(&(self.colors.len() as u32).to_le_bytes());
+        for color in &self.colors {
+            buf.extend_from_slice(&[color.0, color.1, color.2]);
+        }
+    }
+
+    /// `None` if the bytes are truncated or the indicator kind is unknown
+    pub fn read_from_bytes(data: &[u8]) -> Option<(Self, usize)> {
+        let mut pos = 0;
+        let (kind, used) = read_str(data)?;
+        pos += used;
+        let mut indicator = indicator::create(&kind)?;
+
+        let param_count = u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        pos += 4;
+        for _ in 0..param_count {
+            let (name, used) = read_str(&data[pos..])?;
+            pos += used;
+            let value = f32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?);
+            pos += 4;
+            indicator.set_param(&name, value);
+        }
+
+        let visible = *data.get(pos)? != 0;
+        pos += 1;
+        let color_count = u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        pos += 4;
+        let mut colors = Vec::with_capacity(color_count);
+        for _ in 0..color_count {
+            let rgb = data.get(pos..pos + 3)?;
+            colors.push(RGBColor(rgb[0], rgb[1], rgb[2]));
+            pos += 3;
+        }
+
+        let mut instance = IndicatorInstance::new(indicator).with_colors(colors);
+        instance.visible = visible;
+        Some((instance, pos))
+    }
+}
+
+// Manual serialization for PaneLayout: the sub-panes and their heights
+impl PaneLayout {
+    pub fn write_to_bytes(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&(self.panes.len() as u32).to_le_bytes());
+        for pane in &self.panes {
+            match &pane.kind {
+                PaneKind::Volume => buf.push(0),
+                PaneKind::Indicator(index) => {
+                    buf.push(1);
+                    buf.extend_from_slice(&(*index as u32).to_le_bytes());
+                }
+                PaneKind::Series(name) => {
+                    buf.push(2);
+                    write_str(buf, name);
+                }
+            }
+            buf.extend_from_slice(&pane.height.to_le_bytes());
+        }
+    }
+
+    pub fn read_from_bytes(data: &[u8]) -> Option<(Self, usize)> {
+        let mut pos = 0;
+        let count = u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        pos += 4;
+        let mut layout = PaneLayout::empty();
+        for _ in 0..count {
+            let tag = *data.get(pos)?;
+            pos += 1;
+            let kind = match tag {
+                0 => PaneKind::Volume,
+                1 => {
+                    let index = u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?);
+                    pos += 4;
+                    PaneKind::Indicator(index as usize)
+                }
+                2 => {
+                    let (name, used) = read_str(&data[pos..])?;
+                    pos += used;
+                    PaneKind::Series(name)
+                }
+                _ => return None,
+            };
+            let height = f32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?);
+            pos += 4;
+            layout.panes.push(Pane { kind, height });
+        }
+        Some((layout, pos))
+    }
+}
+
+// Manual serialization for History: the settings, then the edits when the
+// history is persisted
+impl History {
+    pub fn write_to_bytes(&self, buf: &mut Vec<u8>, scale: &TimeScale) {
+        buf.push(self.persist as u8);
+        buf.extend_from_slice(&(self.limit() as u32).to_le_bytes());
+        if !self.persist {
+            // No edit to undo or redo
+            buf.extend_from_slice(&[0; 8]);
+            return;
+        }
+        buf.extend_from_slice(&(self.undo.len() as u32).to_le_bytes());
+        for edit in &self.undo {
+            edit.write_to_bytes(buf, scale);
+        }
+        buf.extend_from_slice(&(self.redo.len() as u32).to_le_bytes());
+        for edit in &self.redo {
+            edit.write_to_bytes(buf, scale);
+        }
+    }
+
+    pub fn read_from_bytes(data: &[u8], scale: &TimeScale) -> Option<(Self, usize)> {
+        Self::read_as(data, DrawFormat::current(scale))
+    }
+
+    pub(crate) fn read_as(data: &[u8], format: DrawFormat) -> Option<(Self, usize)> {
+        let mut pos = 0;
+        let persist = *data.get(pos)? != 0;
+        pos += 1;
+        let limit = u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        pos += 4;
+        let mut history = History::with_limit(limit);
+        history.persist = persist;
+        for redo in [false, true] {
+            let count = u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+            pos += 4;
+            for _ in 0..count {
+                let (edit, used) = Edit::read_as(&data[pos..], format)?;
+                pos += used;
+                match redo {
+                    false => history.undo.push_back(edit),
+                    true => history.redo.push(edit),
+                }
+            }
+        }
+        Some((history, pos))
+    }
+}
+
+// Manual serialization for Edit: a tag then the drawings with their index
+impl Edit {
+    pub fn write_to_bytes(&self, buf: &mut Vec<u8>, scale: &TimeScale) {
+        let (tag, items) = match self {
+            Edit::Insert(items) => (0, items),
+            Edit::Remove(items) => (1, items),
+            Edit::Change(items) => {
+                buf.push(2);
+                buf.extend_from_slice(&(items.len() as u32).to_le_bytes());
+                for (index, before, after) in items {
+                    buf.extend_from_slice(&(*index as u32).to_le_bytes());
+                    before.write_to_bytes(buf, scale);
+                    after.write_to_bytes(buf, scale);
+                }
+                return;
+            }
+        };
+        buf.push(tag);
+        buf.extend_from_slice(&(items.len() as u32).to_le_bytes());
+        for (index, object) in items {
+            buf.extend_from_slice(&(*index as u32).to_le_bytes());
+            object.write_to_bytes(buf, scale);
+        }
+    }
+
+    pub fn read_from_bytes(data: &[u8], scale: &TimeScale) -> Option<(Self, usize)> {
+        Self::read_as(data, DrawFormat::current(scale))
+    }
+
+    pub(crate) fn read_as(data: &[u8], format: DrawFormat) -> Option<(Self, usize)> {
+        let mut pos = 0;
+        let tag = *data.get(pos)?;
+        pos += 1;
+        let count = u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        pos += 4;
+        let mut objects = Vec::with_capacity(count.min(1024));
+        let mut changes = Vec::new();
+        for _ in 0..count {
+            let index = u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+            pos += 4;
+            let (object, used) = DrawObject::read_as(data.get(pos..)?, format)?;
+            pos += used;
+            if tag == 2 {
+                let (after, used) = DrawObject::read_as(data.get(pos..)?, format)?;
+                pos += used;
+                changes.push((index, object, after));
+            } else {
+                objects.push((index, object));
+            }
+        }
+        let edit = match tag {
+            0 => Edit::Insert(objects),
+            1 => Edit::Remove(objects),
+            2 => Edit::Change(changes),
+            _ => return None,
+        };
+        Some((edit, pos))
+    }
+}
+
+// Manual serialization for ChartGrid: the layout, the active cell and the
+// links, then every cell including those the layout hides
+impl ChartGrid {
+    pub fn write_to_bytes(&self, buf: &mut Vec<u8>) {
+        buf.push(self.layout as u8);
+        buf.extend_from_slice(&(self.active as u32).to_le_bytes());
+        buf.push(self.links.crosshair as u8);
+        buf.push(self.links.symbol as u8);
+        buf.push(self.links.drawings as u8);
+        buf.extend_from_slice(&(self.cells.len() as u32).to_le_bytes());
+        for cell in &self.cells {
+            write_str(buf, &cell.symbol);
+            write_str(buf, &cell.time_frame);
+        }
+    }
+
+    pub fn read_from_bytes(data: &[u8]) -> Option<(Self, usize)> {
+        let mut pos = 0;
+        let layout = GridLayout::from_u8(*data.get(pos)?);
+        pos += 1;
+        let active = u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        pos += 4;
+        let flags = data.get(pos..pos + 3)?;
+        let links = Links {
+            crosshair: flags[0] != 0,
+            symbol: flags[1] != 0,
+            drawings: flags[2] != 0,
+        };
+        pos += 3;
+        let count = u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        pos += 4;
+        let mut cells = Vec::with_capacity(count.min(16));
+        for _ in 0..count {
+            let (symbol, used) = read_str(data.get(pos..)?)?;
+            pos += used;
+            let (time_frame, used) = read_str(data.get(pos..)?)?;
+            pos += used;
+            cells.push(GridCell::new(&symbol, &time_frame));
+        }
+        // The layout always has its cells and the active one among them
+        let first = cells.first()?.clone();
+        while cells.len() < layout.cell_count() {
+            cells.push(first.clone());
+        }
+        if active >= layout.cell_count() {
+            return None;
+        }
+        let grid = ChartGrid {
+            layout,
+            cells,
+            active,
+            links,
+        };
+        Some((grid, pos))
+    }
+}
+
+// Manual serialization for Comparison: the symbol, its label, color and closes
+impl Comparison {
+    pub fn write_to_bytes(&self, buf: &mut Vec<u8>) {
+        write_str(buf, &self.symbol);
+        write_str(buf, &self.label);
+        buf.extend_from_slice(&[self.color.0, self.color.1, self.color.2]);
+        buf.extend_from_slice(&(self.closes.len() as u32).to_le_bytes());
+        for (time, close) in &self.closes {
+            buf.extend_from_slice(&time.timestamp_millis().to_le_bytes());
+            buf.extend_from_slice(&close.to_le_bytes());
+        }
+    }
+
+    pub fn read_from_bytes(data: &[u8]) -> Option<(Self, usize)> {
+        let (symbol, mut pos) = read_str(data)?;
+        let (label, used) = read_str(&data[pos..])?;
+        pos += used;
+        let rgb = data.get(pos..pos + 3)?;
+        let mut comparison = Comparison::new(&symbol, &label, RGBColor(rgb[0], rgb[1], rgb[2]));
+        pos += 3;
+        let count = u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        pos += 4;
+        let mut closes = Vec::with_capacity(count);
+        for _ in 0..count {
+            let millis = i64::from_le_bytes(data.get(pos..pos + 8)?.try_into().ok()?);
+            let close = f32::from_le_bytes(data.get(pos + 8..pos + 12)?.try_into().ok()?);
+            pos += 12;
+            closes.push((DateTime::from_timestamp_millis(millis)?, close));
+        }
+        comparison.closes = closes;
+        Some((comparison, pos))
+    }
+}
+
+/// Time frame of candles at one of the base intervals of the quote API
+fn base_time_frame(candles: &[CandleData]) -> &'static str {
+    let mut gaps: Vec<i64> = candles
+        .windows(2)
+        .map(|pair| (pair[1].time - pair[0].time).num_seconds())
+        .filter(|&gap| gap > 0)
+        .collect();
+    if gaps.is_empty() {
+        return "1D";
+    }
+    gaps.sort_unstable();
+    match gaps[gaps.len() / 2] {
+        gap if gap < 3600 => "1m",
+        gap if gap < 24 * 3600 => "1H",
+        gap if gap < 7 * 24 * 3600 => "1D",
+        _ => "1W",
+    }
+}
+
+fn write_str(buf: &mut Vec<u8>, value: &str) {
+    buf.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    buf.extend_from_slice(value.as_bytes());
+}
+
+fn read_str(data: &[u8]) -> Option<(String, usize)> {
+    let len = u32::from_le_bytes(data.get(0..4)?.try_into().ok()?) as usize;
+    let bytes = data.get(4..4 + len)?;
+    Some((String::from_utf8_lossy(bytes).to_string(), 4 + len))
+}
@@ -1,32 +1,51 @@
-use aim_data::OHLCData;
-use chrono::{DateTime, Duration, Utc};
+use std::collections::BTreeMap;
+
+use aim_data::{OHLCData, Timeframe};
+use chrono::{DateTime, Duration, FixedOffset, Utc};
 use log::debug;
 
 use plotters::{
-    backend::BitMapBackend,
+    backend::{BitMapBackend, DrawingBackend},
     chart::ChartBuilder,
-    coord::types::RangedCoordf32,
-    drawing::IntoDrawingArea,
-    element::{CandleStick, PathElement, Polygon},
+    coord::{Shift, types::RangedCoordf32},
+    drawing::{DrawingArea, DrawingAreaErrorKind, IntoDrawingArea},
+    element::{CandleStick, Circle, Cross, EmptyElement, PathElement, Polygon},
     prelude::{Cartesian2d, ChartContext, Rectangle, Text},
-    series::LineSeries,
+    series::{AreaSeries, DashedLineSeries, LineSeries},
     style::{BLUE, Color, IntoFont, RED, RGBColor, ShapeStyle, WHITE, full_palette::GREY},
 };
-use slint::SharedPixelBuffer;
+use slint::{Rgb8Pixel, SharedPixelBuffer};
 
 const UP_COLOR: RGBColor = RGBColor(0x2E, 0xBD, 0x85);
 const DOWN_COLOR: RGBColor = RGBColor(0xF6, 0x46, 0x5D);
 const NO_CHANGE_COLOR: RGBColor = RGBColor(0xFF, 0xEB, 0x3B);
-const THREAD_HOLD: f32 = 2.0; // in pixel
+const LINE_COLOR: RGBColor = RGBColor(0x29, 0x62, 0xFF); // close line and area styles
+pub(crate) const THREAD_HOLD: f32 = 2.0; // in pixel
 const DEFAULT_CANDLE_NUMER: usize = 300; // default number of candles to show
+const HISTORY_PAGE_CANDLES: usize = 500; // candles requested per history fetch
+const TEXT_BACKGROUND: RGBColor = RGBColor(0x2A, 0x2E, 0x39); // box behind a text note
+const HANDLE_SIZE: i32 = 4; // half the side of a selection handle, in pixel
+const OFF_PLOT: i32 = 1000; // distance in pixel of a parked mouse from the plot
 
 use crate::{MouseType, UiData};
 
+/// Price pane of the chart, on the bitmap of the UI or another backend
+type PriceChart<'a, DB> = ChartContext<'a, DB, Cartesian2d<RangedCoordf32, RangedCoordf32>>;
+
 use super::{
     CandleData, CandleDataVec,
     company_info::CompanyInfo,
+    compare::{self, Comparison},
     convert_candlesticks,
-    draw::{DrawObject, DrawType, Point},
+    draw::{DEFAULT_FONT_SIZE, DrawObject, DrawType, LineStyle, Point, TEXT_PADDING, TextNote},
+    history::{Edit, History},
+    indicator::{Indicator, IndicatorInstance, RenderStyle, Sma},
+    layer::{BaseKey, Layer, Layers},
+    pane::{Pane, PaneKind, PaneLayout},
+    price_scale::{PriceScale, ScaleMode},
+    selection::{Grab, HANDLE_GRAB, Selection},
+    style::{self, ChartStyle},
+    time_scale::TimeScale,
 };
 
 #[derive(Debug, Clone)]
@@ -75,23 +94,148 @@ impl Default for ChartData {
 #[derive(Debug, Clone)]
 pub struct Chart {
     pub stock_name: String,
+    pub time_frame: String, // timeframe label shown by the chart, e.g. "15m"
     pub company_info: CompanyInfo,
-    pub candle_data: CandleDataVec, // Candle sticks data
+    pub candle_data: CandleDataVec, // Candle sticks data, in the style of the chart
+    pub(crate) source_data: CandleDataVec, // candles as fetched, candle_data is derived from them
+    pub style: ChartStyle,          // how the price series is drawn
+    pub box_size: f32,              // Renko and point-and-figure box, 0 picks one
+    pub history_exhausted: bool,    // no older candles left to lazy-load
+    // First bar of the last older page that fell in one bucket, and the pages
+    // of history the next older request covers to reach the bucket start
+    pub(crate) partial_page: Option<(DateTime<Utc>, u32)>,
     pub chart_data: ChartData,
     pub current_draw_data: DrawObject,
-    pub moving_object: DrawObject,
     pub all_draw_data: Vec<DrawObject>,
+    pub selection: Selection,               // drawings picked with the mouse
+    pub history: History,                   // edits of all_draw_data, for undo
+    pub(crate) drawing_scale: TimeScale,    // candles the x of the drawings refers to
+    pub indicators: Vec<IndicatorInstance>, // studies drawn on the chart
+    pub layout: PaneLayout,                 // sub-panes under the price pane
+    pub scale_mode: ScaleMode,              // how the price axis maps prices
+    pub series: BTreeMap<String, Vec<(DateTime<Utc>, f32)>>, // values shown by series panes
+    pub comparisons: Vec<Comparison>,       // other symbols drawn over the price pane
     pub backup_points: Vec<Point>,
     pub backup_chart_offset: Option<BackupChartOffset>,
     pub is_in_object: (usize, bool, bool),
+    pub text_edit: Option<usize>, // text note in all_draw_data following the text box
+    pub placing_point: Option<bool>, // last point of a three-point drawing follows the mouse, true once pressed
     pub delta: (f32, f32),
+    pub(crate) layers: Layers, // candles, studies and drawings kept offscreen
+}
+
+/// Panes and view of the frame being rendered
+struct Frame {
+    /// Pixel rows of the panes from `PaneLayout::bounds`, the price pane first
+    bounds: Vec<(u32, u32)>,
+    /// min x, max x, min y and max y of the price pane
+    view: (f32, f32, f32, f32),
+    /// Third of the height of the view, see `update_y_axis_after_moving`
+    space: f32,
 }
 
 #[allow(clippy::type_complexity, clippy::too_many_arguments)]
 impl Chart {
     // new_dafault to set value default for inititalization
-    pub fn new_default(stock_name: String, stock: OHLCData, company_info: CompanyInfo) -> Self {
-        let data = convert_candlesticks(stock.symbol.len() == 3, stock.to_candlesticks());
+    pub fn new_default(
+        stock_name: String,
+        time_frame: String,
+        stock: OHLCData,
+        company_info: CompanyInfo,
+    ) -> Self {
+        let timeframe = Timeframe::parse(&time_frame).unwrap_or(Timeframe::DAY);
+        let data = convert_candlesticks(
+            stock.symbol.len() == 3,
+            timeframe.resample(&stock.to_candlesticks()),
+        );
+        let chart_data = Self::default_view(&data);
+
+        Self {
+            stock_name,
+            time_frame,
+            company_info,
+            candle_data: data.clone(),
+            source_data: data,
+            style: ChartStyle::default(),
+            box_size: 0.0,
+            history_exhausted: false,
+            partial_page: None,
+            chart_data,
+            current_draw_data: DrawObject::default(),
+            all_draw_data: Vec::default(),
+            selection: Selection::default(),
+            history: History::default(),
+            drawing_scale: TimeScale::default(),
+            indicators: Self::default_indicators(),
+            layout: PaneLayout::default(),
+            scale_mode: ScaleMode::default(),
+            series: BTreeMap::new(),
+            comparisons: Vec::new(),
+            backup_points: Vec::default(),
+            backup_chart_offset: None,
+            is_in_object: (0, false, false),
+            text_edit: None,
+            placing_point: None,
+            delta: (0.0, 0.0),
+            layers: Layers::default(),
+        }
+    }
+
+    /// Replace the candles with `stock`, bars at the base interval of the chart
+    /// timeframe which are resampled before display
+    pub fn update_candle_data(&mut self, stock: OHLCData) {
+        self.source_data = convert_candlesticks(
+            self.stock_name.len() == 3,
+            self.timeframe().resample(&stock.to_candlesticks()),
+        );
+        self.restyle();
+    }
+
+    /// Show the price series in `style` with boxes of `box_size`, 0 picks the
+    /// box size from the candles. The view shows the last candles again when
+    /// the series has other candles.
+    pub fn set_style(&mut self, style: ChartStyle, box_size: f32) {
+        let box_size = if box_size.is_finite() {
+            box_size.max(0.0)
+        } else {
+            0.0
+        };
+        if style == self.style && (!style.uses_box() || box_size == self.box_size) {
+            return;
+        }
+        let same_candles = !style.is_derived() && !self.style.is_derived();
+        self.style = style;
+        self.box_size = box_size;
+        self.restyle();
+        if !same_candles && !self.candle_data.is_empty() {
+            let view = Self::default_view(&self.candle_data);
+            self.chart_data.x_offset_min = view.x_offset_min;
+            self.chart_data.x_offset_max = view.x_offset_max;
+            self.chart_data.y_offset_min = view.y_offset_min;
+            self.chart_data.y_offset_max = view.y_offset_max;
+            self.chart_data.zoom_x = 0.0;
+            self.chart_data.zoom_y = 0.0;
+            self.backup_chart_offset = None;
+        }
+    }
+
+    /// Box of the Renko bricks and point-and-figure columns
+    pub fn box_size(&self) -> f32 {
+        if self.box_size > 0.0 {
+            self.box_size
+        } else {
+            style::auto_box_size(&self.source_data)
+        }
+    }
+
+    /// Derive the candles shown from the fetched ones
+    fn restyle(&mut self) {
+        self.candle_data = self.style.series(&self.source_data, self.box_size);
+        self.layers.invalidate();
+    }
+
+    /// Viewport showing the last `DEFAULT_CANDLE_NUMER` candles of `data`
+    fn default_view(data: &[CandleData]) -> ChartData {
         let (x_offset_min, data_slice) = if data.len() < DEFAULT_CANDLE_NUMER {
             (0 as f32, &data[0..(data.len() - 1)])
         } else {
@@ -100,58 +244,712 @@ impl Chart {
                 &data[(data.len() - DEFAULT_CANDLE_NUMER)..(data.len() - 1)],
             )
         };
-        let chart_data = ChartData {
+        ChartData {
             x_offset_min,
             x_offset_max: (data.len() - 1) as f32,
             y_offset_min: Self::get_y_min(data_slice.to_vec()),
             y_offset_max: Self::get_y_max(data_slice.to_vec()),
             ..Default::default()
+        }
+    }
+
+    /// Show every candle, e.g. the date range of an exported chart
+    pub fn fit_view(&mut self) {
+        if self.candle_data.is_empty() {
+            return;
+        }
+        self.chart_data.x_offset_min = 0.0;
+        self.chart_data.x_offset_max = (self.candle_data.len() - 1) as f32;
+        self.chart_data.y_offset_min = Self::get_y_min(self.candle_data.clone());
+        self.chart_data.y_offset_max = Self::get_y_max(self.candle_data.clone());
+        self.chart_data.zoom_x = 0.0;
+        self.chart_data.zoom_y = 0.0;
+        self.backup_chart_offset = None;
+    }
+
+    /// Show the price axis in `mode`, the view fits the candles in view again
+    /// when the mode changes
+    pub fn set_scale_mode(&mut self, mode: ScaleMode) {
+        if mode == self.scale_mode {
+            return;
+        }
+        self.scale_mode = mode;
+        self.layers.invalidate();
+        let (min_candle_x, max_candle_x) = self.get_min_max_of_candle_after_moving();
+        let shown = &self.candle_data[min_candle_x..max_candle_x];
+        if !shown.is_empty() {
+            self.chart_data.y_offset_min = Self::get_y_min(shown.to_vec());
+            self.chart_data.y_offset_max = Self::get_y_max(shown.to_vec());
+        }
+    }
+
+    /// Scale of the price axis, the percent scale starts at the close of the
+    /// first candle in view
+    pub fn price_scale(&self) -> PriceScale {
+        let (min_candle_x, _) = self.get_min_max_of_candle_after_moving();
+        let base = self
+            .candle_data
+            .get(min_candle_x)
+            .or(self.candle_data.last())
+            .map_or(1.0, |candle| candle.close);
+        PriceScale::new(self.scale_mode, base)
+    }
+
+    /// Bottom and top of the view on the price axis
+    fn y_offsets_on_axis(&self) -> (f32, f32) {
+        let scale = self.price_scale();
+        (
+            scale.to_axis(self.chart_data.y_offset_min),
+            scale.to_axis(self.chart_data.y_offset_max),
+        )
+    }
+
+    /// `object` on the price axis of `scale`, as it is drawn and hit
+    fn on_axis(object: &DrawObject, scale: PriceScale) -> DrawObject {
+        let mut object = object.clone();
+        object.map_y(|y| scale.to_axis(y));
+        object
+    }
+
+    /// Studies shown on a new chart: MA20, MA50 and MA200
+    pub fn default_indicators() -> Vec<IndicatorInstance> {
+        [(20, RED), (50, BLUE), (200, WHITE)]
+            .into_iter()
+            .map(|(period, color)| {
+                IndicatorInstance::new(Box::new(Sma::new(period))).with_colors(vec![color])
+            })
+            .collect()
+    }
+
+    /// Add a study to the chart, returns its index in `indicators`. Oscillators
+    /// get a pane of their own under the price pane.
+    pub fn add_indicator(&mut self, indicator: Box<dyn Indicator>) -> usize {
+        self.indicators.push(IndicatorInstance::new(indicator));
+        self.layout.sync(&self.indicators);
+        self.layers.invalidate();
+        self.indicators.len() - 1
+    }
+
+    pub fn remove_indicator(&mut self, index: usize) -> Option<IndicatorInstance> {
+        if index >= self.indicators.len() {
+            return None;
+        }
+        self.layout.remove_indicator(index);
+        self.layers.invalidate();
+        Some(self.indicators.remove(index))
+    }
+
+    /// Replace the values of the series `name`, drawn by the panes of kind
+    /// `PaneKind::Series(name)` as one bar per candle (values falling in the
+    /// same candle add up), e.g. the foreign net flow
+    pub fn set_series(&mut self, name: &str, mut values: Vec<(DateTime<Utc>, f32)>) {
+        values.sort_by_key(|(time, _)| *time);
+        self.series.insert(name.to_string(), values);
+        self.layers.invalidate();
+    }
+
+    /// Compare the chart with `symbol` shown as `label`, false if it is the
+    /// symbol of the chart or already compared. Its closes come with
+    /// `set_comparison`.
+    pub fn add_comparison(&mut self, symbol: &str, label: &str) -> bool {
+        let symbol = symbol.to_uppercase();
+        if symbol.is_empty()
+            || symbol == self.stock_name.to_uppercase()
+            || self.comparisons.iter().any(|c| c.symbol == symbol)
+        {
+            return false;
+        }
+        let color = compare::COLORS[self.comparisons.len() % compare::COLORS.len()];
+        self.comparisons
+            .push(Comparison::new(&symbol, label, color));
+        self.layers.invalidate();
+        true
+    }
+
+    /// Time to request the closes of the comparisons from, the first candle
+    pub fn comparison_start(&self) -> Option<DateTime<Utc>> {
+        self.source_data.first().map(|candle| candle.time)
+    }
+
+    pub fn remove_comparison(&mut self, symbol: &str) -> bool {
+        let count = self.comparisons.len();
+        self.comparisons
+            .retain(|c| !c.symbol.eq_ignore_ascii_case(symbol));
+        self.layers.invalidate();
+        self.comparisons.len() != count
+    }
+
+    /// Replace the closes of the compared `symbol` with `stock`, bars at the
+    /// base interval of the chart timeframe as for `update_candle_data`
+    pub fn set_comparison(&mut self, symbol: &str, stock: OHLCData) {
+        let timeframe = self.timeframe();
+        let Some(comparison) = self
+            .comparisons
+            .iter_mut()
+            .find(|c| c.symbol.eq_ignore_ascii_case(symbol))
+        else {
+            return;
         };
+        let bars = timeframe.resample(&stock.to_candlesticks());
+        comparison.set_closes(
+            bars.into_iter()
+                .map(|bar| (bar.timestamp, bar.close as f32))
+                .collect(),
+        );
+        self.layers.invalidate();
+    }
 
-        Self {
-            stock_name,
-            company_info,
-            candle_data: data.clone(),
-            chart_data,
-            moving_object: DrawObject::default(),
-            current_draw_data: DrawObject::default(),
-            all_draw_data: Vec::default(),
-            backup_points: Vec::default(),
-            backup_chart_offset: None,
-            is_in_object: (0, false, false),
-            delta: (0.0, 0.0),
+    /// Change in percent of `comparison` over the candles `from..to`, from
+    /// its close at the first of them
+    fn comparison_percent(
+        &self,
+        comparison: &Comparison,
+        from: usize,
+        to: usize,
+    ) -> Vec<Option<f32>> {
+        let to = to.min(self.candle_data.len());
+        let from = from.min(to);
+        compare::rebased(&comparison.aligned(self.candle_data[from..to].iter().map(|c| c.time)))
+    }
+
+    /// Start of the history to request for a new chart of `time_frame` showing
+    /// candles up to `end`
+    pub fn history_start(time_frame: &str, end: DateTime<Utc>) -> DateTime<Utc> {
+        let timeframe = Timeframe::parse(time_frame).unwrap_or(Timeframe::DAY);
+        end - timeframe.lookback(HISTORY_PAGE_CANDLES)
+    }
+
+    /// Time to request new bars from: the start of the last candle, which may
+    /// still be forming
+    pub fn next_fetch_start(&self) -> Option<DateTime<Utc>> {
+        self.source_data.last().map(|candle| candle.time)
+    }
+
+    /// Merge bars fetched from `next_fetch_start`: the last candle is replaced
+    /// and newer ones are appended, so the viewport stays where it is
+    pub fn merge_candle_data(&mut self, stock: OHLCData) {
+        let Some(start) = self.next_fetch_start() else {
+            self.update_candle_data(stock);
+            return;
+        };
+        let bars: Vec<_> = stock
+            .to_candlesticks()
+            .into_iter()
+            .filter(|bar| bar.timestamp >= start)
+            .collect();
+        let new_data =
+            convert_candlesticks(self.stock_name.len() == 3, self.timeframe().resample(&bars));
+        let Some(first_new) = new_data.first() else {
+            return;
+        };
+        let keep = self
+            .source_data
+            .partition_point(|c| c.time < first_new.time);
+        self.source_data.truncate(keep);
+        self.source_data.extend(new_data);
+        self.restyle();
+    }
+
+    /// Time range of the next page of older history, `None` once the viewport
+    /// has not been panned to the first candle or nothing older is left
+    pub fn older_history_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        if self.history_exhausted || self.chart_data.x_offset_min > 0.0 {
+            return None;
         }
+        let end = self.source_data.first()?.time - Duration::seconds(1);
+        let pages = self.partial_page.map_or(1, |(_, pages)| pages as usize);
+        Some((
+            end - self.timeframe().lookback(HISTORY_PAGE_CANDLES * pages),
+            end,
+        ))
     }
 
-    pub fn update_candle_data(&mut self, stock: OHLCData) {
-        self.candle_data =
-            convert_candlesticks(self.stock_name.len() == 3, stock.to_candlesticks());
+    /// Prepend a page of older history fetched for `older_history_range`,
+    /// shifting the viewport so the view does not move, the drawings follow at
+    /// the next render
+    pub fn prepend_candle_data(&mut self, stock: OHLCData) {
+        let first_time = self.source_data.first().map(|candle| candle.time);
+        let bars: Vec<_> = stock
+            .to_candlesticks()
+            .into_iter()
+            .filter(|bar| first_time.is_none_or(|first| bar.timestamp < first))
+            .collect();
+        let Some(first_bar) = bars.iter().map(|bar| bar.timestamp).min() else {
+            self.history_exhausted = true;
+            return;
+        };
+        let mut older =
+            convert_candlesticks(self.stock_name.len() == 3, self.timeframe().resample(&bars));
+        match self.partial_page {
+            // A wider page found nothing before the bucket, it starts the history
+            Some((first, _)) if older.len() == 1 && first == first_bar => {
+                self.history_exhausted = true;
+            }
+            // The page may start in the middle of its first bucket, which is
+            // requested again with the next page, a wider one when the page
+            // held nothing else
+            _ => {
+                older.remove(0);
+                if older.is_empty() {
+                    let pages = self
+                        .partial_page
+                        .map_or(2, |(_, pages)| pages.saturating_mul(2));
+                    self.partial_page = Some((first_bar, pages));
+                    return;
+                }
+            }
+        }
+        self.partial_page = None;
+
+        // Bricks and columns of the older candles may join the first ones
+        let shown = self.candle_data.len();
+        older.append(&mut self.source_data);
+        self.source_data = older;
+        self.restyle();
+        let shift = self.candle_data.len() as f32 - shown as f32;
+
+        self.chart_data.x_offset_min += shift;
+        self.chart_data.x_offset_max += shift;
+        if let Some(offset) = self.backup_chart_offset.as_mut() {
+            offset.x_offset_min += shift;
+            offset.x_offset_max += shift;
+        }
+    }
+
+    /// Take the drawings of `other`, a chart of the same symbol, with their
+    /// history; they are projected on the candles of this chart at the next
+    /// render
+    pub fn adopt_drawings(&mut self, other: &Chart) {
+        self.all_draw_data = other.all_draw_data.clone();
+        self.history = other.history.clone();
+        self.drawing_scale = other.drawing_scale.clone();
+        self.selection.clear();
+        self.text_edit = None;
+        self.layers.invalidate();
+    }
+
+    /// Project the drawings on the candles of the chart if they refer to other
+    /// candles: older history was prepended, the history was reloaded, or the
+    /// drawings come from another timeframe
+    fn project_drawings(&mut self) {
+        let bar_duration = self.timeframe().bar_duration();
+        if self
+            .drawing_scale
+            .is_scale_of(&self.candle_data, bar_duration)
+        {
+            return;
+        }
+        let from = std::mem::replace(
+            &mut self.drawing_scale,
+            TimeScale::of(&self.candle_data, bar_duration),
+        );
+        let to = &self.drawing_scale;
+        let project = |x| from.project(x, to);
+        for object in self
+            .all_draw_data
+            .iter_mut()
+            .chain([&mut self.current_draw_data])
+        {
+            object.map_x(project);
+        }
+        self.history.map_x(project);
+        self.selection.map_x(project);
+        self.backup_points
+            .iter_mut()
+            .for_each(|point| point.0 = project(point.0));
+        self.layers.invalidate();
+    }
+
+    /// Timeframe of the displayed candles, daily if the label is unknown
+    pub fn timeframe(&self) -> Timeframe {
+        Timeframe::parse(&self.time_frame).unwrap_or(Timeframe::DAY)
     }
 
     pub fn update_company_info(&mut self, info: CompanyInfo) {
         self.company_info = info;
+        self.layers.invalidate();
+    }
+
+    /// Note being edited from the text box, if any
+    pub fn edited_text(&self) -> Option<&TextNote> {
+        match &self.all_draw_data.get(self.text_edit?)?.draw_type {
+            DrawType::Text(note) => Some(note),
+            _ => None,
+        }
+    }
+
+    /// Change the note being edited, false when no note is edited
+    pub fn edit_text(&mut self, text: &str, font_size: u32, background: Option<RGBColor>) -> bool {
+        let Some(index) = self.text_edit else {
+            return false;
+        };
+        let Some(before) = self.all_draw_data.get(index).cloned() else {
+            return false;
+        };
+        let DrawType::Text(note) = &before.draw_type else {
+            return false;
+        };
+        let edited = TextNote::new(note.anchor, text.to_string(), font_size, background);
+        if *note != edited {
+            let after = DrawObject {
+                draw_type: DrawType::Text(edited),
+                ..before.clone()
+            };
+            self.all_draw_data[index] = after.clone();
+            self.history.record_typing(index, before, after);
+            self.layers.invalidate();
+        }
+        true
+    }
+
+    /// Delete the selected drawings, they come back with an undo
+    pub fn delete_selection(&mut self) {
+        let mut indices = self.selection.indices.clone();
+        indices.sort_unstable();
+        let removed: Vec<_> = indices
+            .into_iter()
+            .filter_map(|index| Some((index, self.all_draw_data.get(index)?.clone())))
+            .collect();
+        if !removed.is_empty() {
+            self.history
+                .apply(Edit::Remove(removed), &mut self.all_draw_data);
+        }
+        self.selection.clear();
+        self.text_edit = None;
+        self.layers.invalidate();
+    }
+
+    /// Give the selected drawings a color and a line style
+    pub fn restyle_selection(&mut self, color: RGBColor, line_style: LineStyle, line_width: u32) {
+        let changes: Vec<_> = self
+            .selection
+            .indices
+            .iter()
+            .filter_map(|&index| {
+                let before = self.all_draw_data.get(index)?.clone();
+                let after = DrawObject {
+                    color,
+                    line_style,
+                    line_width,
+                    ..before.clone()
+                };
+                (after != before).then_some((index, before, after))
+            })
+            .collect();
+        if !changes.is_empty() {
+            self.history
+                .apply(Edit::Change(changes), &mut self.all_draw_data);
+            self.layers.invalidate();
+        }
+    }
+
+    /// Undo the last edit of the drawings
+    pub fn undo(&mut self) -> bool {
+        let undone = self.history.undo(&mut self.all_draw_data);
+        // Indices may point at other drawings now
+        self.selection.clear();
+        self.text_edit = None;
+        self.layers.invalidate();
+        undone
+    }
+
+    /// Make the last undone edit of the drawings again
+    pub fn redo(&mut self) -> bool {
+        let redone = self.history.redo(&mut self.all_draw_data);
+        self.selection.clear();
+        self.text_edit = None;
+        self.layers.invalidate();
+        redone
+    }
+
+    /// Apply the edits asked from the toolbar, each of them can be undone
+    fn apply_drawing_edits(&mut self) {
+        let ui_data = &self.chart_data.ui_data;
+        let (is_clean, is_undo, is_redo) = (ui_data.is_clean, ui_data.is_undo, ui_data.is_redo);
+        let (is_delete, is_restyle) = (ui_data.is_delete, ui_data.is_restyle);
+        // clean all object on the chart
+        if is_clean {
+            if !self.all_draw_data.is_empty() {
+                let removed = self.all_draw_data.iter().cloned().enumerate().collect();
+                self.history
+                    .apply(Edit::Remove(removed), &mut self.all_draw_data);
+            }
+            self.current_draw_data.clear();
+            self.placing_point = None;
+            self.selection.clear();
+            self.text_edit = None;
+            self.layers.invalidate();
+        }
+        if is_delete {
+            self.delete_selection();
+        }
+        if is_restyle {
+            let color = self.draw_color();
+            let (line_style, line_width) = self.line_style();
+            self.restyle_selection(color, line_style, line_width);
+        }
+        if is_undo {
+            self.undo();
+        } else if is_redo {
+            self.redo();
+        } else {
+            // The text box still shows the text the undo or redo replaced
+            self.apply_text_edit();
+        }
+    }
+
+    /// Color picked for drawings in the toolbar
+    fn draw_color(&self) -> RGBColor {
+        let color = self.chart_data.ui_data.color;
+        RGBColor(color.red(), color.green(), color.blue())
+    }
+
+    /// Line style and width picked for drawings in the toolbar
+    fn line_style(&self) -> (LineStyle, u32) {
+        let ui_data = &self.chart_data.ui_data;
+        let style = LineStyle::from_u8(ui_data.line_style.clamp(0, u8::MAX as i32) as u8);
+        let width = u32::try_from(ui_data.line_width)
+            .ok()
+            .filter(|&width| width > 0)
+            .unwrap_or(1);
+        (style, width)
+    }
+
+    /// Hit thresholds of [`DrawObject::hit_cursor`], `THREAD_HOLD` pixels in
+    /// chart values
+    fn hit_thresholds(&self) -> (f32, f32) {
+        let x_thresh_hold = (self.chart_data.x_offset_max - self.chart_data.x_offset_min)
+            * THREAD_HOLD
+            / (self.chart_data.width as f32 - 60.0);
+        let (y_min, y_max) = self.y_offsets_on_axis();
+        let y_thresh_hold =
+            (3.0 * (y_max - y_min)) * THREAD_HOLD / (self.chart_data.height as f32 - 60.0);
+        (x_thresh_hold, y_thresh_hold)
+    }
+
+    /// Follow the mouse over the drawings: hovering one shows the pointer, a
+    /// press selects the drawing under the mouse and drags it, or drags the
+    /// handle of a selected drawing
+    fn update_selection(&mut self, press_started: bool) {
+        if self.selection.is_dragging() {
+            if self.chart_data.ui_data.is_release {
+                if let Some(edit) = self.selection.end_drag(&self.all_draw_data) {
+                    self.history.record(edit);
+                }
+                self.layers.invalidate();
+                return;
+            }
+            let (y_min, y_max) = self.y_offsets_on_axis();
+            let offset = (
+                self.chart_data.ui_data.move_x as f32 / self.chart_data.candle_distance,
+                -(self.chart_data.ui_data.move_y as f32) * (3.0 * (y_max - y_min))
+                    / (self.chart_data.height - self.chart_data.range_x as u32) as f32,
+            );
+            debug!("moving - dx: {}, dy: {}", offset.0, offset.1);
+            // The chart does not pan while a drawing moves
+            self.chart_data.ui_data.move_x = 0;
+            self.chart_data.ui_data.move_y = 0;
+            let point = self.get_mouse_position();
+            let scale = self.price_scale();
+            self.selection
+                .drag(&mut self.all_draw_data, offset, point, scale);
+            self.layers.invalidate();
+            return;
+        }
+
+        let ui_data = &self.chart_data.ui_data;
+        let in_price_pane =
+            ui_data.position_y < self.chart_data.height as i32 - self.chart_data.range_x;
+        if self.placing_point.is_some()
+            || self.layout.is_resizing()
+            || !in_price_pane
+            || !matches!(ui_data.mouse_type, MouseType::Move)
+        {
+            self.is_in_object = (0, false, false);
+            return;
+        }
+
+        let point = self.get_mouse_position();
+        let (x_thresh_hold, y_thresh_hold) = self.hit_thresholds();
+        // The mouse is on the price axis, so are the drawings it hits
+        let scale = self.price_scale();
+        let drawings: Vec<DrawObject> = self
+            .all_draw_data
+            .iter()
+            .map(|object| Self::on_axis(object, scale))
+            .collect();
+        // Handles sit on the drawings, they come first
+        let grabbed = self
+            .selection
+            .indices
+            .iter()
+            .find_map(|&index| {
+                let handle = drawings.get(index)?.handle_at(
+                    point,
+                    HANDLE_GRAB * x_thresh_hold,
+                    HANDLE_GRAB * y_thresh_hold,
+                )?;
+                Some((index, Grab::Handle(handle)))
+            })
+            .or_else(|| {
+                // The drawing on top wins
+                drawings
+                    .iter()
+                    .rposition(|object| object.hit_cursor(point, x_thresh_hold, y_thresh_hold))
+                    .map(|index| (index, Grab::Object))
+            });
+        self.is_in_object = grabbed.map_or((0, false, false), |(index, _)| (index, true, false));
+        if !press_started {
+            return;
+        }
+
+        let toggle = self.chart_data.ui_data.is_multi_select;
+        self.layers.invalidate();
+        match grabbed {
+            Some((index, grab)) => {
+                // A press on a selected drawing keeps the selection to move it whole
+                if toggle || !self.selection.contains(index) {
+                    self.selection.select(index, toggle);
+                }
+                if self.selection.contains(index) {
+                    self.selection.start_drag(index, grab, &self.all_draw_data);
+                    self.is_in_object.2 = true;
+                }
+                // Pressing a note edits it from the text box
+                self.text_edit = match self.all_draw_data[index].draw_type {
+                    DrawType::Text(_) => Some(index),
+                    _ => None,
+                };
+                self.chart_data.ui_data.move_x = 0;
+                self.chart_data.ui_data.move_y = 0;
+            }
+            None => {
+                if !toggle {
+                    self.selection.clear();
+                }
+                // A press away from the drawings ends the edit of a note
+                self.text_edit = None;
+            }
+        }
+    }
+
+    /// Text, font size and background picked in the text box
+    fn text_style(&self) -> (String, u32, Option<RGBColor>) {
+        let ui_data = &self.chart_data.ui_data;
+        let font_size = u32::try_from(ui_data.font_size)
+            .ok()
+            .filter(|&size| size > 0)
+            .unwrap_or(DEFAULT_FONT_SIZE);
+        let background = ui_data.text_background.then_some(TEXT_BACKGROUND);
+        (ui_data.text.clone(), font_size, background)
+    }
+
+    fn apply_text_edit(&mut self) {
+        let (text, font_size, background) = self.text_style();
+        self.edit_text(&text, font_size, background);
     }
 
     /// main function for rendering the chart with plotter
     pub fn render_plot(&mut self, ui_data: UiData) -> (slint::Image, bool) {
-        // Check if candle_data is empty and return empty image
+        match self.render_frame(ui_data) {
+            Some(pixel_buffer) => (slint::Image::from_rgb8(pixel_buffer), self.is_in_object.1),
+            None => (slint::Image::default(), false),
+        }
+    }
+
+    /// Render the chart on `backend`, e.g. a `BitMapBackend` writing a PNG file
+    /// or an `SVGBackend`, at the size of the backend and without a crosshair
+    /// as in a grid cell. `Ok(false)` without candles to show.
+    pub fn render_to<DB: DrawingBackend>(
+        &mut self,
+        backend: DB,
+    ) -> Result<bool, DrawingAreaErrorKind<DB::ErrorType>> {
+        let (width, height) = backend.get_size();
+        let ui_data = self.cell_ui_data(width as i32, height as i32, None);
+        let Some(frame) = self.begin_frame(ui_data) else {
+            return Ok(false);
+        };
+        let root = backend.into_drawing_area();
+        self.draw_layer(Layer::Base, &root, &frame);
+        self.draw_layer(Layer::Top, &root, &frame);
+        root.present()?;
+        self.end_frame(&frame);
+        Ok(true)
+    }
+
+    /// Follow `ui_data` and render the chart, `None` without candles to show
+    fn render_frame(&mut self, ui_data: UiData) -> Option<SharedPixelBuffer<Rgb8Pixel>> {
+        let frame = self.begin_frame(ui_data)?;
+
+        // The base layer is drawn again only for other data or another view,
+        // the top layer is drawn over a copy of it every frame
+        let key = self.base_key(&frame);
+        let mut pixel_buffer =
+            SharedPixelBuffer::new(self.chart_data.width, self.layout.image_height);
+        let pixels = pixel_buffer.make_mut_bytes();
+        if let Some(base) = self.layers.base(&key) {
+            pixels.copy_from_slice(base);
+        } else {
+            self.draw_pixels(Layer::Base, pixels, &frame);
+            self.layers.store(key, pixels);
+        }
+        self.draw_pixels(Layer::Top, pixels, &frame);
+
+        self.end_frame(&frame);
+        Some(pixel_buffer)
+    }
+
+    /// Follow `ui_data`: mouse, zoom, pan and drawing in progress, and lay out
+    /// the panes of the frame, `None` without candles to show
+    fn begin_frame(&mut self, ui_data: UiData) -> Option<Frame> {
+        // Check if candle_data is empty and return no image
         if self.candle_data.is_empty() {
             log::warn!(
                 "Cannot render chart: candle_data is empty for stock {}",
                 self.stock_name
             );
-            return (slint::Image::default(), false);
+            return None;
         }
 
+        let press_started = !ui_data.is_release && self.chart_data.ui_data.is_release;
         self.chart_data.ui_data = ui_data;
-        // clean all object on the chart
-        if self.chart_data.ui_data.is_clean {
-            self.all_draw_data.clear();
-            self.current_draw_data.clear();
+        self.project_drawings();
+        self.apply_drawing_edits();
+        if self.chart_data.ui_data.is_rescale {
+            let mode = self.chart_data.ui_data.scale_mode.clamp(0, u8::MAX as i32) as u8;
+            self.set_scale_mode(ScaleMode::from_u8(mode));
+        }
+        if self.chart_data.ui_data.is_new_chart_style {
+            let style = self.chart_data.ui_data.chart_style.clamp(0, u8::MAX as i32) as u8;
+            self.set_style(ChartStyle::from_u8(style), self.chart_data.ui_data.box_size);
+            // Bricks larger than the moves of the candles leave nothing to show
+            if self.candle_data.is_empty() {
+                return None;
+            }
+            self.project_drawings();
+        }
+        // Update width and height of chart when users resize the window, before
+        // the mouse is mapped on the candles
+        if self.chart_data.ui_data.height != 0 && self.chart_data.ui_data.width != 0 {
+            self.chart_data.width = self.chart_data.ui_data.width as u32;
+            self.layout.image_height = self.chart_data.ui_data.height as u32;
         }
 
-        if self.chart_data.ui_data.is_undo {
-            self.all_draw_data.pop();
+        // A press on a pane separator resizes the panes instead of panning
+        let ui = &self.chart_data.ui_data;
+        let plot_height = self
+            .layout
+            .image_height
+            .saturating_sub(self.chart_data.range_x as u32);
+        let press_in_plot = ui.press_x < self.chart_data.width as i32 - self.chart_data.range_y;
+        if (self.layout.is_resizing() || press_in_plot)
+            && self
+                .layout
+                .drag(ui.press_y, ui.position_y, ui.is_release, plot_height)
+        {
+            self.chart_data.ui_data.move_x = 0;
+            self.chart_data.ui_data.move_y = 0;
+            self.chart_data.ui_data.zoom = 0;
         }
 
         if self.chart_data.candle_distance < 1.5
@@ -193,124 +991,85 @@ impl Chart {
             };
         self.chart_data.zoom_x = zoom_x;
         self.chart_data.zoom_y = zoom_y as f32;
-        let (mouse_x, mouse_y) = self.get_mouse_position();
-        if self.is_in_object.2 && !self.chart_data.ui_data.is_release {
-            let dx = self.chart_data.ui_data.move_x as f32 / self.chart_data.candle_distance;
-            let dy = -(self.chart_data.ui_data.move_y as f32)
-                * (3.0 * (self.chart_data.y_offset_max - self.chart_data.y_offset_min))
-                / (self.chart_data.height - self.chart_data.range_x as u32) as f32;
-
-            // Move the object
-            debug!("moving - dx: {dx}, dy: {dy}");
-            let mut updated_object = self.moving_object.clone();
-            updated_object.translate(dx, dy);
-            self.all_draw_data[self.is_in_object.0] = updated_object;
-            self.chart_data.ui_data.move_x = 0;
-            self.chart_data.ui_data.move_y = 0;
-        } else {
-            for (idx, draw_obj) in self.all_draw_data.iter().enumerate() {
-                let x_thresh_hold = (self.chart_data.x_offset_max - self.chart_data.x_offset_min)
-                    * THREAD_HOLD
-                    / (self.chart_data.width as f32 - 60.0);
-                let y_thresh_hold = (3.0
-                    * (self.chart_data.y_offset_max - self.chart_data.y_offset_min))
-                    * THREAD_HOLD
-                    / (self.chart_data.height as f32 - 60.0);
-
-                if draw_obj.hit_cursor((mouse_x, mouse_y), x_thresh_hold, y_thresh_hold) {
-                    if self.chart_data.ui_data.is_release {
-                        // If the object is not moving, set it to be moving
-                        self.is_in_object = (idx, true, false);
-                    } else {
-                        self.is_in_object = (idx, true, true);
-                        // Calculate the delta in chart coordinates
-                        self.moving_object = draw_obj.clone();
-                    }
-                    self.chart_data.ui_data.move_x = 0;
-                    self.chart_data.ui_data.move_y = 0;
-                    break;
-                }
-                if !self.is_in_object.2 {
-                    self.is_in_object = (0, false, false);
-                }
-            }
-        }
+        self.update_selection(press_started);
 
-        if self.is_in_object.2 {
+        // Neither dragging a drawing nor putting down its last point pans
+        if self.selection.is_dragging() || self.placing_point.is_some() {
             self.chart_data.ui_data.move_x = 0;
             self.chart_data.ui_data.move_y = 0;
         }
 
         self.chart_data.candle_distance = candle_distance;
-        // Update width and height of chart when users resize the window
-        if self.chart_data.ui_data.height != 0 && self.chart_data.ui_data.width != 0 {
-            self.chart_data.width = self.chart_data.ui_data.width as u32;
-            self.chart_data.height = self.chart_data.ui_data.height as u32;
+        if self.layout.image_height == 0 {
+            self.layout.image_height = self.chart_data.height;
         }
-
-        // Init data for plotters
-        let mut pixel_buffer =
-            SharedPixelBuffer::new(self.chart_data.width, self.chart_data.height);
-        let size = (pixel_buffer.width(), pixel_buffer.height());
-        let backend = BitMapBackend::with_buffer(pixel_buffer.make_mut_bytes(), size);
-        let root = backend.into_drawing_area();
-        // background: 181c27
-        root.fill(&RGBColor(0x19, 0x19, 0x1C))
-            .expect("error filling drawing area");
+        // `chart_data.height` is the price pane with the x-axis strip, which is
+        // what the mouse and label math of the price pane expects
+        self.layout.sync(&self.indicators);
+        let plot_height = self
+            .layout
+            .image_height
+            .saturating_sub(self.chart_data.range_x as u32);
+        let bounds = self.layout.bounds(plot_height);
+        self.chart_data.height = bounds[0].1 + self.chart_data.range_x as u32;
 
         // get candle data to show
         let (space, max_y, min_y) = self.update_y_axis_after_moving();
         let (min_x, max_x) = self.update_x_axis_after_moving();
 
-        // Init the first candle chart with x,y range
-        let mut chart = ChartBuilder::on(&root)
-            .x_label_area_size(self.chart_data.range_x)
-            .right_y_label_area_size(self.chart_data.range_y)
-            .build_cartesian_2d(min_x..max_x, min_y..max_y)
-            .expect("error building coordinate system");
-
         self.delta = (
             (max_x - min_x) / (10.0 * self.chart_data.height as f32),
             (max_y - min_y) / (10.0 * self.chart_data.width as f32),
         );
-        // Draw stock name watermark in the background
-        self.chart_draw_stock_name_watermark(&mut chart);
-
-        // Handle candle sticks on the chart
-        self.chart_update_candle_sticks(&mut chart);
-
-        // Handle drawing on the chart
-        // Convert x axis to date time unit
-        self.chart_custom_x_axis(&mut chart);
-
-        // Show candle information at the position of mouse
-        self.chart_show_candle_info(&mut chart);
 
-        // show company information
-        self.chart_show_company_info(&mut chart);
+        // Bring the studies in line with the candles before showing their values
+        for indicator in &mut self.indicators {
+            indicator.update(&self.candle_data);
+        }
 
-        // Draw all user's objects on the chart
+        // Follow the mouse with the drawing in progress
         self.update_current_draw();
 
-        // Draw all user's objects on the chart
-        self.chart_draw_objects(&mut chart);
-
-        self.chart_draw_moving_averages(&mut chart);
+        Some(Frame {
+            bounds,
+            view: (min_x, max_x, min_y, max_y),
+            space,
+        })
+    }
 
-        self.chart_draw_labels(&mut chart, max_x);
+    /// Keep the view and the drawing in progress after a release of the mouse
+    /// in the frame
+    fn end_frame(&mut self, frame: &Frame) {
+        let (min_x, max_x, _, max_y) = frame.view;
+        let space = frame.space;
 
-        // update data after a mouse release
+        // update data after a mouse release, the view keeps prices
+        let scale = self.price_scale();
         if self.chart_data.ui_data.is_release {
-            self.chart_data.y_offset_max = max_y - space;
-            self.chart_data.y_offset_min = max_y - 2.0 * space;
+            self.chart_data.y_offset_max = scale.to_price(max_y - space);
+            self.chart_data.y_offset_min = scale.to_price(max_y - 2.0 * space);
             self.chart_data.x_offset_min = min_x;
             self.chart_data.x_offset_max = max_x;
             self.is_in_object.2 = false;
             if !self.current_draw_data.is_empty() {
                 if let DrawType::Ruler(_) = self.current_draw_data.draw_type {
                     // nothing to do
+                } else if self.current_draw_data.draw_type.is_three_point()
+                    && self.placing_point != Some(true)
+                {
+                    // The drag set the first two points, the next click sets the last one
+                    self.placing_point = Some(false);
                 } else {
-                    self.all_draw_data.push(self.current_draw_data.clone());
+                    self.placing_point = None;
+                    // A new note is edited right away
+                    if let DrawType::Text(_) = self.current_draw_data.draw_type {
+                        self.text_edit = Some(self.all_draw_data.len());
+                    }
+                    let index = self.all_draw_data.len();
+                    let object = self.current_draw_data.clone();
+                    self.history
+                        .apply(Edit::Insert(vec![(index, object)]), &mut self.all_draw_data);
+                    self.layers.invalidate();
                     self.current_draw_data.clear();
                     self.backup_points.clear();
                 }
@@ -319,26 +1078,175 @@ impl Chart {
             && !self.chart_data.ui_data.is_release
         {
             self.backup_chart_offset = Some(BackupChartOffset {
-                y_offset_min: max_y - 2.0 * space,
-                y_offset_max: max_y - space,
+                y_offset_min: scale.to_price(max_y - 2.0 * space),
+                y_offset_max: scale.to_price(max_y - space),
                 x_offset_min: min_x,
                 x_offset_max: max_x,
             });
             debug!("save backup_chart_offset {:?}", self.backup_chart_offset);
         }
+    }
 
-        root.present().expect("error presenting");
-        drop(chart);
-        drop(root);
+    /// Key of the base layer of `frame`
+    fn base_key(&self, frame: &Frame) -> BaseKey {
+        let (min_x, max_x, min_y, max_y) = frame.view;
+        let data = &self.chart_data;
+        BaseKey {
+            revision: self.layers.revision(),
+            size: (data.width, self.layout.image_height),
+            bounds: frame.bounds.clone(),
+            view: [min_x, max_x, min_y, max_y],
+            offsets: [
+                data.x_offset_min,
+                data.x_offset_max,
+                data.y_offset_min,
+                data.y_offset_max,
+                data.candle_distance,
+            ],
+            move_x: data.ui_data.move_x,
+        }
+    }
 
-        (slint::Image::from_rgb8(pixel_buffer), self.is_in_object.1)
+    /// Draw `layer` of `frame` into the RGB `pixels` of the image
+    fn draw_pixels(&self, layer: Layer, pixels: &mut [u8], frame: &Frame) {
+        let size = (self.chart_data.width, self.layout.image_height);
+        let root = BitMapBackend::with_buffer(pixels, size).into_drawing_area();
+        self.draw_layer(layer, &root, frame);
+        root.present().expect("error presenting");
     }
 
-    /// Handle drawing all candle sticks on the chart
-    fn chart_update_candle_sticks(
+    /// Draw `layer` of `frame` on `root`, the whole image
+    fn draw_layer<DB: DrawingBackend>(
         &self,
-        chart: &mut ChartContext<BitMapBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>,
+        layer: Layer,
+        root: &DrawingArea<DB, Shift>,
+        frame: &Frame,
     ) {
+        let (min_x, max_x, min_y, max_y) = frame.view;
+        let bounds = &frame.bounds;
+
+        // The x-axis labels go under the lowest pane
+        let (price_area, price_labels) = if self.layout.panes.is_empty() {
+            (root.clone(), self.chart_data.range_x)
+        } else {
+            (root.split_vertically(bounds[0].1).0, 0)
+        };
+
+        // Init the first candle chart with x,y range
+        let mut chart = ChartBuilder::on(&price_area)
+            .x_label_area_size(price_labels)
+            .right_y_label_area_size(self.chart_data.range_y)
+            .build_cartesian_2d(min_x..max_x, min_y..max_y)
+            .expect("error building coordinate system");
+
+        match layer {
+            Layer::Base => {
+                // background: 181c27
+                root.fill(&RGBColor(0x19, 0x19, 0x1C))
+                    .expect("error filling drawing area");
+
+                // Draw stock name watermark in the background
+                self.chart_draw_stock_name_watermark(&mut chart);
+
+                // Handle candle sticks on the chart
+                self.chart_update_candle_sticks(&mut chart);
+
+                // Convert x axis to date time unit
+                self.chart_custom_x_axis(&mut chart);
+
+                // show company information
+                self.chart_show_company_info(&mut chart);
+
+                self.chart_draw_indicators(&mut chart);
+
+                self.chart_draw_comparisons(&mut chart);
+
+                // Draw all user's objects on the chart
+                self.chart_draw_objects(&mut chart, &self.all_draw_data, 0);
+
+                self.chart_draw_labels(&mut chart, max_x);
+            }
+            Layer::Top => {
+                // Draw cursor lines following the mouse position
+                let (x_position, y_position) = self.get_mouse_position();
+                self.draw_cursor_lines(&mut chart, x_position, y_position);
+
+                // The drawing in progress comes after the saved ones
+                let current = std::slice::from_ref(&self.current_draw_data);
+                self.chart_draw_objects(&mut chart, current, self.all_draw_data.len());
+
+                // Show candle information at the position of mouse
+                self.chart_show_candle_info(&mut chart);
+
+                self.draw_cursor_labels(&mut chart, max_x, y_position);
+            }
+        }
+
+        self.chart_draw_panes(root, layer, bounds, min_x, max_x);
+    }
+
+    /// Render the chart in a grid cell the mouse is not over, `width` by
+    /// `height` pixels, with the crosshair at `time` when the cells link it
+    pub fn render_cell(
+        &mut self,
+        width: i32,
+        height: i32,
+        time: Option<DateTime<Utc>>,
+    ) -> slint::Image {
+        let ui_data = self.cell_ui_data(width, height, time);
+        self.render_plot(ui_data).0
+    }
+
+    /// Mouse of a chart `width` by `height` pixels the mouse is not over, on
+    /// the candle at `time` if any
+    fn cell_ui_data(&self, width: i32, height: i32, time: Option<DateTime<Utc>>) -> UiData {
+        // Without a crosshair the mouse is parked right of the plot, where no
+        // line is drawn and the candle info shows the last candle
+        let position_x = time
+            .and_then(|time| self.position_of(time, width))
+            .unwrap_or(width + OFF_PLOT);
+        UiData {
+            ticker: self.stock_name.clone(),
+            width,
+            height,
+            time_frame: self.time_frame.clone(),
+            position_x,
+            press_x: position_x,
+            position_y: -OFF_PLOT,
+            press_y: -OFF_PLOT,
+            is_release: true,
+            color: self.chart_data.ui_data.color,
+            line_style: self.chart_data.ui_data.line_style,
+            line_width: self.chart_data.ui_data.line_width,
+            scale_mode: self.scale_mode as i32,
+            chart_style: self.style as i32,
+            box_size: self.box_size,
+            ..UiData::default()
+        }
+    }
+
+    /// Time of the candle under the mouse, for the crosshair of linked cells
+    pub fn cursor_time(&self) -> Option<DateTime<Utc>> {
+        if self.candle_data.is_empty() {
+            return None;
+        }
+        let (x, _) = self.get_mouse_position();
+        Some(self.candle_time(x.round() as i32))
+    }
+
+    /// Pixel column of the candle at `time` on a plot `width` pixels wide, if
+    /// it is in view
+    fn position_of(&self, time: DateTime<Utc>, width: i32) -> Option<i32> {
+        let scale = TimeScale::of(&self.candle_data, self.timeframe().bar_duration());
+        let index = scale.index_at(time.timestamp_millis()).round();
+        let (min_x, max_x) = (self.chart_data.x_offset_min, self.chart_data.x_offset_max);
+        let plot_width = (width - self.chart_data.range_y) as f32;
+        let x = ((index - min_x) * plot_width / (max_x - min_x)).round();
+        (0.0..plot_width).contains(&x).then_some(x as i32)
+    }
+
+    /// Handle drawing all candle sticks on the chart
+    fn chart_update_candle_sticks<DB: DrawingBackend>(&self, chart: &mut PriceChart<DB>) {
         let (min_candle_x, max_candle_x) = self.get_min_max_of_candle_after_moving();
         // draw candle stick on the chart
         let chart_candles = &self.candle_data[min_candle_x..max_candle_x];
@@ -346,46 +1254,152 @@ impl Chart {
         // Calculate candles size based on the candle_distance to ensure consistency
         // This ensures the candle size remains the same regardless of how many candles are displayed
         let candles_size = self.candle_distance(0) * 0.8;
+        let scale = self.price_scale();
+        let (_, max_y, min_y) = self.update_y_axis_after_moving();
 
-        // Draw candlesticks
+        match self.style {
+            ChartStyle::Bars => self.draw_bars(chart, chart_candles),
+            ChartStyle::Line | ChartStyle::Area => self.draw_close_line(chart, chart_candles),
+            ChartStyle::PointAndFigure => {
+                self.draw_point_and_figure(chart, chart_candles, candles_size)
+            }
+            // Heikin-Ashi candles and Renko bricks are drawn as candles
+            ChartStyle::Candles | ChartStyle::HeikinAshi | ChartStyle::Renko => chart
+                .draw_series(chart_candles.iter().map(|x| {
+                    let (open, high, low, close) = (
+                        scale.to_axis(x.open),
+                        scale.to_axis(x.high),
+                        scale.to_axis(x.low),
+                        scale.to_axis(x.close),
+                    );
+                    if x.open == x.close {
+                        let candle_height = self.pixels_to_y_distance(1.0, max_y - min_y);
+                        CandleStick::new(
+                            x.num,
+                            open - candle_height,
+                            high,
+                            low,
+                            close,
+                            UP_COLOR.filled(),
+                            DOWN_COLOR.filled(),
+                            candles_size as u32,
+                        )
+                    } else {
+                        CandleStick::new(
+                            x.num,
+                            open,
+                            high,
+                            low,
+                            close,
+                            UP_COLOR.filled(),
+                            DOWN_COLOR.filled(),
+                            candles_size as u32,
+                        )
+                    }
+                }))
+                .map(|_| ())
+                .unwrap(),
+        }
+
+        // Draw volume data over the candles when it has no pane of its own
+        if !self.layout.contains(&PaneKind::Volume) {
+            self.draw_volume_data(chart, chart_candles, candles_size);
+        }
+    }
+
+    /// Draw OHLC bars: the range of each candle with its open on the left and
+    /// its close on the right
+    fn draw_bars<DB: DrawingBackend>(&self, chart: &mut PriceChart<DB>, candles: &[CandleData]) {
+        // Half the width of a bar, in candles
+        const TICK: f32 = 0.35;
+        let scale = self.price_scale();
         chart
-            .draw_series(chart_candles.iter().map(|x| {
-                if x.open == x.close {
-                    let (_, max_y, min_y) = self.update_y_axis_after_moving();
-                    let candle_height = self.pixels_to_y_distance(1.0, max_y - min_y);
-                    CandleStick::new(
-                        x.num,
-                        x.open - candle_height,
-                        x.high,
-                        x.low,
-                        x.close,
-                        UP_COLOR.filled(),
-                        DOWN_COLOR.filled(),
-                        candles_size as u32,
-                    )
+            .draw_series(candles.iter().flat_map(|x| {
+                let color = if x.close >= x.open {
+                    UP_COLOR
                 } else {
-                    CandleStick::new(
-                        x.num,
-                        x.open,
-                        x.high,
-                        x.low,
-                        x.close,
-                        UP_COLOR.filled(),
-                        DOWN_COLOR.filled(),
-                        candles_size as u32,
-                    )
-                }
+                    DOWN_COLOR
+                };
+                let (open, close) = (scale.to_axis(x.open), scale.to_axis(x.close));
+                [
+                    PathElement::new(
+                        vec![
+                            (x.num, scale.to_axis(x.low)),
+                            (x.num, scale.to_axis(x.high)),
+                        ],
+                        color.stroke_width(1),
+                    ),
+                    PathElement::new(
+                        vec![(x.num - TICK, open), (x.num, open)],
+                        color.stroke_width(1),
+                    ),
+                    PathElement::new(
+                        vec![(x.num, close), (x.num + TICK, close)],
+                        color.stroke_width(1),
+                    ),
+                ]
             }))
-            .unwrap();
+            .expect("Error drawing bars");
+    }
 
-        // Draw volume data
-        self.draw_volume_data(chart, chart_candles, candles_size);
+    /// Draw a line through the closes, filled down to the bottom of the view
+    /// in the area style
+    fn draw_close_line<DB: DrawingBackend>(
+        &self,
+        chart: &mut PriceChart<DB>,
+        candles: &[CandleData],
+    ) {
+        let scale = self.price_scale();
+        let points = candles.iter().map(|x| (x.num, scale.to_axis(x.close)));
+        if self.style == ChartStyle::Area {
+            let (_, _, min_y) = self.update_y_axis_after_moving();
+            chart
+                .draw_series(
+                    AreaSeries::new(points, min_y, LINE_COLOR.mix(0.2))
+                        .border_style(LINE_COLOR.stroke_width(2)),
+                )
+                .expect("Error drawing area");
+        } else {
+            chart
+                .draw_series(LineSeries::new(points, LINE_COLOR.stroke_width(2)))
+                .expect("Error drawing line");
+        }
+    }
+
+    /// Draw the point-and-figure columns with a mark per box: X going up, O
+    /// going down
+    fn draw_point_and_figure<DB: DrawingBackend>(
+        &self,
+        chart: &mut PriceChart<DB>,
+        columns: &[CandleData],
+        candles_size: f32,
+    ) {
+        let scale = self.price_scale();
+        let box_size = self.box_size();
+        let size = (candles_size / 2.0).clamp(2.0, 10.0) as i32;
+        for column in columns {
+            let boxes = ((column.high - column.low) / box_size).round().max(1.0) as usize;
+            let centers = (0..boxes).map(|i| {
+                let price = column.low + (i as f32 + 0.5) * box_size;
+                (column.num, scale.to_axis(price))
+            });
+            let drawn = if column.close > column.open {
+                chart.draw_series(
+                    centers.map(|center| Cross::new(center, size, UP_COLOR.stroke_width(2))),
+                )
+            } else {
+                chart.draw_series(
+                    centers.map(|center| Circle::new(center, size, DOWN_COLOR.stroke_width(2))),
+                )
+            };
+            drawn.expect("Error drawing point and figure");
+        }
     }
 
     /// Draw volume data into the chart
-    fn draw_volume_data(
+    fn draw_volume_data<DB: DrawingBackend>(
         &self,
-        chart: &mut ChartContext<BitMapBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>,
+        chart: &mut PriceChart<DB>,
         chart_candles: &[CandleData],
         candles_size: f32,
     ) {
@@ -414,23 +1428,26 @@ impl Chart {
     }
 
     /// Handle drawing all user's objects on the chart
-    fn chart_draw_objects(
-        &mut self,
-        chart: &mut ChartContext<BitMapBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>,
+    /// Draw `objects`, the drawings from index `first` in `all_draw_data`
+    /// (the drawing in progress comes after the last one)
+    fn chart_draw_objects<DB: DrawingBackend>(
+        &self,
+        chart: &mut PriceChart<DB>,
+        objects: &[DrawObject],
+        first: usize,
     ) {
-        let mut draw_all_data = self.all_draw_data.clone();
-
-        // Handle drawing feature
-        let (x_position, y_position) = self.get_mouse_position();
-
-        // Draw cursor lines following the mouse position
-        self.draw_cursor_lines(chart, x_position, y_position);
         let (_, max_y, min_y) = self.update_y_axis_after_moving();
         let (min_x, max_x) = self.update_x_axis_after_moving();
 
-        // Draw all saved lines but don't add new ones
-        draw_all_data.push(self.current_draw_data.clone());
-        for object in draw_all_data {
+        // Drawings keep prices, they are drawn on the price axis
+        let scale = self.price_scale();
+        for (index, object) in (first..).zip(objects) {
+            let object = Self::on_axis(object, scale);
+            // Selected drawings are drawn a pixel wider
+            let is_selected = self.selection.contains(index);
+            let stroke = object
+                .color
+                .stroke_width(object.line_width.max(1) + is_selected as u32);
             let mut new_object = object.clone();
             new_object.clipped(min_x, max_x, min_y, max_y);
             let draw_points = new_object.to_vec((min_x, max_y), (max_x, min_y));
@@ -453,12 +1470,14 @@ impl Chart {
                         .unwrap();
 
                     // Draw the border as usual
-                    chart
-                        .draw_series(std::iter::once(Rectangle::new(
-                            [(min_x, min_y), (max_x, max_y)],
-                            object.color,
-                        )))
-                        .unwrap();
+                    let border = vec![
+                        (min_x, min_y),
+                        (max_x, min_y),
+                        (max_x, max_y),
+                        (min_x, max_y),
+                        (min_x, min_y),
+                    ];
+                    Self::draw_styled_path(chart, border, stroke, object.line_style);
                 }
                 DrawType::Oval((start, end)) => {
                     // Use original object coordinates, not clipped ones
@@ -502,12 +1521,7 @@ impl Chart {
                         .collect();
 
                     if visible_points.len() > 1 {
-                        chart
-                            .draw_series(LineSeries::new(
-                                visible_points,
-                                object.color.stroke_width(1),
-                            ))
-                            .unwrap();
+                        Self::draw_styled_path(chart, visible_points, stroke, object.line_style);
                     }
                 }
                 DrawType::Ruler((end, start)) => {
@@ -529,11 +1543,12 @@ impl Chart {
                     let max_x = x1.max(x2);
                     let min_y = y1.min(y2);
                     let max_y = y1.max(y2);
-                    let percent = (end.1 - start.1) * 100.0 / start.1;
+                    let (from, to) = (scale.to_price(start.1), scale.to_price(end.1));
+                    let percent = (to - from) * 100.0 / from;
                     let percent_string = if percent > 0.0 {
-                        format!("+{:.02} (+{:.02}%)", (end.1 - start.1), percent)
+                        format!("+{:.02} (+{:.02}%)", (to - from), percent)
                     } else {
-                        format!("{:.02} ({:.02}%)", (end.1 - start.1), percent)
+                        format!("{:.02} ({:.02}%)", (to - from), percent)
                     };
 
                     let color = if percent >= 0.0 { BLUE } else { RED };
@@ -542,7 +1557,7 @@ impl Chart {
                         chart,
                         &first_arrow_start,
                         &first_arrow_end,
-                        color,
+                        (color.stroke_width(1), LineStyle::Solid),
                         max_x,
                         min_x,
                         max_y,
@@ -552,7 +1567,7 @@ impl Chart {
                         chart,
                         &second_arrow_start,
                         &second_arrow_end,
-                        color,
+                        (color.stroke_width(1), LineStyle::Solid),
                         max_x,
                         min_x,
                         max_y,
@@ -600,34 +1615,91 @@ impl Chart {
                     ));
                 }
                 DrawType::Arrow((end, start)) => {
-                    // Draw the main line
-                    self.draw_arrow(chart, start, end, object.color, max_x, min_x, max_y, min_y);
+                    let line = (stroke, object.line_style);
+                    self.draw_arrow(chart, start, end, line, max_x, min_x, max_y, min_y);
+                }
+                DrawType::FibRetracement(_) | DrawType::FibExtension(_) => {
+                    if !new_object.is_empty() {
+                        Self::draw_fib(chart, &object, stroke, scale);
+                    }
+                }
+                DrawType::Channel(_) | DrawType::Pitchfork(_) => {
+                    if !new_object.is_empty() {
+                        Self::draw_channel(chart, &object, stroke);
+                    }
+                }
+                DrawType::PriceRange(_) | DrawType::DateRange(_) => {
+                    if !new_object.is_empty() {
+                        self.draw_range(
+                            chart,
+                            &object.draw_type,
+                            object.color,
+                            (min_x, max_x, min_y, max_y),
+                        );
+                    }
+                }
+                DrawType::Text(note) => {
+                    // The clipped note is empty when its anchor left the view
+                    if !new_object.is_empty() {
+                        let is_edited = self.text_edit == Some(index);
+                        Self::draw_text_note(chart, note, object.color, is_edited);
+                    }
                 }
                 _ => {
-                    chart
-                        .draw_series(LineSeries::new(draw_points, object.color.stroke_width(1)))
-                        .unwrap()
-                        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x, y)], object.color));
+                    Self::draw_styled_path(chart, draw_points, stroke, object.line_style);
                 }
             }
+            if is_selected {
+                Self::draw_handles(chart, &object);
+            }
         }
     }
 
-    fn draw_arrow(
+    /// Draw a path in the line style of a drawing
+    fn draw_styled_path<DB: DrawingBackend>(
+        chart: &mut PriceChart<DB>,
+        points: Vec<Point>,
+        style: ShapeStyle,
+        line_style: LineStyle,
+    ) {
+        let width = style.stroke_width.max(1);
+        match line_style {
+            LineStyle::Solid => chart.draw_series(LineSeries::new(points, style)),
+            LineStyle::Dashed => {
+                chart.draw_series(DashedLineSeries::new(points, 6 * width, 4 * width, style))
+            }
+            LineStyle::Dotted => {
+                chart.draw_series(DashedLineSeries::new(points, width, 3 * width, style))
+            }
+        }
+        .unwrap();
+    }
+
+    /// Draw the handles of a selected drawing, a press on one drags it
+    fn draw_handles<DB: DrawingBackend>(chart: &mut PriceChart<DB>, object: &DrawObject) {
+        let corners = [(-HANDLE_SIZE, -HANDLE_SIZE), (HANDLE_SIZE, HANDLE_SIZE)];
+        for handle in object.handles() {
+            let _ = chart.plotting_area().draw(
+                &(EmptyElement::at(handle)
+                    + Rectangle::new(corners, WHITE.filled())
+                    + Rectangle::new(corners, object.color.stroke_width(1))),
+            );
+        }
+    }
+
+    fn draw_arrow<DB: DrawingBackend>(
         &self,
-        chart: &mut ChartContext<BitMapBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>,
+        chart: &mut PriceChart<DB>,
         start: &(f32, f32),
         end: &(f32, f32),
-        color: RGBColor,
+        (style, line_style): (ShapeStyle, LineStyle),
         max_x: f32,
         min_x: f32,
         max_y: f32,
         min_y: f32,
     ) {
         // Draw the main line
-        chart
-            .draw_series(LineSeries::new(vec![*start, *end], color.stroke_width(1)))
-            .unwrap();
+        Self::draw_styled_path(chart, vec![*start, *end], style, line_style);
 
         // Draw the arrowhead
         let arrow_length_x = self.pixels_to_x_distance(20.0, max_x - min_x); // in pixels
@@ -651,21 +1723,178 @@ impl Chart {
         chart
             .draw_series(std::iter::once(PathElement::new(
                 vec![*end, arrow_point1],
-                color.stroke_width(1),
+                style,
             )))
             .unwrap();
         chart
             .draw_series(std::iter::once(PathElement::new(
                 vec![*end, arrow_point2],
-                color.stroke_width(1),
+                style,
+            )))
+            .unwrap();
+    }
+
+    /// Draw the levels of a Fibonacci tool with their ratio and price, and the
+    /// legs of an extension. `object` is on the price axis of `scale`.
+    fn draw_fib<DB: DrawingBackend>(
+        chart: &mut PriceChart<DB>,
+        object: &DrawObject,
+        stroke: ShapeStyle,
+        scale: PriceScale,
+    ) {
+        let color = object.color;
+        let segments = object.draw_type.segments();
+        let levels = object.draw_type.fib_levels();
+        // The levels come after the legs
+        let (legs, lines) = segments.split_at(segments.len() - levels.len());
+        let leg_stroke = color.mix(0.5).stroke_width(stroke.stroke_width);
+        for (start, end) in legs {
+            Self::draw_styled_path(chart, vec![*start, *end], leg_stroke, object.line_style);
+        }
+        for band in lines.windows(2) {
+            chart
+                .draw_series(std::iter::once(Rectangle::new(
+                    [band[0].0, band[1].1],
+                    color.mix(0.08).filled(),
+                )))
+                .unwrap();
+        }
+        let font = ("Arial", 12).into_font().color(&color);
+        for ((start, end), (ratio, price)) in lines.iter().zip(levels) {
+            Self::draw_styled_path(chart, vec![*start, *end], stroke, object.line_style);
+            let label = format!("{ratio} ({:.2})", scale.to_price(price));
+            let _ = chart
+                .plotting_area()
+                .draw(&(EmptyElement::at(*start) + Text::new(label, (2, -14), &font)));
+        }
+    }
+
+    /// Draw a channel with its middle line, or a pitchfork with its tines
+    fn draw_channel<DB: DrawingBackend>(
+        chart: &mut PriceChart<DB>,
+        object: &DrawObject,
+        stroke: ShapeStyle,
+    ) {
+        let (draw_type, color) = (&object.draw_type, object.color);
+        let segments = draw_type.segments();
+        if let DrawType::Channel(_) = draw_type {
+            let (base, parallel) = (segments[0], segments[1]);
+            chart
+                .draw_series(std::iter::once(Polygon::new(
+                    vec![base.0, base.1, parallel.1, parallel.0],
+                    color.mix(0.1).filled(),
+                )))
+                .unwrap();
+        }
+        for (i, (start, end)) in segments.iter().enumerate() {
+            // The middle line of a channel and the base of a pitchfork are guides
+            let style = if (i == 2 && matches!(draw_type, DrawType::Channel(_))) || i == 3 {
+                color.mix(0.5).stroke_width(stroke.stroke_width)
+            } else {
+                stroke
+            };
+            Self::draw_styled_path(chart, vec![*start, *end], style, object.line_style);
+        }
+    }
+
+    /// Draw a price or date range: the box, the measuring arrow and a label
+    /// with the change, or with the candles and time between both ends. The
+    /// range is on the price axis.
+    fn draw_range<DB: DrawingBackend>(
+        &self,
+        chart: &mut PriceChart<DB>,
+        draw_type: &DrawType,
+        color: RGBColor,
+        (min_x, max_x, min_y, max_y): (f32, f32, f32, f32),
+    ) {
+        let (start, end, label) = match draw_type {
+            DrawType::PriceRange((start, end)) => {
+                let scale = self.price_scale();
+                let (from, to) = (scale.to_price(start.1), scale.to_price(end.1));
+                let change = to - from;
+                let percent = change * 100.0 / from;
+                (start, end, format!("{change:+.2} ({percent:+.2}%)"))
+            }
+            DrawType::DateRange((start, end)) => {
+                let candles = (end.0 - start.0).round() as i32;
+                let duration = self.candle_time(end.0.round() as i32)
+                    - self.candle_time(start.0.round() as i32);
+                let time = if self.timeframe().is_intraday() && duration.num_days() == 0 {
+                    format!(
+                        "{}h{:02}",
+                        duration.num_hours(),
+                        duration.num_minutes().abs() % 60
+                    )
+                } else {
+                    format!("{} ngày", duration.num_days())
+                };
+                (start, end, format!("{candles} nến, {time}"))
+            }
+            _ => return,
+        };
+
+        chart
+            .draw_series(std::iter::once(Rectangle::new(
+                [*start, *end],
+                color.mix(0.15).filled(),
             )))
             .unwrap();
+        let arrow = *draw_type.segments().last().unwrap();
+        let line = (color.stroke_width(1), LineStyle::Solid);
+        self.draw_arrow(chart, &arrow.0, &arrow.1, line, max_x, min_x, max_y, min_y);
+
+        // The label sits past the end of the arrow
+        let font = ("Arial-Bold", 14).into_font().color(&WHITE);
+        let (width, height) = chart
+            .plotting_area()
+            .estimate_text_size(&label, &font)
+            .unwrap_or((120, 14));
+        let (width, height) = (width as i32 + 8, height as i32 + 6);
+        let top = if end.1 >= start.1 { -height - 4 } else { 4 };
+        let anchor = ((start.0 + end.0) / 2.0, end.1);
+        let corners = [(-width / 2, top), (width / 2, top + height)];
+        let _ = chart.plotting_area().draw(
+            &(EmptyElement::at(anchor)
+                + Rectangle::new(corners, color.filled())
+                + Text::new(label, (-width / 2 + 4, top + 3), &font)),
+        );
+    }
+
+    /// Draw a text note with its top-left corner at the anchor, the box has a
+    /// fixed size in pixels whatever the zoom
+    fn draw_text_note<DB: DrawingBackend>(
+        chart: &mut PriceChart<DB>,
+        note: &TextNote,
+        color: RGBColor,
+        is_edited: bool,
+    ) {
+        let (width, height) = note.size();
+        let corners = [(0, 0), (width as i32, height as i32)];
+        let area = chart.plotting_area();
+        if let Some(background) = note.background {
+            let _ = area.draw(
+                &(EmptyElement::at(note.anchor) + Rectangle::new(corners, background.filled())),
+            );
+        }
+        if is_edited {
+            let _ = area.draw(&(EmptyElement::at(note.anchor) + Rectangle::new(corners, color)));
+        }
+        let font = ("Arial", note.font_size).into_font().color(&color);
+        for (i, line) in note.lines().enumerate() {
+            let offset = (
+                TEXT_PADDING as i32,
+                (TEXT_PADDING + i as u32 * note.line_height()) as i32,
+            );
+            let _ = area.draw(
+                &(EmptyElement::at(note.anchor) + Text::new(line.to_string(), offset, &font)),
+            );
+        }
     }
 
     /// Draw cursor lines for mouse position
-    fn draw_cursor_lines(
+    fn draw_cursor_lines<DB: DrawingBackend>(
         &self,
-        chart: &mut ChartContext<BitMapBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>,
+        chart: &mut PriceChart<DB>,
         x_position: f32,
         y_position: f32,
     ) {
@@ -701,15 +1930,34 @@ impl Chart {
         if self.is_in_y_field() {
             return;
         }
+        // Drawings live in the price pane, a press in a sub-pane or on a
+        // separator draws nothing
+        if self.layout.is_resizing()
+            || self.chart_data.ui_data.press_y
+                >= self.chart_data.height as i32 - self.chart_data.range_x
+        {
+            return;
+        }
 
+        // New points are prices, the mouse is on the price axis
+        let scale = self.price_scale();
         let (x, y) = self.get_mouse_position();
+        let y = scale.to_price(y);
+        if let Some(pressed) = self.placing_point {
+            let ui_data = &self.chart_data.ui_data;
+            if ui_data.is_release || matches!(ui_data.mouse_type, MouseType::Move) {
+                self.current_draw_data.draw_type.set_last_point((x, y));
+                self.placing_point = Some(pressed || !ui_data.is_release);
+                return;
+            }
+            // Another tool was picked, the unfinished drawing is dropped
+            self.placing_point = None;
+            self.current_draw_data.clear();
+        }
         // Save the current mouse position
-        let press_point = self.get_press_position();
-        let color = RGBColor(
-            self.chart_data.ui_data.color.red(),
-            self.chart_data.ui_data.color.green(),
-            self.chart_data.ui_data.color.blue(),
-        );
+        let (press_x, press_y) = self.get_press_position();
+        let press_point = (press_x, scale.to_price(press_y));
+        let color = self.draw_color();
         if !self.chart_data.ui_data.is_release
             && self.chart_data.ui_data.press_x < self.chart_data.width as i32 - 60
         {
@@ -748,66 +1996,136 @@ impl Chart {
                     self.current_draw_data =
                         DrawObject::new(DrawType::Ruler(((x, y), press_point)), color);
                 }
+                MouseType::FibRetracement => {
+                    self.current_draw_data =
+                        DrawObject::new(DrawType::FibRetracement((press_point, (x, y))), color);
+                }
+                MouseType::FibExtension => {
+                    let points = (press_point, (x, y), (x, y));
+                    self.current_draw_data = DrawObject::new(DrawType::FibExtension(points), color);
+                }
+                MouseType::Channel => {
+                    let points = (press_point, (x, y), (x, y));
+                    self.current_draw_data = DrawObject::new(DrawType::Channel(points), color);
+                }
+                MouseType::Pitchfork => {
+                    let points = (press_point, (x, y), (x, y));
+                    self.current_draw_data = DrawObject::new(DrawType::Pitchfork(points), color);
+                }
+                MouseType::PriceRange => {
+                    self.current_draw_data =
+                        DrawObject::new(DrawType::PriceRange((press_point, (x, y))), color);
+                }
+                MouseType::DateRange => {
+                    self.current_draw_data =
+                        DrawObject::new(DrawType::DateRange((press_point, (x, y))), color);
+                }
+                MouseType::Text => {
+                    let (text, font_size, background) = self.text_style();
+                    let text = if text.is_empty() {
+                        "Text".to_string()
+                    } else {
+                        text
+                    };
+                    let note = TextNote::new(press_point, text, font_size, background);
+                    self.current_draw_data = DrawObject::new(DrawType::Text(note), color);
+                }
                 _ => {}
             }
+            (
+                self.current_draw_data.line_style,
+                self.current_draw_data.line_width,
+            ) = self.line_style();
         }
     }
 
-    /// Draw all labels of the chart:
+    /// Draw the labels of the latest values of the chart, the cursor labels
+    /// follow the mouse in the top layer:
     ///   - Latest price label
     ///   - latest volume label
-    ///   - cursor price label
-    ///   - cursor date label
-    fn chart_draw_labels(
-        &self,
-        chart: &mut ChartContext<BitMapBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>,
-        max_x: f32,
-    ) {
+    fn chart_draw_labels<DB: DrawingBackend>(&self, chart: &mut PriceChart<DB>, max_x: f32) {
         let (min_candle_x, max_candle_x) = self.get_min_max_of_candle_after_moving();
 
-        let space = self.chart_data.y_offset_max - self.chart_data.y_offset_min;
+        let (y_min, y_max) = self.y_offsets_on_axis();
+        let space = y_max - y_min;
         let (_, _, min) = self.update_y_axis_after_moving();
-        let (_, y_position) = self.get_mouse_position();
         let chart_candles = &self.candle_data[min_candle_x..max_candle_x];
         let max_volume = self.get_volume_max(chart_candles.to_vec());
         let last_volume = self.get_last_volume();
         let last_volume_map_to_y = last_volume / max_volume * 0.25 * 3.0 * space + min;
-
-        // Define styles
-        let cursor_label_style = ShapeStyle {
-            color: RGBColor(0x3A, 0x36, 0x45).into(),
-            filled: true,
-            stroke_width: 0,
-        };
+
+        // Define styles
         let green_label_style = ShapeStyle {
             color: RGBColor(0x23, 0xBA, 0x75).into(),
             filled: true,
             stroke_width: 0,
         };
 
-        // Draw cursor labels
-        self.draw_cursor_labels(chart, max_x, y_position, min, cursor_label_style);
-
         // Draw latest price label
         self.draw_latest_price_label(chart, max_x, space);
 
-        // Draw latest volume label
-        self.draw_latest_volume_label(chart, max_x, last_volume_map_to_y, space, green_label_style);
+        // Draw latest volume label, the volume pane has its own
+        if !self.layout.contains(&PaneKind::Volume) {
+            self.draw_latest_volume_label(
+                chart,
+                max_x,
+                last_volume_map_to_y,
+                space,
+                green_label_style,
+            );
+        }
     }
 
-    /// Draw cursor labels
-    fn draw_cursor_labels(
+    /// Draw the price and date labels of the cursor
+    fn draw_cursor_labels<DB: DrawingBackend>(
         &self,
-        chart: &mut ChartContext<BitMapBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>,
+        chart: &mut PriceChart<DB>,
         max_x: f32,
         y_position: f32,
-        min: f32,
-        cursor_label_style: ShapeStyle,
     ) {
-        let space = self.chart_data.y_offset_max - self.chart_data.y_offset_min;
+        let cursor_label_style = ShapeStyle {
+            color: RGBColor(0x3A, 0x36, 0x45).into(),
+            filled: true,
+            stroke_width: 0,
+        };
+        let (y_min, y_max) = self.y_offsets_on_axis();
+        let space = y_max - y_min;
+        let (_, _, min) = self.update_y_axis_after_moving();
         let (x_position, _) = self.get_mouse_position();
+        // With sub-panes the pane under the mouse shows the value label and the
+        // lowest pane the date label
+        let has_panes = !self.layout.panes.is_empty();
+        let in_price_pane = self.chart_data.ui_data.position_y
+            < self.chart_data.height as i32 - self.chart_data.range_x;
+
+        if !has_panes || in_price_pane {
+            self.draw_cursor_price_label(chart, max_x, y_position, space, cursor_label_style);
+        }
+        if has_panes {
+            return;
+        }
 
-        // Draw the label for the cursor date
+        let output = self.cursor_date_label(x_position);
+        let mut rectangle_cursor_x =
+            Rectangle::new([(x_position, min), (x_position, min)], cursor_label_style);
+        rectangle_cursor_x.set_margin(15, 0, 60, 5);
+        let _ = chart.plotting_area().draw(&rectangle_cursor_x);
+        let _ = chart.plotting_area().draw(&Text::new(
+            output,
+            (x_position, min - 2.0),
+            ("Arial-Bold", 14).into_font().color(&WHITE),
+        ));
+    }
+
+    /// Draw the price label of the cursor on the y-axis
+    fn draw_cursor_price_label<DB: DrawingBackend>(
+        &self,
+        chart: &mut PriceChart<DB>,
+        max_x: f32,
+        y_position: f32,
+        space: f32,
+        cursor_label_style: ShapeStyle,
+    ) {
         let mut cursor_rectangle = Rectangle::new(
             [
                 (max_x + 10.0, y_position - 0.7 / 20.0 * space),
@@ -817,57 +2135,63 @@ impl Chart {
         );
         cursor_rectangle.set_margin(0, 0, 50, 0);
 
-        // Show the price of current mouse position
-        let y_position_str = format!("   {y_position:.02}");
+        // Show the price of current mouse position, or its change on the
+        // percent scale
+        let y_position_str = format!("   {}", self.price_scale().label(y_position));
         let _ = chart.plotting_area().draw(&cursor_rectangle);
         let _ = chart.plotting_area().draw(&Text::new(
             y_position_str,
             (
                 max_x,
                 y_position
-                    + (7.0 * (self.chart_data.y_offset_max - self.chart_data.y_offset_min)
+                    + (7.0 * space
                         / (self.chart_data.height - self.chart_data.range_x as u32) as f32),
             ),
             ("Arial-Bold", 14).into_font().color(&WHITE),
         ));
+    }
+
+    /// Date of the candle at `x_position`, including dates before the first
+    /// and after the last candle
+    fn cursor_date_label(&self, x_position: f32) -> String {
+        let cursor_date = self.candle_time(x_position.round() as i32);
+        if self.timeframe().is_intraday() {
+            cursor_date
+                .with_timezone(&vn_offset())
+                .format("%d-%m %H:%M")
+                .to_string()
+        } else {
+            cursor_date.to_string()[0..11].to_string()
+        }
+    }
 
-        // Draw the label for the cursor date
-        // Calculate the date based on cursor position, including future dates
-        let cursor_index = x_position.round() as i32;
-        let cursor_date = if cursor_index < 0 {
+    /// Time of the candle at `index`, extrapolated by the bar duration before
+    /// the first candle and after the last one
+    fn candle_time(&self, index: i32) -> DateTime<Utc> {
+        if index < 0 {
             // Past dates - go backwards from the first data point
-            let days_back = (-cursor_index) as i64;
-            self.candle_data[0].time - Duration::days(days_back)
-        } else if cursor_index >= self.candle_data.len() as i32 {
+            self.candle_data[0].time - self.timeframe().bar_duration() * -index
+        } else if index >= self.candle_data.len() as i32 {
             // Future dates - calculate forward from the last data point
-            let days_forward = (cursor_index - (self.candle_data.len() as i32 - 1)) as i64;
-            self.candle_data[self.candle_data.len() - 1].time + Duration::days(days_forward)
+            let bars_forward = index - (self.candle_data.len() as i32 - 1);
+            self.candle_data[self.candle_data.len() - 1].time
+                + self.timeframe().bar_duration() * bars_forward
         } else {
             // Within data range - use actual data
-            self.candle_data[cursor_index as usize].time
-        };
-
-        let mut rectangle_cursor_x =
-            Rectangle::new([(x_position, min), (x_position, min)], cursor_label_style);
-        rectangle_cursor_x.set_margin(15, 0, 60, 5);
-        let output = &cursor_date.to_string()[0..11];
-        let _ = chart.plotting_area().draw(&rectangle_cursor_x);
-        let _ = chart.plotting_area().draw(&Text::new(
-            output.to_string(),
-            (x_position, min - 2.0),
-            ("Arial-Bold", 14).into_font().color(&WHITE),
-        ));
+            self.candle_data[index as usize].time
+        }
     }
 
     /// Draw latest price label
-    fn draw_latest_price_label(
+    fn draw_latest_price_label<DB: DrawingBackend>(
         &self,
-        chart: &mut ChartContext<BitMapBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>,
+        chart: &mut PriceChart<DB>,
         max_x: f32,
         space: f32,
     ) {
-        let last_price = self.get_last_price();
-        let last_price_str = format!("    {last_price:.02}");
+        let scale = self.price_scale();
+        let last_price = scale.to_axis(self.get_last_price());
+        let last_price_str = format!("    {}", scale.label(last_price));
 
         // Determine the color based on the last candle
         let is_up = self.is_last_candle_up();
@@ -894,7 +2218,7 @@ impl Chart {
             (
                 max_x,
                 last_price
-                    + (7.0 * (self.chart_data.y_offset_max - self.chart_data.y_offset_min)
+                    + (7.0 * space
                         / (self.chart_data.height - self.chart_data.range_x as u32) as f32),
             ),
             ("Arial-Bold", 14).into_font().color(&WHITE),
@@ -926,9 +2250,9 @@ impl Chart {
     }
 
     /// Draw latest volume label
-    fn draw_latest_volume_label(
+    fn draw_latest_volume_label<DB: DrawingBackend>(
         &self,
-        chart: &mut ChartContext<BitMapBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>,
+        chart: &mut PriceChart<DB>,
         max_x: f32,
         last_volume_map_to_y: f32,
         space: f32,
@@ -958,7 +2282,7 @@ impl Chart {
             (
                 max_x,
                 last_volume_map_to_y
-                    + (7.0 * (self.chart_data.y_offset_max - self.chart_data.y_offset_min)
+                    + (7.0 * space
                         / (self.chart_data.height - self.chart_data.range_x as u32) as f32),
             ),
             ("Arial-Bold", 14).into_font().color(&WHITE),
@@ -967,58 +2291,48 @@ impl Chart {
 
     /// Customize the x value from DateTime<Utc> to index to prevent
     /// the date which has no data of candle stick.
-    fn chart_custom_x_axis(
-        &self,
-        chart: &mut ChartContext<BitMapBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>,
-    ) {
-        // Create a mapping of dates to indices
-        let date_to_index: Vec<(DateTime<Utc>, usize)> = self
-            .candle_data
-            .iter()
-            .enumerate()
-            .map(|(idx, x)| (x.time, idx))
-            .collect();
-
+    fn chart_custom_x_axis<DB: DrawingBackend>(&self, chart: &mut PriceChart<DB>) {
         // Configure the x-axis and y-axis labels with larger font sizes
+        let scale = self.price_scale();
         chart
             .configure_mesh()
             .disable_x_mesh()
             .disable_y_mesh()
-            .x_label_formatter(&|&idx| {
-                if idx > 0.0 && idx < date_to_index.len() as f32 {
-                    let date = &(date_to_index[idx as usize].0
-                        + Duration::days(
-                            ((self.chart_data.ui_data.move_x as f32
-                                / self.chart_data.candle_distance)
-                                as i32)
-                                .into(),
-                        ));
-                    format!("{}", date.format("Tháng %m\n"))
-                } else {
-                    "".to_string()
-                }
-            })
-            .y_label_formatter(&|&val| format!("{val:.2}")) // Format y-axis labels with 2 decimals
+            .x_label_formatter(&|&idx| self.x_axis_label(idx))
+            .y_label_formatter(&|&val| scale.label(val)) // Prices with 2 decimals, or percents
             .label_style(("Arial-Bold", 16).into_font().color(&WHITE)) // Increased font size
             .axis_style(WHITE.stroke_width(1))
             .draw()
             .expect("error drawing mesh");
     }
 
+    /// Label of the x-axis at candle `idx`, empty outside the candles
+    fn x_axis_label(&self, idx: f32) -> String {
+        if idx > 0.0 && idx < self.candle_data.len() as f32 {
+            let date = &(self.candle_data[idx as usize].time
+                + Duration::days(
+                    ((self.chart_data.ui_data.move_x as f32 / self.chart_data.candle_distance)
+                        as i32)
+                        .into(),
+                ));
+            if self.timeframe().is_intraday() {
+                format!("{}", date.with_timezone(&vn_offset()).format("%d/%m\n"))
+            } else {
+                format!("{}", date.format("Tháng %m\n"))
+            }
+        } else {
+            "".to_string()
+        }
+    }
+
     /// show the information of candle stick on the left-top-corner
     /// basing on the mouse position: low, high, open, close prices
-    fn chart_show_candle_info(
-        &self,
-        chart: &mut ChartContext<BitMapBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>,
-    ) {
+    fn chart_show_candle_info<DB: DrawingBackend>(&self, chart: &mut PriceChart<DB>) {
         // get candle data to show
         let (_, max_y, min_y) = self.update_y_axis_after_moving();
         let (min_x, _) = self.update_x_axis_after_moving();
         let y_stock_text = max_y - (max_y - min_y) * (20.0 / self.chart_data.height as f32);
         let y_volumn_text = max_y - (max_y - min_y) * (40.0 / self.chart_data.height as f32);
-        let y_ma_20_text = max_y - (max_y - min_y) * (60.0 / self.chart_data.height as f32);
-        let y_ma_50_text = max_y - (max_y - min_y) * (80.0 / self.chart_data.height as f32);
-        let y_ma_200_text = max_y - (max_y - min_y) * (100.0 / self.chart_data.height as f32);
 
         let (x, _) = self.get_mouse_position();
         // If cursor is exactly at the middle of a candle, show info for that candle
@@ -1036,7 +2350,7 @@ impl Chart {
                 if x as usize >= self.candle_data.len() - 1 {
                     (
                         self.candle_data[self.candle_data.len() - 1].clone(),
-                        self.candle_data[self.candle_data.len() - 2].clone(),
+                        self.candle_data[self.candle_data.len().saturating_sub(2)].clone(),
                     )
                 } else {
                     (
@@ -1063,7 +2377,7 @@ impl Chart {
         };
         let output = format!(
             "  {} - Open: {:.02}, High: {:.02}, Close: {:.02}, Low: {:.02}, {} ({})",
-            self.stock_name,
+            self.series_label(),
             candle_data.open,
             candle_data.high,
             candle_data.close,
@@ -1100,28 +2414,74 @@ impl Chart {
             (min_x, y_volumn_text),
             ("sans-serif", 15).into_font().color(&color),
         ));
-        let _ = chart.plotting_area().draw(&Text::new(
-            "  MA20    _________",
-            (min_x, y_ma_20_text),
-            ("sans-serif", 15).into_font().color(&RED),
-        ));
-        let _ = chart.plotting_area().draw(&Text::new(
-            "  MA50    _________",
-            (min_x, y_ma_50_text),
-            ("sans-serif", 15).into_font().color(&BLUE),
-        ));
-        let _ = chart.plotting_area().draw(&Text::new(
-            "  MA200  _________",
-            (min_x, y_ma_200_text),
-            ("sans-serif", 15).into_font().color(&WHITE),
-        ));
+
+        // One legend line per overlay study, with its values at the cursor
+        // candle, the studies in sub-panes have their legend there
+        let cursor_idx = x.round().clamp(0.0, (self.candle_data.len() - 1) as f32) as usize;
+        let overlays = self
+            .indicators
+            .iter()
+            .filter(|i| i.visible && i.indicator.style() == RenderStyle::Overlay);
+        let mut line = 0;
+        for indicator in overlays {
+            let values: Vec<String> = (0..indicator.colors.len())
+                .filter_map(|output| indicator.value(cursor_idx, output))
+                .map(|value| format!("{value:.02}"))
+                .collect();
+            let y_text = max_y
+                - (max_y - min_y) * ((60.0 + 20.0 * line as f32) / self.chart_data.height as f32);
+            let color = indicator.colors.first().copied().unwrap_or(WHITE);
+            let _ = chart.plotting_area().draw(&Text::new(
+                format!("  {}  {}", indicator.indicator.label(), values.join("  ")),
+                (min_x, y_text),
+                ("sans-serif", 15).into_font().color(&color),
+            ));
+            line += 1;
+        }
+
+        // Then one line per compared symbol, with its change from the first
+        // candle in view to the cursor candle
+        let (min_candle_x, _) = self.get_min_max_of_candle_after_moving();
+        for comparison in &self.comparisons {
+            let change = if cursor_idx < min_candle_x {
+                None
+            } else {
+                self.comparison_percent(comparison, min_candle_x, cursor_idx + 1)
+                    .last()
+                    .copied()
+                    .flatten()
+            };
+            let change = change.map_or("-".to_string(), |change| format!("{change:+.2}%"));
+            let y_text = max_y
+                - (max_y - min_y) * ((60.0 + 20.0 * line as f32) / self.chart_data.height as f32);
+            let _ = chart.plotting_area().draw(&Text::new(
+                format!("  {}  {}", comparison.label, change),
+                (min_x, y_text),
+                ("sans-serif", 15).into_font().color(&comparison.color),
+            ));
+            line += 1;
+        }
+    }
+
+    /// Name of the series in the candle info: the symbol, with the style when
+    /// the candles are derived and the box size of bricks and columns
+    fn series_label(&self) -> String {
+        if self.style.uses_box() {
+            format!(
+                "{} {} {:.2}",
+                self.stock_name,
+                self.style.label(),
+                self.box_size()
+            )
+        } else if self.style.is_derived() {
+            format!("{} {}", self.stock_name, self.style.label())
+        } else {
+            self.stock_name.clone()
+        }
     }
 
     /// Draw stock name as watermark in the background center of the chart
-    fn chart_draw_stock_name_watermark(
-        &self,
-        chart: &mut ChartContext<BitMapBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>,
-    ) {
+    fn chart_draw_stock_name_watermark<DB: DrawingBackend>(&self, chart: &mut PriceChart<DB>) {
         let (_, max_y, min_y) = self.update_y_axis_after_moving();
         let (min_x, max_x) = self.update_x_axis_after_moving();
 
@@ -1134,17 +2494,14 @@ impl Chart {
 
         // Draw the stock name with a very large, bold font as watermark
         let _ = chart.plotting_area().draw(&Text::new(
-            format!("{}, 1D", self.stock_name), // Add timeframe like in the image
+            format!("{}, {}", self.stock_name, self.timeframe()),
             (center_x, center_y),
             ("Arial-Bold", 120).into_font().color(&watermark_color), // Slightly smaller font
         ));
     }
 
     /// Show the company information on the right-top-corner
-    fn chart_show_company_info(
-        &self,
-        chart: &mut ChartContext<BitMapBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>,
-    ) {
+    fn chart_show_company_info<DB: DrawingBackend>(&self, chart: &mut PriceChart<DB>) {
         // Define the starting position for the text
         let (_, max_y, min_y) = self.update_y_axis_after_moving();
         let (min_x, max_x) = self.update_x_axis_after_moving();
@@ -1198,86 +2555,344 @@ impl Chart {
 
     /// this function will create a vector of position for vertical line of cursor position
     fn cursor_vertical_line(&self, x_position: f32) -> Vec<(f32, f32)> {
-        let space = self.chart_data.y_offset_max - self.chart_data.y_offset_min;
+        let (y_min, y_max) = self.y_offsets_on_axis();
+        let space = y_max - y_min;
         let converted_move_y = space * 3.0 * (self.chart_data.ui_data.move_y as f32)
             / ((self.chart_data.height as f32) - (self.chart_data.range_x as f32));
         vec![
-            (
-                x_position,
-                self.chart_data.y_offset_min - space + converted_move_y,
-            ),
-            (
-                x_position,
-                self.chart_data.y_offset_max + space + converted_move_y,
-            ),
+            (x_position, y_min - space + converted_move_y),
+            (x_position, y_max + space + converted_move_y),
         ]
     }
 
+    /// Moving average of the close over `period` candles for the candles
+    /// `min_candle_x..max_candle_x`, `None` where fewer than `period` candles
+    /// precede the candle
     pub fn calculate_moving_average(
         &self,
         period: usize,
         min_candle_x: usize,
         max_candle_x: usize,
-    ) -> Vec<f32> {
+    ) -> Vec<Option<f32>> {
+        let period = period.max(1);
+        let max_candle_x = max_candle_x.min(self.candle_data.len());
+        let start = min_candle_x.saturating_sub(period - 1);
         let mut ma_values = Vec::new();
         let mut sum = 0.0;
 
-        for i in (min_candle_x - period)..max_candle_x {
+        for i in start..max_candle_x {
             sum += self.candle_data[i].close; // Add the closing price
-
-            if i - (min_candle_x - period) >= period {
+            if i >= start + period {
                 sum -= self.candle_data[i - period].close; // Remove the price outside the window
             }
-            if i - (min_candle_x - period) >= period - 1 {
-                ma_values.push(sum / period as f32); // Calculate the moving average
+            if i >= min_candle_x {
+                ma_values.push((i + 1 >= period).then(|| sum / period as f32));
             }
         }
         ma_values
     }
 
-    fn chart_draw_moving_averages(
+    /// Draw the visible overlay studies on the price scale, the other studies
+    /// are drawn in their pane by `chart_draw_panes`
+    fn chart_draw_indicators<DB: DrawingBackend>(&self, chart: &mut PriceChart<DB>) {
+        let (min_candle_x, _) = self.get_min_max_of_candle_after_moving();
+        // Up to the right edge, outputs drawn ahead of their candle go past the last one
+        let (_, max_x) = self.update_x_axis_after_moving();
+        let to = max_x.max(0.0).ceil() as usize + 1;
+
+        let scale = self.price_scale();
+        let overlays = self
+            .indicators
+            .iter()
+            .filter(|i| i.visible && i.indicator.style() == RenderStyle::Overlay);
+        for indicator in overlays {
+            for (output, color) in indicator.colors.iter().enumerate() {
+                let points = indicator.points(output, min_candle_x, to);
+                chart
+                    .draw_series(LineSeries::new(
+                        points.into_iter().map(|(x, y)| (x, scale.to_axis(y))),
+                        color.stroke_width(1),
+                    ))
+                    .expect("Error drawing indicator");
+            }
+        }
+    }
+
+    /// Draw the compared symbols as lines starting at the close of the first
+    /// candle in view, so each line shows the change of its symbol against
+    /// the chart from there on
+    fn chart_draw_comparisons<DB: DrawingBackend>(&self, chart: &mut PriceChart<DB>) {
+        let (min_candle_x, max_candle_x) = self.get_min_max_of_candle_after_moving();
+        let scale = self.price_scale();
+        for comparison in &self.comparisons {
+            let percent = self.comparison_percent(comparison, min_candle_x, max_candle_x + 1);
+            for line in compare::segments(&percent, min_candle_x) {
+                let points = line
+                    .into_iter()
+                    .map(|(x, change)| (x, scale.to_axis(scale.base * (1.0 + change / 100.0))));
+                chart
+                    .draw_series(LineSeries::new(points, comparison.color.stroke_width(2)))
+                    .expect("Error drawing comparison");
+            }
+        }
+    }
+
+    /// Draw `layer` of the sub-panes under the price pane. `bounds` are the
+    /// pixel rows of the panes from `PaneLayout::bounds`, the price pane first.
+    fn chart_draw_panes<DB: DrawingBackend>(
         &self,
-        chart: &mut ChartContext<BitMapBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>,
+        root: &DrawingArea<DB, Shift>,
+        layer: Layer,
+        bounds: &[(u32, u32)],
+        min_x: f32,
+        max_x: f32,
     ) {
+        let range_x = self.chart_data.range_x as u32;
+        let plot_height = bounds.last().map_or(0, |bound| bound.1);
+        let hovered = self
+            .layout
+            .pane_at(self.chart_data.ui_data.position_y, plot_height);
+        let (cursor_x, _) = self.get_mouse_position();
         let (min_candle_x, max_candle_x) = self.get_min_max_of_candle_after_moving();
-        // Calculate MA5, MA20, MA100
-        let ma20 = self.calculate_moving_average(20, min_candle_x, max_candle_x);
-        let ma50 = self.calculate_moving_average(50, min_candle_x, max_candle_x);
-        let ma200 = self.calculate_moving_average(200, min_candle_x, max_candle_x);
+        // Snap the crosshair to the candle under the cursor, as on the price pane
+        let cursor_x = if (min_candle_x..max_candle_x).contains(&(cursor_x.round() as usize))
+            && cursor_x >= 0.0
+        {
+            cursor_x.round()
+        } else {
+            cursor_x
+        };
+        let label_style = ShapeStyle {
+            color: RGBColor(0x3A, 0x36, 0x45).into(),
+            filled: true,
+            stroke_width: 0,
+        };
 
-        // Draw MA5
-        let ma20_points: Vec<(f32, f32)> = self
-            .candle_data
-            .iter()
-            .enumerate()
-            .enumerate()
-            .filter_map(|(i, _)| ma20.get(i).map(|&ma| ((i + min_candle_x) as f32, ma)))
-            .collect();
-        chart
-            .draw_series(LineSeries::new(ma20_points, RED.stroke_width(1)))
-            .expect("Error drawing MA20");
+        for (index, pane) in self.layout.panes.iter().enumerate() {
+            let (top, bottom) = bounds[index + 1];
+            let is_lowest = index + 1 == self.layout.panes.len();
+            let label_area = if is_lowest { range_x } else { 0 };
+            let area = root
+                .clone()
+                .shrink((0, top), (self.chart_data.width, bottom - top + label_area));
+            let cursor_idx = cursor_x
+                .round()
+                .clamp(0.0, (self.candle_data.len() - 1) as f32)
+                as usize;
+            let content = self.pane_content(pane, min_candle_x, max_candle_x, cursor_idx);
+            let (low, high) = content.value_range();
+
+            let mut chart = ChartBuilder::on(&area)
+                .x_label_area_size(label_area)
+                .right_y_label_area_size(self.chart_data.range_y)
+                .build_cartesian_2d(min_x..max_x, low..high)
+                .expect("error building pane coordinate system");
+            let right = (
+                self.chart_data.width as i32 - self.chart_data.range_y,
+                self.chart_data.width as i32,
+            );
 
-        // Draw MA20
-        let ma50_points: Vec<(f32, f32)> = self
-            .candle_data
-            .iter()
-            .enumerate()
-            .filter_map(|(i, _)| ma50.get(i).map(|&ma| ((i + min_candle_x) as f32, ma)))
-            .collect();
-        chart
-            .draw_series(LineSeries::new(ma50_points, BLUE.stroke_width(1)))
-            .expect("Error drawing MA50");
+            if layer == Layer::Base {
+                chart
+                    .configure_mesh()
+                    .disable_x_mesh()
+                    .disable_y_mesh()
+                    .x_labels(if is_lowest { 10 } else { 0 })
+                    .y_labels(3)
+                    .x_label_formatter(&|&idx| self.x_axis_label(idx))
+                    .y_label_formatter(&|&value| compact_value(value))
+                    .label_style(("Arial-Bold", 16).into_font().color(&WHITE))
+                    .axis_style(WHITE.stroke_width(1))
+                    .draw()
+                    .expect("error drawing pane mesh");
+
+                let bar_width = 0.4;
+                chart
+                    .draw_series(content.bars.iter().map(|&(x, value, color)| {
+                        Rectangle::new(
+                            [
+                                (x - bar_width, 0.0f32.clamp(low, high)),
+                                (x + bar_width, value),
+                            ],
+                            color.filled(),
+                        )
+                    }))
+                    .expect("error drawing pane bars");
+                for (color, points) in &content.lines {
+                    chart
+                        .draw_series(LineSeries::new(points.clone(), color.stroke_width(1)))
+                        .expect("error drawing pane line");
+                }
 
-        // Draw MA100
-        let ma200_points: Vec<(f32, f32)> = self
-            .candle_data
-            .iter()
-            .enumerate()
-            .filter_map(|(i, _)| ma200.get(i).map(|&ma| ((i + min_candle_x) as f32, ma)))
-            .collect();
-        chart
-            .draw_series(LineSeries::new(ma200_points, WHITE.stroke_width(1)))
-            .expect("Error drawing MA200");
+                // Latest value on the y-axis
+                if let Some((value, color)) = content.latest {
+                    let (_, y) = chart.backend_coord(&(max_x, value));
+                    let style = ShapeStyle {
+                        color: color.into(),
+                        filled: true,
+                        stroke_width: 0,
+                    };
+                    let _ = root.draw(&Rectangle::new([(right.0, y - 9), (right.1, y + 9)], style));
+                    let _ = root.draw(&Text::new(
+                        format!(" {}", compact_value(value)),
+                        (right.0, y - 7),
+                        ("Arial-Bold", 14).into_font().color(&WHITE),
+                    ));
+                }
+
+                // Separator
+                let _ = root.draw(&PathElement::new(
+                    vec![(0, top as i32), (self.chart_data.width as i32, top as i32)],
+                    RGBColor(0x3A, 0x36, 0x45).stroke_width(2),
+                ));
+                continue;
+            }
+
+            // Crosshair: the vertical line crosses every pane, the horizontal
+            // line and the value label follow the pane under the mouse
+            chart
+                .draw_series(LineSeries::new(
+                    vec![(cursor_x, low), (cursor_x, high)],
+                    GREY.stroke_width(1),
+                ))
+                .expect("error drawing pane cursor");
+            if hovered == Some(index + 1) {
+                let y = self.chart_data.ui_data.position_y;
+                let value =
+                    high - (y - top as i32) as f32 / (bottom - top).max(1) as f32 * (high - low);
+                chart
+                    .draw_series(LineSeries::new(
+                        vec![(min_x, value), (max_x, value)],
+                        GREY.stroke_width(1),
+                    ))
+                    .expect("error drawing pane cursor");
+                let _ = root.draw(&Rectangle::new(
+                    [(right.0, y - 9), (right.1, y + 9)],
+                    label_style,
+                ));
+                let _ = root.draw(&Text::new(
+                    format!(" {}", compact_value(value)),
+                    (right.0, y - 7),
+                    ("Arial-Bold", 14).into_font().color(&WHITE),
+                ));
+            }
+
+            if is_lowest {
+                let (x, _) = chart.backend_coord(&(cursor_x, low));
+                let y = bottom as i32 + 2;
+                let _ = root.draw(&Rectangle::new(
+                    [(x - 50, y), (x + 50, y + 20)],
+                    label_style,
+                ));
+                let _ = root.draw(&Text::new(
+                    self.cursor_date_label(cursor_x),
+                    (x - 45, y + 3),
+                    ("Arial-Bold", 14).into_font().color(&WHITE),
+                ));
+            }
+
+            // Legend with the values under the cursor
+            let _ = root.draw(&Text::new(
+                format!("  {}", content.legend()),
+                (0, top as i32 + 6),
+                ("sans-serif", 15).into_font().color(&content.legend_color),
+            ));
+        }
+    }
+
+    /// Bars and lines of a sub-pane for the candles `from..to`, with the
+    /// values of the candle under the cursor for the legend
+    fn pane_content(&self, pane: &Pane, from: usize, to: usize, cursor_idx: usize) -> PaneContent {
+        let mut content = PaneContent {
+            label: String::new(),
+            legend_color: WHITE,
+            lines: Vec::new(),
+            bars: Vec::new(),
+            cursor_values: Vec::new(),
+            latest: None,
+        };
+        let sign_color = |value: f32| if value >= 0.0 { UP_COLOR } else { DOWN_COLOR };
+
+        match &pane.kind {
+            PaneKind::Volume => {
+                content.label = "Volume".to_string();
+                content.bars = self.candle_data[from..to]
+                    .iter()
+                    .map(|candle| {
+                        let color = if candle.open < candle.close {
+                            UP_COLOR
+                        } else {
+                            DOWN_COLOR
+                        };
+                        (candle.num, candle.volume, color)
+                    })
+                    .collect();
+                content.cursor_values = vec![self.candle_data[cursor_idx].volume];
+                content.latest = self
+                    .candle_data
+                    .last()
+                    .map(|candle| (candle.volume, RGBColor(0x23, 0xBA, 0x75)));
+            }
+            PaneKind::Indicator(index) => {
+                let Some(instance) = self.indicators.get(*index).filter(|i| i.visible) else {
+                    return content;
+                };
+                content.label = instance.indicator.label();
+                content.legend_color = instance.colors.first().copied().unwrap_or(WHITE);
+                let outputs = instance.indicator.outputs();
+                for (output, color) in instance.colors.iter().enumerate() {
+                    let points = instance.points(output, from, to);
+                    // MACD style histograms are drawn as bars around zero
+                    if outputs.get(output).is_some_and(|o| o.name == "histogram") {
+                        content.bars.extend(
+                            points
+                                .iter()
+                                .map(|&(x, value)| (x, value, sign_color(value))),
+                        );
+                    } else {
+                        content.lines.push((*color, points));
+                    }
+                    content
+                        .cursor_values
+                        .extend(instance.value(cursor_idx, output));
+                }
+            }
+            PaneKind::Series(name) => {
+                content.label = name.clone();
+                let values = self.series_by_candle(name);
+                content.bars = (from..to)
+                    .filter_map(|i| Some((i as f32, values[i]?, sign_color(values[i]?))))
+                    .collect();
+                content.latest = values
+                    .iter()
+                    .rev()
+                    .find_map(|value| *value)
+                    .map(|value| (value, sign_color(value)));
+                content.cursor_values = values[cursor_idx].into_iter().collect();
+            }
+        }
+        content
+    }
+
+    /// Values of the series `name` summed per candle, `None` for candles
+    /// without any
+    fn series_by_candle(&self, name: &str) -> Vec<Option<f32>> {
+        let mut by_candle = vec![None; self.candle_data.len()];
+        let Some(values) = self.series.get(name) else {
+            return by_candle;
+        };
+        let bar = self.timeframe().bar_duration();
+        for &(time, value) in values {
+            let index = self
+                .candle_data
+                .partition_point(|candle| candle.time <= time);
+            if index == 0 || time >= self.candle_data[index - 1].time + bar {
+                continue;
+            }
+            let total: &mut Option<f32> = &mut by_candle[index - 1];
+            *total = Some(total.unwrap_or(0.0) + value);
+        }
+        by_candle
     }
 
     fn cursor_horizontal_line(&self, y_position: f32) -> Vec<(f32, f32)> {
@@ -1302,23 +2917,24 @@ impl Chart {
                 - (self.chart_data.x_offset_min - zoom as f32))
     }
 
+    /// Size of the view, top and bottom of the plot, on the price axis
     fn update_y_axis_after_moving(&self) -> (f32, f32, f32) {
         let zoom_ratio = (100.0 + self.chart_data.zoom_y) / 100.0;
+        let (y_min, y_max) = self.y_offsets_on_axis();
 
         // Calculate the real size of the y-axis based on the zoom ratio
-        let real_size =
-            3.0 * (self.chart_data.y_offset_max - self.chart_data.y_offset_min) * zoom_ratio;
+        let real_size = 3.0 * (y_max - y_min) * zoom_ratio;
 
         // Calculate the difference between the real size and the current size
-        let difference = real_size - (self.chart_data.y_offset_max - self.chart_data.y_offset_min);
+        let difference = real_size - (y_max - y_min);
 
         // Calculate y_moving based on move_y
         let y_moving = real_size * (self.chart_data.ui_data.move_y as f32)
             / ((self.chart_data.height as i32) - self.chart_data.range_x) as f32;
 
         // Calculate new min and max values for y-axis
-        let max = self.chart_data.y_offset_max + difference / 2.0 + y_moving;
-        let min = self.chart_data.y_offset_min - difference / 2.0 + y_moving;
+        let max = y_max + difference / 2.0 + y_moving;
+        let min = y_min - difference / 2.0 + y_moving;
 
         (real_size / 3.0, max, min)
     }
@@ -1462,6 +3078,66 @@ impl Chart {
     }
 }
 
+/// What a sub-pane draws, in candle index space
+struct PaneContent {
+    label: String,
+    legend_color: RGBColor,
+    lines: Vec<(RGBColor, Vec<(f32, f32)>)>,
+    /// `(candle index, value, color)`, bars start at zero
+    bars: Vec<(f32, f32, RGBColor)>,
+    /// Values at the candle under the cursor, for the legend
+    cursor_values: Vec<f32>,
+    /// Last value and the color of its y-axis label
+    latest: Option<(f32, RGBColor)>,
+}
+
+impl PaneContent {
+    /// y range fitting the visible values with a margin, bars include zero
+    fn value_range(&self) -> (f32, f32) {
+        let (mut low, mut high) = self
+            .lines
+            .iter()
+            .flat_map(|(_, points)| points.iter().map(|point| point.1))
+            .chain(self.bars.iter().flat_map(|bar| [bar.1, 0.0]))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), value| {
+                (low.min(value), high.max(value))
+            });
+        if low > high {
+            (low, high) = (0.0, 1.0);
+        }
+        let margin = ((high - low) * 0.1).max(f32::EPSILON.max(high.abs() * 0.01));
+        (low - margin, high + margin)
+    }
+
+    fn legend(&self) -> String {
+        let values: Vec<String> = self
+            .cursor_values
+            .iter()
+            .map(|&value| compact_value(value))
+            .collect();
+        format!("{}  {}", self.label, values.join("  "))
+    }
+}
+
+/// Short label of a pane value: 1.25M, 350.00K, 55.20
+fn compact_value(value: f32) -> String {
+    let size = value.abs();
+    if size >= 1e9 {
+        format!("{:.2}B", value / 1e9)
+    } else if size >= 1e6 {
+        format!("{:.2}M", value / 1e6)
+    } else if size >= 1e4 {
+        format!("{:.2}K", value / 1e3)
+    } else {
+        format!("{value:.2}")
+    }
+}
+
+/// Vietnam time, used to label intraday candles and to tell trading days
+pub fn vn_offset() -> FixedOffset {
+    FixedOffset::east_opt(7 * 3600).expect("valid offset")
+}
+
 /// Interpolates points so that the distance between consecutive points is at most delta_x or delta_y.
 pub fn interpolate_min_distance(points: &[Point], delta_x: f32, delta_y: f32) -> Vec<Point> {
     if points.is_empty() {
@@ -1500,3 +3176,457 @@ pub fn interpolate_min_distance(points: &[Point], delta_x: f32, delta_y: f32) ->
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use aim_data::Candlestick;
+    use plotters::backend::SVGBackend;
+
+    fn chart(closes: &[f64]) -> Chart {
+        Chart::new_default(
+            "VNINDEX".to_string(),
+            "1D".to_string(),
+            OHLCData::from_candlesticks("VNINDEX", &test_support::bars(closes)),
+            CompanyInfo::default(),
+        )
+    }
+
+    #[test]
+    fn test_moving_average_near_first_candle() {
+        let chart = chart(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(
+            chart.calculate_moving_average(3, 0, 5),
+            vec![None, None, Some(2.0), Some(3.0), Some(4.0)]
+        );
+        assert_eq!(
+            chart.calculate_moving_average(3, 3, 10),
+            vec![Some(3.0), Some(4.0)]
+        );
+    }
+
+    #[test]
+    fn test_panes_share_the_image_height() {
+        let closes: Vec<f64> = (0..60).map(|i| 100.0 + (i % 7) as f64).collect();
+        let mut chart = chart(&closes);
+        chart.add_indicator(Box::new(crate::indicator::Rsi::new(14)));
+        chart.set_series("foreign", vec![(chart.candle_data[59].time, -5.0)]);
+        chart.layout.add(PaneKind::Series("foreign".to_string()));
+
+        let ui_data = UiData {
+            width: 800,
+            height: 600,
+            position_x: 400,
+            position_y: 550,
+            is_release: true,
+            ..UiData::default()
+        };
+        chart.render_plot(ui_data);
+        let bounds = chart.layout.bounds(600 - chart.chart_data.range_x as u32);
+        assert_eq!(bounds.len(), 4);
+        assert_eq!(
+            chart.chart_data.height,
+            bounds[0].1 + chart.chart_data.range_x as u32
+        );
+        assert_eq!(chart.series_by_candle("foreign")[59], Some(-5.0));
+    }
+
+    /// Chart of closes from 10 to 39 viewing prices 10 to 40, with the mouse
+    /// halfway up the price pane
+    fn scaled_chart(mode: ScaleMode) -> Chart {
+        let closes: Vec<f64> = (0..30).map(|i| 10.0 + i as f64).collect();
+        let mut chart = chart(&closes);
+        chart.scale_mode = mode;
+        chart.chart_data.y_offset_min = 10.0;
+        chart.chart_data.y_offset_max = 40.0;
+        chart.chart_data.ui_data.position_y =
+            (chart.chart_data.height as i32 - chart.chart_data.range_x) / 2;
+        chart
+    }
+
+    #[test]
+    fn test_cursor_price_on_each_scale() {
+        let price_at_mouse = |chart: &Chart| {
+            let (_, y) = chart.get_mouse_position();
+            chart.price_scale().to_price(y)
+        };
+        let linear = scaled_chart(ScaleMode::Linear);
+        assert!((price_at_mouse(&linear) - 25.0).abs() < 1e-3);
+        // Halfway between 10 and 40 on a log scale is their geometric mean
+        let log = scaled_chart(ScaleMode::Log);
+        assert!((price_at_mouse(&log) - 20.0).abs() < 1e-3);
+        assert_eq!(log.price_scale().label(log.get_mouse_position().1), "20.00");
+        // The percent scale starts at the close of the first candle, 10
+        let percent = scaled_chart(ScaleMode::Percent);
+        let (_, y) = percent.get_mouse_position();
+        assert_eq!(percent.price_scale().label(y), "+150.00%");
+    }
+
+    #[test]
+    fn test_drawings_are_hit_on_the_log_scale() {
+        let hit_at_mouse = |mode| {
+            let mut chart = scaled_chart(mode);
+            let line = DrawObject::new(DrawType::HorizontalLine(20.0), WHITE);
+            chart.all_draw_data.push(line);
+            let ui_data = UiData {
+                width: 800,
+                height: chart.chart_data.height as i32,
+                position_x: 400,
+                position_y: chart.chart_data.ui_data.position_y,
+                is_release: true,
+                ..UiData::default()
+            };
+            chart.render_plot(ui_data);
+            chart.is_in_object.1
+        };
+        assert!(hit_at_mouse(ScaleMode::Log));
+        assert!(!hit_at_mouse(ScaleMode::Linear));
+    }
+
+    #[test]
+    fn test_drawings_keep_their_time_across_styles() {
+        let closes: Vec<f64> = (0..60).map(|i| 100.0 + i as f64).collect();
+        let mut chart = chart(&closes);
+        let last = chart.candle_data[59].time;
+        chart.render_cell(800, 600, None);
+        chart
+            .all_draw_data
+            .push(DrawObject::new(DrawType::VerticalLine(50.0), WHITE));
+        let restyle = |chart: &mut Chart, style: ChartStyle| {
+            let ui_data = UiData {
+                width: 800,
+                height: 600,
+                is_release: true,
+                chart_style: style as i32,
+                box_size: 5.0,
+                is_new_chart_style: true,
+                ..UiData::default()
+            };
+            chart.render_plot(ui_data);
+            match chart.all_draw_data[0].draw_type {
+                DrawType::VerticalLine(x) => x,
+                _ => unreachable!(),
+            }
+        };
+
+        // A brick every 5 candles, the one of candle 50 is the tenth
+        assert!((restyle(&mut chart, ChartStyle::Renko) - 9.0).abs() < 1e-3);
+        assert_eq!(chart.candle_data.len(), 11);
+        assert_eq!(chart.next_fetch_start(), Some(last));
+
+        let (read, _) = Chart::read_from_bytes(&chart.to_bytes()).unwrap();
+        assert_eq!(read.style, ChartStyle::Renko);
+        assert_eq!(read.candle_data.len(), 11);
+        assert_eq!(read.source_data.len(), 60);
+
+        assert!((restyle(&mut chart, ChartStyle::Bars) - 50.0).abs() < 1e-3);
+        assert_eq!(chart.candle_data.len(), 60);
+    }
+
+    #[test]
+    fn test_older_pages_keep_whole_buckets() {
+        // Daily bars of the weekdays in `days`, closing at their day
+        let weekdays = |days: std::ops::Range<i64>| {
+            let bars: Vec<Candlestick> = days
+                .filter(|day| day % 7 < 5)
+                .map(|day| test_support::bar(day, day as f64))
+                .collect();
+            OHLCData::from_candlesticks("VNINDEX", &bars)
+        };
+        let mut chart = Chart::new_default(
+            "VNINDEX".to_string(),
+            "1W".to_string(),
+            weekdays(21..49),
+            CompanyInfo::default(),
+        );
+        chart.chart_data.x_offset_min = 0.0;
+        assert_eq!(chart.candle_data.len(), 4);
+        let (start, _) = chart.older_history_range().unwrap();
+
+        // A page within the week before: nothing is added, the next page is wider
+        chart.prepend_candle_data(weekdays(16..21));
+        assert_eq!(chart.candle_data.len(), 4);
+        assert!(!chart.history_exhausted);
+        let (wider, _) = chart.older_history_range().unwrap();
+        assert!(wider < start);
+
+        // The wider page fills that week from its Monday
+        chart.prepend_candle_data(weekdays(0..21));
+        assert_eq!(chart.candle_data.len(), 6);
+        assert_eq!(chart.candle_data[0].open, 7.0);
+        chart.chart_data.x_offset_min = 0.0;
+        assert_eq!(
+            chart.older_history_range().unwrap().0,
+            start - Duration::weeks(2)
+        );
+
+        // The first week is found again by a wider page, so it starts the history
+        chart.prepend_candle_data(weekdays(0..7));
+        assert_eq!(chart.candle_data.len(), 6);
+        chart.prepend_candle_data(weekdays(0..7));
+        assert_eq!(chart.candle_data.len(), 7);
+        assert_eq!(chart.candle_data[0].open, 0.0);
+        assert!(chart.history_exhausted);
+    }
+
+    #[test]
+    fn test_undo_ends_the_edit_of_a_note() {
+        let mut chart = chart(&[1.0, 2.0, 3.0]);
+        let note = |text: &str| {
+            let note = TextNote::new((1.0, 2.0), text.to_string(), DEFAULT_FONT_SIZE, None);
+            DrawObject::new(DrawType::Text(note), WHITE)
+        };
+        chart.history.apply(
+            Edit::Insert(vec![(0, note("A")), (1, note("B"))]),
+            &mut chart.all_draw_data,
+        );
+        chart
+            .history
+            .apply(Edit::Remove(vec![(0, note("A"))]), &mut chart.all_draw_data);
+        // B moved to the index of A, which the undo puts back
+        chart.text_edit = Some(0);
+        assert!(chart.undo());
+        assert_eq!(chart.text_edit, None);
+        assert!(!chart.edit_text("C", DEFAULT_FONT_SIZE, None));
+        assert_eq!(chart.all_draw_data, vec![note("A"), note("B")]);
+    }
+
+    #[test]
+    fn test_crosshair_moves_keep_the_base_layer() {
+        let closes: Vec<f64> = (0..60).map(|i| 100.0 + (i % 7) as f64).collect();
+        let mut chart = chart(&closes);
+        chart.add_indicator(Box::new(crate::indicator::Rsi::new(14)));
+        chart
+            .all_draw_data
+            .push(DrawObject::new(DrawType::VerticalLine(30.0), WHITE));
+        let hover = |x: i32, y: i32| UiData {
+            width: 800,
+            height: 600,
+            position_x: x,
+            press_x: x,
+            position_y: y,
+            press_y: y,
+            is_release: true,
+            ..UiData::default()
+        };
+        chart.render_frame(hover(100, 100)).unwrap();
+        assert_eq!(chart.layers.redraws, 1);
+        let moved = chart.render_frame(hover(400, 500)).unwrap();
+        assert_eq!(chart.layers.redraws, 1);
+
+        // The frame is the one a full repaint gives
+        chart.layers.invalidate();
+        let repainted = chart.render_frame(hover(400, 500)).unwrap();
+        assert_eq!(chart.layers.redraws, 2);
+        assert!(moved.as_bytes() == repainted.as_bytes());
+
+        // Panning, edits of the drawings and new data draw it again
+        let pan = UiData {
+            move_x: 40,
+            is_release: false,
+            ..hover(400, 100)
+        };
+        chart.render_frame(pan).unwrap();
+        assert_eq!(chart.layers.redraws, 3);
+        let clean = UiData {
+            is_clean: true,
+            ..hover(400, 100)
+        };
+        chart.render_frame(clean).unwrap();
+        assert_eq!(chart.layers.redraws, 4);
+        assert!(chart.all_draw_data.is_empty());
+        chart.render_frame(hover(400, 100)).unwrap();
+        assert_eq!(chart.layers.redraws, 4);
+        assert!(chart.undo());
+        chart.render_frame(hover(400, 100)).unwrap();
+        assert_eq!(chart.layers.redraws, 5);
+        chart.set_series("foreign", vec![(chart.candle_data[59].time, 1.0)]);
+        chart.render_frame(hover(400, 100)).unwrap();
+        assert_eq!(chart.layers.redraws, 6);
+    }
+
+    #[test]
+    fn test_headless_render_is_the_frame_of_a_cell() {
+        let closes: Vec<f64> = (0..60).map(|i| 100.0 + (i % 7) as f64).collect();
+        let mut chart = chart(&closes);
+        chart.fit_view();
+        let mut pixels = vec![0; 800 * 600 * 3];
+        let backend = BitMapBackend::with_buffer(&mut pixels, (800, 600));
+        assert!(chart.render_to(backend).unwrap());
+        assert_eq!(chart.chart_data.x_offset_min, 0.0);
+        assert_eq!(chart.chart_data.x_offset_max, 59.0);
+        let cell = chart.render_frame(chart.cell_ui_data(800, 600, None));
+        assert!(cell.unwrap().as_bytes() == pixels.as_slice());
+
+        let mut svg = String::new();
+        let backend = SVGBackend::with_string(&mut svg, (800, 600));
+        assert!(chart.render_to(backend).unwrap());
+        assert!(svg.starts_with("<svg") && svg.contains("VNINDEX, 1D"));
+    }
+
+    #[test]
+    fn test_linked_crosshair_lands_on_the_candle_of_the_time() {
+        let closes: Vec<f64> = (0..60).map(|i| 100.0 + i as f64).collect();
+        let mut chart = chart(&closes);
+        let time = chart.candle_data[50].time;
+        chart.render_cell(800, 600, Some(time));
+        assert_eq!(chart.cursor_time(), Some(time));
+
+        // Without a linked time the mouse is right of the last candle
+        chart.render_cell(800, 600, None);
+        assert!(chart.cursor_time().unwrap() > chart.candle_data[59].time);
+        assert!(!chart.is_in_object.1);
+    }
+
+    #[test]
+    fn test_comparison_is_saved_and_rebased_on_the_first_candle_in_view() {
+        let closes: Vec<f64> = (0..60).map(|i| 100.0 + i as f64).collect();
+        let mut chart = chart(&closes);
+        assert!(chart.add_comparison("vn30", ""));
+        assert!(!chart.add_comparison("VN30", "VN30"));
+        assert!(!chart.add_comparison("vnindex", ""));
+
+        // The index doubles over the chart and has no bar on day 40
+        let bars: Vec<Candlestick> = (0..60)
+            .filter(|&i| i != 40)
+            .map(|i| Candlestick {
+                timestamp: chart.candle_data[i].time,
+                open: 0.0,
+                high: 0.0,
+                low: 0.0,
+                close: 1000.0 + 1000.0 * i as f64 / 59.0,
+                volume: 0,
+            })
+            .collect();
+        chart.set_comparison("VN30", OHLCData::from_candlesticks("VN30", &bars));
+        let percent = chart.comparison_percent(&chart.comparisons[0], 30, 60);
+        assert_eq!(percent[0], Some(0.0));
+        assert_eq!(percent[10], None);
+        assert!(percent[29].unwrap() > 0.0);
+        chart.render_cell(800, 600, None);
+
+        let (read, _) = Chart::read_from_bytes(&chart.to_bytes()).unwrap();
+        assert_eq!(read.comparisons, chart.comparisons);
+        assert!(chart.remove_comparison("vn30"));
+        assert!(chart.comparisons.is_empty());
+    }
+
+    /// A drawing as caches before version 7 wrote it, at (candle index, price)
+    fn write_indexed_drawing(buf: &mut Vec<u8>, object: &DrawObject, styled: bool) {
+        match &object.draw_type {
+            DrawType::Line(((x1, y1), (x2, y2))) => {
+                buf.push(1);
+                for value in [x1, y1, x2, y2] {
+                    buf.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            DrawType::VerticalLine(x) => {
+                buf.push(6);
+                buf.extend_from_slice(&x.to_le_bytes());
+            }
+            DrawType::Empty => buf.push(0),
+            _ => unimplemented!(),
+        }
+        buf.extend_from_slice(&[object.color.0, object.color.1, object.color.2]);
+        if styled {
+            buf.push(object.line_style as u8);
+            buf.extend_from_slice(&object.line_width.to_le_bytes());
+        }
+    }
+
+    /// `chart` as cache `version` 1 or 6 wrote it, version 6 with the insert
+    /// of the first drawing to undo
+    fn legacy_bytes(chart: &Chart, version: u32) -> Vec<u8> {
+        let styled = version >= 5;
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(chart.stock_name.len() as u32).to_le_bytes());
+        buf.extend_from_slice(chart.stock_name.as_bytes());
+        if version >= 2 {
+            buf.extend_from_slice(&(chart.time_frame.len() as u32).to_le_bytes());
+            buf.extend_from_slice(chart.time_frame.as_bytes());
+        }
+        buf.extend_from_slice(&[0; 40]);
+        buf.extend_from_slice(&(chart.candle_data.len() as u32).to_le_bytes());
+        for candle in &chart.candle_data {
+            candle.write_to_bytes(&mut buf);
+        }
+        chart.chart_data.write_to_bytes(&mut buf);
+        write_indexed_drawing(&mut buf, &chart.current_draw_data, styled);
+        if version < 5 {
+            // moving_object
+            write_indexed_drawing(&mut buf, &DrawObject::default(), styled);
+        }
+        buf.extend_from_slice(&(chart.all_draw_data.len() as u32).to_le_bytes());
+        for object in &chart.all_draw_data {
+            write_indexed_drawing(&mut buf, object, styled);
+        }
+        // backup_points, backup_chart_offset, is_in_object and delta
+        buf.extend_from_slice(&[0; 4 + 1 + 10 + 8]);
+        if version >= 3 {
+            buf.extend_from_slice(&(chart.indicators.len() as u32).to_le_bytes());
+            for indicator in &chart.indicators {
+                indicator.write_to_bytes(&mut buf);
+            }
+        }
+        if version >= 4 {
+            chart.layout.write_to_bytes(&mut buf);
+        }
+        if version >= 6 {
+            buf.push(1);
+            buf.extend_from_slice(&100u32.to_le_bytes());
+            buf.extend_from_slice(&1u32.to_le_bytes());
+            buf.push(0);
+            buf.extend_from_slice(&1u32.to_le_bytes());
+            buf.extend_from_slice(&0u32.to_le_bytes());
+            write_indexed_drawing(&mut buf, &chart.all_draw_data[0], styled);
+            buf.extend_from_slice(&0u32.to_le_bytes());
+        }
+        buf
+    }
+
+    #[test]
+    fn test_older_caches_keep_their_drawings() {
+        let closes: Vec<f64> = (0..30).map(|i| 100.0 + i as f64).collect();
+        let mut chart = chart(&closes);
+        chart.add_indicator(Box::new(crate::indicator::Rsi::new(14)));
+        chart.all_draw_data = vec![
+            DrawObject {
+                line_style: LineStyle::Dashed,
+                line_width: 3,
+                ..DrawObject::new(DrawType::Line(((5.0, 105.0), (12.5, 110.0))), BLUE)
+            },
+            DrawObject::new(DrawType::VerticalLine(20.0), RED),
+        ];
+
+        // The first format has no time frame, style or studies
+        let bytes = legacy_bytes(&chart, 1);
+        let (read, used) = Chart::read_version_from_bytes(&bytes, 1).unwrap();
+        assert_eq!(used, bytes.len());
+        assert_eq!(read.time_frame, "1D");
+        assert_eq!(read.candle_data.len(), 30);
+        assert_eq!(read.indicators.len(), Chart::default_indicators().len());
+        assert_eq!(read.layout.panes.len(), 1);
+        assert_eq!(
+            read.all_draw_data[0].draw_type,
+            chart.all_draw_data[0].draw_type
+        );
+        assert_eq!(read.all_draw_data[0].line_style, LineStyle::Solid);
+        assert_eq!(read.all_draw_data[1], chart.all_draw_data[1]);
+
+        // Saved again as time anchors on the same candles
+        let (saved, _) = Chart::read_from_bytes(&read.to_bytes()).unwrap();
+        assert_eq!(saved.all_draw_data, read.all_draw_data);
+
+        let bytes = legacy_bytes(&chart, 6);
+        let (read, used) = Chart::read_version_from_bytes(&bytes, 6).unwrap();
+        assert_eq!(used, bytes.len());
+        assert_eq!(read.all_draw_data, chart.all_draw_data);
+        assert_eq!(read.indicators.len(), chart.indicators.len());
+        assert_eq!(read.layout.panes, chart.layout.panes);
+        assert!(read.history.can_undo());
+        let (saved, _) = Chart::read_from_bytes(&read.to_bytes()).unwrap();
+        assert_eq!(saved.all_draw_data, chart.all_draw_data);
+        assert_eq!(saved.history.undo, read.history.undo);
+    }
+}
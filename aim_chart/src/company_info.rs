@@ -1,3 +1,5 @@
+use aim_data::explorer::vci::company_info::CompanyFinancialRatio;
+
 #[derive(Debug, Clone, Default)]
 pub struct CompanyInfo {
     pub roe: f64,
@@ -6,3 +8,15 @@ pub struct CompanyInfo {
     pub pb: f64,
     pub eps: f64,
 }
+
+impl From<&CompanyFinancialRatio> for CompanyInfo {
+    fn from(ratio: &CompanyFinancialRatio) -> Self {
+        Self {
+            roe: ratio.roe.unwrap_or(0.0),
+            roa: ratio.roa.unwrap_or(0.0),
+            pe: ratio.pe.unwrap_or(0.0),
+            pb: ratio.pb.unwrap_or(0.0),
+            eps: ratio.eps.unwrap_or(0.0),
+        }
+    }
+}
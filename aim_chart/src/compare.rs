@@ -0,0 +1,125 @@
+//! Symbols compared with the chart
+//!
+//! Other symbols or indices (VNINDEX, VN30, a sector index) are drawn over
+//! the price pane as lines of their change in percent from the first candle
+//! in view. The closes of a comparison are matched to the candles of the
+//! chart by time, a candle without a bar of the symbol, e.g. a day it was
+//! halted, leaves a gap in its line.
+
+use chrono::{DateTime, Utc};
+use plotters::style::RGBColor;
+
+/// Colors given to the comparisons in the order they are added
+pub const COLORS: [RGBColor; 5] = [
+    RGBColor(0xFF, 0xA7, 0x26),
+    RGBColor(0x26, 0xC6, 0xDA),
+    RGBColor(0xEC, 0x40, 0x7A),
+    RGBColor(0x9C, 0xCC, 0x65),
+    RGBColor(0xAB, 0x47, 0xBC),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// Upper-case symbol, as asked from the quote provider
+    pub symbol: String,
+    /// Name shown in the legend, e.g. of a sector index
+    pub label: String,
+    pub color: RGBColor,
+    /// Close of each bar by its start time, in time order
+    pub closes: Vec<(DateTime<Utc>, f32)>,
+}
+
+impl Comparison {
+    /// Comparison with `symbol`, shown as `label` or the symbol if it is empty
+    pub fn new(symbol: &str, label: &str, color: RGBColor) -> Self {
+        let symbol = symbol.to_uppercase();
+        Self {
+            label: if label.is_empty() {
+                symbol.clone()
+            } else {
+                label.to_string()
+            },
+            symbol,
+            color,
+            closes: Vec::new(),
+        }
+    }
+
+    pub fn set_closes(&mut self, mut closes: Vec<(DateTime<Utc>, f32)>) {
+        closes.sort_by_key(|(time, _)| *time);
+        closes.dedup_by_key(|(time, _)| *time);
+        self.closes = closes;
+    }
+
+    /// Close of the bar starting at each of `times`, `None` where the symbol
+    /// has no such bar
+    pub fn aligned(&self, times: impl IntoIterator<Item = DateTime<Utc>>) -> Vec<Option<f32>> {
+        times
+            .into_iter()
+            .map(|time| {
+                self.closes
+                    .binary_search_by_key(&time, |(bar, _)| *bar)
+                    .ok()
+                    .map(|index| self.closes[index].1)
+            })
+            .collect()
+    }
+}
+
+/// Change in percent of `closes` from the first of them with a value
+pub fn rebased(closes: &[Option<f32>]) -> Vec<Option<f32>> {
+    let Some(base) = closes.iter().flatten().copied().find(|&close| close > 0.0) else {
+        return vec![None; closes.len()];
+    };
+    closes
+        .iter()
+        .map(|close| close.map(|close| (close / base - 1.0) * 100.0))
+        .collect()
+}
+
+/// Runs of consecutive values as (index, value) lines, a gap ends a line
+pub fn segments(values: &[Option<f32>], first: usize) -> Vec<Vec<(f32, f32)>> {
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+    for (index, value) in values.iter().enumerate() {
+        match value {
+            Some(value) => line.push(((first + index) as f32, *value)),
+            None if !line.is_empty() => lines.push(std::mem::take(&mut line)),
+            None => {}
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::day;
+
+    #[test]
+    fn test_halted_days_leave_gaps() {
+        let mut comparison = Comparison::new("vn30", "", COLORS[0]);
+        comparison.set_closes(vec![(day(3), 13.0), (day(0), 10.0), (day(1), 11.0)]);
+        assert_eq!(comparison.label, "VN30");
+        let aligned = comparison.aligned((0..4).map(day));
+        assert_eq!(aligned, vec![Some(10.0), Some(11.0), None, Some(13.0)]);
+
+        let lines = segments(&rebased(&aligned), 5);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 2);
+        assert_eq!(lines[1][0].0, 8.0);
+        assert!((lines[1][0].1 - 30.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_rebased_from_the_first_bar_with_a_close() {
+        let percent = rebased(&[None, Some(20.0), Some(25.0)]);
+        assert_eq!(percent[0], None);
+        assert_eq!(percent[1], Some(0.0));
+        assert_eq!(percent[2], Some(25.0));
+        assert_eq!(rebased(&[None, None]), vec![None, None]);
+    }
+}
@@ -1,5 +1,19 @@
 use plotters::style::RGBColor;
 
+use crate::{chart::THREAD_HOLD, time_scale::TimeScale};
+
+/// Space in pixels between the text of a note and the edge of its box
+pub(crate) const TEXT_PADDING: u32 = 4;
+/// Estimated width of a character and height of a line, in font sizes
+const CHAR_WIDTH: f32 = 0.6;
+const LINE_HEIGHT: f32 = 1.2;
+pub const DEFAULT_FONT_SIZE: u32 = 14;
+
+/// Ratios of the Fibonacci retracement levels, 0 at the second point
+pub const FIB_RETRACEMENT_LEVELS: [f32; 7] = [0.0, 0.236, 0.382, 0.5, 0.618, 0.786, 1.0];
+/// Ratios of the Fibonacci extension levels, in moves of the first leg
+pub const FIB_EXTENSION_LEVELS: [f32; 6] = [0.0, 0.382, 0.618, 1.0, 1.618, 2.618];
+
 pub type Point = (f32, f32);
 #[derive(Debug, Clone, PartialEq, Default)]
 pub enum DrawType {
@@ -11,57 +25,251 @@ pub enum DrawType {
     VerticalLine(f32),
     HorizontalLine(f32),
     Pen(Vec<Point>),
+    Text(TextNote),
+    /// From the start to the end of the move, drawn as the Fibonacci levels
+    /// between both prices
+    FibRetracement((Point, Point)),
+    /// A move, its pullback and the levels projected from the pullback
+    FibExtension((Point, Point, Point)),
+    /// A trend line and the parallel going through the third point
+    Channel((Point, Point, Point)),
+    /// Andrews' pitchfork: the pivot then the two reaction points
+    Pitchfork((Point, Point, Point)),
+    /// Price move between two points, with its change in percent
+    PriceRange((Point, Point)),
+    /// Candles and days between two points
+    DateRange((Point, Point)),
     #[default]
     Empty,
 }
 
-#[derive(Debug, Clone, Default)]
+impl DrawType {
+    /// Drawings put down with a drag then a click for their last point
+    pub fn is_three_point(&self) -> bool {
+        matches!(
+            self,
+            DrawType::FibExtension(_) | DrawType::Channel(_) | DrawType::Pitchfork(_)
+        )
+    }
+
+    /// Move the last point of a three-point drawing
+    pub fn set_last_point(&mut self, point: Point) {
+        if let DrawType::FibExtension((_, _, last))
+        | DrawType::Channel((_, _, last))
+        | DrawType::Pitchfork((_, _, last)) = self
+        {
+            *last = point;
+        }
+    }
+
+    /// `(ratio, price)` of the levels of a Fibonacci tool
+    pub fn fib_levels(&self) -> Vec<(f32, f32)> {
+        match self {
+            DrawType::FibRetracement((start, end)) => FIB_RETRACEMENT_LEVELS
+                .iter()
+                .map(|&ratio| (ratio, end.1 + (start.1 - end.1) * ratio))
+                .collect(),
+            DrawType::FibExtension((start, end, pullback)) => FIB_EXTENSION_LEVELS
+                .iter()
+                .map(|&ratio| (ratio, pullback.1 + (end.1 - start.1) * ratio))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Straight lines the tools made of lines are drawn with, also used to
+    /// hit-test and clip them
+    pub fn segments(&self) -> Vec<(Point, Point)> {
+        match self {
+            DrawType::FibRetracement((start, end)) => {
+                let (left, right) = (start.0.min(end.0), start.0.max(end.0));
+                self.fib_levels()
+                    .into_iter()
+                    .map(|(_, price)| ((left, price), (right, price)))
+                    .collect()
+            }
+            DrawType::FibExtension((start, end, pullback)) => {
+                // The levels span the width of the first leg from the pullback
+                let right = pullback.0 + (end.0 - start.0).abs().max(1.0);
+                let mut segments = vec![(*start, *end), (*end, *pullback)];
+                segments.extend(
+                    self.fib_levels()
+                        .into_iter()
+                        .map(|(_, price)| ((pullback.0, price), (right, price))),
+                );
+                segments
+            }
+            DrawType::Channel((start, end, through)) => {
+                // A vertical trend line gets a side offset instead
+                let offset = if start.0 == end.0 {
+                    (through.0 - start.0, 0.0)
+                } else {
+                    let slope = (end.1 - start.1) / (end.0 - start.0);
+                    (0.0, through.1 - (start.1 + slope * (through.0 - start.0)))
+                };
+                let shift = |p: &Point, k: f32| (p.0 + offset.0 * k, p.1 + offset.1 * k);
+                vec![
+                    (*start, *end),
+                    (shift(start, 1.0), shift(end, 1.0)),
+                    (shift(start, 0.5), shift(end, 0.5)),
+                ]
+            }
+            DrawType::Pitchfork((pivot, upper, lower)) => {
+                // Every tine runs as far again as the pivot is from the middle
+                let middle = ((upper.0 + lower.0) / 2.0, (upper.1 + lower.1) / 2.0);
+                let handle = (middle.0 - pivot.0, middle.1 - pivot.1);
+                let tine = |p: &Point| (*p, (p.0 + handle.0, p.1 + handle.1));
+                vec![
+                    (*pivot, tine(&middle).1),
+                    tine(upper),
+                    tine(lower),
+                    (*upper, *lower),
+                ]
+            }
+            DrawType::PriceRange((start, end)) | DrawType::DateRange((start, end)) => {
+                let corners = [*start, (end.0, start.1), *end, (start.0, end.1)];
+                let mut segments: Vec<_> =
+                    (0..4).map(|i| (corners[i], corners[(i + 1) % 4])).collect();
+                let middle = ((start.0 + end.0) / 2.0, (start.1 + end.1) / 2.0);
+                segments.push(match self {
+                    DrawType::PriceRange(_) => ((middle.0, start.1), (middle.0, end.1)),
+                    _ => ((start.0, middle.1), (end.0, middle.1)),
+                });
+                segments
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Points the tools made of lines are defined by, in drawing order
+    pub fn points(&self) -> Vec<Point> {
+        match self {
+            DrawType::FibRetracement((a, b))
+            | DrawType::PriceRange((a, b))
+            | DrawType::DateRange((a, b)) => vec![*a, *b],
+            DrawType::FibExtension((a, b, c))
+            | DrawType::Channel((a, b, c))
+            | DrawType::Pitchfork((a, b, c)) => vec![*a, *b, *c],
+            _ => Vec::new(),
+        }
+    }
+
+    fn points_mut(&mut self) -> Vec<&mut Point> {
+        match self {
+            DrawType::FibRetracement((a, b))
+            | DrawType::PriceRange((a, b))
+            | DrawType::DateRange((a, b)) => vec![a, b],
+            DrawType::FibExtension((a, b, c))
+            | DrawType::Channel((a, b, c))
+            | DrawType::Pitchfork((a, b, c)) => vec![a, b, c],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A note written on the chart, its color is the color of the object
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextNote {
+    /// Top-left corner of the box, (candle index, price)
+    pub anchor: Point,
+    pub text: String,
+    /// In pixels
+    pub font_size: u32,
+    /// Filled behind the text when set
+    pub background: Option<RGBColor>,
+}
+
+impl TextNote {
+    pub fn new(anchor: Point, text: String, font_size: u32, background: Option<RGBColor>) -> Self {
+        Self {
+            anchor,
+            text,
+            font_size,
+            background,
+        }
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.text.lines()
+    }
+
+    /// Height in pixels of a line of text
+    pub fn line_height(&self) -> u32 {
+        (self.font_size as f32 * LINE_HEIGHT).ceil() as u32
+    }
+
+    /// Estimated size in pixels of the box around the text, the same estimate
+    /// is used to draw the box and to hit-test it so both always agree
+    pub fn size(&self) -> (u32, u32) {
+        let chars = self.lines().map(|line| line.chars().count()).max();
+        let chars = chars.unwrap_or(0).max(1);
+        let lines = self.lines().count().max(1) as u32;
+        let width = (chars as f32 * self.font_size as f32 * CHAR_WIDTH).ceil() as u32;
+        (
+            width + 2 * TEXT_PADDING,
+            lines * self.line_height() + 2 * TEXT_PADDING,
+        )
+    }
+}
+
+/// Dash pattern of the lines of a drawing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl LineStyle {
+    /// Style saved as `value`, solid for unknown values
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => LineStyle::Dashed,
+            2 => LineStyle::Dotted,
+            _ => LineStyle::Solid,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct DrawObject {
     pub draw_type: DrawType,
     pub color: RGBColor,
+    pub line_style: LineStyle,
+    /// In pixels, 0 draws 1 pixel wide
+    pub line_width: u32,
 }
 
 // Manual serialization for DrawType
-pub fn draw_type_write_to_bytes(draw_type: &DrawType, buf: &mut Vec<u8>) {
+//
+// Points are written as (time, price) anchors: the candle index goes through
+// `scale`, the scale the drawing is projected on, to a time in milliseconds.
+pub fn draw_type_write_to_bytes(draw_type: &DrawType, buf: &mut Vec<u8>, scale: &TimeScale) {
     match draw_type {
         DrawType::Line((a, b)) => {
             buf.push(1);
-            buf.extend_from_slice(&a.0.to_le_bytes());
-            buf.extend_from_slice(&a.1.to_le_bytes());
-            buf.extend_from_slice(&b.0.to_le_bytes());
-            buf.extend_from_slice(&b.1.to_le_bytes());
+            write_points(buf, &[*a, *b], scale);
         }
         DrawType::Arrow((a, b)) => {
             buf.push(2);
-            buf.extend_from_slice(&a.0.to_le_bytes());
-            buf.extend_from_slice(&a.1.to_le_bytes());
-            buf.extend_from_slice(&b.0.to_le_bytes());
-            buf.extend_from_slice(&b.1.to_le_bytes());
+            write_points(buf, &[*a, *b], scale);
         }
         DrawType::Rectangle((a, b)) => {
             buf.push(3);
-            buf.extend_from_slice(&a.0.to_le_bytes());
-            buf.extend_from_slice(&a.1.to_le_bytes());
-            buf.extend_from_slice(&b.0.to_le_bytes());
-            buf.extend_from_slice(&b.1.to_le_bytes());
+            write_points(buf, &[*a, *b], scale);
         }
         DrawType::Oval((a, b)) => {
             buf.push(4);
-            buf.extend_from_slice(&a.0.to_le_bytes());
-            buf.extend_from_slice(&a.1.to_le_bytes());
-            buf.extend_from_slice(&b.0.to_le_bytes());
-            buf.extend_from_slice(&b.1.to_le_bytes());
+            write_points(buf, &[*a, *b], scale);
         }
         DrawType::Ruler((a, b)) => {
             buf.push(5);
-            buf.extend_from_slice(&a.0.to_le_bytes());
-            buf.extend_from_slice(&a.1.to_le_bytes());
-            buf.extend_from_slice(&b.0.to_le_bytes());
-            buf.extend_from_slice(&b.1.to_le_bytes());
+            write_points(buf, &[*a, *b], scale);
         }
         DrawType::VerticalLine(x) => {
             buf.push(6);
-            buf.extend_from_slice(&x.to_le_bytes());
+            buf.extend_from_slice(&scale.time_at(*x).to_le_bytes());
         }
         DrawType::HorizontalLine(y) => {
             buf.push(7);
@@ -71,18 +279,113 @@ pub fn draw_type_write_to_bytes(draw_type: &DrawType, buf: &mut Vec<u8>) {
             buf.push(8);
             let count = points.len() as u32;
             buf.extend_from_slice(&count.to_le_bytes());
-            for p in points {
-                buf.extend_from_slice(&p.0.to_le_bytes());
-                buf.extend_from_slice(&p.1.to_le_bytes());
+            write_points(buf, points, scale);
+        }
+        DrawType::Text(note) => {
+            buf.push(9);
+            write_points(buf, &[note.anchor], scale);
+            buf.extend_from_slice(&note.font_size.to_le_bytes());
+            let text = note.text.as_bytes();
+            buf.extend_from_slice(&(text.len() as u32).to_le_bytes());
+            buf.extend_from_slice(text);
+            match note.background {
+                Some(color) => buf.extend_from_slice(&[1, color.0, color.1, color.2]),
+                None => buf.push(0),
             }
         }
+        DrawType::FibRetracement((a, b)) => {
+            buf.push(10);
+            write_points(buf, &[*a, *b], scale);
+        }
+        DrawType::PriceRange((a, b)) => {
+            buf.push(11);
+            write_points(buf, &[*a, *b], scale);
+        }
+        DrawType::DateRange((a, b)) => {
+            buf.push(12);
+            write_points(buf, &[*a, *b], scale);
+        }
+        DrawType::FibExtension((a, b, c)) => {
+            buf.push(13);
+            write_points(buf, &[*a, *b, *c], scale);
+        }
+        DrawType::Channel((a, b, c)) => {
+            buf.push(14);
+            write_points(buf, &[*a, *b, *c], scale);
+        }
+        DrawType::Pitchfork((a, b, c)) => {
+            buf.push(15);
+            write_points(buf, &[*a, *b, *c], scale);
+        }
         DrawType::Empty => {
             buf.push(0);
         }
     }
 }
 
-pub fn draw_type_read_from_bytes(data: &[u8]) -> Option<(DrawType, usize)> {
+fn write_points(buf: &mut Vec<u8>, points: &[Point], scale: &TimeScale) {
+    for point in points {
+        buf.extend_from_slice(&scale.time_at(point.0).to_le_bytes());
+        buf.extend_from_slice(&point.1.to_le_bytes());
+    }
+}
+
+/// How drawings were written, it changed with the chart cache version
+#[derive(Clone, Copy)]
+pub struct DrawFormat<'a> {
+    /// Points are (time, price) anchors projected on this scale. Before
+    /// version 7 they were the (candle index, price) of the candles saved with
+    /// the chart, read as they are
+    pub scale: Option<&'a TimeScale>,
+    /// The line style and width follow the color, since version 5
+    pub styled: bool,
+}
+
+impl<'a> DrawFormat<'a> {
+    /// What `DrawObject::write_to_bytes` writes, projected on `scale`
+    pub fn current(scale: &'a TimeScale) -> Self {
+        Self {
+            scale: Some(scale),
+            styled: true,
+        }
+    }
+
+    /// What a chart cache of `version` holds, projected on `scale`
+    pub fn of_version(version: u32, scale: &'a TimeScale) -> Self {
+        Self {
+            scale: (version >= 7).then_some(scale),
+            styled: version >= 5,
+        }
+    }
+
+    /// Candle index of a point's time, and the bytes it took
+    fn read_x(self, data: &[u8]) -> Option<(f32, usize)> {
+        match self.scale {
+            Some(scale) => {
+                let time = i64::from_le_bytes(data.get(..8)?.try_into().ok()?);
+                Some((scale.index_at(time), 8))
+            }
+            None => Some((f32::from_le_bytes(data.get(..4)?.try_into().ok()?), 4)),
+        }
+    }
+}
+
+fn read_points<const N: usize>(data: &[u8], format: DrawFormat) -> Option<([Point; N], usize)> {
+    let mut pos = 0;
+    let mut points = [(0.0, 0.0); N];
+    for point in &mut points {
+        let (x, used) = format.read_x(data.get(pos..)?)?;
+        pos += used;
+        let price = f32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?);
+        pos += 4;
+        *point = (x, price);
+    }
+    Some((points, pos))
+}
+
+/// Read a drawing written by `draw_type_write_to_bytes`, or by an older
+/// version of it as `format` says
+pub fn draw_type_read_from_bytes(data: &[u8], format: DrawFormat) -> Option<(DrawType, usize)> {
     if data.is_empty() {
         return None;
     }
@@ -90,18 +393,9 @@ pub fn draw_type_read_from_bytes(data: &[u8]) -> Option<(DrawType, usize)> {
     let mut pos = 1;
     match tag {
         1..=5 => {
-            if data.len() < pos + 16 {
-                return None;
-            }
-            let a0 = f32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
-            pos += 4;
-            let a1 = f32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
-            pos += 4;
-            let b0 = f32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
-            pos += 4;
-            let b1 = f32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
-            pos += 4;
-            let points = ((a0, a1), (b0, b1));
+            let ([a, b], used) = read_points::<2>(&data[pos..], format)?;
+            pos += used;
+            let points = (a, b);
             let dt = match tag {
                 1 => DrawType::Line(points),
                 2 => DrawType::Arrow(points),
@@ -113,11 +407,8 @@ pub fn draw_type_read_from_bytes(data: &[u8]) -> Option<(DrawType, usize)> {
             Some((dt, pos))
         }
         6 => {
-            if data.len() < pos + 4 {
-                return None;
-            }
-            let x = f32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
-            pos += 4;
+            let (x, used) = format.read_x(&data[pos..])?;
+            pos += used;
             Some((DrawType::VerticalLine(x), pos))
         }
         7 => {
@@ -134,46 +425,160 @@ pub fn draw_type_read_from_bytes(data: &[u8]) -> Option<(DrawType, usize)> {
             }
             let count = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
             pos += 4;
-            let mut points = Vec::with_capacity(count);
+            let mut points = Vec::with_capacity(count.min(data.len() / 8));
             for _ in 0..count {
-                if data.len() < pos + 8 {
-                    return None;
-                }
-                let x = f32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
-                pos += 4;
-                let y = f32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
-                pos += 4;
-                points.push((x, y));
+                let ([point], used) = read_points::<1>(&data[pos..], format)?;
+                pos += used;
+                points.push(point);
             }
             Some((DrawType::Pen(points), pos))
         }
+        9 => {
+            let ([anchor], used) = read_points::<1>(&data[pos..], format)?;
+            pos += used;
+            if data.len() < pos + 8 {
+                return None;
+            }
+            let font_size = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+            pos += 4;
+            let len = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+            pos += 4;
+            if data.len() < pos + len + 1 {
+                return None;
+            }
+            let text = String::from_utf8_lossy(&data[pos..pos + len]).to_string();
+            pos += len;
+            let has_background = data[pos] != 0;
+            pos += 1;
+            let background = if has_background {
+                if data.len() < pos + 3 {
+                    return None;
+                }
+                pos += 3;
+                Some(RGBColor(data[pos - 3], data[pos - 2], data[pos - 1]))
+            } else {
+                None
+            };
+            let note = TextNote::new(anchor, text, font_size, background);
+            Some((DrawType::Text(note), pos))
+        }
+        10..=12 => {
+            let ([a, b], used) = read_points::<2>(&data[pos..], format)?;
+            let dt = match tag {
+                10 => DrawType::FibRetracement((a, b)),
+                11 => DrawType::PriceRange((a, b)),
+                _ => DrawType::DateRange((a, b)),
+            };
+            Some((dt, pos + used))
+        }
+        13..=15 => {
+            let ([a, b, c], used) = read_points::<3>(&data[pos..], format)?;
+            let dt = match tag {
+                13 => DrawType::FibExtension((a, b, c)),
+                14 => DrawType::Channel((a, b, c)),
+                _ => DrawType::Pitchfork((a, b, c)),
+            };
+            Some((dt, pos + used))
+        }
         0 => Some((DrawType::Empty, pos)),
         _ => None,
     }
 }
 
 impl DrawObject {
-    pub fn write_to_bytes(&self, buf: &mut Vec<u8>) {
-        draw_type_write_to_bytes(&self.draw_type, buf);
+    /// Write the drawing projected on `scale` as (time, price) anchors
+    pub fn write_to_bytes(&self, buf: &mut Vec<u8>, scale: &TimeScale) {
+        draw_type_write_to_bytes(&self.draw_type, buf, scale);
         buf.push(self.color.0);
         buf.push(self.color.1);
         buf.push(self.color.2);
+        buf.push(self.line_style as u8);
+        buf.extend_from_slice(&self.line_width.to_le_bytes());
+    }
+
+    /// Read a drawing written by [`DrawObject::write_to_bytes`], projected on
+    /// `scale`
+    pub fn read_from_bytes(data: &[u8], scale: &TimeScale) -> Option<(Self, usize)> {
+        Self::read_as(data, DrawFormat::current(scale))
     }
 
-    pub fn read_from_bytes(data: &[u8]) -> Option<(Self, usize)> {
-        let (draw_type, used1) = draw_type_read_from_bytes(data)?;
-        if data.len() < used1 + 3 {
-            return None;
+    /// Read a drawing of `format`, unstyled drawings get the style of
+    /// [`DrawObject::new`]
+    pub(crate) fn read_as(data: &[u8], format: DrawFormat) -> Option<(Self, usize)> {
+        let (draw_type, mut pos) = draw_type_read_from_bytes(data, format)?;
+        let color = data.get(pos..pos + 3)?;
+        let mut object = Self::new(draw_type, RGBColor(color[0], color[1], color[2]));
+        pos += 3;
+        if format.styled {
+            object.line_style = LineStyle::from_u8(*data.get(pos)?);
+            object.line_width = u32::from_le_bytes(data.get(pos + 1..pos + 5)?.try_into().ok()?);
+            pos += 5;
         }
-        let r = data[used1];
-        let g = data[used1 + 1];
-        let b = data[used1 + 2];
-        let color = RGBColor(r, g, b);
-        Some((Self { draw_type, color }, used1 + 3))
+        Some((object, pos))
     }
 
     pub fn new(draw_type: DrawType, color: RGBColor) -> Self {
-        Self { draw_type, color }
+        Self {
+            draw_type,
+            color,
+            line_style: LineStyle::Solid,
+            line_width: 1,
+        }
+    }
+
+    /// Points a selected drawing can be reshaped by
+    ///
+    /// Rectangles and ovals have a handle on every corner of their box, lines
+    /// spanning the whole chart and pen strokes can only be moved.
+    pub fn handles(&self) -> Vec<Point> {
+        match &self.draw_type {
+            DrawType::Line((a, b)) | DrawType::Arrow((a, b)) | DrawType::Ruler((a, b)) => {
+                vec![*a, *b]
+            }
+            DrawType::Rectangle((a, b)) | DrawType::Oval((a, b)) => {
+                vec![*a, (b.0, a.1), *b, (a.0, b.1)]
+            }
+            DrawType::Text(note) => vec![note.anchor],
+            draw_type => draw_type.points(),
+        }
+    }
+
+    /// Handle within the thresholds of `point`, the last one drawn wins
+    pub fn handle_at(&self, point: Point, x_thresh_hold: f32, y_thresh_hold: f32) -> Option<usize> {
+        self.handles().iter().rposition(|handle| {
+            (handle.0 - point.0).abs() < x_thresh_hold && (handle.1 - point.1).abs() < y_thresh_hold
+        })
+    }
+
+    /// Put the handle `index` of [`DrawObject::handles`] at `point`
+    pub fn move_handle(&mut self, index: usize, point: Point) {
+        match &mut self.draw_type {
+            DrawType::Line((a, b)) | DrawType::Arrow((a, b)) | DrawType::Ruler((a, b)) => {
+                match index {
+                    0 => *a = point,
+                    1 => *b = point,
+                    _ => {}
+                }
+            }
+            DrawType::Rectangle((a, b)) | DrawType::Oval((a, b)) => {
+                // The opposite corner stays where it is
+                let corners = [*a, (b.0, a.1), *b, (a.0, b.1)];
+                if index < corners.len() {
+                    *a = point;
+                    *b = corners[(index + 2) % 4];
+                }
+            }
+            DrawType::Text(note) => {
+                if index == 0 {
+                    note.anchor = point;
+                }
+            }
+            draw_type => {
+                if let Some(handle) = draw_type.points_mut().into_iter().nth(index) {
+                    *handle = point;
+                }
+            }
+        }
     }
 
     pub fn hit_cursor(&self, point: Point, x_thresh_hold: f32, y_thresh_hold: f32) -> bool {
@@ -279,6 +684,27 @@ impl DrawObject {
                     }
                 })
             }
+            DrawType::Text(note) => {
+                // The thresholds span THREAD_HOLD pixels, which gives the scale
+                // of the box in chart values
+                let (width, height) = note.size();
+                let width = width as f32 * x_thresh_hold / THREAD_HOLD;
+                let height = height as f32 * y_thresh_hold / THREAD_HOLD;
+                let (x, y) = note.anchor;
+                let (px, py) = point;
+                px >= x - x_thresh_hold
+                    && px <= x + width + x_thresh_hold
+                    && py <= y + y_thresh_hold
+                    && py >= y - height - y_thresh_hold
+            }
+            DrawType::FibRetracement(_)
+            | DrawType::FibExtension(_)
+            | DrawType::Channel(_)
+            | DrawType::Pitchfork(_)
+            | DrawType::PriceRange(_)
+            | DrawType::DateRange(_) => self.draw_type.segments().iter().any(|(start, end)| {
+                near_segment(point, *start, *end, x_thresh_hold, y_thresh_hold)
+            }),
             _ => false,
         }
     }
@@ -309,7 +735,72 @@ impl DrawObject {
                     p.1 += dy;
                 }
             }
-            _ => {}
+            DrawType::Text(note) => {
+                note.anchor.0 += dx;
+                note.anchor.1 += dy;
+            }
+            draw_type => {
+                for point in draw_type.points_mut() {
+                    point.0 += dx;
+                    point.1 += dy;
+                }
+            }
+        }
+    }
+
+    /// Change every x coordinate with `f`, e.g. to project the drawing on
+    /// other candles
+    pub fn map_x(&mut self, f: impl Fn(f32) -> f32) {
+        match &mut self.draw_type {
+            DrawType::Line((start, end))
+            | DrawType::Arrow((start, end))
+            | DrawType::Rectangle((start, end))
+            | DrawType::Oval((start, end))
+            | DrawType::Ruler((start, end)) => {
+                start.0 = f(start.0);
+                end.0 = f(end.0);
+            }
+            DrawType::VerticalLine(x) => *x = f(*x),
+            DrawType::HorizontalLine(_) | DrawType::Empty => {}
+            DrawType::Pen(points) => {
+                for point in points.iter_mut() {
+                    point.0 = f(point.0);
+                }
+            }
+            DrawType::Text(note) => note.anchor.0 = f(note.anchor.0),
+            draw_type => {
+                for point in draw_type.points_mut() {
+                    point.0 = f(point.0);
+                }
+            }
+        }
+    }
+
+    /// Change every y coordinate with `f`, e.g. to put the drawing on the
+    /// price axis of a [`crate::price_scale::PriceScale`]
+    pub fn map_y(&mut self, f: impl Fn(f32) -> f32) {
+        match &mut self.draw_type {
+            DrawType::Line((start, end))
+            | DrawType::Arrow((start, end))
+            | DrawType::Rectangle((start, end))
+            | DrawType::Oval((start, end))
+            | DrawType::Ruler((start, end)) => {
+                start.1 = f(start.1);
+                end.1 = f(end.1);
+            }
+            DrawType::HorizontalLine(y) => *y = f(*y),
+            DrawType::VerticalLine(_) | DrawType::Empty => {}
+            DrawType::Pen(points) => {
+                for point in points.iter_mut() {
+                    point.1 = f(point.1);
+                }
+            }
+            DrawType::Text(note) => note.anchor.1 = f(note.anchor.1),
+            draw_type => {
+                for point in draw_type.points_mut() {
+                    point.1 = f(point.1);
+                }
+            }
         }
     }
 
@@ -370,7 +861,23 @@ impl DrawObject {
                     self.draw_type = DrawType::Empty;
                 }
             }
-            _ => {}
+            DrawType::Text(note) => {
+                // The box is drawn whole or not at all
+                let (x, y) = note.anchor;
+                if x < x_min || x > x_max || y < y_min || y > y_max {
+                    self.draw_type = DrawType::Empty;
+                }
+            }
+            draw_type => {
+                // Drawn whole, plotters clips what sticks out of the view
+                let segments = draw_type.segments();
+                let visible = segments.iter().any(|&(mut start, mut end)| {
+                    cohen_sutherland_clip(&mut start, &mut end, x_min, x_max, y_min, y_max)
+                });
+                if !segments.is_empty() && !visible {
+                    self.draw_type = DrawType::Empty;
+                }
+            }
         }
     }
 
@@ -409,11 +916,33 @@ impl DrawObject {
             DrawType::VerticalLine(x) => vec![(*x, p1.1), (*x, p2.1)],
             DrawType::HorizontalLine(y) => vec![(p1.0, *y), (p2.0, *y)],
             DrawType::Pen(points) => points.clone(),
-            _ => vec![],
+            DrawType::Text(note) => vec![note.anchor],
+            draw_type => draw_type.points(),
         }
     }
 }
 
+// Helper: whether a point is within the thresholds of a segment
+fn near_segment(
+    point: Point,
+    start: Point,
+    end: Point,
+    x_thresh_hold: f32,
+    y_thresh_hold: f32,
+) -> bool {
+    let (px, py) = point;
+    let dx = end.0 - start.0;
+    let dy = end.1 - start.1;
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq == 0.0 {
+        0.0
+    } else {
+        (((px - start.0) * dx + (py - start.1) * dy) / length_sq).clamp(0.0, 1.0)
+    };
+    (px - (start.0 + t * dx)).abs() < x_thresh_hold
+        && (py - (start.1 + t * dy)).abs() < y_thresh_hold
+}
+
 // Helper: Cohen–Sutherland line clipping algorithm for 2D lines
 fn cohen_sutherland_clip(
     p1: &mut (f32, f32),
@@ -486,3 +1015,111 @@ fn cohen_sutherland_clip(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(background: Option<RGBColor>) -> DrawObject {
+        let note = TextNote::new((10.0, 50.0), "Đỉnh\nbreakout".into(), 14, background);
+        DrawObject::new(DrawType::Text(note), RGBColor(1, 2, 3))
+    }
+
+    #[test]
+    fn test_text_note_round_trip() {
+        for object in [note(None), note(Some(RGBColor(9, 8, 7)))] {
+            let mut buf = Vec::new();
+            object.write_to_bytes(&mut buf, &TimeScale::default());
+            let (read, used) = DrawObject::read_from_bytes(&buf, &TimeScale::default()).unwrap();
+            assert_eq!(used, buf.len());
+            assert_eq!(read.draw_type, object.draw_type);
+            assert_eq!(read.color, object.color);
+        }
+    }
+
+    #[test]
+    fn test_text_note_hit_box() {
+        let object = note(None);
+        // One candle and one price unit are 10 pixels
+        let (x_th, y_th) = (THREAD_HOLD / 10.0, THREAD_HOLD / 10.0);
+        let DrawType::Text(text) = &object.draw_type else {
+            unreachable!()
+        };
+        let (width, height) = text.size();
+        assert_eq!(height, 2 * text.line_height() + 2 * TEXT_PADDING);
+
+        assert!(object.hit_cursor((10.5, 49.5), x_th, y_th));
+        let right = 10.0 + width as f32 / 10.0;
+        let bottom = 50.0 - height as f32 / 10.0;
+        assert!(object.hit_cursor((right - 0.1, bottom + 0.1), x_th, y_th));
+        assert!(!object.hit_cursor((right + 1.0, 49.5), x_th, y_th));
+        assert!(!object.hit_cursor((10.5, 51.0), x_th, y_th));
+    }
+
+    #[test]
+    fn test_line_tools_round_trip_and_translate() {
+        let (a, b, c) = ((1.0, 10.0), (5.0, 20.0), (8.0, 15.0));
+        for draw_type in [
+            DrawType::FibRetracement((a, b)),
+            DrawType::PriceRange((a, b)),
+            DrawType::DateRange((a, b)),
+            DrawType::FibExtension((a, b, c)),
+            DrawType::Channel((a, b, c)),
+            DrawType::Pitchfork((a, b, c)),
+        ] {
+            let mut buf = Vec::new();
+            let scale = TimeScale::default();
+            draw_type_write_to_bytes(&draw_type, &mut buf, &scale);
+            assert_eq!(
+                draw_type_read_from_bytes(&buf, DrawFormat::current(&scale)),
+                Some((draw_type.clone(), buf.len()))
+            );
+
+            let mut object = DrawObject::new(draw_type.clone(), RGBColor(0, 0, 0));
+            object.translate(1.0, -1.0);
+            let moved = object.draw_type.points();
+            for (point, original) in moved.iter().zip(draw_type.points()) {
+                assert_eq!(*point, (original.0 + 1.0, original.1 - 1.0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_fib_retracement_levels() {
+        let fib = DrawType::FibRetracement(((0.0, 100.0), (10.0, 200.0)));
+        let levels = fib.fib_levels();
+        assert_eq!(levels.first(), Some(&(0.0, 200.0)));
+        assert_eq!(levels.last(), Some(&(1.0, 100.0)));
+        assert!((levels[4].1 - 138.2).abs() < 1e-3);
+
+        let object = DrawObject::new(fib, RGBColor(0, 0, 0));
+        assert!(object.hit_cursor((5.0, 150.1), 0.5, 0.5));
+        assert!(!object.hit_cursor((5.0, 145.0), 0.5, 0.5));
+        assert!(!object.hit_cursor((12.0, 150.0), 0.5, 0.5));
+    }
+
+    #[test]
+    fn test_channel_parallel_through_third_point() {
+        let channel = DrawType::Channel(((0.0, 0.0), (10.0, 10.0), (5.0, 9.0)));
+        let segments = channel.segments();
+        assert_eq!(segments[1], ((0.0, 4.0), (10.0, 14.0)));
+        assert_eq!(segments[2], ((0.0, 2.0), (10.0, 12.0)));
+
+        let mut object = DrawObject::new(channel, RGBColor(0, 0, 0));
+        assert!(object.hit_cursor((5.0, 9.0), 0.2, 0.2));
+        // Only the lines count, not the inside of the channel
+        assert!(!object.hit_cursor((5.0, 6.0), 0.2, 0.2));
+
+        object.clipped(20.0, 30.0, 0.0, 100.0);
+        assert!(object.is_empty());
+    }
+
+    #[test]
+    fn test_pitchfork_tines_follow_the_median() {
+        let pitchfork = DrawType::Pitchfork(((0.0, 0.0), (4.0, 6.0), (4.0, 2.0)));
+        let segments = pitchfork.segments();
+        assert_eq!(segments[0], ((0.0, 0.0), (8.0, 8.0)));
+        assert_eq!(segments[1], ((4.0, 6.0), (8.0, 10.0)));
+        assert_eq!(segments[2], ((4.0, 2.0), (8.0, 6.0)));
+    }
+}
@@ -0,0 +1,230 @@
+//! Grid of charts on the chart page
+//!
+//! Every cell shows a chart of its own symbol and timeframe. The mouse works
+//! on the active cell, the others show their last candles. The crosshair time,
+//! the symbol and the drawings of the active cell can be linked to the other
+//! cells. Cells are kept when the grid shrinks, so going back to a larger grid
+//! shows them again.
+
+/// Arrangement of the cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GridLayout {
+    #[default]
+    Single,
+    /// Two charts side by side
+    OneByTwo,
+    TwoByTwo,
+    /// A large chart on the left and three stacked on the right
+    OnePlusThree,
+}
+
+impl GridLayout {
+    /// Layout saved as `value`, a single chart for unknown values
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => GridLayout::OneByTwo,
+            2 => GridLayout::TwoByTwo,
+            3 => GridLayout::OnePlusThree,
+            _ => GridLayout::Single,
+        }
+    }
+
+    pub fn cell_count(self) -> usize {
+        self.rects().len()
+    }
+
+    /// Cells as (x, y, width, height) fractions of the chart area
+    pub fn rects(self) -> Vec<(f32, f32, f32, f32)> {
+        const THIRD: f32 = 1.0 / 3.0;
+        match self {
+            GridLayout::Single => vec![(0.0, 0.0, 1.0, 1.0)],
+            GridLayout::OneByTwo => vec![(0.0, 0.0, 0.5, 1.0), (0.5, 0.0, 0.5, 1.0)],
+            GridLayout::TwoByTwo => vec![
+                (0.0, 0.0, 0.5, 0.5),
+                (0.5, 0.0, 0.5, 0.5),
+                (0.0, 0.5, 0.5, 0.5),
+                (0.5, 0.5, 0.5, 0.5),
+            ],
+            GridLayout::OnePlusThree => vec![
+                (0.0, 0.0, 2.0 * THIRD, 1.0),
+                (2.0 * THIRD, 0.0, THIRD, THIRD),
+                (2.0 * THIRD, THIRD, THIRD, THIRD),
+                (2.0 * THIRD, 2.0 * THIRD, THIRD, THIRD),
+            ],
+        }
+    }
+
+    /// Size in pixels of cell `index` in a chart area of `width` by `height`
+    pub fn cell_size(self, index: usize, width: f32, height: f32) -> (i32, i32) {
+        self.rects().get(index).map_or((0, 0), |&(_, _, w, h)| {
+            ((w * width) as i32, (h * height) as i32)
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridCell {
+    /// Upper-case symbol
+    pub symbol: String,
+    pub time_frame: String,
+}
+
+impl GridCell {
+    pub fn new(symbol: &str, time_frame: &str) -> Self {
+        Self {
+            symbol: symbol.to_uppercase(),
+            time_frame: time_frame.to_string(),
+        }
+    }
+}
+
+/// What the active cell shares with the others
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Links {
+    /// The other cells show the crosshair at the time under the mouse
+    pub crosshair: bool,
+    /// A symbol picked for the active cell is shown in every cell
+    pub symbol: bool,
+    /// Cells of the same symbol show the same drawings
+    pub drawings: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartGrid {
+    pub layout: GridLayout,
+    /// Cells in the order of the layout, there may be more than it shows
+    pub cells: Vec<GridCell>,
+    /// Index of the cell the mouse works on
+    pub active: usize,
+    pub links: Links,
+}
+
+impl ChartGrid {
+    /// A single chart of `symbol` on `time_frame`
+    pub fn new(symbol: &str, time_frame: &str) -> Self {
+        Self {
+            layout: GridLayout::Single,
+            cells: vec![GridCell::new(symbol, time_frame)],
+            active: 0,
+            links: Links::default(),
+        }
+    }
+
+    /// Show the cells of `layout`, new cells start as copies of the active
+    /// one. The active cell moves to the first place when the layout has no
+    /// room for it, so the chart in use stays on screen.
+    pub fn set_layout(&mut self, layout: GridLayout) {
+        self.layout = layout;
+        let count = layout.cell_count();
+        while self.cells.len() < count {
+            self.cells.push(self.active_cell().clone());
+        }
+        if self.active >= count {
+            self.cells.swap(0, self.active);
+            self.active = 0;
+        }
+    }
+
+    /// Cells on screen
+    pub fn visible(&self) -> &[GridCell] {
+        &self.cells[..self.layout.cell_count().min(self.cells.len())]
+    }
+
+    pub fn active_cell(&self) -> &GridCell {
+        &self.cells[self.active]
+    }
+
+    /// Make cell `index` the active one, false if it is not on screen
+    pub fn select(&mut self, index: usize) -> bool {
+        if index >= self.visible().len() {
+            return false;
+        }
+        self.active = index;
+        true
+    }
+
+    /// Show `symbol` on `time_frame` in the active cell, and the symbol in
+    /// every cell when the symbols are linked
+    pub fn show(&mut self, symbol: &str, time_frame: &str) {
+        self.cells[self.active] = GridCell::new(symbol, time_frame);
+        if self.links.symbol {
+            let symbol = symbol.to_uppercase();
+            for cell in &mut self.cells {
+                cell.symbol.clone_from(&symbol);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layouts_cover_the_chart_area() {
+        for layout in (0..4).map(GridLayout::from_u8) {
+            let area: f32 = layout.rects().iter().map(|&(_, _, w, h)| w * h).sum();
+            assert!((area - 1.0).abs() < 1e-5, "{layout:?}");
+            for (x, y, w, h) in layout.rects() {
+                assert!(x + w <= 1.0 + 1e-5 && y + h <= 1.0 + 1e-5, "{layout:?}");
+            }
+        }
+        assert_eq!(
+            GridLayout::OnePlusThree.cell_size(0, 900.0, 600.0),
+            (600, 600)
+        );
+        assert_eq!(GridLayout::TwoByTwo.cell_size(3, 900.0, 600.0), (450, 300));
+    }
+
+    #[test]
+    fn test_cells_survive_a_smaller_layout() {
+        let mut grid = ChartGrid::new("fpt", "1D");
+        grid.set_layout(GridLayout::TwoByTwo);
+        assert_eq!(grid.visible().len(), 4);
+        assert!(grid.select(3));
+        grid.show("vcb", "1H");
+
+        // The active cell takes the first place of a single chart
+        grid.set_layout(GridLayout::Single);
+        assert_eq!(grid.active, 0);
+        assert_eq!(grid.active_cell(), &GridCell::new("VCB", "1H"));
+        assert!(!grid.select(1));
+
+        grid.set_layout(GridLayout::TwoByTwo);
+        assert_eq!(grid.cells[3], GridCell::new("FPT", "1D"));
+    }
+
+    #[test]
+    fn test_linked_symbol_keeps_the_timeframes() {
+        let mut grid = ChartGrid::new("FPT", "1D");
+        grid.set_layout(GridLayout::OneByTwo);
+        grid.select(1);
+        grid.show("FPT", "15m");
+        grid.show("HPG", "15m");
+        assert_eq!(grid.cells[0], GridCell::new("FPT", "1D"));
+
+        grid.links.symbol = true;
+        grid.show("SSI", "15m");
+        assert_eq!(grid.cells[0], GridCell::new("SSI", "1D"));
+        assert_eq!(grid.cells[1], GridCell::new("SSI", "15m"));
+    }
+
+    #[test]
+    fn test_grid_round_trip() {
+        let mut grid = ChartGrid::new("FPT", "1D");
+        grid.set_layout(GridLayout::OnePlusThree);
+        grid.select(2);
+        grid.show("VCB", "1W");
+        grid.links = Links {
+            crosshair: true,
+            symbol: false,
+            drawings: true,
+        };
+        let mut buf = Vec::new();
+        grid.write_to_bytes(&mut buf);
+        buf.push(0xFF);
+        let (read, used) = ChartGrid::read_from_bytes(&buf).unwrap();
+        assert_eq!(read, grid);
+        assert_eq!(used, buf.len() - 1);
+    }
+}
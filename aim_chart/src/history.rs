@@ -0,0 +1,305 @@
+//! Edits of the drawings of a chart, kept to be undone and redone
+//!
+//! Every change to `Chart::all_draw_data` made from the UI goes through an
+//! [`Edit`] which knows how to revert itself, so undo covers moves, reshapes,
+//! deletes, clears and style changes as well as new drawings. The history is
+//! saved with the chart unless [`History::persist`] is off, so a "clear all"
+//! can still be undone after a restart.
+
+use std::collections::VecDeque;
+
+use crate::draw::DrawObject;
+
+/// Edits kept by default, the oldest are forgotten past it
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// A change to the drawings of a chart
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    /// Drawings put at their index, by ascending index
+    Insert(Vec<(usize, DrawObject)>),
+    /// Drawings taken from their index, by ascending index
+    Remove(Vec<(usize, DrawObject)>),
+    /// Drawings changed in place, with their state before and after
+    Change(Vec<(usize, DrawObject, DrawObject)>),
+}
+
+impl Edit {
+    pub fn apply(&self, drawings: &mut Vec<DrawObject>) {
+        match self {
+            Edit::Insert(items) => {
+                for (index, object) in items {
+                    drawings.insert((*index).min(drawings.len()), object.clone());
+                }
+            }
+            Edit::Remove(items) => {
+                // From the back so the indices still hold
+                for (index, _) in items.iter().rev() {
+                    if *index < drawings.len() {
+                        drawings.remove(*index);
+                    }
+                }
+            }
+            Edit::Change(items) => {
+                for (index, _, after) in items {
+                    if let Some(object) = drawings.get_mut(*index) {
+                        *object = after.clone();
+                    }
+                }
+            }
+        }
+    }
+
+    /// The edit reverting this one
+    pub fn inverse(&self) -> Edit {
+        match self {
+            Edit::Insert(items) => Edit::Remove(items.clone()),
+            Edit::Remove(items) => Edit::Insert(items.clone()),
+            Edit::Change(items) => Edit::Change(
+                items
+                    .iter()
+                    .map(|(index, before, after)| (*index, after.clone(), before.clone()))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn objects_mut(&mut self) -> Vec<&mut DrawObject> {
+        match self {
+            Edit::Insert(items) | Edit::Remove(items) => {
+                items.iter_mut().map(|(_, object)| object).collect()
+            }
+            Edit::Change(items) => items
+                .iter_mut()
+                .flat_map(|(_, before, after)| [before, after])
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct History {
+    /// Edits made, the last one is undone first
+    pub(crate) undo: VecDeque<Edit>,
+    /// Edits undone, the last one is redone first
+    pub(crate) redo: Vec<Edit>,
+    /// Most edits kept
+    limit: usize,
+    /// Save the edits with the chart
+    pub persist: bool,
+    /// Note whose text the last edit changed, typing more into it extends
+    /// that edit instead of recording one per key
+    typing: Option<usize>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::with_limit(DEFAULT_HISTORY_LIMIT)
+    }
+}
+
+impl History {
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit,
+            persist: true,
+            typing: None,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Keep at most `limit` edits, forgetting the oldest ones
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+        self.redo.truncate(self.limit);
+    }
+
+    /// Make `edit` on `drawings` and remember it
+    pub fn apply(&mut self, edit: Edit, drawings: &mut Vec<DrawObject>) {
+        edit.apply(drawings);
+        self.record(edit);
+    }
+
+    /// Remember an edit already made, the edits undone so far cannot be
+    /// redone anymore
+    pub fn record(&mut self, edit: Edit) {
+        self.typing = None;
+        self.redo.clear();
+        self.undo.push_back(edit);
+        self.trim();
+    }
+
+    /// Remember a change of the note at `index` made from the text box
+    pub fn record_typing(&mut self, index: usize, before: DrawObject, after: DrawObject) {
+        if self.typing == Some(index)
+            && let Some(Edit::Change(items)) = self.undo.back_mut()
+            && let [(changed, _, last)] = items.as_mut_slice()
+            && *changed == index
+        {
+            *last = after;
+            return;
+        }
+        self.record(Edit::Change(vec![(index, before, after)]));
+        self.typing = Some(index);
+    }
+
+    /// Revert the last edit, false when there is none
+    pub fn undo(&mut self, drawings: &mut Vec<DrawObject>) -> bool {
+        self.typing = None;
+        match self.undo.pop_back() {
+            Some(edit) => {
+                edit.inverse().apply(drawings);
+                self.redo.push(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Make the last undone edit again, false when there is none
+    pub fn redo(&mut self, drawings: &mut Vec<DrawObject>) -> bool {
+        self.typing = None;
+        match self.redo.pop() {
+            Some(edit) => {
+                edit.apply(drawings);
+                self.undo.push_back(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Change the x coordinates of the drawings kept by the edits, along with
+    /// the drawings of the chart when they are projected on other candles
+    pub fn map_x(&mut self, f: impl Fn(f32) -> f32) {
+        for edit in self.undo.iter_mut().chain(&mut self.redo) {
+            for object in edit.objects_mut() {
+                object.map_x(&f);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{draw::DrawType, time_scale::TimeScale};
+    use plotters::style::RGBColor;
+
+    fn line(y: f32) -> DrawObject {
+        DrawObject::new(DrawType::Line(((0.0, y), (1.0, y))), RGBColor(0, 0, 0))
+    }
+
+    #[test]
+    fn test_undo_reverts_every_kind_of_edit() {
+        let mut history = History::default();
+        let mut drawings = Vec::new();
+        history.apply(Edit::Insert(vec![(0, line(1.0))]), &mut drawings);
+        history.apply(Edit::Insert(vec![(1, line(2.0))]), &mut drawings);
+        history.apply(Edit::Change(vec![(0, line(1.0), line(5.0))]), &mut drawings);
+        history.apply(
+            Edit::Remove(vec![(0, line(5.0)), (1, line(2.0))]),
+            &mut drawings,
+        );
+        assert!(drawings.is_empty());
+
+        assert!(history.undo(&mut drawings));
+        assert_eq!(drawings, vec![line(5.0), line(2.0)]);
+        assert!(history.undo(&mut drawings));
+        assert_eq!(drawings, vec![line(1.0), line(2.0)]);
+        assert!(history.undo(&mut drawings));
+        assert!(history.undo(&mut drawings));
+        assert!(drawings.is_empty());
+        assert!(!history.undo(&mut drawings));
+
+        // Redo brings every edit back in order
+        while history.redo(&mut drawings) {}
+        assert!(drawings.is_empty());
+        assert!(history.undo(&mut drawings));
+        assert_eq!(drawings, vec![line(5.0), line(2.0)]);
+    }
+
+    #[test]
+    fn test_new_edit_drops_redo_and_limit_holds() {
+        let mut history = History::with_limit(2);
+        let mut drawings = Vec::new();
+        for (index, y) in [1.0, 2.0, 3.0].into_iter().enumerate() {
+            history.apply(Edit::Insert(vec![(index, line(y))]), &mut drawings);
+        }
+        // The first insert was forgotten
+        assert!(history.undo(&mut drawings));
+        assert!(history.undo(&mut drawings));
+        assert!(!history.undo(&mut drawings));
+        assert_eq!(drawings, vec![line(1.0)]);
+
+        assert!(history.redo(&mut drawings));
+        history.apply(Edit::Remove(vec![(0, line(1.0))]), &mut drawings);
+        assert!(!history.can_redo());
+        assert_eq!(drawings, vec![line(2.0)]);
+    }
+
+    #[test]
+    fn test_history_round_trip() {
+        let mut history = History::with_limit(10);
+        let mut drawings = vec![line(1.0), line(2.0)];
+        let cleared = drawings.iter().cloned().enumerate().collect();
+        history.apply(Edit::Remove(cleared), &mut drawings);
+        history.apply(Edit::Insert(vec![(0, line(3.0))]), &mut drawings);
+        history.apply(Edit::Change(vec![(0, line(3.0), line(4.0))]), &mut drawings);
+        history.undo(&mut drawings);
+
+        let mut buf = Vec::new();
+        let scale = TimeScale::default();
+        history.write_to_bytes(&mut buf, &scale);
+        let (mut read, used) = History::read_from_bytes(&buf, &scale).unwrap();
+        assert_eq!(used, buf.len());
+        assert_eq!(read.limit(), 10);
+        assert_eq!(read.undo, history.undo);
+        assert_eq!(read.redo, history.redo);
+
+        // The clear can still be undone after a restart
+        assert!(read.undo(&mut drawings));
+        assert!(read.undo(&mut drawings));
+        assert_eq!(drawings, vec![line(1.0), line(2.0)]);
+
+        history.persist = false;
+        buf.clear();
+        history.write_to_bytes(&mut buf, &scale);
+        let (read, _) = History::read_from_bytes(&buf, &scale).unwrap();
+        assert!(!read.persist && !read.can_undo() && !read.can_redo());
+    }
+
+    #[test]
+    fn test_typing_makes_one_edit() {
+        let mut history = History::default();
+        let mut drawings = vec![line(1.0)];
+        for y in [2.0, 3.0, 4.0] {
+            let before = drawings[0].clone();
+            drawings[0] = line(y);
+            history.record_typing(0, before, line(y));
+        }
+        assert!(history.undo(&mut drawings));
+        assert_eq!(drawings, vec![line(1.0)]);
+        assert!(!history.can_undo());
+    }
+}
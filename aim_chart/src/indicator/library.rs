@@ -0,0 +1,1138 @@
+//! Built-in studies
+//!
+//! Every study keeps only the state it needs to take the next candle, so they
+//! can be fed a live stream as well as a whole history. Periods and multipliers
+//! follow the usual defaults of charting packages, see [`super::create`].
+
+use std::collections::VecDeque;
+
+use chrono::NaiveDate;
+use plotters::style::RGBColor;
+
+use super::{Indicator, Input, Output, Param, RenderStyle};
+use crate::CandleData;
+use crate::chart::vn_offset;
+
+const WHITE: RGBColor = RGBColor(0xFF, 0xFF, 0xFF);
+const YELLOW: RGBColor = RGBColor(0xFF, 0xD5, 0x4F);
+const ORANGE: RGBColor = RGBColor(0xFF, 0x98, 0x00);
+const BLUE: RGBColor = RGBColor(0x42, 0xA5, 0xF5);
+const PURPLE: RGBColor = RGBColor(0xAB, 0x47, 0xBC);
+const GREEN: RGBColor = RGBColor(0x26, 0xA6, 0x9A);
+const RED: RGBColor = RGBColor(0xEF, 0x53, 0x50);
+const GREY: RGBColor = RGBColor(0x9E, 0x9E, 0x9E);
+
+fn output(name: &'static str, color: RGBColor) -> Output {
+    Output {
+        name,
+        color,
+        offset: 0,
+    }
+}
+
+fn param(name: &'static str, value: impl Into<f64>) -> Param {
+    Param {
+        name,
+        value: value.into() as f32,
+    }
+}
+
+/// Average of the last `period` values
+#[derive(Debug, Clone)]
+struct Mean {
+    period: usize,
+    window: VecDeque<f64>,
+    sum: f64,
+}
+
+impl Mean {
+    fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            window: VecDeque::new(),
+            sum: 0.0,
+        }
+    }
+
+    fn next(&mut self, value: f64) -> Option<f64> {
+        self.window.push_back(value);
+        self.sum += value;
+        if self.window.len() > self.period
+            && let Some(oldest) = self.window.pop_front()
+        {
+            self.sum -= oldest;
+        }
+        (self.window.len() == self.period).then(|| self.sum / self.period as f64)
+    }
+}
+
+/// Exponential smoothing seeded with the simple average of the first `period`
+/// values
+#[derive(Debug, Clone)]
+struct Smoothing {
+    period: usize,
+    alpha: f64,
+    count: usize,
+    seed: f64,
+    value: Option<f64>,
+}
+
+impl Smoothing {
+    /// EMA weighting, `2 / (period + 1)`
+    fn ema(period: usize) -> Self {
+        let period = period.max(1);
+        Self::with_alpha(period, 2.0 / (period as f64 + 1.0))
+    }
+
+    /// Wilder's weighting, `1 / period`, used by RSI and ATR
+    fn wilder(period: usize) -> Self {
+        let period = period.max(1);
+        Self::with_alpha(period, 1.0 / period as f64)
+    }
+
+    fn with_alpha(period: usize, alpha: f64) -> Self {
+        Self {
+            period,
+            alpha,
+            count: 0,
+            seed: 0.0,
+            value: None,
+        }
+    }
+
+    fn next(&mut self, value: f64) -> Option<f64> {
+        self.value = match self.value {
+            Some(previous) => Some(previous + self.alpha * (value - previous)),
+            None => {
+                self.count += 1;
+                self.seed += value;
+                (self.count == self.period).then(|| self.seed / self.period as f64)
+            }
+        };
+        self.value
+    }
+}
+
+/// Highest high and lowest low of the last `period` candles
+#[derive(Debug, Clone)]
+struct Extremes {
+    period: usize,
+    window: VecDeque<(f32, f32)>,
+}
+
+impl Extremes {
+    fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            window: VecDeque::new(),
+        }
+    }
+
+    fn next(&mut self, candle: &CandleData) -> Option<(f32, f32)> {
+        self.window.push_back((candle.high, candle.low));
+        if self.window.len() > self.period {
+            self.window.pop_front();
+        }
+        (self.window.len() == self.period).then(|| {
+            self.window.iter().fold(
+                (f32::NEG_INFINITY, f32::INFINITY),
+                |(high, low), &(h, l)| (high.max(h), low.min(l)),
+            )
+        })
+    }
+
+    /// Middle of the channel, the base line of Ichimoku
+    fn midpoint(&mut self, candle: &CandleData) -> Option<f32> {
+        self.next(candle).map(|(high, low)| (high + low) / 2.0)
+    }
+}
+
+/// Simple moving average
+#[derive(Debug, Clone)]
+pub struct Sma {
+    period: usize,
+    input: Input,
+    mean: Mean,
+}
+
+impl Sma {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            input: Input::Close,
+            mean: Mean::new(period),
+        }
+    }
+
+    pub fn with_input(mut self, input: Input) -> Self {
+        self.input = input;
+        self
+    }
+}
+
+impl Indicator for Sma {
+    fn kind(&self) -> &'static str {
+        "sma"
+    }
+
+    fn label(&self) -> String {
+        format!("MA{}", self.period)
+    }
+
+    fn inputs(&self) -> Vec<Input> {
+        vec![self.input]
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![param("period", self.period as u32)]
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "period" => *self = Self::new(value as usize).with_input(self.input),
+            _ => return false,
+        }
+        true
+    }
+
+    fn outputs(&self) -> Vec<Output> {
+        vec![output("ma", WHITE)]
+    }
+
+    fn style(&self) -> RenderStyle {
+        RenderStyle::Overlay
+    }
+
+    fn reset(&mut self) {
+        self.mean = Mean::new(self.period);
+    }
+
+    fn next(&mut self, candle: &CandleData) -> Vec<Option<f32>> {
+        let value = self.input.value(candle) as f64;
+        vec![self.mean.next(value).map(|average| average as f32)]
+    }
+
+    fn clone_box(&self) -> Box<dyn Indicator> {
+        Box::new(self.clone())
+    }
+}
+
+/// Exponential moving average
+#[derive(Debug, Clone)]
+pub struct Ema {
+    period: usize,
+    input: Input,
+    smoothing: Smoothing,
+}
+
+impl Ema {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            input: Input::Close,
+            smoothing: Smoothing::ema(period),
+        }
+    }
+
+    pub fn with_input(mut self, input: Input) -> Self {
+        self.input = input;
+        self
+    }
+}
+
+impl Indicator for Ema {
+    fn kind(&self) -> &'static str {
+        "ema"
+    }
+
+    fn label(&self) -> String {
+        format!("EMA{}", self.period)
+    }
+
+    fn inputs(&self) -> Vec<Input> {
+        vec![self.input]
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![param("period", self.period as u32)]
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "period" => *self = Self::new(value as usize).with_input(self.input),
+            _ => return false,
+        }
+        true
+    }
+
+    fn outputs(&self) -> Vec<Output> {
+        vec![output("ema", YELLOW)]
+    }
+
+    fn style(&self) -> RenderStyle {
+        RenderStyle::Overlay
+    }
+
+    fn reset(&mut self) {
+        self.smoothing = Smoothing::ema(self.period);
+    }
+
+    fn next(&mut self, candle: &CandleData) -> Vec<Option<f32>> {
+        let value = self.input.value(candle) as f64;
+        vec![self.smoothing.next(value).map(|ema| ema as f32)]
+    }
+
+    fn clone_box(&self) -> Box<dyn Indicator> {
+        Box::new(self.clone())
+    }
+}
+
+/// Relative strength index with Wilder's smoothing, 0 to 100
+#[derive(Debug, Clone)]
+pub struct Rsi {
+    period: usize,
+    previous: Option<f32>,
+    gain: Smoothing,
+    loss: Smoothing,
+}
+
+impl Rsi {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            previous: None,
+            gain: Smoothing::wilder(period),
+            loss: Smoothing::wilder(period),
+        }
+    }
+}
+
+impl Indicator for Rsi {
+    fn kind(&self) -> &'static str {
+        "rsi"
+    }
+
+    fn label(&self) -> String {
+        format!("RSI{}", self.period)
+    }
+
+    fn inputs(&self) -> Vec<Input> {
+        vec![Input::Close]
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![param("period", self.period as u32)]
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "period" => *self = Self::new(value as usize),
+            _ => return false,
+        }
+        true
+    }
+
+    fn outputs(&self) -> Vec<Output> {
+        vec![output("rsi", PURPLE)]
+    }
+
+    fn style(&self) -> RenderStyle {
+        RenderStyle::Pane
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.period);
+    }
+
+    fn next(&mut self, candle: &CandleData) -> Vec<Option<f32>> {
+        let Some(previous) = self.previous.replace(candle.close) else {
+            return vec![None];
+        };
+        let change = (candle.close - previous) as f64;
+        let gain = self.gain.next(change.max(0.0));
+        let loss = self.loss.next((-change).max(0.0));
+        let rsi = match (gain, loss) {
+            (Some(gain), Some(loss)) if loss > 0.0 => Some(100.0 - 100.0 / (1.0 + gain / loss)),
+            (Some(gain), Some(_)) if gain > 0.0 => Some(100.0),
+            (Some(_), Some(_)) => Some(50.0),
+            _ => None,
+        };
+        vec![rsi.map(|rsi| rsi as f32)]
+    }
+
+    fn clone_box(&self) -> Box<dyn Indicator> {
+        Box::new(self.clone())
+    }
+}
+
+/// Moving average convergence divergence: the MACD line, its signal line and
+/// the histogram between them
+#[derive(Debug, Clone)]
+pub struct Macd {
+    fast: usize,
+    slow: usize,
+    signal: usize,
+    fast_ema: Smoothing,
+    slow_ema: Smoothing,
+    signal_ema: Smoothing,
+}
+
+impl Macd {
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Self {
+        Self {
+            fast: fast.max(1),
+            slow: slow.max(1),
+            signal: signal.max(1),
+            fast_ema: Smoothing::ema(fast),
+            slow_ema: Smoothing::ema(slow),
+            signal_ema: Smoothing::ema(signal),
+        }
+    }
+}
+
+impl Indicator for Macd {
+    fn kind(&self) -> &'static str {
+        "macd"
+    }
+
+    fn label(&self) -> String {
+        format!("MACD({},{},{})", self.fast, self.slow, self.signal)
+    }
+
+    fn inputs(&self) -> Vec<Input> {
+        vec![Input::Close]
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            param("fast", self.fast as u32),
+            param("slow", self.slow as u32),
+            param("signal", self.signal as u32),
+        ]
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        let value = value as usize;
+        *self = match name {
+            "fast" => Self::new(value, self.slow, self.signal),
+            "slow" => Self::new(self.fast, value, self.signal),
+            "signal" => Self::new(self.fast, self.slow, value),
+            _ => return false,
+        };
+        true
+    }
+
+    fn outputs(&self) -> Vec<Output> {
+        vec![
+            output("macd", BLUE),
+            output("signal", ORANGE),
+            output("histogram", GREY),
+        ]
+    }
+
+    fn style(&self) -> RenderStyle {
+        RenderStyle::Pane
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.fast, self.slow, self.signal);
+    }
+
+    fn next(&mut self, candle: &CandleData) -> Vec<Option<f32>> {
+        let close = candle.close as f64;
+        let fast = self.fast_ema.next(close);
+        let slow = self.slow_ema.next(close);
+        let Some(macd) = fast.zip(slow).map(|(fast, slow)| fast - slow) else {
+            return vec![None, None, None];
+        };
+        let signal = self.signal_ema.next(macd);
+        vec![
+            Some(macd as f32),
+            signal.map(|signal| signal as f32),
+            signal.map(|signal| (macd - signal) as f32),
+        ]
+    }
+
+    fn clone_box(&self) -> Box<dyn Indicator> {
+        Box::new(self.clone())
+    }
+}
+
+/// Bollinger bands: a simple moving average with bands `multiplier` standard
+/// deviations above and below
+#[derive(Debug, Clone)]
+pub struct Bollinger {
+    period: usize,
+    multiplier: f32,
+    window: VecDeque<f64>,
+    sum: f64,
+    sum_squares: f64,
+}
+
+impl Bollinger {
+    pub fn new(period: usize, multiplier: f32) -> Self {
+        Self {
+            period: period.max(1),
+            multiplier,
+            window: VecDeque::new(),
+            sum: 0.0,
+            sum_squares: 0.0,
+        }
+    }
+}
+
+impl Indicator for Bollinger {
+    fn kind(&self) -> &'static str {
+        "bollinger"
+    }
+
+    fn label(&self) -> String {
+        format!("BB({},{})", self.period, self.multiplier)
+    }
+
+    fn inputs(&self) -> Vec<Input> {
+        vec![Input::Close]
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            param("period", self.period as u32),
+            param("multiplier", self.multiplier),
+        ]
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        *self = match name {
+            "period" => Self::new(value as usize, self.multiplier),
+            "multiplier" => Self::new(self.period, value),
+            _ => return false,
+        };
+        true
+    }
+
+    fn outputs(&self) -> Vec<Output> {
+        vec![
+            output("middle", ORANGE),
+            output("upper", BLUE),
+            output("lower", BLUE),
+        ]
+    }
+
+    fn style(&self) -> RenderStyle {
+        RenderStyle::Overlay
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.period, self.multiplier);
+    }
+
+    fn next(&mut self, candle: &CandleData) -> Vec<Option<f32>> {
+        let close = candle.close as f64;
+        self.window.push_back(close);
+        self.sum += close;
+        self.sum_squares += close * close;
+        if self.window.len() > self.period
+            && let Some(oldest) = self.window.pop_front()
+        {
+            self.sum -= oldest;
+            self.sum_squares -= oldest * oldest;
+        }
+        if self.window.len() < self.period {
+            return vec![None, None, None];
+        }
+
+        let n = self.period as f64;
+        let mean = self.sum / n;
+        let deviation = (self.sum_squares / n - mean * mean).max(0.0).sqrt();
+        let band = self.multiplier as f64 * deviation;
+        vec![
+            Some(mean as f32),
+            Some((mean + band) as f32),
+            Some((mean - band) as f32),
+        ]
+    }
+
+    fn clone_box(&self) -> Box<dyn Indicator> {
+        Box::new(self.clone())
+    }
+}
+
+/// Stochastic oscillator: %K smoothed over `smooth` candles and its average %D,
+/// 0 to 100
+#[derive(Debug, Clone)]
+pub struct Stochastic {
+    period: usize,
+    smooth: usize,
+    signal: usize,
+    extremes: Extremes,
+    k: Mean,
+    d: Mean,
+}
+
+impl Stochastic {
+    pub fn new(period: usize, smooth: usize, signal: usize) -> Self {
+        Self {
+            period: period.max(1),
+            smooth: smooth.max(1),
+            signal: signal.max(1),
+            extremes: Extremes::new(period),
+            k: Mean::new(smooth),
+            d: Mean::new(signal),
+        }
+    }
+}
+
+impl Indicator for Stochastic {
+    fn kind(&self) -> &'static str {
+        "stochastic"
+    }
+
+    fn label(&self) -> String {
+        format!("Stoch({},{},{})", self.period, self.smooth, self.signal)
+    }
+
+    fn inputs(&self) -> Vec<Input> {
+        vec![Input::High, Input::Low, Input::Close]
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            param("period", self.period as u32),
+            param("smooth", self.smooth as u32),
+            param("signal", self.signal as u32),
+        ]
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        let value = value as usize;
+        *self = match name {
+            "period" => Self::new(value, self.smooth, self.signal),
+            "smooth" => Self::new(self.period, value, self.signal),
+            "signal" => Self::new(self.period, self.smooth, value),
+            _ => return false,
+        };
+        true
+    }
+
+    fn outputs(&self) -> Vec<Output> {
+        vec![output("k", BLUE), output("d", ORANGE)]
+    }
+
+    fn style(&self) -> RenderStyle {
+        RenderStyle::Pane
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.period, self.smooth, self.signal);
+    }
+
+    fn next(&mut self, candle: &CandleData) -> Vec<Option<f32>> {
+        let Some((high, low)) = self.extremes.next(candle) else {
+            return vec![None, None];
+        };
+        // A flat range has no position in it, sit in the middle
+        let raw = if high > low {
+            ((candle.close - low) / (high - low) * 100.0) as f64
+        } else {
+            50.0
+        };
+        let Some(k) = self.k.next(raw) else {
+            return vec![None, None];
+        };
+        let d = self.d.next(k);
+        vec![Some(k as f32), d.map(|d| d as f32)]
+    }
+
+    fn clone_box(&self) -> Box<dyn Indicator> {
+        Box::new(self.clone())
+    }
+}
+
+/// Average true range with Wilder's smoothing
+#[derive(Debug, Clone)]
+pub struct Atr {
+    period: usize,
+    previous_close: Option<f32>,
+    smoothing: Smoothing,
+}
+
+impl Atr {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            previous_close: None,
+            smoothing: Smoothing::wilder(period),
+        }
+    }
+}
+
+impl Indicator for Atr {
+    fn kind(&self) -> &'static str {
+        "atr"
+    }
+
+    fn label(&self) -> String {
+        format!("ATR{}", self.period)
+    }
+
+    fn inputs(&self) -> Vec<Input> {
+        vec![Input::High, Input::Low, Input::Close]
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![param("period", self.period as u32)]
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "period" => *self = Self::new(value as usize),
+            _ => return false,
+        }
+        true
+    }
+
+    fn outputs(&self) -> Vec<Output> {
+        vec![output("atr", RED)]
+    }
+
+    fn style(&self) -> RenderStyle {
+        RenderStyle::Pane
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.period);
+    }
+
+    fn next(&mut self, candle: &CandleData) -> Vec<Option<f32>> {
+        let range = candle.high - candle.low;
+        let true_range = match self.previous_close.replace(candle.close) {
+            Some(close) => range
+                .max((candle.high - close).abs())
+                .max((candle.low - close).abs()),
+            None => range,
+        };
+        vec![self.smoothing.next(true_range as f64).map(|atr| atr as f32)]
+    }
+
+    fn clone_box(&self) -> Box<dyn Indicator> {
+        Box::new(self.clone())
+    }
+}
+
+/// Volume weighted average price of the typical price, starting over at each
+/// trading day (Vietnam time)
+#[derive(Debug, Clone, Default)]
+pub struct Vwap {
+    session: Option<NaiveDate>,
+    value: f64,
+    volume: f64,
+}
+
+impl Vwap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Indicator for Vwap {
+    fn kind(&self) -> &'static str {
+        "vwap"
+    }
+
+    fn label(&self) -> String {
+        "VWAP".to_string()
+    }
+
+    fn inputs(&self) -> Vec<Input> {
+        vec![Input::Typical, Input::Volume]
+    }
+
+    fn params(&self) -> Vec<Param> {
+        Vec::new()
+    }
+
+    fn set_param(&mut self, _name: &str, _value: f32) -> bool {
+        false
+    }
+
+    fn outputs(&self) -> Vec<Output> {
+        vec![output("vwap", ORANGE)]
+    }
+
+    fn style(&self) -> RenderStyle {
+        RenderStyle::Overlay
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    fn next(&mut self, candle: &CandleData) -> Vec<Option<f32>> {
+        let session = candle.time.with_timezone(&vn_offset()).date_naive();
+        if self.session != Some(session) {
+            *self = Self {
+                session: Some(session),
+                ..Self::default()
+            };
+        }
+        let typical = Input::Typical.value(candle) as f64;
+        self.value += typical * candle.volume as f64;
+        self.volume += candle.volume as f64;
+        // Before the first trade of the day there is nothing to weigh
+        let vwap = if self.volume > 0.0 {
+            self.value / self.volume
+        } else {
+            typical
+        };
+        vec![Some(vwap as f32)]
+    }
+
+    fn clone_box(&self) -> Box<dyn Indicator> {
+        Box::new(self.clone())
+    }
+}
+
+/// On-balance volume, the running sum of volume signed by the close to close
+/// change
+#[derive(Debug, Clone, Default)]
+pub struct Obv {
+    previous_close: Option<f32>,
+    total: f64,
+}
+
+impl Obv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Indicator for Obv {
+    fn kind(&self) -> &'static str {
+        "obv"
+    }
+
+    fn label(&self) -> String {
+        "OBV".to_string()
+    }
+
+    fn inputs(&self) -> Vec<Input> {
+        vec![Input::Close, Input::Volume]
+    }
+
+    fn params(&self) -> Vec<Param> {
+        Vec::new()
+    }
+
+    fn set_param(&mut self, _name: &str, _value: f32) -> bool {
+        false
+    }
+
+    fn outputs(&self) -> Vec<Output> {
+        vec![output("obv", GREEN)]
+    }
+
+    fn style(&self) -> RenderStyle {
+        RenderStyle::Pane
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    fn next(&mut self, candle: &CandleData) -> Vec<Option<f32>> {
+        if let Some(previous) = self.previous_close.replace(candle.close) {
+            if candle.close > previous {
+                self.total += candle.volume as f64;
+            } else if candle.close < previous {
+                self.total -= candle.volume as f64;
+            }
+        }
+        vec![Some(self.total as f32)]
+    }
+
+    fn clone_box(&self) -> Box<dyn Indicator> {
+        Box::new(self.clone())
+    }
+}
+
+/// Ichimoku cloud
+///
+/// The leading spans are computed at each candle and drawn `displacement`
+/// candles ahead, the lagging span is the close drawn `displacement` candles
+/// back, see [`Output::offset`].
+#[derive(Debug, Clone)]
+pub struct Ichimoku {
+    conversion: usize,
+    base: usize,
+    span: usize,
+    displacement: usize,
+    conversion_line: Extremes,
+    base_line: Extremes,
+    span_b: Extremes,
+}
+
+impl Ichimoku {
+    pub fn new(conversion: usize, base: usize, span: usize, displacement: usize) -> Self {
+        Self {
+            conversion: conversion.max(1),
+            base: base.max(1),
+            span: span.max(1),
+            displacement,
+            conversion_line: Extremes::new(conversion),
+            base_line: Extremes::new(base),
+            span_b: Extremes::new(span),
+        }
+    }
+}
+
+impl Indicator for Ichimoku {
+    fn kind(&self) -> &'static str {
+        "ichimoku"
+    }
+
+    fn label(&self) -> String {
+        format!(
+            "Ichimoku({},{},{},{})",
+            self.conversion, self.base, self.span, self.displacement
+        )
+    }
+
+    fn inputs(&self) -> Vec<Input> {
+        vec![Input::High, Input::Low, Input::Close]
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            param("conversion", self.conversion as u32),
+            param("base", self.base as u32),
+            param("span", self.span as u32),
+            param("displacement", self.displacement as u32),
+        ]
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> bool {
+        let value = value as usize;
+        let (conversion, base, span, displacement) =
+            (self.conversion, self.base, self.span, self.displacement);
+        *self = match name {
+            "conversion" => Self::new(value, base, span, displacement),
+            "base" => Self::new(conversion, value, span, displacement),
+            "span" => Self::new(conversion, base, value, displacement),
+            "displacement" => Self::new(conversion, base, span, value),
+            _ => return false,
+        };
+        true
+    }
+
+    fn outputs(&self) -> Vec<Output> {
+        let ahead = self.displacement as i32;
+        vec![
+            output("conversion", BLUE),
+            output("base", RED),
+            Output {
+                offset: ahead,
+                ..output("span_a", GREEN)
+            },
+            Output {
+                offset: ahead,
+                ..output("span_b", ORANGE)
+            },
+            Output {
+                offset: -ahead,
+                ..output("lagging", PURPLE)
+            },
+        ]
+    }
+
+    fn style(&self) -> RenderStyle {
+        RenderStyle::Overlay
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.conversion, self.base, self.span, self.displacement);
+    }
+
+    fn next(&mut self, candle: &CandleData) -> Vec<Option<f32>> {
+        let conversion = self.conversion_line.midpoint(candle);
+        let base = self.base_line.midpoint(candle);
+        let span_a = conversion
+            .zip(base)
+            .map(|(conversion, base)| (conversion + base) / 2.0);
+        let span_b = self.span_b.midpoint(candle);
+        vec![conversion, base, span_a, span_b, Some(candle.close)]
+    }
+
+    fn clone_box(&self) -> Box<dyn Indicator> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicator::run;
+    use crate::test_support::{candles, day};
+    use chrono::Duration;
+
+    fn closes(closes: &[f32]) -> Vec<CandleData> {
+        let bars: Vec<_> = closes.iter().map(|&c| (c, c, c, 1000.0)).collect();
+        candles(&bars)
+    }
+
+    fn series(
+        indicator: &mut dyn Indicator,
+        data: &[CandleData],
+        output: usize,
+    ) -> Vec<Option<f32>> {
+        run(indicator, data)
+            .into_iter()
+            .map(|values| values[output])
+            .collect()
+    }
+
+    fn assert_close(actual: Option<f32>, expected: f32) {
+        let actual = actual.expect("value");
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "{actual} is not {expected}"
+        );
+    }
+
+    #[test]
+    fn test_ema_seeds_with_average() {
+        let values = series(
+            &mut Ema::new(3),
+            &closes(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]),
+            0,
+        );
+        assert_eq!(
+            values,
+            vec![None, None, Some(2.0), Some(3.0), Some(4.0), Some(5.0)]
+        );
+    }
+
+    #[test]
+    fn test_rsi_wilder_smoothing() {
+        let values = series(&mut Rsi::new(2), &closes(&[1.0, 2.0, 1.0, 2.0, 1.0]), 0);
+        assert_eq!(&values[..2], &[None, None]);
+        assert_close(values[2], 50.0);
+        assert_close(values[3], 75.0);
+        assert_close(values[4], 37.5);
+
+        let rising = series(&mut Rsi::new(2), &closes(&[1.0, 2.0, 3.0, 4.0]), 0);
+        assert_close(rising[3], 100.0);
+    }
+
+    #[test]
+    fn test_macd_of_linear_trend() {
+        let data = closes(&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+        let values = run(&mut Macd::new(2, 3, 2), &data);
+        assert_eq!(values[1], vec![None, None, None]);
+        assert_close(values[2][0], 0.5);
+        assert_eq!(values[2][1], None);
+        assert_close(values[3][1], 0.5);
+        assert_close(values[5][2], 0.0);
+    }
+
+    #[test]
+    fn test_bollinger_bands() {
+        let values = run(
+            &mut Bollinger::new(4, 2.0),
+            &closes(&[1.0, 2.0, 3.0, 4.0, 5.0]),
+        );
+        assert_eq!(values[2], vec![None, None, None]);
+        let deviation = 1.25f32.sqrt();
+        assert_close(values[3][0], 2.5);
+        assert_close(values[3][1], 2.5 + 2.0 * deviation);
+        assert_close(values[3][2], 2.5 - 2.0 * deviation);
+        assert_close(values[4][0], 3.5);
+    }
+
+    #[test]
+    fn test_stochastic_position_in_range() {
+        let data = candles(&[
+            (10.0, 8.0, 9.0, 0.0),
+            (12.0, 9.0, 11.0, 0.0),
+            (11.0, 7.0, 8.0, 0.0),
+            (13.0, 10.0, 13.0, 0.0),
+        ]);
+        let values = run(&mut Stochastic::new(3, 1, 2), &data);
+        assert_eq!(values[1], vec![None, None]);
+        // 8 in 7..12
+        assert_close(values[2][0], 20.0);
+        assert_eq!(values[2][1], None);
+        // 13 in 7..13
+        assert_close(values[3][0], 100.0);
+        assert_close(values[3][1], 60.0);
+    }
+
+    #[test]
+    fn test_atr_uses_previous_close() {
+        let data = candles(&[
+            (10.0, 8.0, 9.0, 0.0),
+            (12.0, 11.0, 11.5, 0.0),
+            (11.0, 10.0, 10.0, 0.0),
+        ]);
+        let values = series(&mut Atr::new(2), &data, 0);
+        // True ranges: 2, 3 (12 - 9), 1.5 (11.5 - 10)
+        assert_eq!(values[0], None);
+        assert_close(values[1], 2.5);
+        assert_close(values[2], 2.0);
+    }
+
+    #[test]
+    fn test_vwap_restarts_each_day() {
+        let bar = |minutes: i64, price: f32, volume: f32| CandleData {
+            num: 0.0,
+            time: day(0) + Duration::minutes(minutes),
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume,
+        };
+        // 02:00 UTC is 09:00 in Vietnam, the next session starts a day later
+        let data = vec![
+            bar(120, 10.0, 100.0),
+            bar(121, 20.0, 300.0),
+            bar(120 + 24 * 60, 30.0, 50.0),
+        ];
+        let values = series(&mut Vwap::new(), &data, 0);
+        assert_close(values[0], 10.0);
+        assert_close(values[1], 17.5);
+        assert_close(values[2], 30.0);
+    }
+
+    #[test]
+    fn test_obv_signs_volume() {
+        let data = candles(&[
+            (1.0, 1.0, 1.0, 10.0),
+            (2.0, 2.0, 2.0, 20.0),
+            (2.0, 2.0, 2.0, 30.0),
+            (1.0, 1.0, 1.0, 40.0),
+        ]);
+        let values = series(&mut Obv::new(), &data, 0);
+        assert_eq!(values, vec![Some(0.0), Some(20.0), Some(20.0), Some(-20.0)]);
+    }
+
+    #[test]
+    fn test_ichimoku_lines_and_offsets() {
+        let data = candles(&[
+            (10.0, 6.0, 8.0, 0.0),
+            (12.0, 8.0, 9.0, 0.0),
+            (14.0, 9.0, 13.0, 0.0),
+        ]);
+        let mut ichimoku = Ichimoku::new(2, 3, 3, 2);
+        let values = run(&mut ichimoku, &data);
+        assert_eq!(values[0][0], None);
+        // Conversion over the last 2 candles: (14 + 8) / 2
+        assert_close(values[2][0], 11.0);
+        // Base over 3 candles: (14 + 6) / 2
+        assert_close(values[2][1], 10.0);
+        assert_close(values[2][2], 10.5);
+        assert_close(values[2][4], 13.0);
+
+        let offsets: Vec<_> = ichimoku.outputs().iter().map(|o| o.offset).collect();
+        assert_eq!(offsets, vec![0, 0, 2, 2, -2]);
+    }
+}
@@ -0,0 +1,309 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use plotters::style::RGBColor;
+
+use crate::CandleData;
+
+mod library;
+
+pub use library::{Atr, Bollinger, Ema, Ichimoku, Macd, Obv, Rsi, Sma, Stochastic, Vwap};
+
+/// Candle field an indicator reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Open,
+    High,
+    Low,
+    Close,
+    Volume,
+    /// (high + low + close) / 3
+    Typical,
+}
+
+impl Input {
+    pub fn value(&self, candle: &CandleData) -> f32 {
+        match self {
+            Input::Open => candle.open,
+            Input::High => candle.high,
+            Input::Low => candle.low,
+            Input::Close => candle.close,
+            Input::Volume => candle.volume,
+            Input::Typical => (candle.high + candle.low + candle.close) / 3.0,
+        }
+    }
+}
+
+/// Where the outputs of an indicator are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderStyle {
+    /// On top of the candles, sharing the price scale
+    Overlay,
+    /// Below the candles, with a scale of its own
+    Pane,
+}
+
+/// Tunable parameter of an indicator, e.g. the period of a moving average
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: &'static str,
+    pub value: f32,
+}
+
+/// One series produced by an indicator, with its default color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Output {
+    pub name: &'static str,
+    pub color: RGBColor,
+    /// Candles the series is drawn ahead of (positive) or behind (negative)
+    /// the candle it was computed at, e.g. the Ichimoku spans
+    pub offset: i32,
+}
+
+/// A technical study computed from the candles of a chart
+///
+/// Indicators are fed one candle at a time with [`Indicator::next`] and keep
+/// whatever state they need, so appending a bar costs one step instead of a
+/// pass over the whole history. [`IndicatorInstance`] drives them for a chart.
+pub trait Indicator: fmt::Debug + Send + Sync {
+    /// Identifier the indicator is saved under, see [`create`]
+    fn kind(&self) -> &'static str;
+
+    /// Legend label, e.g. `MA20`
+    fn label(&self) -> String;
+
+    /// Candle fields the indicator reads
+    fn inputs(&self) -> Vec<Input>;
+
+    fn params(&self) -> Vec<Param>;
+
+    /// Change a parameter and forget the candles seen so far, false if the
+    /// indicator has no parameter called `name`
+    fn set_param(&mut self, name: &str, value: f32) -> bool;
+
+    /// Series returned by `next`, in the same order
+    fn outputs(&self) -> Vec<Output>;
+
+    fn style(&self) -> RenderStyle;
+
+    /// Forget the candles seen so far
+    fn reset(&mut self);
+
+    /// Feed the next candle, returns one value per output (`None` while the
+    /// indicator has not seen enough candles)
+    fn next(&mut self, candle: &CandleData) -> Vec<Option<f32>>;
+
+    fn clone_box(&self) -> Box<dyn Indicator>;
+}
+
+impl Clone for Box<dyn Indicator> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Build an indicator with its default parameters from its saved `kind`
+pub fn create(kind: &str) -> Option<Box<dyn Indicator>> {
+    match kind {
+        "sma" => Some(Box::new(Sma::new(20))),
+        "ema" => Some(Box::new(Ema::new(20))),
+        "rsi" => Some(Box::new(Rsi::new(14))),
+        "macd" => Some(Box::new(Macd::new(12, 26, 9))),
+        "bollinger" => Some(Box::new(Bollinger::new(20, 2.0))),
+        "stochastic" => Some(Box::new(Stochastic::new(14, 3, 3))),
+        "atr" => Some(Box::new(Atr::new(14))),
+        "vwap" => Some(Box::new(Vwap::new())),
+        "obv" => Some(Box::new(Obv::new())),
+        "ichimoku" => Some(Box::new(Ichimoku::new(9, 26, 52, 26))),
+        _ => None,
+    }
+}
+
+/// Every kind [`create`] knows, in the order a picker would list them
+pub const KINDS: [&str; 10] = [
+    "sma",
+    "ema",
+    "bollinger",
+    "vwap",
+    "ichimoku",
+    "rsi",
+    "macd",
+    "stochastic",
+    "atr",
+    "obv",
+];
+
+/// Feed `candles` to `indicator` from a fresh state, returns the outputs of
+/// every candle
+///
+/// This is the way to use a study away from a chart, e.g. to score a watchlist:
+/// `run(&mut Rsi::new(14), &candles).last()`.
+pub fn run(indicator: &mut dyn Indicator, candles: &[CandleData]) -> Vec<Vec<Option<f32>>> {
+    indicator.reset();
+    candles
+        .iter()
+        .map(|candle| indicator.next(candle))
+        .collect()
+}
+
+/// An indicator configured on a chart, with the values computed for its candles
+#[derive(Debug, Clone)]
+pub struct IndicatorInstance {
+    pub indicator: Box<dyn Indicator>,
+    /// Color of each output
+    pub colors: Vec<RGBColor>,
+    pub visible: bool,
+    /// Outputs of every candle computed so far
+    values: Vec<Vec<Option<f32>>>,
+    /// State before the last computed candle, which may still be forming
+    checkpoint: Option<Box<dyn Indicator>>,
+    first_time: Option<DateTime<Utc>>,
+    last_time: Option<DateTime<Utc>>,
+}
+
+impl IndicatorInstance {
+    pub fn new(indicator: Box<dyn Indicator>) -> Self {
+        let colors = indicator
+            .outputs()
+            .iter()
+            .map(|output| output.color)
+            .collect();
+        Self {
+            indicator,
+            colors,
+            visible: true,
+            values: Vec::new(),
+            checkpoint: None,
+            first_time: None,
+            last_time: None,
+        }
+    }
+
+    pub fn with_colors(mut self, colors: Vec<RGBColor>) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    pub fn set_param(&mut self, name: &str, value: f32) -> bool {
+        let changed = self.indicator.set_param(name, value);
+        if changed {
+            self.invalidate();
+        }
+        changed
+    }
+
+    /// Drop the computed values, they are computed again on the next `update`
+    pub fn invalidate(&mut self) {
+        self.values.clear();
+        self.checkpoint = None;
+        self.first_time = None;
+        self.last_time = None;
+    }
+
+    /// Bring the values in line with `candles`
+    ///
+    /// When the candles only changed at the end (the last bar updated, new bars
+    /// appended) the indicator resumes from its state before the last computed
+    /// candle. Any other change (older history prepended, another timeframe)
+    /// computes everything again.
+    pub fn update(&mut self, candles: &[CandleData]) {
+        let computed = self.values.len();
+        let resumable = computed > 0
+            && computed <= candles.len()
+            && self.first_time == candles.first().map(|candle| candle.time)
+            && self.last_time == Some(candles[computed - 1].time);
+
+        match self.checkpoint.take() {
+            Some(state) if resumable => {
+                self.indicator = state;
+                self.values.truncate(computed - 1);
+            }
+            _ => {
+                self.indicator.reset();
+                self.values.clear();
+            }
+        }
+
+        let start = self.values.len();
+        for (i, candle) in candles.iter().enumerate().skip(start) {
+            if i + 1 == candles.len() {
+                self.checkpoint = Some(self.indicator.clone_box());
+            }
+            self.values.push(self.indicator.next(candle));
+        }
+        self.first_time = candles.first().map(|candle| candle.time);
+        self.last_time = candles.last().map(|candle| candle.time);
+    }
+
+    fn offset(&self, output: usize) -> i32 {
+        self.indicator
+            .outputs()
+            .get(output)
+            .map_or(0, |output| output.offset)
+    }
+
+    /// Value of `output` drawn at candle `index`
+    pub fn value(&self, index: usize, output: usize) -> Option<f32> {
+        let computed_at = usize::try_from(index as i64 - self.offset(output) as i64).ok()?;
+        *self.values.get(computed_at)?.get(output)?
+    }
+
+    /// `(candle index, value)` points of `output` for the candles `from..to`,
+    /// shifted by the output offset
+    pub fn points(&self, output: usize, from: usize, to: usize) -> Vec<(f32, f32)> {
+        let offset = self.offset(output) as i64;
+        let first = (from as i64 - offset).max(0) as usize;
+        let last = (to as i64 - offset).clamp(0, self.values.len() as i64) as usize;
+        (first..last)
+            .filter_map(|i| {
+                let value = (*self.values[i].get(output)?)?;
+                Some(((i as i64 + offset) as f32, value))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::daily_candles as candles;
+    use chrono::Duration;
+
+    #[test]
+    fn test_sma_warms_up() {
+        let mut sma = Sma::new(3);
+        let values: Vec<_> = candles(&[1.0, 2.0, 3.0, 4.0])
+            .iter()
+            .map(|candle| sma.next(candle)[0])
+            .collect();
+        assert_eq!(values, vec![None, None, Some(2.0), Some(3.0)]);
+    }
+
+    #[test]
+    fn test_instance_resumes_after_last_candle() {
+        let mut instance = IndicatorInstance::new(Box::new(Sma::new(2)));
+        let mut data = candles(&[1.0, 2.0, 3.0]);
+        instance.update(&data);
+        assert_eq!(instance.value(2, 0), Some(2.5));
+
+        // The forming candle changes and a new one is appended
+        data[2].close = 5.0;
+        data.extend(candles(&[0.0, 0.0, 0.0, 7.0]).into_iter().skip(3));
+        instance.update(&data);
+        assert_eq!(instance.value(2, 0), Some(3.5));
+        assert_eq!(instance.value(3, 0), Some(6.0));
+
+        // Older candles shift every index, everything is computed again
+        let older = candles(&[9.0, 1.0, 2.0, 5.0, 7.0]);
+        let shifted: Vec<_> = older
+            .iter()
+            .map(|candle| CandleData {
+                time: candle.time - Duration::days(1),
+                ..candle.clone()
+            })
+            .collect();
+        instance.update(&shifted);
+        assert_eq!(instance.value(1, 0), Some(5.0));
+        assert_eq!(instance.points(0, 0, 10).len(), 4);
+    }
+}
@@ -0,0 +1,90 @@
+//! Layers of the chart image
+//!
+//! Drawing every candle, study and drawing is most of the cost of a frame,
+//! while most frames only move the mouse. These are drawn into a base layer
+//! kept offscreen, drawn again only once the data or the view changes. Each
+//! frame starts from a copy of the base layer and draws the crosshair, the
+//! labels following the mouse and the drawing in progress over it.
+
+use std::fmt;
+
+/// Part of the image drawn by a pass of `Chart::draw_layer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Layer {
+    /// Candles, studies, saved drawings, axes and latest values
+    Base,
+    /// Crosshair, candle info, cursor labels and the drawing in progress
+    Top,
+}
+
+/// What the base layer shows, it is drawn again for any other key. Changes
+/// of the drawings and of their selection bump the revision instead
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BaseKey {
+    /// Revision of the shown data, see [`Layers::invalidate`]
+    pub revision: u64,
+    /// Width and height of the image in pixels
+    pub size: (u32, u32),
+    /// Pixel rows of the panes, the price pane first
+    pub bounds: Vec<(u32, u32)>,
+    /// Candles and axis values in view: min x, max x, min y, max y
+    pub view: [f32; 4],
+    /// View at the last release and the candle distance it gives
+    pub offsets: [f32; 5],
+    /// Pan since the press, the x-axis labels follow it
+    pub move_x: i32,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Layers {
+    revision: u64,
+    base: Option<(BaseKey, Vec<u8>)>,
+    /// Times the base layer was drawn, for the tests
+    #[cfg(test)]
+    pub(crate) redraws: u64,
+}
+
+impl Layers {
+    /// Draw the base layer again at the next frame, after a change of the
+    /// candles, studies, drawings or other data it shows
+    pub fn invalidate(&mut self) {
+        self.revision += 1;
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// RGB pixels of the base layer if it was drawn for `key`
+    pub fn base(&self, key: &BaseKey) -> Option<&[u8]> {
+        self.base
+            .as_ref()
+            .filter(|(shown, _)| shown == key)
+            .map(|(_, pixels)| pixels.as_slice())
+    }
+
+    /// Keep `pixels` as the base layer drawn for `key`
+    pub fn store(&mut self, key: BaseKey, pixels: &[u8]) {
+        let mut buffer = self
+            .base
+            .take()
+            .map(|(_, buffer)| buffer)
+            .unwrap_or_default();
+        buffer.clear();
+        buffer.extend_from_slice(pixels);
+        self.base = Some((key, buffer));
+        #[cfg(test)]
+        {
+            self.redraws += 1;
+        }
+    }
+}
+
+impl fmt::Debug for Layers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Layers")
+            .field("revision", &self.revision)
+            .field("base", &self.base.as_ref().map(|(key, _)| key.size))
+            .finish()
+    }
+}
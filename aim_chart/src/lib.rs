@@ -1,11 +1,26 @@
+pub mod alert;
 mod cache;
 mod chart;
 mod company_info;
+pub mod compare;
 mod draw;
+pub mod grid;
+pub mod history;
+pub mod indicator;
+mod layer;
 pub mod mini_chart;
-pub use chart::Chart;
+pub mod pane;
+pub mod price_scale;
+pub mod selection;
+pub mod style;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod time_scale;
+pub use cache::CACHE_VERSION;
+pub use chart::{Chart, vn_offset};
 use chrono::{DateTime, Utc};
 pub use company_info::CompanyInfo;
+pub use draw::{LineStyle, TextNote};
 
 use aim_data::Candlestick;
 use slint::Color;
@@ -22,6 +37,12 @@ pub enum MouseType {
     Text,
     HorizontalLine,
     VerticalLine,
+    FibRetracement,
+    FibExtension,
+    Channel,
+    Pitchfork,
+    PriceRange,
+    DateRange,
 }
 
 #[derive(Debug, Clone)]
@@ -44,8 +65,22 @@ pub struct UiData {
     pub is_new_stock: bool,
     pub is_in_object: bool,
     pub is_undo: bool,
+    pub is_redo: bool,
     pub is_in_update: bool,
     pub color: Color,
+    pub text: String,
+    pub font_size: i32,
+    pub text_background: bool,
+    pub is_delete: bool,
+    pub is_restyle: bool,
+    pub is_multi_select: bool,
+    pub line_style: i32,
+    pub line_width: i32,
+    pub scale_mode: i32,
+    pub is_rescale: bool,
+    pub chart_style: i32,
+    pub box_size: f32,
+    pub is_new_chart_style: bool,
 }
 
 impl Default for UiData {
@@ -69,8 +104,22 @@ impl Default for UiData {
             is_new_stock: false,
             is_in_object: false,
             is_undo: false,
+            is_redo: false,
             is_in_update: false,
             color: Color::default(),
+            text: String::new(),
+            font_size: 0,
+            text_background: false,
+            is_delete: false,
+            is_restyle: false,
+            is_multi_select: false,
+            line_style: 0,
+            line_width: 1,
+            scale_mode: 0,
+            is_rescale: false,
+            chart_style: 0,
+            box_size: 0.0,
+            is_new_chart_style: false,
         }
     }
 }
@@ -107,7 +156,7 @@ impl CandleData {
     }
 
     pub fn read_from_bytes(data: &[u8]) -> Option<(Self, usize)> {
-        if data.len() < 4 + 8 + 4 * 6 {
+        if data.len() < 4 + 8 + 4 * 5 {
             return None;
         }
         let mut pos = 0;
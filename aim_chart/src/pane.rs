@@ -0,0 +1,285 @@
+//! Sub-panes stacked under the price pane
+//!
+//! Every pane shares the x-axis of the price pane and has a y-scale of its
+//! own. Heights are fractions of the plot height (the image without the x-axis
+//! labels) so the layout survives window resizes, the price pane takes what the
+//! sub-panes leave.
+
+use crate::indicator::{IndicatorInstance, RenderStyle};
+
+/// Smallest height of a sub-pane, as a fraction of the plot height
+const MIN_PANE_HEIGHT: f32 = 0.05;
+/// Smallest height of the price pane
+const MIN_PRICE_HEIGHT: f32 = 0.3;
+/// Distance in pixels from a separator at which a press grabs it
+const SEPARATOR_GRAB: i32 = 4;
+
+/// What a sub-pane shows
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaneKind {
+    Volume,
+    /// A pane-style study, by index in `Chart::indicators`
+    Indicator(usize),
+    /// A series supplied with `Chart::set_series`, e.g. the foreign net flow
+    Series(String),
+}
+
+impl PaneKind {
+    fn default_height(&self) -> f32 {
+        match self {
+            PaneKind::Volume => 0.15,
+            PaneKind::Indicator(_) => 0.18,
+            PaneKind::Series(_) => 0.15,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pane {
+    pub kind: PaneKind,
+    /// Fraction of the plot height
+    pub height: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct PaneLayout {
+    /// Sub-panes from top to bottom
+    pub panes: Vec<Pane>,
+    /// Height in pixels of the whole image, known after the first render
+    pub(crate) image_height: u32,
+    /// Separator being dragged and the heights when the drag started
+    resizing: Option<(usize, Vec<f32>)>,
+}
+
+impl Default for PaneLayout {
+    /// Volume under the price pane
+    fn default() -> Self {
+        let mut layout = Self::empty();
+        layout.add(PaneKind::Volume);
+        layout
+    }
+}
+
+impl PaneLayout {
+    /// Only the price pane
+    pub fn empty() -> Self {
+        Self {
+            panes: Vec::new(),
+            image_height: 0,
+            resizing: None,
+        }
+    }
+
+    /// Append a pane at its default height, shrinking the others if the price
+    /// pane would get too small
+    pub fn add(&mut self, kind: PaneKind) {
+        let height = kind.default_height();
+        self.panes.push(Pane { kind, height });
+        self.fit();
+    }
+
+    /// Remove the panes showing `kind`
+    pub fn remove(&mut self, kind: &PaneKind) {
+        self.panes.retain(|pane| &pane.kind != kind);
+    }
+
+    pub fn contains(&self, kind: &PaneKind) -> bool {
+        self.panes.iter().any(|pane| &pane.kind == kind)
+    }
+
+    /// Forget the pane of the study at `index`, the studies after it move up
+    pub fn remove_indicator(&mut self, index: usize) {
+        self.remove(&PaneKind::Indicator(index));
+        for pane in &mut self.panes {
+            if let PaneKind::Indicator(i) = &mut pane.kind
+                && *i > index
+            {
+                *i -= 1;
+            }
+        }
+    }
+
+    /// Give every pane-style study a pane and drop the panes of studies that
+    /// are gone or drawn over the candles
+    pub fn sync(&mut self, indicators: &[IndicatorInstance]) {
+        let is_pane = |index: usize| {
+            indicators
+                .get(index)
+                .is_some_and(|i| i.indicator.style() == RenderStyle::Pane)
+        };
+        self.panes.retain(|pane| match pane.kind {
+            PaneKind::Indicator(index) => is_pane(index),
+            _ => true,
+        });
+        for index in (0..indicators.len()).filter(|&index| is_pane(index)) {
+            if !self.contains(&PaneKind::Indicator(index)) {
+                self.add(PaneKind::Indicator(index));
+            }
+        }
+    }
+
+    fn fit(&mut self) {
+        let total: f32 = self.panes.iter().map(|pane| pane.height).sum();
+        if total > 1.0 - MIN_PRICE_HEIGHT {
+            let scale = (1.0 - MIN_PRICE_HEIGHT) / total;
+            for pane in &mut self.panes {
+                pane.height *= scale;
+            }
+        }
+    }
+
+    /// `(top, bottom)` pixel rows of the price pane then of every sub-pane, for
+    /// a plot `plot_height` pixels high
+    pub fn bounds(&self, plot_height: u32) -> Vec<(u32, u32)> {
+        let mut bottom = plot_height;
+        let mut bounds = Vec::with_capacity(self.panes.len() + 1);
+        for pane in self.panes.iter().rev() {
+            let top = bottom.saturating_sub((pane.height * plot_height as f32).round() as u32);
+            bounds.push((top, bottom));
+            bottom = top;
+        }
+        bounds.push((0, bottom));
+        bounds.reverse();
+        bounds
+    }
+
+    /// Index of the pane under pixel row `y`, 0 for the price pane
+    pub fn pane_at(&self, y: i32, plot_height: u32) -> Option<usize> {
+        self.bounds(plot_height)
+            .iter()
+            .position(|&(top, bottom)| y >= top as i32 && y < bottom as i32)
+    }
+
+    /// Separator near pixel row `y`, separator `i` lies under pane `i`
+    fn separator_at(&self, y: i32, plot_height: u32) -> Option<usize> {
+        let bounds = self.bounds(plot_height);
+        bounds[..bounds.len() - 1]
+            .iter()
+            .position(|&(_, bottom)| (y - bottom as i32).abs() <= SEPARATOR_GRAB)
+    }
+
+    pub fn is_resizing(&self) -> bool {
+        self.resizing.is_some()
+    }
+
+    /// Follow a press at `press_y` dragged to `position_y`, true while a
+    /// separator is being dragged (the press must not pan the chart)
+    pub fn drag(
+        &mut self,
+        press_y: i32,
+        position_y: i32,
+        is_release: bool,
+        plot_height: u32,
+    ) -> bool {
+        if plot_height == 0 {
+            return false;
+        }
+        if self.resizing.is_none() && !is_release {
+            self.resizing = self.separator_at(press_y, plot_height).map(|separator| {
+                let heights = self.panes.iter().map(|pane| pane.height).collect();
+                (separator, heights)
+            });
+        }
+        let Some((separator, heights)) = self.resizing.clone() else {
+            return false;
+        };
+
+        // The pane above grows by what the pane below loses
+        let below = heights[separator];
+        let (above, above_min) = match separator {
+            0 => (1.0 - heights.iter().sum::<f32>(), MIN_PRICE_HEIGHT),
+            _ => (heights[separator - 1], MIN_PANE_HEIGHT),
+        };
+        let moved = (position_y - press_y) as f32 / plot_height as f32;
+        let moved = moved.clamp(
+            -(above - above_min).max(0.0),
+            (below - MIN_PANE_HEIGHT).max(0.0),
+        );
+        self.panes[separator].height = below - moved;
+        if separator > 0 {
+            self.panes[separator - 1].height = above + moved;
+        }
+
+        if is_release {
+            self.resizing = None;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicator::{Ema, IndicatorInstance, Rsi};
+
+    fn layout(heights: &[f32]) -> PaneLayout {
+        let mut layout = PaneLayout::empty();
+        layout.panes = heights
+            .iter()
+            .enumerate()
+            .map(|(i, &height)| Pane {
+                kind: PaneKind::Indicator(i),
+                height,
+            })
+            .collect();
+        layout
+    }
+
+    #[test]
+    fn test_bounds_stack_under_price() {
+        let layout = layout(&[0.25, 0.25]);
+        assert_eq!(layout.bounds(400), vec![(0, 200), (200, 300), (300, 400)]);
+        assert_eq!(layout.pane_at(250, 400), Some(1));
+        assert_eq!(layout.pane_at(400, 400), None);
+    }
+
+    #[test]
+    fn test_drag_separator_resizes_neighbours() {
+        let mut layout = layout(&[0.25, 0.25]);
+        // Grab the separator between the two sub-panes and pull it down 40px
+        assert!(layout.drag(301, 341, false, 400));
+        assert!((layout.panes[0].height - 0.35).abs() < 1e-6);
+        assert!((layout.panes[1].height - 0.15).abs() < 1e-6);
+
+        // The pane below cannot get smaller than the minimum
+        assert!(layout.drag(301, 399, true, 400));
+        assert!((layout.panes[1].height - MIN_PANE_HEIGHT).abs() < 1e-6);
+        assert!(!layout.is_resizing());
+
+        // A press away from separators pans the chart as before
+        assert!(!layout.drag(100, 150, false, 400));
+    }
+
+    #[test]
+    fn test_drag_keeps_price_pane_visible() {
+        let mut layout = layout(&[0.5]);
+        assert!(layout.drag(200, 0, false, 400));
+        assert!((layout.panes[0].height - (1.0 - MIN_PRICE_HEIGHT)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_sync_follows_indicators() {
+        let mut layout = PaneLayout::default();
+        let mut indicators = vec![
+            IndicatorInstance::new(Box::new(Ema::new(9))),
+            IndicatorInstance::new(Box::new(Rsi::new(14))),
+            IndicatorInstance::new(Box::new(Rsi::new(7))),
+        ];
+        layout.sync(&indicators);
+        let kinds: Vec<_> = layout.panes.iter().map(|pane| pane.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                PaneKind::Volume,
+                PaneKind::Indicator(1),
+                PaneKind::Indicator(2)
+            ]
+        );
+
+        indicators.remove(1);
+        layout.remove_indicator(1);
+        layout.sync(&indicators);
+        assert!(layout.contains(&PaneKind::Indicator(1)));
+        assert_eq!(layout.panes.len(), 2);
+    }
+}
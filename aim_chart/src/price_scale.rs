@@ -0,0 +1,113 @@
+//! Scales of the price axis
+//!
+//! The price pane is plotted on axis values: the price itself, its natural
+//! log, or its change in percent from the first candle in view. Candles,
+//! studies and drawings keep their prices and go through the scale when they
+//! are drawn or hit. The viewport keeps the prices at its bottom and top, so
+//! it stays put when the scale or the first candle in view changes.
+
+/// Lowest price on a log scale, prices under it are drawn at it
+const MIN_LOG_PRICE: f32 = 1e-3;
+
+/// How the price axis maps prices
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScaleMode {
+    #[default]
+    Linear,
+    /// Equal percent moves take the same height
+    Log,
+    /// Percent change from the close of the first candle in view
+    Percent,
+}
+
+impl ScaleMode {
+    /// Mode saved as `value`, linear for unknown values
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => ScaleMode::Log,
+            2 => ScaleMode::Percent,
+            _ => ScaleMode::Linear,
+        }
+    }
+}
+
+/// Mapping between prices and values on the price axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceScale {
+    pub mode: ScaleMode,
+    /// Price at 0% of the percent scale
+    pub base: f32,
+}
+
+impl Default for PriceScale {
+    fn default() -> Self {
+        Self::new(ScaleMode::Linear, 1.0)
+    }
+}
+
+impl PriceScale {
+    /// A base which is not a positive price counts as 1
+    pub fn new(mode: ScaleMode, base: f32) -> Self {
+        let base = if base > 0.0 { base } else { 1.0 };
+        Self { mode, base }
+    }
+
+    pub fn to_axis(&self, price: f32) -> f32 {
+        match self.mode {
+            ScaleMode::Linear => price,
+            ScaleMode::Log => price.max(MIN_LOG_PRICE).ln(),
+            ScaleMode::Percent => (price / self.base - 1.0) * 100.0,
+        }
+    }
+
+    pub fn to_price(&self, value: f32) -> f32 {
+        match self.mode {
+            ScaleMode::Linear => value,
+            ScaleMode::Log => value.exp(),
+            ScaleMode::Percent => (value / 100.0 + 1.0) * self.base,
+        }
+    }
+
+    /// Label of the axis value `value`: the price, or the percent change on
+    /// the percent scale
+    pub fn label(&self, value: f32) -> String {
+        match self.mode {
+            ScaleMode::Percent => format!("{value:+.2}%"),
+            _ => format!("{:.2}", self.to_price(value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_axis_round_trip() {
+        for mode in [ScaleMode::Linear, ScaleMode::Log, ScaleMode::Percent] {
+            let scale = PriceScale::new(mode, 25.0);
+            for price in [0.5, 25.0, 81.3, 1250.0] {
+                let back = scale.to_price(scale.to_axis(price));
+                assert!((back - price).abs() < price * 1e-5, "{mode:?} {price}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_axis_values() {
+        let log = PriceScale::new(ScaleMode::Log, 1.0);
+        // 10 -> 20 and 20 -> 40 take the same height
+        let (a, b, c) = (log.to_axis(10.0), log.to_axis(20.0), log.to_axis(40.0));
+        assert!(((b - a) - (c - b)).abs() < 1e-5);
+        assert_eq!(log.label(b), "20.00");
+
+        let percent = PriceScale::new(ScaleMode::Percent, 20.0);
+        assert_eq!(percent.to_axis(20.0), 0.0);
+        assert_eq!(percent.to_axis(25.0), 25.0);
+        assert_eq!(percent.label(-10.0), "-10.00%");
+        assert_eq!(percent.label(25.0), "+25.00%");
+
+        // No candles in view: the percent scale starts at 1
+        assert_eq!(PriceScale::new(ScaleMode::Percent, 0.0).base, 1.0);
+    }
+}
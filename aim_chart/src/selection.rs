@@ -0,0 +1,217 @@
+//! Drawings picked with the mouse and the drags reshaping them
+
+use crate::{
+    draw::{DrawObject, Point},
+    history::Edit,
+    price_scale::PriceScale,
+};
+
+/// Distance from a handle at which a press grabs it, in hit thresholds
+pub(crate) const HANDLE_GRAB: f32 = 3.0;
+
+/// What a press on a drawing took hold of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grab {
+    /// The whole drawing, the selection moves with it
+    Object,
+    /// One handle of the drawing, see [`DrawObject::handles`]
+    Handle(usize),
+}
+
+#[derive(Debug, Clone)]
+struct Drag {
+    grab: Grab,
+    /// Dragged drawings as they were at the press
+    before: Vec<(usize, DrawObject)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    /// Indices in `Chart::all_draw_data`
+    pub indices: Vec<usize>,
+    drag: Option<Drag>,
+}
+
+impl Selection {
+    pub fn contains(&self, index: usize) -> bool {
+        self.indices.contains(&index)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Select the drawing at `index` alone, or add it to the selection (remove
+    /// it when already there) with `toggle`
+    pub fn select(&mut self, index: usize, toggle: bool) {
+        if !toggle {
+            self.indices = vec![index];
+        } else if let Some(position) = self.indices.iter().position(|&i| i == index) {
+            self.indices.remove(position);
+        } else {
+            self.indices.push(index);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.indices.clear();
+        self.drag = None;
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Start a drag at a press on `target`: a handle reshapes `target` alone,
+    /// the drawing itself moves the whole selection
+    pub fn start_drag(&mut self, target: usize, grab: Grab, drawings: &[DrawObject]) {
+        let indices = match grab {
+            Grab::Handle(_) => vec![target],
+            Grab::Object => self.indices.clone(),
+        };
+        let before = indices
+            .into_iter()
+            .filter_map(|index| Some((index, drawings.get(index)?.clone())))
+            .collect();
+        self.drag = Some(Drag { grab, before });
+    }
+
+    /// Follow the mouse, `offset` is the move since the press and `point` the
+    /// mouse position, both in chart values on the price axis of `scale`
+    pub fn drag(
+        &self,
+        drawings: &mut [DrawObject],
+        offset: (f32, f32),
+        point: Point,
+        scale: PriceScale,
+    ) {
+        let Some(drag) = &self.drag else {
+            return;
+        };
+        for (index, before) in &drag.before {
+            let mut object = before.clone();
+            match drag.grab {
+                Grab::Object => {
+                    object.translate(offset.0, 0.0);
+                    // Prices left alone when the drag is level, the scale
+                    // does not round-trip exactly
+                    if offset.1 != 0.0 {
+                        object.map_y(|y| scale.to_price(scale.to_axis(y) + offset.1));
+                    }
+                }
+                Grab::Handle(handle) => {
+                    object.map_y(|y| scale.to_axis(y));
+                    object.move_handle(handle, point);
+                    object.map_y(|y| scale.to_price(y));
+                }
+            }
+            if let Some(drawing) = drawings.get_mut(*index) {
+                *drawing = object;
+            }
+        }
+    }
+
+    /// Change the x coordinates of the drawings as they were at the press, see
+    /// [`DrawObject::map_x`]
+    pub fn map_x(&mut self, f: impl Fn(f32) -> f32) {
+        if let Some(drag) = &mut self.drag {
+            for (_, object) in &mut drag.before {
+                object.map_x(&f);
+            }
+        }
+    }
+
+    /// Stop dragging, returns the edit made unless nothing moved
+    pub fn end_drag(&mut self, drawings: &[DrawObject]) -> Option<Edit> {
+        let drag = self.drag.take()?;
+        let changes: Vec<_> = drag
+            .before
+            .into_iter()
+            .filter_map(|(index, before)| {
+                let after = drawings.get(index)?;
+                (*after != before).then(|| (index, before, after.clone()))
+            })
+            .collect();
+        (!changes.is_empty()).then_some(Edit::Change(changes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::draw::DrawType;
+    use plotters::style::RGBColor;
+
+    fn rectangle_before() -> DrawType {
+        DrawType::Rectangle(((0.0, 0.0), (4.0, 2.0)))
+    }
+
+    #[test]
+    fn test_handle_drag_moves_one_corner() {
+        let mut drawings = vec![DrawObject::new(rectangle_before(), RGBColor(0, 0, 0))];
+        let mut selection = Selection::default();
+        selection.select(0, false);
+
+        // Grab the top-right corner and pull it out
+        let handle = drawings[0].handle_at((4.0, 0.0), 0.1, 0.1).unwrap();
+        selection.start_drag(0, Grab::Handle(handle), &drawings);
+        selection.drag(
+            &mut drawings,
+            (0.0, 0.0),
+            (6.0, -1.0),
+            PriceScale::default(),
+        );
+        assert_eq!(
+            drawings[0].draw_type,
+            DrawType::Rectangle(((6.0, -1.0), (0.0, 2.0)))
+        );
+
+        let edit = selection.end_drag(&drawings).unwrap();
+        assert!(!selection.is_dragging());
+        let mut reverted = drawings.clone();
+        edit.inverse().apply(&mut reverted);
+        assert_eq!(reverted[0].draw_type, rectangle_before());
+    }
+
+    #[test]
+    fn test_object_drag_moves_the_selection() {
+        let line = |y| DrawObject::new(DrawType::HorizontalLine(y), RGBColor(0, 0, 0));
+        let mut drawings = vec![line(1.0), line(2.0), line(3.0)];
+        let mut selection = Selection::default();
+        selection.select(0, false);
+        selection.select(2, true);
+        selection.start_drag(0, Grab::Object, &drawings);
+        selection.drag(&mut drawings, (0.0, 1.5), (0.0, 0.0), PriceScale::default());
+        assert_eq!(drawings, vec![line(2.5), line(2.0), line(4.5)]);
+
+        // A press without a move changes nothing
+        selection.start_drag(1, Grab::Object, &drawings);
+        assert_eq!(selection.end_drag(&drawings), None);
+    }
+
+    #[test]
+    fn test_object_drag_on_the_log_scale() {
+        use crate::price_scale::ScaleMode;
+
+        let scale = PriceScale::new(ScaleMode::Log, 1.0);
+        let line = |y| DrawObject::new(DrawType::HorizontalLine(y), RGBColor(0, 0, 0));
+        let mut drawings = vec![line(10.0), line(30.0)];
+        let mut selection = Selection::default();
+        selection.select(0, false);
+        selection.select(1, true);
+        selection.start_drag(0, Grab::Object, &drawings);
+        // The same height doubles both prices
+        selection.drag(&mut drawings, (0.0, 2f32.ln()), (0.0, 0.0), scale);
+        for (drawing, price) in drawings.iter().zip([20.0, 60.0]) {
+            let DrawType::HorizontalLine(y) = drawing.draw_type else {
+                panic!("not a horizontal line");
+            };
+            assert!((y - price).abs() < 1e-3);
+        }
+
+        // A level drag leaves the prices as they were
+        selection.start_drag(0, Grab::Object, &drawings);
+        selection.drag(&mut drawings, (0.0, 0.0), (0.0, 0.0), scale);
+        assert_eq!(selection.end_drag(&drawings), None);
+    }
+}
@@ -0,0 +1,288 @@
+//! Styles of the price series
+//!
+//! Candles, OHLC bars, a close line and an area show the candles as they are.
+//! Heikin-Ashi candles are averaged from them. Renko bricks and
+//! point-and-figure columns only move by a box size and leave time out, each
+//! takes the time of the candle it starts at. The chart shows the derived
+//! series in place of its candles, so the time axis, the candle info and the
+//! drawings work on it as on candles.
+
+use crate::{CandleData, CandleDataVec};
+
+/// Boxes a point-and-figure column has to go back to turn
+pub const REVERSAL_BOXES: f32 = 3.0;
+
+/// Candles the automatic box size is the average true range of
+const BOX_PERIOD: usize = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChartStyle {
+    #[default]
+    Candles,
+    /// OHLC bars: open tick on the left, close tick on the right
+    Bars,
+    /// Line through the closes
+    Line,
+    /// Line through the closes, filled down to the bottom of the view
+    Area,
+    HeikinAshi,
+    Renko,
+    /// X columns going up and O columns going down
+    PointAndFigure,
+}
+
+impl ChartStyle {
+    /// Style saved as `value`, candles for unknown values
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => ChartStyle::Bars,
+            2 => ChartStyle::Line,
+            3 => ChartStyle::Area,
+            4 => ChartStyle::HeikinAshi,
+            5 => ChartStyle::Renko,
+            6 => ChartStyle::PointAndFigure,
+            _ => ChartStyle::Candles,
+        }
+    }
+
+    /// Whether the style shows other values than the candles
+    pub fn is_derived(self) -> bool {
+        matches!(
+            self,
+            ChartStyle::HeikinAshi | ChartStyle::Renko | ChartStyle::PointAndFigure
+        )
+    }
+
+    /// Whether the style works with a box size
+    pub fn uses_box(self) -> bool {
+        matches!(self, ChartStyle::Renko | ChartStyle::PointAndFigure)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ChartStyle::Candles => "Candles",
+            ChartStyle::Bars => "Bars",
+            ChartStyle::Line => "Line",
+            ChartStyle::Area => "Area",
+            ChartStyle::HeikinAshi => "Heikin-Ashi",
+            ChartStyle::Renko => "Renko",
+            ChartStyle::PointAndFigure => "P&F",
+        }
+    }
+
+    /// Series shown for `candles` in this style, with boxes of `box_size`.
+    /// A box size which is not a positive number picks one from the candles.
+    pub fn series(self, candles: &[CandleData], box_size: f32) -> CandleDataVec {
+        let box_size = if box_size > 0.0 && box_size.is_finite() {
+            box_size
+        } else {
+            auto_box_size(candles)
+        };
+        let series = match self {
+            ChartStyle::HeikinAshi => heikin_ashi(candles),
+            ChartStyle::Renko => renko(candles, box_size),
+            ChartStyle::PointAndFigure => point_and_figure(candles, box_size),
+            _ => candles.to_vec(),
+        };
+        numbered(series)
+    }
+}
+
+/// Box size of `candles` when none is set: their average true range over the
+/// last candles, or a percent of the last close for a flat series
+pub fn auto_box_size(candles: &[CandleData]) -> f32 {
+    let start = candles.len().saturating_sub(BOX_PERIOD + 1);
+    let recent = &candles[start..];
+    let ranges: Vec<f32> = recent
+        .windows(2)
+        .map(|pair| {
+            let (previous, candle) = (&pair[0], &pair[1]);
+            (candle.high - candle.low)
+                .max((candle.high - previous.close).abs())
+                .max((candle.low - previous.close).abs())
+        })
+        .collect();
+    let atr = ranges.iter().sum::<f32>() / ranges.len().max(1) as f32;
+    if atr > 0.0 {
+        return atr;
+    }
+    let close = candles.last().map_or(0.0, |candle| candle.close.abs());
+    if close > 0.0 { close * 0.01 } else { 1.0 }
+}
+
+fn numbered(mut series: CandleDataVec) -> CandleDataVec {
+    for (i, candle) in series.iter_mut().enumerate() {
+        candle.num = i as f32;
+    }
+    series
+}
+
+fn heikin_ashi(candles: &[CandleData]) -> CandleDataVec {
+    let mut series: CandleDataVec = Vec::with_capacity(candles.len());
+    for candle in candles {
+        let close = (candle.open + candle.high + candle.low + candle.close) / 4.0;
+        let open = series
+            .last()
+            .map_or((candle.open + candle.close) / 2.0, |previous| {
+                (previous.open + previous.close) / 2.0
+            });
+        series.push(CandleData {
+            open,
+            close,
+            high: candle.high.max(open).max(close),
+            low: candle.low.min(open).min(close),
+            ..candle.clone()
+        });
+    }
+    series
+}
+
+/// A brick or column of `from` going from `open` to `close`
+fn brick(from: &CandleData, open: f32, close: f32, volume: f32) -> CandleData {
+    CandleData {
+        open,
+        close,
+        high: open.max(close),
+        low: open.min(close),
+        volume,
+        ..from.clone()
+    }
+}
+
+/// Bricks of `box_size` on the closes, a brick the other way needs the close
+/// a box past the other side of the last brick
+fn renko(candles: &[CandleData], box_size: f32) -> CandleDataVec {
+    let mut bricks: CandleDataVec = Vec::new();
+    let Some(first) = candles.first() else {
+        return bricks;
+    };
+    let (mut low, mut high) = (first.close, first.close);
+    // Volume of the candles since the last brick, carried by the next one
+    let mut volume = 0.0;
+    for candle in candles {
+        volume += candle.volume;
+        while candle.close >= high + box_size {
+            bricks.push(brick(candle, high, high + box_size, volume));
+            (low, high) = (high, high + box_size);
+            volume = 0.0;
+        }
+        while candle.close <= low - box_size {
+            bricks.push(brick(candle, low, low - box_size, volume));
+            (low, high) = (low - box_size, low);
+            volume = 0.0;
+        }
+    }
+    bricks
+}
+
+/// Columns of boxes of `box_size` on the closes: an X column grows with each
+/// box the close goes up and turns into an O column once the close is
+/// `REVERSAL_BOXES` boxes under its top, and the other way round
+fn point_and_figure(candles: &[CandleData], box_size: f32) -> CandleDataVec {
+    let mut columns: CandleDataVec = Vec::new();
+    let Some(first) = candles.first() else {
+        return columns;
+    };
+    // Highest box edge a rising close reached, lowest one a falling close did
+    let top = |price: f32| (price / box_size).floor() * box_size;
+    let bottom = |price: f32| (price / box_size).ceil() * box_size;
+    let reversal = REVERSAL_BOXES * box_size;
+    let start = top(first.close);
+    for candle in candles {
+        let close = candle.close;
+        let Some(column) = columns.last_mut() else {
+            // The first column starts once the close is a box away
+            if close >= start + box_size {
+                columns.push(brick(candle, start, top(close), candle.volume));
+            } else if close <= start - box_size {
+                columns.push(brick(candle, start, bottom(close), candle.volume));
+            }
+            continue;
+        };
+        let rising = column.close > column.open;
+        if rising && close >= column.high + box_size {
+            column.close = top(close);
+            column.high = column.close;
+        } else if !rising && close <= column.low - box_size {
+            column.close = bottom(close);
+            column.low = column.close;
+        } else if rising && close <= column.high - reversal {
+            let open = column.high - box_size;
+            columns.push(brick(candle, open, bottom(close), candle.volume));
+            continue;
+        } else if !rising && close >= column.low + reversal {
+            let open = column.low + box_size;
+            columns.push(brick(candle, open, top(close), candle.volume));
+            continue;
+        }
+        column.volume += candle.volume;
+    }
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::daily_candles;
+
+    /// Daily candles closing at `closes`, opening half a point lower on a
+    /// volume of 100
+    fn candles(closes: &[f32]) -> CandleDataVec {
+        daily_candles(closes)
+            .into_iter()
+            .map(|candle| CandleData {
+                open: candle.close - 0.5,
+                volume: 100.0,
+                ..candle
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_heikin_ashi_averages_the_candles() {
+        let data = candles(&[10.0, 12.0]);
+        let series = ChartStyle::HeikinAshi.series(&data, 1.0);
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].open, 9.75);
+        assert_eq!(series[0].close, 9.875);
+        // Open halfway through the previous Heikin-Ashi candle
+        assert_eq!(series[1].open, 9.8125);
+        assert_eq!(series[1].close, 11.875);
+        assert_eq!(series[1].time, data[1].time);
+        assert!(series[1].high >= series[1].close && series[1].low <= series[1].open);
+    }
+
+    #[test]
+    fn test_renko_turns_two_boxes_away() {
+        // Up three bricks, a dip of one box is not enough to turn
+        let data = candles(&[10.0, 13.0, 12.0, 10.0]);
+        let bricks = ChartStyle::Renko.series(&data, 1.0);
+        let closes: Vec<f32> = bricks.iter().map(|brick| brick.close).collect();
+        assert_eq!(closes, vec![11.0, 12.0, 13.0, 11.0, 10.0]);
+        assert_eq!(bricks[3].open, 12.0);
+        assert_eq!(bricks[0].time, data[1].time);
+        assert_eq!(bricks[3].time, data[3].time);
+        assert_eq!(bricks[3].num, 3.0);
+        // The volume of the candles of a brick goes to its first brick
+        assert_eq!(bricks[0].volume, 200.0);
+        assert_eq!(bricks[1].volume, 0.0);
+    }
+
+    #[test]
+    fn test_point_and_figure_turns_after_three_boxes() {
+        let data = candles(&[10.0, 14.5, 13.0, 11.0, 9.0, 12.5]);
+        let columns = ChartStyle::PointAndFigure.series(&data, 1.0);
+        let shown: Vec<(f32, f32)> = columns.iter().map(|c| (c.open, c.close)).collect();
+        // X from 10 to 14, O from 13 down to 9, then X from 10 up to 12
+        assert_eq!(shown, vec![(10.0, 14.0), (13.0, 9.0), (10.0, 12.0)]);
+        assert_eq!(columns[1].time, data[3].time);
+        assert_eq!(columns[1].volume, 200.0);
+    }
+
+    #[test]
+    fn test_auto_box_size_is_the_average_true_range() {
+        let data = candles(&[10.0, 10.0, 10.0]);
+        assert_eq!(auto_box_size(&data), 2.0);
+        assert_eq!(auto_box_size(&[]), 1.0);
+    }
+}
@@ -0,0 +1,65 @@
+//! Candles and bars shared by the tests of the crate, one a day from Monday
+//! 2024-01-01
+
+use aim_data::Candlestick;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::CandleData;
+
+/// Start of the `n`th day of the fixtures
+pub(crate) fn day(n: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(1_704_067_200, 0).unwrap() + Duration::days(n)
+}
+
+/// Daily candles from `start`, from `(high, low, close, volume)`, each opening
+/// at its close
+pub(crate) fn candles_from(start: DateTime<Utc>, bars: &[(f32, f32, f32, f32)]) -> Vec<CandleData> {
+    bars.iter()
+        .enumerate()
+        .map(|(i, &(high, low, close, volume))| CandleData {
+            num: i as f32,
+            time: start + Duration::days(i as i64),
+            open: close,
+            high,
+            low,
+            close,
+            volume,
+        })
+        .collect()
+}
+
+/// Daily candles from `(high, low, close, volume)`, each opening at its close
+pub(crate) fn candles(bars: &[(f32, f32, f32, f32)]) -> Vec<CandleData> {
+    candles_from(day(0), bars)
+}
+
+/// Daily candles closing at `closes`, opening there too with a high and a
+/// low 1 away
+pub(crate) fn daily_candles(closes: &[f32]) -> Vec<CandleData> {
+    let bars: Vec<_> = closes
+        .iter()
+        .map(|&close| (close + 1.0, close - 1.0, close, 1000.0))
+        .collect();
+    candles(&bars)
+}
+
+/// Bar of the quote API on day `n`, all its prices at `close`
+pub(crate) fn bar(n: i64, close: f64) -> Candlestick {
+    Candlestick {
+        timestamp: day(n),
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume: 100,
+    }
+}
+
+/// Daily bars of the quote API closing at `closes`
+pub(crate) fn bars(closes: &[f64]) -> Vec<Candlestick> {
+    closes
+        .iter()
+        .enumerate()
+        .map(|(i, &close)| bar(i as i64, close))
+        .collect()
+}
@@ -0,0 +1,142 @@
+//! Projection of drawings between candle indices and times
+//!
+//! Drawings are anchored at (UTC time, price). While a chart shows them their
+//! x coordinates are fractional candle indices of the series they were
+//! projected on, which is what hit-tests and drags work with. When the series
+//! changes (another timeframe, a reload of the history, older candles
+//! prepended) the drawings go through their times to the new indices, so a
+//! trendline drawn on 1D lands on the same dates on 1H or 1W.
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::CandleData;
+
+/// Start times of the candles of a series, mapping candle indices to times
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeScale {
+    /// Start of every candle, in milliseconds since the epoch
+    times: Vec<i64>,
+    /// Length of a bar in milliseconds, to extrapolate past the candles
+    bar: i64,
+}
+
+impl Default for TimeScale {
+    /// Daily bars from the epoch, for drawings not projected on candles yet
+    fn default() -> Self {
+        Self::new(Vec::new(), Duration::days(1))
+    }
+}
+
+impl TimeScale {
+    pub fn new(times: Vec<DateTime<Utc>>, bar_duration: Duration) -> Self {
+        Self {
+            times: times.iter().map(DateTime::timestamp_millis).collect(),
+            bar: bar_duration.num_milliseconds().max(1),
+        }
+    }
+
+    pub(crate) fn of(candles: &[CandleData], bar_duration: Duration) -> Self {
+        Self::new(
+            candles.iter().map(|candle| candle.time).collect(),
+            bar_duration,
+        )
+    }
+
+    /// Whether this is the scale of `candles`, without building it
+    pub(crate) fn is_scale_of(&self, candles: &[CandleData], bar_duration: Duration) -> bool {
+        self.bar == bar_duration.num_milliseconds().max(1)
+            && self.times.len() == candles.len()
+            && self
+                .times
+                .iter()
+                .zip(candles)
+                .all(|(&time, candle)| time == candle.time.timestamp_millis())
+    }
+
+    /// Time at a fractional candle index, in milliseconds since the epoch
+    ///
+    /// Between two candles the time is interpolated, before the first and
+    /// after the last one it goes a bar per index.
+    pub fn time_at(&self, index: f32) -> i64 {
+        let index = index as f64;
+        let (Some(&first), Some(&last)) = (self.times.first(), self.times.last()) else {
+            return (index * self.bar as f64).round() as i64;
+        };
+        let last_index = (self.times.len() - 1) as f64;
+        if index <= 0.0 {
+            return first + (index * self.bar as f64).round() as i64;
+        }
+        if index >= last_index {
+            return last + ((index - last_index) * self.bar as f64).round() as i64;
+        }
+        let i = index.floor() as usize;
+        let (start, end) = (self.times[i], self.times[i + 1]);
+        start + ((index - i as f64) * (end - start) as f64).round() as i64
+    }
+
+    /// Fractional candle index at `time` in milliseconds since the epoch, the
+    /// inverse of [`TimeScale::time_at`]
+    pub fn index_at(&self, time: i64) -> f32 {
+        let (Some(&first), Some(&last)) = (self.times.first(), self.times.last()) else {
+            return (time as f64 / self.bar as f64) as f32;
+        };
+        let last_index = (self.times.len() - 1) as f64;
+        let index = if time <= first {
+            (time - first) as f64 / self.bar as f64
+        } else if time >= last {
+            last_index + (time - last) as f64 / self.bar as f64
+        } else {
+            // Candle starting at or before `time`, the next one starts after it
+            let i = self.times.partition_point(|&start| start <= time) - 1;
+            let (start, end) = (self.times[i], self.times[i + 1]);
+            i as f64 + (time - start) as f64 / (end - start) as f64
+        };
+        index as f32
+    }
+
+    /// Index on the scale `to` of the index `x` on this scale
+    pub fn project(&self, x: f32, to: &TimeScale) -> f32 {
+        to.index_at(self.time_at(x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::day;
+
+    fn daily(days: i64) -> Vec<DateTime<Utc>> {
+        (0..days).map(day).collect()
+    }
+
+    #[test]
+    fn test_index_and_time_round_trip() {
+        let scale = TimeScale::new(daily(10), Duration::days(1));
+        for index in [-3.0, 0.0, 2.5, 9.0, 14.0] {
+            assert!((scale.index_at(scale.time_at(index)) - index).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_daily_points_land_on_the_same_dates_weekly() {
+        let days = daily(28);
+        let weeks: Vec<_> = days.iter().step_by(7).copied().collect();
+        let day_scale = TimeScale::new(days, Duration::days(1));
+        let week_scale = TimeScale::new(weeks, Duration::weeks(1));
+
+        // The 15th day opens the third week, the 18th is 3/7 into it
+        assert_eq!(day_scale.project(14.0, &week_scale), 2.0);
+        assert!((day_scale.project(17.0, &week_scale) - (2.0 + 3.0 / 7.0)).abs() < 1e-5);
+        // Past the last candles, a day after the last day and a week after the
+        // last week are both the 29th day
+        assert!((day_scale.project(28.0, &week_scale) - 4.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_prepended_candles_shift_the_indices() {
+        let days = daily(20);
+        let recent = TimeScale::new(days[5..].to_vec(), Duration::days(1));
+        let all = TimeScale::new(days, Duration::days(1));
+        assert_eq!(recent.project(3.0, &all), 8.0);
+    }
+}
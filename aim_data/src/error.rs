@@ -0,0 +1,121 @@
+use reqwest::{Response, StatusCode};
+use serde::de::DeserializeOwned;
+
+/// Maximum number of characters of a response body kept in a `Decode` error
+const BODY_SNIPPET_LEN: usize = 200;
+
+/// Error returned by every data fetch in `aim_data`
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The request never produced a response (DNS, connect, TLS, timeout)
+    #[error("transport error: {0}")]
+    Transport(#[from] reqwest::Error),
+
+    /// The server answered with a non-success status
+    #[error("{endpoint} returned HTTP {status}")]
+    Status {
+        endpoint: String,
+        status: StatusCode,
+    },
+
+    /// The server rejected the credentials (HTTP 401/403)
+    #[error("{endpoint} rejected the credentials (HTTP {status})")]
+    Auth {
+        endpoint: String,
+        status: StatusCode,
+    },
+
+    /// The body does not match the expected schema
+    #[error("failed to decode {endpoint}: {source} (body: {body})")]
+    Decode {
+        endpoint: String,
+        #[source]
+        source: serde_json::Error,
+        body: String,
+    },
+
+    /// The request succeeded but returned no usable data
+    #[error("{endpoint} returned an empty result")]
+    Empty { endpoint: String },
+
+    /// The host kept failing and its circuit breaker is open, the request was not sent
+    #[error("{host} is unavailable, requests are paused")]
+    CircuitOpen { host: String },
+
+    /// The client could not be configured (certificate file, config file, ...)
+    #[error("invalid configuration: {0}")]
+    Config(String),
+
+    /// The local history store could not be read or written
+    #[error("history store {path}: {source}")]
+    Store {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Whether retrying the same request later may succeed
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Transport(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            Error::Status { status, .. } => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Check the status of `resp` and decode its JSON body, keeping a snippet of
+/// the body when it does not match `T`
+pub(crate) async fn decode_response<T>(endpoint: &str, resp: Response) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let status = resp.status();
+    if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        return Err(Error::Auth {
+            endpoint: endpoint.to_string(),
+            status,
+        });
+    }
+    if !status.is_success() {
+        return Err(Error::Status {
+            endpoint: endpoint.to_string(),
+            status,
+        });
+    }
+
+    let body = resp.text().await?;
+    serde_json::from_str(&body).map_err(|source| Error::Decode {
+        endpoint: endpoint.to_string(),
+        source,
+        body: body.chars().take(BODY_SNIPPET_LEN).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transient_status() {
+        let status = |code| Error::Status {
+            endpoint: "api/price/symbols/getList".to_string(),
+            status: StatusCode::from_u16(code).unwrap(),
+        };
+        assert!(status(503).is_transient());
+        assert!(status(429).is_transient());
+        assert!(!status(404).is_transient());
+        assert!(
+            !Error::Empty {
+                endpoint: "data-mt/graphql".to_string()
+            }
+            .is_transient()
+        );
+    }
+}
@@ -0,0 +1,444 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use reqwest::RequestBuilder;
+use serde::de::DeserializeOwned;
+
+use crate::error::{Error, Result, decode_response};
+
+/// Token-bucket limit applied to every request sent to one host
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Tokens added per second
+    pub per_second: f64,
+    /// Maximum number of requests sent back to back
+    pub burst: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            per_second: 10.0,
+            burst: 20,
+        }
+    }
+}
+
+/// Settings of the shared request executor
+#[derive(Debug, Clone)]
+pub struct ExecutorConfig {
+    /// Limit of hosts without an entry in `host_limits`
+    pub default_limit: RateLimit,
+    /// Per-host limits, keyed by `host` or `host:port`
+    pub host_limits: HashMap<String, RateLimit>,
+    /// Retries after the first attempt for transient errors (timeouts, 5xx, 429)
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Consecutive transient failures that open the circuit of a host
+    pub failure_threshold: u32,
+    /// How long an open circuit rejects requests before letting a probe through
+    pub open_duration: Duration,
+}
+
+impl Default for ExecutorConfig {
+    fn default() -> Self {
+        Self {
+            default_limit: RateLimit::default(),
+            host_limits: HashMap::new(),
+            max_retries: 3,
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(8),
+            failure_threshold: 5,
+            open_duration: Duration::from_secs(30),
+        }
+    }
+}
+
+/// State of the circuit breaker of a host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests go through
+    Closed,
+    /// The host kept failing, requests are rejected until the cool-down ends
+    Open,
+    /// The cool-down ended, one request probes the host and decides whether
+    /// the circuit closes again, the others are rejected meanwhile
+    HalfOpen,
+}
+
+/// Snapshot of one host, used by the UI connection indicator
+#[derive(Debug, Clone)]
+pub struct HostHealth {
+    pub host: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+}
+
+struct HostState {
+    limit: RateLimit,
+    tokens: f64,
+    refilled_at: Instant,
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// Start of the request probing the host while half-open
+    probe_in_flight: Option<Instant>,
+    last_error: Option<String>,
+}
+
+impl HostState {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst as f64,
+            refilled_at: Instant::now(),
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            probe_in_flight: None,
+            last_error: None,
+        }
+    }
+
+    /// Take a token, or return how long to wait for the next one
+    fn take_token(&mut self, now: Instant) -> Option<Duration> {
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.per_second).min(self.limit.burst as f64);
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.limit.per_second,
+            ))
+        }
+    }
+}
+
+/// Request executor shared by every fetch of `aim_data`
+///
+/// Each host gets a token bucket and a circuit breaker. Transient errors are
+/// retried with exponential backoff and jitter, and a host that keeps failing
+/// is short-circuited with `Error::CircuitOpen` until its cool-down ends.
+pub struct RequestExecutor {
+    config: ExecutorConfig,
+    hosts: Mutex<HashMap<String, HostState>>,
+}
+
+impl RequestExecutor {
+    pub fn new(config: ExecutorConfig) -> Self {
+        Self {
+            config,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &ExecutorConfig {
+        &self.config
+    }
+
+    /// Run `request` against `host`, retrying transient errors
+    pub async fn execute<T, F, Fut>(&self, host: &str, mut request: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            self.admit(host)?;
+            self.acquire(host).await;
+
+            match request().await {
+                Ok(value) => {
+                    self.record_success(host);
+                    return Ok(value);
+                }
+                Err(e) if e.is_transient() => {
+                    let opened = self.record_failure(host, &e);
+                    if opened || attempt >= self.config.max_retries {
+                        return Err(e);
+                    }
+                    log::warn!(
+                        "{host}: {e}, retrying ({}/{})",
+                        attempt + 1,
+                        self.config.max_retries
+                    );
+                    tokio::time::sleep(self.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    // The host answered, so it is reachable even if the request was rejected
+                    self.record_success(host);
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// Send the request built by `build` and decode its JSON body
+    pub(crate) async fn send_json<T, B>(&self, host: &str, endpoint: &str, build: B) -> Result<T>
+    where
+        T: DeserializeOwned,
+        B: Fn() -> RequestBuilder,
+    {
+        let build = &build;
+        self.execute(host, || async move {
+            let resp = build().send().await?;
+            decode_response(endpoint, resp).await
+        })
+        .await
+    }
+
+    /// Snapshot of every host seen so far, sorted by host
+    pub fn health(&self) -> Vec<HostHealth> {
+        let hosts = self.hosts.lock().unwrap();
+        let mut health: Vec<HostHealth> = hosts
+            .iter()
+            .map(|(host, state)| HostHealth {
+                host: host.clone(),
+                state: state.state,
+                consecutive_failures: state.consecutive_failures,
+                last_error: state.last_error.clone(),
+            })
+            .collect();
+        health.sort_by(|a, b| a.host.cmp(&b.host));
+        health
+    }
+
+    fn with_host<R>(&self, host: &str, f: impl FnOnce(&mut HostState) -> R) -> R {
+        let mut hosts = self.hosts.lock().unwrap();
+        let state = hosts.entry(host.to_string()).or_insert_with(|| {
+            let limit = self
+                .config
+                .host_limits
+                .get(host)
+                .copied()
+                .unwrap_or(self.config.default_limit);
+            HostState::new(limit)
+        });
+        f(state)
+    }
+
+    /// Reject the request while the circuit is open, and while another one
+    /// probes the host once it is half-open
+    fn admit(&self, host: &str) -> Result<()> {
+        let open_duration = self.config.open_duration;
+        self.with_host(host, |state| {
+            let admitted = match state.state {
+                CircuitState::Closed => true,
+                CircuitState::Open => state
+                    .opened_at
+                    .is_none_or(|opened_at| opened_at.elapsed() >= open_duration),
+                // A probe dropped before it ended no longer holds the others
+                CircuitState::HalfOpen => state
+                    .probe_in_flight
+                    .is_none_or(|started| started.elapsed() >= open_duration),
+            };
+            if !admitted {
+                return Err(Error::CircuitOpen {
+                    host: host.to_string(),
+                });
+            }
+            if state.state != CircuitState::Closed {
+                state.state = CircuitState::HalfOpen;
+                state.probe_in_flight = Some(Instant::now());
+            }
+            Ok(())
+        })
+    }
+
+    /// Wait until the token bucket of `host` lets a request through
+    async fn acquire(&self, host: &str) {
+        while let Some(wait) = self.with_host(host, |state| state.take_token(Instant::now())) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn record_success(&self, host: &str) {
+        self.with_host(host, |state| {
+            state.state = CircuitState::Closed;
+            state.consecutive_failures = 0;
+            state.opened_at = None;
+            state.probe_in_flight = None;
+        });
+    }
+
+    /// Count a transient failure, returns true if it opened the circuit
+    fn record_failure(&self, host: &str, error: &Error) -> bool {
+        let threshold = self.config.failure_threshold;
+        self.with_host(host, |state| {
+            state.consecutive_failures += 1;
+            state.last_error = Some(error.to_string());
+            state.probe_in_flight = None;
+            let open =
+                state.state == CircuitState::HalfOpen || state.consecutive_failures >= threshold;
+            if open && state.state != CircuitState::Open {
+                log::error!(
+                    "{host}: circuit opened after {} failures",
+                    state.consecutive_failures
+                );
+                state.state = CircuitState::Open;
+                state.opened_at = Some(Instant::now());
+            }
+            open
+        })
+    }
+
+    /// Exponential backoff with full jitter
+    fn backoff(&self, attempt: u32) -> Duration {
+        let max = self
+            .config
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.config.max_delay);
+        max.mul_f64(fastrand::f64())
+    }
+}
+
+/// Key of the host of `url` used for rate limits and circuit breakers (`host:port`)
+pub(crate) fn host_key(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(url) => match (url.host_str(), url.port_or_known_default()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            _ => url.to_string(),
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+static EXECUTOR: OnceLock<Arc<RequestExecutor>> = OnceLock::new();
+
+/// Install the executor returned by `executor()`, returns false if one was already installed
+pub fn install_executor(config: ExecutorConfig) -> bool {
+    EXECUTOR.set(Arc::new(RequestExecutor::new(config))).is_ok()
+}
+
+/// Get the process-wide executor, built with the default config if none was installed
+pub fn executor() -> Arc<RequestExecutor> {
+    EXECUTOR
+        .get_or_init(|| Arc::new(RequestExecutor::new(ExecutorConfig::default())))
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn unavailable() -> Error {
+        Error::Status {
+            endpoint: "api/price/symbols/getList".to_string(),
+            status: StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    fn fast_config() -> ExecutorConfig {
+        ExecutorConfig {
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+            ..ExecutorConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_then_succeed() {
+        let executor = RequestExecutor::new(fast_config());
+        let calls = AtomicU32::new(0);
+        let result = executor
+            .execute("vci:443", || async {
+                if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(unavailable())
+                } else {
+                    Ok(7)
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 7);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(executor.health()[0].state, CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_and_rejects() {
+        let executor = RequestExecutor::new(ExecutorConfig {
+            failure_threshold: 2,
+            ..fast_config()
+        });
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = executor
+            .execute("vci:443", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(unavailable())
+            })
+            .await;
+        assert!(matches!(result, Err(Error::Status { .. })));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(executor.health()[0].state, CircuitState::Open);
+
+        let result: Result<()> = executor.execute("vci:443", || async { Ok(()) }).await;
+        assert!(matches!(result, Err(Error::CircuitOpen { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_half_open_circuit_lets_one_probe_through() {
+        let executor = RequestExecutor::new(ExecutorConfig {
+            failure_threshold: 1,
+            max_retries: 0,
+            open_duration: Duration::from_millis(20),
+            ..fast_config()
+        });
+        let result: Result<()> = executor
+            .execute("vci:443", || async { Err(unavailable()) })
+            .await;
+        assert!(matches!(result, Err(Error::Status { .. })));
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        let calls = AtomicU32::new(0);
+        let request = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Ok(())
+        };
+        let (probe, other) = tokio::join!(
+            executor.execute("vci:443", request),
+            executor.execute("vci:443", request)
+        );
+        assert!(probe.is_ok());
+        assert!(matches!(other, Err(Error::CircuitOpen { .. })));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(executor.health()[0].state, CircuitState::Closed);
+
+        let result = executor.execute("vci:443", request).await;
+        assert!(result.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_token_bucket_waits_when_empty() {
+        let mut state = HostState::new(RateLimit {
+            per_second: 4.0,
+            burst: 2,
+        });
+        let now = state.refilled_at;
+        assert!(state.take_token(now).is_none());
+        assert!(state.take_token(now).is_none());
+        assert_eq!(state.take_token(now), Some(Duration::from_millis(250)));
+    }
+
+    #[test]
+    fn test_host_key() {
+        assert_eq!(
+            host_key("https://trading.vietcap.com.vn/api/x"),
+            "trading.vietcap.com.vn:443"
+        );
+        assert_eq!(host_key("http://103.48.84.52:4040"), "103.48.84.52:4040");
+    }
+}
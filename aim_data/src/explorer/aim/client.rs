@@ -0,0 +1,397 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use reqwest::{Certificate, Client, ClientBuilder, RequestBuilder};
+use serde::{Deserialize, Serialize};
+
+use super::PdfReport;
+use crate::error::{Error, Result};
+use crate::executor::{RequestExecutor, executor, host_key};
+
+pub const DEFAULT_SERVER_URL: &str = "https://103.48.84.52:4443";
+pub const DEFAULT_MARKET_CAP_SERVER_URL: &str = "http://103.48.84.52:4040";
+
+/// How the client verifies the TLS certificate of the AIM server
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", content = "path", rename_all = "snake_case")]
+pub enum TlsTrust {
+    /// Use the system root certificates
+    #[default]
+    System,
+    /// Trust an extra CA certificate (PEM file) on top of the system roots
+    CustomCa(PathBuf),
+    /// Only trust this server certificate (PEM file), the system roots are ignored
+    Pinned(PathBuf),
+    /// Skip certificate verification, only meant for a local development server
+    Insecure,
+}
+
+/// Connection settings of the AIM backend
+///
+/// Loaded from a JSON file and/or the `AIM_*` environment variables so the
+/// token never has to be compiled into the binary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AimConfig {
+    pub server_url: String,
+    pub market_cap_server_url: String,
+    pub token: Option<String>,
+    pub tls: TlsTrust,
+    pub connect_timeout_ms: u64,
+    pub request_timeout_ms: u64,
+}
+
+impl Default for AimConfig {
+    fn default() -> Self {
+        Self {
+            server_url: DEFAULT_SERVER_URL.to_string(),
+            market_cap_server_url: DEFAULT_MARKET_CAP_SERVER_URL.to_string(),
+            token: None,
+            tls: TlsTrust::System,
+            connect_timeout_ms: 5_000,
+            request_timeout_ms: 30_000,
+        }
+    }
+}
+
+impl AimConfig {
+    /// Build a config from the defaults and the `AIM_*` environment variables
+    pub fn from_env() -> Self {
+        Self::default().with_env()
+    }
+
+    /// Load a config from a JSON file, the `AIM_*` environment variables take precedence
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| Error::Config(format!("{}: {e}", path.display())))?;
+        let config: Self = serde_json::from_str(&content)
+            .map_err(|e| Error::Config(format!("{}: {e}", path.display())))?;
+        Ok(config.with_env())
+    }
+
+    /// Override fields with the environment variables that are set:
+    /// `AIM_SERVER_URL`, `AIM_MARKET_CAP_SERVER_URL`, `AIM_API_TOKEN`,
+    /// `AIM_CA_CERT`, `AIM_PINNED_CERT` and `AIM_TLS_INSECURE`
+    pub fn with_env(mut self) -> Self {
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+        if let Some(url) = var("AIM_SERVER_URL") {
+            self.server_url = url;
+        }
+        if let Some(url) = var("AIM_MARKET_CAP_SERVER_URL") {
+            self.market_cap_server_url = url;
+        }
+        if let Some(token) = var("AIM_API_TOKEN") {
+            self.token = Some(token);
+        }
+        if let Some(path) = var("AIM_CA_CERT") {
+            self.tls = TlsTrust::CustomCa(PathBuf::from(path));
+        }
+        if let Some(path) = var("AIM_PINNED_CERT") {
+            self.tls = TlsTrust::Pinned(PathBuf::from(path));
+        }
+        if var("AIM_TLS_INSECURE").is_some_and(|v| v == "1" || v == "true") {
+            self.tls = TlsTrust::Insecure;
+        }
+        self
+    }
+}
+
+/// HTTP client of the AIM backend, every `fetch_*` endpoint is a method on it
+pub struct AimClient {
+    config: AimConfig,
+    client: Client,
+    executor: Arc<RequestExecutor>,
+}
+
+impl AimClient {
+    pub fn new(config: AimConfig) -> Result<Self> {
+        let mut builder = ClientBuilder::new()
+            .connect_timeout(Duration::from_millis(config.connect_timeout_ms))
+            .timeout(Duration::from_millis(config.request_timeout_ms));
+
+        builder = match &config.tls {
+            TlsTrust::System => builder,
+            TlsTrust::CustomCa(path) => builder.add_root_certificate(read_certificate(path)?),
+            TlsTrust::Pinned(path) => builder
+                .tls_built_in_root_certs(false)
+                .add_root_certificate(read_certificate(path)?),
+            TlsTrust::Insecure => builder.danger_accept_invalid_certs(true),
+        };
+
+        Ok(Self {
+            client: builder.build()?,
+            config,
+            executor: executor(),
+        })
+    }
+
+    /// Send requests through `executor` instead of the process-wide one
+    pub fn with_executor(mut self, executor: Arc<RequestExecutor>) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    pub fn config(&self) -> &AimConfig {
+        &self.config
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.config.token {
+            Some(token) => request.header("Authorization", format!("Bearer {token}")),
+            None => request,
+        }
+    }
+
+    // Generic function to fetch data from API endpoints
+    pub(crate) async fn fetch_api_data<T>(&self, endpoint: &str) -> Result<T>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        let url = format!("{}/{endpoint}", self.config.server_url);
+        self.executor
+            .send_json(&host_key(&url), endpoint, || {
+                self.authorize(self.client.get(&url))
+            })
+            .await
+    }
+
+    // Generic function to fetch data from Market Cap API (port 4040, HTTP)
+    pub(crate) async fn fetch_market_cap_api_data<T>(&self, endpoint: &str) -> Result<T>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        let url = format!("{}/{endpoint}", self.config.market_cap_server_url);
+        self.executor
+            .send_json(&host_key(&url), endpoint, || {
+                self.authorize(self.client.get(&url))
+            })
+            .await
+    }
+
+    pub async fn fetch_api_finance_report_pdf(&self, symbol: &str) -> Result<PdfReport> {
+        let cache_dir = "cache";
+        let file_path = format!("{cache_dir}/{symbol}.pdf");
+        if Path::new(&file_path).exists() {
+            return Ok(PdfReport {
+                symbol: symbol.to_string(),
+                file_path,
+            });
+        }
+
+        let endpoint = format!("report-file/{symbol}");
+        let url = format!("{}/{endpoint}", self.config.server_url);
+        let bytes = self
+            .executor
+            .execute(&host_key(&url), || async {
+                let resp = self
+                    .authorize(self.client.get(&url))
+                    .header("Accept", "application/pdf")
+                    .send()
+                    .await?; // <- Error::Transport nếu lỗi mạng
+
+                let status = resp.status();
+                if status == reqwest::StatusCode::UNAUTHORIZED
+                    || status == reqwest::StatusCode::FORBIDDEN
+                {
+                    return Err(Error::Auth {
+                        endpoint: endpoint.clone(),
+                        status,
+                    });
+                }
+                if !status.is_success() {
+                    return Err(Error::Status {
+                        endpoint: endpoint.clone(),
+                        status,
+                    });
+                }
+
+                Ok(resp.bytes().await?) // <- Error::Transport nếu lỗi đọc
+            })
+            .await?;
+
+        fs::create_dir_all(cache_dir).ok(); // không cần xử lý lỗi tạo thư mục
+        fs::write(&file_path, &bytes).ok(); // lỗi ghi file bỏ qua
+
+        Ok(PdfReport {
+            symbol: symbol.to_string(),
+            file_path,
+        })
+    }
+}
+
+fn read_certificate(path: &Path) -> Result<Certificate> {
+    let pem = fs::read(path).map_err(|e| Error::Config(format!("{}: {e}", path.display())))?;
+    Certificate::from_pem(&pem).map_err(|e| Error::Config(format!("{}: {e}", path.display())))
+}
+
+static AIM_CLIENT: OnceLock<AimClient> = OnceLock::new();
+
+/// Install the client used by `aim_client()`, returns false if one was already installed
+pub fn install_aim_client(client: AimClient) -> bool {
+    AIM_CLIENT.set(client).is_ok()
+}
+
+/// Get the process-wide AIM client, built from `AimConfig::from_env()` if none was installed
+pub fn aim_client() -> &'static AimClient {
+    AIM_CLIENT.get_or_init(|| {
+        AimClient::new(AimConfig::from_env())
+            .or_else(|_| AimClient::new(AimConfig::default()))
+            .expect("Failed to build the AIM HTTP client")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aim_mock::{Fixtures, MockConfig, MockServer};
+
+    #[tokio::test]
+    async fn test_endpoints_from_mock() {
+        let server = MockServer::start(MockConfig::new(Fixtures::bundled())).unwrap();
+        let client = AimClient::new(AimConfig {
+            server_url: server.url(),
+            market_cap_server_url: server.url(),
+            ..AimConfig::default()
+        })
+        .unwrap();
+
+        assert!(
+            !client
+                .fetch_balance_sheet_data("AAA", "Y")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            !client
+                .fetch_cash_flow_gt_sheet_data("AAA", "Y")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            !client
+                .fetch_cash_flow_tt_sheet_data("AAA", "Y")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            !client
+                .fetch_income_statement_sheet_data("AAA", "Q")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(!client.fetch_financial_data("AAA").await.unwrap().is_empty());
+        assert!(
+            !client
+                .fetch_sharedholder_data("AAA")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            client.fetch_institution_data("AAA").await.unwrap().symbol,
+            "AAA"
+        );
+        assert!(
+            !client
+                .fetch_subsidiaries_data("AAA")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(!client.fetch_officers_data("AAA").await.unwrap().is_empty());
+        assert!(
+            !client
+                .fetch_insider_transactions_data("AAA")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            !client
+                .fetch_top_stock_influencer_data()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(!client.fetch_exchange_index_data().await.unwrap().is_empty());
+        assert!(!client.fetch_stock_by_gics_data().await.unwrap().is_empty());
+        assert!(!client.fetch_icb_index_data().await.unwrap().is_empty());
+        assert!(
+            !client
+                .fetch_icb_index_data_filtered()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(!client.fetch_abnormal_trade_data().await.unwrap().is_empty());
+        assert!(!client.fetch_kqgd_td_chart_data().await.unwrap().is_empty());
+        assert!(!client.fetch_kqgd_nn_chart_data().await.unwrap().is_empty());
+        assert!(!client.fetch_sjc_price_data().await.unwrap().is_empty());
+        assert!(!client.fetch_finance_report_list().await.unwrap().is_empty());
+        assert!(
+            !client
+                .fetch_strategy_report_list()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        for variant in [
+            "vn30-correlation",
+            "bank-correlation",
+            "securities-correlation",
+        ] {
+            assert!(
+                !client
+                    .fetch_correlation_matrix(variant)
+                    .await
+                    .unwrap()
+                    .is_empty()
+            );
+        }
+        assert!(
+            !client
+                .fetch_return_matrix("daily", "VNINDEX")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(!client.fetch_rsi14_data().await.unwrap().is_empty());
+        assert!(!client.fetch_ma50_data().await.unwrap().is_empty());
+        assert!(
+            !client
+                .fetch_top_10_market_cap_data()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(!client.fetch_crypto_data("BTC").await.unwrap().is_empty());
+        assert!(!client.fetch_dominance_data().await.unwrap().is_empty());
+        assert!(!client.fetch_crypto_rsi_data().await.unwrap().is_empty());
+        assert!(!client.fetch_etf_flow_data().await.unwrap().is_empty());
+        assert!(
+            !client
+                .fetch_crypto_market_cap_data()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_config_defaults_missing_fields() {
+        let config: AimConfig = serde_json::from_str(
+            r#"{"server_url": "https://localhost:4443", "tls": {"mode": "pinned", "path": "aim.pem"}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.server_url, "https://localhost:4443");
+        assert_eq!(config.market_cap_server_url, DEFAULT_MARKET_CAP_SERVER_URL);
+        assert_eq!(config.token, None);
+        assert_eq!(config.tls, TlsTrust::Pinned(PathBuf::from("aim.pem")));
+    }
+}
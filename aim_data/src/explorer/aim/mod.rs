@@ -1,5 +1,6 @@
 mod api;
-pub use api::*;
+mod client;
+pub use client::*;
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct SjcPriceData {
@@ -10,55 +11,7 @@ pub struct SjcPriceData {
     pub sell_price: String,
     pub updated_at: i64,
 }
-use reqwest::ClientBuilder;
 use serde::{Deserialize, Serialize};
-const TOKEN: &str = "SUPER_SECRET_ADMIN_TOKEN";
-const SERVER_URL: &str = "https://103.48.84.52:4443";
-const MARKET_CAP_SERVER_URL: &str = "http://103.48.84.52:4040";
-
-// Generic function to fetch data from API endpoints
-async fn fetch_api_data<T>(endpoint: &str) -> Result<T, reqwest::Error>
-where
-    T: for<'de> serde::Deserialize<'de>,
-{
-    let url = format!("{SERVER_URL}/{endpoint}");
-    let client = ClientBuilder::new()
-        .danger_accept_invalid_certs(true)
-        .build()?;
-    let resp = client
-        .get(url)
-        .header("Authorization", format!("Bearer {TOKEN}"))
-        .send()
-        .await?;
-
-    let data: T = resp.json().await?;
-    Ok(data)
-}
-
-// Generic function to fetch data from Market Cap API (port 4040, HTTP)
-async fn fetch_market_cap_api_data<T>(endpoint: &str) -> Result<T, reqwest::Error>
-where
-    T: for<'de> serde::Deserialize<'de>,
-{
-    let url = format!("{MARKET_CAP_SERVER_URL}/{endpoint}");
-    let client = ClientBuilder::new()
-        .danger_accept_invalid_certs(true)
-        .build()?;
-    let resp = client
-        .get(&url)
-        .header("authorization", format!("Bearer {TOKEN}")) // Use lowercase "authorization"
-        .send()
-        .await?;
-
-    // Check status before parsing
-    if !resp.status().is_success() {
-        return Err(resp.error_for_status().unwrap_err());
-    }
-
-    let data: T = resp.json().await?;
-    Ok(data)
-}
-
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct IcbIndex {
@@ -1014,9 +967,6 @@ pub struct ApiReport {
 }
 
 
-use std::fs;
-use std::path::Path;
-
 #[derive(Debug, Clone)]
 pub struct PdfReport {
     pub symbol: String,
@@ -1115,42 +1065,6 @@ pub struct CryptoMarketCapData {
     pub other_value: Option<f64>,
 }
 
-pub async fn fetch_api_finance_report_pdf(symbol: &str) -> Result<PdfReport, reqwest::Error> {
-
-    let cache_dir = "cache";
-    let file_path = format!("{cache_dir}/{symbol}.pdf");
-    if Path::new(&file_path).exists() {
-        return Ok(PdfReport {
-            symbol: symbol.to_string(),
-            file_path,
-        });
-    }
-
-    let endpoint = format!("report-file/{symbol}");
-    let url = format!("{SERVER_URL}/{endpoint}");
-
-    let client = ClientBuilder::new()
-        .danger_accept_invalid_certs(true)
-        .build()?; // <- tự động trả reqwest::Error nếu lỗi
-
-    let resp = client
-        .get(&url)
-        .header("Authorization", format!("Bearer {TOKEN}"))
-        .header("Accept", "application/pdf")
-        .send()
-        .await?; // <- reqwest::Error nếu lỗi mạng
-
-    let bytes = resp.bytes().await?; // <- reqwest::Error nếu lỗi đọc
-
-    fs::create_dir_all("cache").ok(); // không cần xử lý lỗi tạo thư mục
-    let file_path = format!("cache/{}.pdf", symbol);
-    fs::write(&file_path, &bytes).ok(); // lỗi ghi file bỏ qua
-
-    Ok(PdfReport {
-        symbol: symbol.to_string(),
-        file_path,
-    })
-}
 // API Report Struct correlating to the JSON response from the API
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct CorrelationMatrixAPI {
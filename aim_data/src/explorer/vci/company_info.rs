@@ -74,6 +74,13 @@ pub struct CompanyInfo {
     pub data: CompanyFinancialRatioData,
 }
 
+impl CompanyInfo {
+    /// Most recent financial ratio, `None` when the server returned no report
+    pub fn latest_ratio(&self) -> Option<&CompanyFinancialRatio> {
+        self.data.company_financial_ratio.ratio.first()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CompanyFinancialRatioData {
     #[serde(rename = "CompanyFinancialRatio")]
@@ -1,25 +1,51 @@
+use crate::error::Error;
+use crate::executor::{RequestExecutor, executor, host_key};
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use company_info::CompanyInfo;
 use market_watch::{MarketWatchResponse, VCIMarketWatch};
 use ohlc::OHLCResponse;
 pub use order_data::{OrderData, OrderList, VCIOderBook};
-use reqwest::Error;
 use serde_json::json;
+use std::sync::Arc;
 
 use super::get_headers;
+use crate::provider::MarketDataProvider;
 
 pub mod company_info;
 pub mod market_watch;
 pub mod ohlc;
 mod order_data;
 
+/// Base URL of the Vietcap trading API
+pub const VCI_BASE_URL: &str = "https://trading.vietcap.com.vn";
+
 pub struct VCIExplorer {
     client: reqwest::Client,
+    base_url: String,
+    host: String,
+    executor: Arc<RequestExecutor>,
 }
 
 impl VCIExplorer {
     pub fn new(client: reqwest::Client) -> Self {
-        Self { client }
+        Self::with_base_url(client, VCI_BASE_URL)
+    }
+
+    /// Create an explorer that talks to another host serving the Vietcap API
+    pub fn with_base_url(client: reqwest::Client, base_url: &str) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            host: host_key(base_url),
+            executor: executor(),
+        }
+    }
+
+    /// Send requests through `executor` instead of the process-wide one
+    pub fn with_executor(mut self, executor: Arc<RequestExecutor>) -> Self {
+        self.executor = executor;
+        self
     }
 
     pub async fn get_quote(
@@ -29,7 +55,8 @@ impl VCIExplorer {
         start_time: Option<DateTime<Utc>>,
         end_time: Option<DateTime<Utc>>,
     ) -> Result<OHLCResponse, Error> {
-        let url = "https://trading.vietcap.com.vn/api/chart/OHLCChart/gap";
+        let endpoint = "api/chart/OHLCChart/gap";
+        let url = format!("{}/{endpoint}", self.base_url);
         let headers = get_headers("VCI");
 
         // Use provided times or default to 30-day range
@@ -47,41 +74,43 @@ impl VCIExplorer {
             "to": to
         });
 
-        let response = self
-            .client
-            .request(reqwest::Method::POST, url)
-            .headers(headers)
-            .json(&payload)
-            .send()
+        let res: OHLCResponse = self
+            .executor
+            .send_json(&self.host, endpoint, || {
+                self.client
+                    .request(reqwest::Method::POST, &url)
+                    .headers(headers.clone())
+                    .json(&payload)
+            })
             .await?;
 
-        let res = response.json::<OHLCResponse>().await?;
-
         Ok(res)
     }
 
     pub async fn get_market_watch(&self, symbols: &[&str]) -> Result<MarketWatchResponse, Error> {
-        let url = "https://trading.vietcap.com.vn/api/price/symbols/getList";
+        let endpoint = "api/price/symbols/getList";
+        let url = format!("{}/{endpoint}", self.base_url);
         let headers = get_headers("VCI");
 
         // Build JSON payload with the provided symbol
         let payload = json!({
             "symbols": symbols,
         });
-        let response = self
-            .client
-            .request(reqwest::Method::POST, url)
-            .headers(headers)
-            .json(&payload)
-            .send()
+        let data: Vec<VCIMarketWatch> = self
+            .executor
+            .send_json(&self.host, endpoint, || {
+                self.client
+                    .request(reqwest::Method::POST, &url)
+                    .headers(headers.clone())
+                    .json(&payload)
+            })
             .await?;
-
-        let data: Vec<VCIMarketWatch> = response.json().await?;
         Ok(MarketWatchResponse(data))
     }
 
     pub async fn get_company_info(&self, symbol: &str, period: &str) -> Result<CompanyInfo, Error> {
-        let url = "https://trading.vietcap.com.vn/data-mt/graphql";
+        let endpoint = "data-mt/graphql";
+        let url = format!("{}/{endpoint}", self.base_url);
         let headers = get_headers("VCI");
 
         // Build JSON payload with the provided symbol
@@ -93,20 +122,26 @@ impl VCIExplorer {
             },
         });
 
-        let response = self
-            .client
-            .request(reqwest::Method::POST, url)
-            .headers(headers)
-            .json(&payload)
-            .send()
+        let data: CompanyInfo = self
+            .executor
+            .send_json(&self.host, endpoint, || {
+                self.client
+                    .request(reqwest::Method::POST, &url)
+                    .headers(headers.clone())
+                    .json(&payload)
+            })
             .await?;
-
-        let data: CompanyInfo = response.json().await?;
+        if data.latest_ratio().is_none() {
+            return Err(Error::Empty {
+                endpoint: endpoint.to_string(),
+            });
+        }
         Ok(data)
     }
 
     pub async fn get_order_list(&self, symbol: &str, limit: u32) -> Result<OrderList, Error> {
-        let url = "https://trading.vietcap.com.vn/api/market-watch/LEData/getAll";
+        let endpoint = "api/market-watch/LEData/getAll";
+        let url = format!("{}/{endpoint}", self.base_url);
         let headers = get_headers("VCI");
 
         // Build JSON payload with the provided symbol
@@ -115,15 +150,15 @@ impl VCIExplorer {
             "limit": limit
         });
 
-        let response = self
-            .client
-            .request(reqwest::Method::POST, url)
-            .headers(headers)
-            .json(&payload)
-            .send()
+        let data: Vec<OrderData> = self
+            .executor
+            .send_json(&self.host, endpoint, || {
+                self.client
+                    .request(reqwest::Method::POST, &url)
+                    .headers(headers.clone())
+                    .json(&payload)
+            })
             .await?;
-
-        let data: Vec<OrderData> = response.json().await?;
         let converted_data = data
             .into_iter()
             .map(|od| VCIOderBook {
@@ -138,15 +173,64 @@ impl VCIExplorer {
     }
 }
 
+#[async_trait]
+impl MarketDataProvider for VCIExplorer {
+    async fn get_quote(
+        &self,
+        symbols: &[&str],
+        time_frame: &str,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> Result<OHLCResponse, Error> {
+        VCIExplorer::get_quote(self, symbols, time_frame, start_time, end_time).await
+    }
+
+    async fn get_market_watch(&self, symbols: &[&str]) -> Result<MarketWatchResponse, Error> {
+        VCIExplorer::get_market_watch(self, symbols).await
+    }
+
+    async fn get_company_info(&self, symbol: &str, period: &str) -> Result<CompanyInfo, Error> {
+        VCIExplorer::get_company_info(self, symbol, period).await
+    }
+
+    async fn get_order_list(&self, symbol: &str, limit: u32) -> Result<OrderList, Error> {
+        VCIExplorer::get_order_list(self, symbol, limit).await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aim_mock::{Fixtures, MockConfig, MockServer};
     use reqwest::Client;
 
+    #[tokio::test]
+    async fn test_endpoints_from_mock() {
+        let server = MockServer::start(MockConfig::new(Fixtures::bundled())).unwrap();
+        let explorer = VCIExplorer::with_base_url(Client::new(), &server.url());
+
+        let quote = explorer
+            .get_quote(&["AAA"], "ONE_DAY", None, None)
+            .await
+            .unwrap();
+        let aaa = quote.0.iter().find(|d| d.symbol == "AAA").unwrap();
+        assert_eq!(aaa.to_candlesticks().len(), aaa.t.len());
+
+        let board = explorer.get_market_watch(&["AAA", "VNM"]).await.unwrap();
+        assert!(board.0.iter().any(|m| m.listing_info.symbol == "VNM"));
+
+        let info = explorer.get_company_info("AAA", "Y").await.unwrap();
+        assert!(info.latest_ratio().unwrap().pe.is_some());
+
+        let orders = explorer.get_order_list("AAA", 100).await.unwrap();
+        assert!(!orders.is_empty());
+        assert!(orders.iter().all(|o| o.price > 0.0 && o.volume > 0));
+    }
+
     #[tokio::test]
     async fn test_get_order_list() {
-        let client = Client::new();
-        let explorer = VCIExplorer::new(client);
+        let server = MockServer::start(MockConfig::new(Fixtures::bundled())).unwrap();
+        let explorer = VCIExplorer::with_base_url(Client::new(), &server.url());
 
         // Use a known symbol and a small limit for testing
         let symbol = "API";
@@ -156,7 +240,6 @@ mod tests {
         assert!(result.is_ok(), "API call failed: {result:?}");
 
         let orders = result.unwrap();
-        println!("output: {orders:?}");
         assert!(!orders.is_empty(), "Order list should not be empty");
         for order in &orders {
             assert!(order.price > 0.0);
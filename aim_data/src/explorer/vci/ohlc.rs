@@ -122,6 +122,38 @@ pub struct Candlestick {
 }
 
 impl OHLCData {
+    /// Build the response layout of the quote API from bars, e.g. read back from
+    /// the local history store
+    pub fn from_candlesticks(symbol: &str, bars: &[Candlestick]) -> Self {
+        let mut accumulated_volume = 0;
+        let mut accumulated_value = 0.0;
+        let mut data = Self {
+            symbol: symbol.to_string(),
+            o: Vec::with_capacity(bars.len()),
+            h: Vec::with_capacity(bars.len()),
+            l: Vec::with_capacity(bars.len()),
+            c: Vec::with_capacity(bars.len()),
+            v: Vec::with_capacity(bars.len()),
+            t: Vec::with_capacity(bars.len()),
+            accumulated_volume: Vec::with_capacity(bars.len()),
+            accumulated_value: Vec::with_capacity(bars.len()),
+            min_batch_trunc_time: String::new(),
+        };
+        for bar in bars {
+            accumulated_volume += bar.volume;
+            accumulated_value += bar.close * bar.volume as f64;
+            data.o.push(Some(bar.open));
+            data.h.push(Some(bar.high));
+            data.l.push(Some(bar.low));
+            data.c.push(Some(bar.close));
+            data.v.push(Some(bar.volume));
+            data.t.push(bar.timestamp.timestamp().to_string());
+            data.accumulated_volume.push(Some(accumulated_volume));
+            data.accumulated_value.push(Some(accumulated_value));
+        }
+        data
+    }
+
     pub fn to_candlesticks(&self) -> Vec<Candlestick> {
         let mut candlesticks = Vec::new();
 
@@ -1,10 +1,10 @@
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::fmt;
 
 pub type OrderList = Vec<VCIOderBook>;
 
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VCIOderBook {
     pub id: u64,
     pub timestamp: String, // hh:mm:ss
@@ -1,10 +1,37 @@
 // pub mod btc;
+mod error;
+pub mod executor;
 pub mod explorer;
+pub mod provider;
+pub mod recording;
+pub mod resample;
+pub mod store;
 
 // pub use btc::get_btc_price;
 use chrono::{DateTime, Utc};
 use explorer::vci::VCIOderBook;
 use reqwest::Client;
+use std::sync::{Arc, OnceLock};
+
+/// Get the process-wide default provider (Vietcap) sharing a single HTTP client
+///
+/// The `VCI_BASE_URL` environment variable points it at another host, e.g. the
+/// `aim-mock-server` fixture server for offline development.
+pub fn default_provider() -> SharedProvider {
+    static DEFAULT_PROVIDER: OnceLock<SharedProvider> = OnceLock::new();
+    DEFAULT_PROVIDER
+        .get_or_init(|| {
+            let base_url = std::env::var("VCI_BASE_URL")
+                .ok()
+                .filter(|url| !url.is_empty())
+                .unwrap_or_else(|| explorer::vci::VCI_BASE_URL.to_string());
+            Arc::new(explorer::VCIExplorer::with_base_url(
+                Client::new(),
+                &base_url,
+            ))
+        })
+        .clone()
+}
 
 /// Get stock quote data for a given symbol
 ///
@@ -14,18 +41,14 @@ use reqwest::Client;
 /// * `end_time` - Optional end time for the data range
 ///
 /// # Returns
-/// * `Result<OHLCResponse, reqwest::Error>` - The OHLC data or an error
+/// * `Result<OHLCResponse, Error>` - The OHLC data or an error
 ///
 /// # Example
-/// ```
-/// use aim_stock;
-/// use chrono::{DateTime, Utc};
-///
+/// ```no_run
 /// #[tokio::main]
 /// async fn main() {
-///     aim_stock::init();
-///     let data = aim_stock::get_quote("VCI", None, None).await.unwrap();
-///     
+///     let data = aim_data::get_quote(&["VCI"], "ONE_DAY", None, None).await.unwrap();
+///
 ///     // Access the first (and only) symbol's data
 ///     if let Some(ohlc) = data.0.first() {
 ///         println!("Symbol: {}", ohlc.symbol);
@@ -42,40 +65,36 @@ pub async fn get_quote(
     time_frame: &str,
     start_time: Option<DateTime<Utc>>,
     end_time: Option<DateTime<Utc>>,
-) -> Result<explorer::vci::ohlc::OHLCResponse, reqwest::Error> {
-    let client = Client::new();
-    let explorer = explorer::VCIExplorer::new(client);
-    explorer
+) -> Result<explorer::vci::ohlc::OHLCResponse> {
+    default_provider()
         .get_quote(symbol, time_frame, start_time, end_time)
         .await
 }
 
 pub async fn get_market_watch(
     symbols: &[&str],
-) -> Result<explorer::vci::market_watch::MarketWatchResponse, reqwest::Error> {
-    let client = Client::new();
-    let explorer = explorer::VCIExplorer::new(client);
-    explorer.get_market_watch(symbols).await
+) -> Result<explorer::vci::market_watch::MarketWatchResponse> {
+    default_provider().get_market_watch(symbols).await
 }
 
-pub async fn get_company_info(
-    symbols: &str,
-) -> Result<explorer::vci::company_info::CompanyInfo, reqwest::Error> {
-    let client = Client::new();
-    let explorer = explorer::VCIExplorer::new(client);
-    explorer.get_company_info(symbols, "Y").await
+pub async fn get_company_info(symbols: &str) -> Result<explorer::vci::company_info::CompanyInfo> {
+    default_provider().get_company_info(symbols, "Y").await
 }
 
 #[allow(dead_code)]
-pub async fn get_order_list(symbol: &str) -> Result<Vec<VCIOderBook>, reqwest::Error> {
-    let client = Client::new();
-    let explorer = explorer::VCIExplorer::new(client);
-    explorer.get_order_list(symbol, 30000).await
+pub async fn get_order_list(symbol: &str) -> Result<Vec<VCIOderBook>> {
+    default_provider().get_order_list(symbol, 30000).await
 }
 
+pub use error::{Error, Result};
+pub use executor::{CircuitState, HostHealth, executor};
 /// Re-export types for direct usage
 pub use explorer::vci::ohlc::{Candlestick, OHLCData};
 pub use explorer::*;
+pub use provider::{MarketDataProvider, SharedProvider};
+pub use recording::{RecordingProvider, ReplayOptions, ReplayProvider};
+pub use resample::{TimeUnit, Timeframe};
+pub use store::{CandleStore, candle_store, install_candle_store};
 
 // mod test {
 //     #[cfg(test)]
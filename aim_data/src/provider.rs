@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::error::Error;
+use crate::explorer::vci::OrderList;
+use crate::explorer::vci::company_info::CompanyInfo;
+use crate::explorer::vci::market_watch::MarketWatchResponse;
+use crate::explorer::vci::ohlc::OHLCResponse;
+
+/// Source of live market data (quotes, market watch, company ratios, matched orders)
+///
+/// `VCIExplorer` is the default implementation. The application holds one
+/// `SharedProvider` and hands clones of it to every task, so another broker or
+/// a local fixture provider can be swapped in without touching the task code.
+#[async_trait]
+pub trait MarketDataProvider: Send + Sync {
+    /// Get OHLC bars for the given symbols and time frame (e.g. "ONE_DAY")
+    async fn get_quote(
+        &self,
+        symbols: &[&str],
+        time_frame: &str,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> Result<OHLCResponse, Error>;
+
+    /// Get the current price board for the given symbols
+    async fn get_market_watch(&self, symbols: &[&str]) -> Result<MarketWatchResponse, Error>;
+
+    /// Get financial ratios of a company for a report period ("Y" or "Q")
+    async fn get_company_info(&self, symbol: &str, period: &str) -> Result<CompanyInfo, Error>;
+
+    /// Get the latest matched orders of a symbol
+    async fn get_order_list(&self, symbol: &str, limit: u32) -> Result<OrderList, Error>;
+
+    /// Whether polling should pause outside trading hours, false for recorded data
+    fn follows_trading_hours(&self) -> bool {
+        true
+    }
+}
+
+/// Provider instance shared between tasks
+pub type SharedProvider = Arc<dyn MarketDataProvider>;
@@ -0,0 +1,528 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::explorer::vci::company_info::CompanyInfo;
+use crate::explorer::vci::market_watch::{MarketWatchResponse, VCIMarketWatch};
+use crate::explorer::vci::ohlc::{Candlestick, OHLCData, OHLCResponse};
+use crate::explorer::vci::{OrderList, VCIOderBook};
+use crate::provider::{MarketDataProvider, SharedProvider};
+
+/// One response captured during a live session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Snapshot {
+    MarketWatch {
+        at: DateTime<Utc>,
+        data: Vec<VCIMarketWatch>,
+    },
+    Quote {
+        at: DateTime<Utc>,
+        time_frame: String,
+        data: Vec<OHLCData>,
+    },
+    OrderList {
+        at: DateTime<Utc>,
+        symbol: String,
+        data: Vec<VCIOderBook>,
+    },
+}
+
+impl Snapshot {
+    pub fn at(&self) -> DateTime<Utc> {
+        match self {
+            Snapshot::MarketWatch { at, .. }
+            | Snapshot::Quote { at, .. }
+            | Snapshot::OrderList { at, .. } => *at,
+        }
+    }
+}
+
+/// Provider that forwards to `inner` and appends every market watch, quote
+/// and order list response to a JSON-lines file
+pub struct RecordingProvider {
+    inner: SharedProvider,
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl RecordingProvider {
+    /// Record to `path`, appending to the file if it already exists
+    pub fn new(inner: SharedProvider, path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .map_err(|e| Error::Config(format!("{}: {e}", dir.display())))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| Error::Config(format!("{}: {e}", path.display())))?;
+        Ok(Self {
+            inner,
+            writer: Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    fn record(&self, snapshot: &Snapshot) {
+        let mut writer = self.writer.lock().unwrap();
+        let written = serde_json::to_writer(&mut *writer, snapshot)
+            .map_err(std::io::Error::from)
+            .and_then(|_| writer.write_all(b"\n"))
+            .and_then(|_| writer.flush());
+        if let Err(e) = written {
+            log::error!("Failed to record snapshot: {e}");
+        }
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for RecordingProvider {
+    async fn get_quote(
+        &self,
+        symbols: &[&str],
+        time_frame: &str,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> Result<OHLCResponse> {
+        let res = self
+            .inner
+            .get_quote(symbols, time_frame, start_time, end_time)
+            .await?;
+        self.record(&Snapshot::Quote {
+            at: Utc::now(),
+            time_frame: time_frame.to_string(),
+            data: res.0.clone(),
+        });
+        Ok(res)
+    }
+
+    async fn get_market_watch(&self, symbols: &[&str]) -> Result<MarketWatchResponse> {
+        let res = self.inner.get_market_watch(symbols).await?;
+        self.record(&Snapshot::MarketWatch {
+            at: Utc::now(),
+            data: res.0.clone(),
+        });
+        Ok(res)
+    }
+
+    async fn get_company_info(&self, symbol: &str, period: &str) -> Result<CompanyInfo> {
+        self.inner.get_company_info(symbol, period).await
+    }
+
+    async fn get_order_list(&self, symbol: &str, limit: u32) -> Result<OrderList> {
+        let res = self.inner.get_order_list(symbol, limit).await?;
+        self.record(&Snapshot::OrderList {
+            at: Utc::now(),
+            symbol: symbol.to_string(),
+            data: res.clone(),
+        });
+        Ok(res)
+    }
+
+    fn follows_trading_hours(&self) -> bool {
+        self.inner.follows_trading_hours()
+    }
+}
+
+/// Playback settings of a `ReplayProvider`
+#[derive(Debug, Clone)]
+pub struct ReplayOptions {
+    /// Recorded time per wall-clock time, 10.0 plays ten minutes per minute
+    pub speed: f64,
+    /// Start this long after the first snapshot, to jump to the middle of a session
+    pub skip: Duration,
+    /// Start over once the last snapshot is reached
+    pub looped: bool,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            skip: Duration::zero(),
+            looped: false,
+        }
+    }
+}
+
+/// Provider answering from a recording made by `RecordingProvider`
+///
+/// Each call returns the latest snapshot recorded at or before the current
+/// playback time, except quotes: the bars of every quote response recorded by
+/// then are merged and those in the requested range returned. Data missing
+/// from the recording (company ratios, symbols that were not polled, history
+/// before the session) comes from the optional fallback provider.
+pub struct ReplayProvider {
+    snapshots: Vec<Snapshot>,
+    options: ReplayOptions,
+    started_at: Instant,
+    fallback: Option<SharedProvider>,
+}
+
+impl ReplayProvider {
+    pub fn load(path: &Path, options: ReplayOptions) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| Error::Config(format!("{}: {e}", path.display())))?;
+        let mut snapshots = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            // A session cut off while recording ends with a partial line
+            match serde_json::from_str::<Snapshot>(line) {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(e) => log::warn!("{}:{}: skipping snapshot: {e}", path.display(), i + 1),
+            }
+        }
+        Ok(Self::from_snapshots(snapshots, options))
+    }
+
+    pub fn from_snapshots(mut snapshots: Vec<Snapshot>, options: ReplayOptions) -> Self {
+        snapshots.sort_by_key(Snapshot::at);
+        Self {
+            snapshots,
+            options,
+            started_at: Instant::now(),
+            fallback: None,
+        }
+    }
+
+    pub fn with_fallback(mut self, fallback: SharedProvider) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    /// Recorded time currently played back
+    pub fn playback_time(&self) -> Option<DateTime<Utc>> {
+        let elapsed = self.started_at.elapsed().as_secs_f64() * self.options.speed;
+        self.playback_time_after(Duration::milliseconds((elapsed * 1000.0) as i64))
+    }
+
+    fn playback_time_after(&self, elapsed: Duration) -> Option<DateTime<Utc>> {
+        let first = self.snapshots.first()?.at();
+        let last = self.snapshots.last()?.at();
+        let mut offset = self.options.skip + elapsed;
+        let length = last - first;
+        if self.options.looped && length > Duration::zero() {
+            offset =
+                Duration::milliseconds(offset.num_milliseconds() % (length.num_milliseconds() + 1));
+        }
+        Some(first + offset)
+    }
+
+    /// Latest snapshot matching `filter` at `now`, the first matching one if playback has not reached it yet
+    fn latest<'a, T>(
+        &'a self,
+        now: Option<DateTime<Utc>>,
+        filter: impl Fn(&'a Snapshot) -> Option<T>,
+    ) -> Option<T> {
+        let now = now?;
+        let reached = self.snapshots.partition_point(|s| s.at() <= now);
+        self.snapshots[..reached]
+            .iter()
+            .rev()
+            .find_map(&filter)
+            .or_else(|| self.snapshots[reached..].iter().find_map(&filter))
+    }
+
+    /// Bars of `symbol` from every `time_frame` quote recorded at or before
+    /// `now`, a bar of a later response replacing the same bar of an earlier
+    /// one, or those of the first response if playback has not reached it yet
+    fn recorded_bars(
+        &self,
+        now: Option<DateTime<Utc>>,
+        symbol: &str,
+        time_frame: &str,
+    ) -> Vec<Candlestick> {
+        let quote = |s: &Snapshot| match s {
+            Snapshot::Quote {
+                time_frame: tf,
+                data,
+                ..
+            } if tf == time_frame => data.iter().find(|d| d.symbol == symbol).cloned(),
+            _ => None,
+        };
+        let Some(now) = now else {
+            return Vec::new();
+        };
+        let reached = self.snapshots.partition_point(|s| s.at() <= now);
+        let mut bars = BTreeMap::new();
+        for data in self.snapshots[..reached].iter().filter_map(quote) {
+            for bar in data.to_candlesticks() {
+                bars.insert(bar.timestamp, bar);
+            }
+        }
+        if bars.is_empty()
+            && let Some(data) = self.snapshots[reached..].iter().find_map(quote)
+        {
+            return data.to_candlesticks();
+        }
+        bars.into_values().collect()
+    }
+
+    fn empty(endpoint: &str) -> Error {
+        Error::Empty {
+            endpoint: format!("replay/{endpoint}"),
+        }
+    }
+}
+
+#[async_trait]
+impl MarketDataProvider for ReplayProvider {
+    async fn get_quote(
+        &self,
+        symbols: &[&str],
+        time_frame: &str,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> Result<OHLCResponse> {
+        let now = self.playback_time();
+        let in_range = |bar: &Candlestick| {
+            start_time.is_none_or(|start| bar.timestamp >= start)
+                && end_time.is_none_or(|end| bar.timestamp <= end)
+        };
+        let mut data = Vec::new();
+        let mut failure = None;
+        for &symbol in symbols {
+            let mut bars: Vec<Candlestick> = self
+                .recorded_bars(now, symbol, time_frame)
+                .into_iter()
+                .filter(in_range)
+                .collect();
+
+            // Bars before the first recorded one, e.g. the history of a chart
+            // opened during the replay, come from the fallback
+            let first = bars.first().map(|bar| bar.timestamp);
+            if let Some(fallback) = &self.fallback
+                && first.is_none_or(|first| start_time.is_none_or(|start| start < first))
+            {
+                let end = first.map_or(end_time, |first| Some(first - Duration::seconds(1)));
+                match fallback
+                    .get_quote(&[symbol], time_frame, start_time, end)
+                    .await
+                {
+                    Ok(res) => {
+                        let mut earlier: Vec<Candlestick> = res
+                            .0
+                            .iter()
+                            .filter(|d| d.symbol == symbol)
+                            .flat_map(OHLCData::to_candlesticks)
+                            .filter(|bar| {
+                                in_range(bar) && first.is_none_or(|first| bar.timestamp < first)
+                            })
+                            .collect();
+                        earlier.append(&mut bars);
+                        bars = earlier;
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to fetch {symbol} bars missing from the replay: {e}");
+                        failure = Some(e);
+                    }
+                }
+            }
+            if !bars.is_empty() {
+                data.push(OHLCData::from_candlesticks(symbol, &bars));
+            }
+        }
+
+        match failure {
+            Some(e) if data.is_empty() => Err(e),
+            _ if data.is_empty() => Err(Self::empty("quote")),
+            _ => Ok(OHLCResponse(data)),
+        }
+    }
+
+    async fn get_market_watch(&self, symbols: &[&str]) -> Result<MarketWatchResponse> {
+        let now = self.playback_time();
+        let board = self.latest(now, |s| match s {
+            Snapshot::MarketWatch { data, .. } => Some(data),
+            _ => None,
+        });
+        let data: Vec<VCIMarketWatch> = board
+            .into_iter()
+            .flatten()
+            .filter(|m| symbols.contains(&m.listing_info.symbol.as_str()))
+            .cloned()
+            .collect();
+
+        match &self.fallback {
+            Some(fallback) if data.is_empty() => fallback.get_market_watch(symbols).await,
+            _ if data.is_empty() => Err(Self::empty("market_watch")),
+            _ => Ok(MarketWatchResponse(data)),
+        }
+    }
+
+    async fn get_company_info(&self, symbol: &str, period: &str) -> Result<CompanyInfo> {
+        match &self.fallback {
+            Some(fallback) => fallback.get_company_info(symbol, period).await,
+            None => Err(Self::empty("company_info")),
+        }
+    }
+
+    async fn get_order_list(&self, symbol: &str, limit: u32) -> Result<OrderList> {
+        let now = self.playback_time();
+        let orders = self.latest(now, |s| match s {
+            Snapshot::OrderList {
+                symbol: recorded,
+                data,
+                ..
+            } if recorded == symbol => Some(data),
+            _ => None,
+        });
+
+        match (orders, &self.fallback) {
+            (Some(orders), _) => Ok(orders.iter().take(limit as usize).cloned().collect()),
+            (None, Some(fallback)) => fallback.get_order_list(symbol, limit).await,
+            (None, None) => Err(Self::empty("order_list")),
+        }
+    }
+
+    /// A replay keeps playing after the market closes
+    fn follows_trading_hours(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn order_list(minute: i64, price: f64) -> Snapshot {
+        let at = DateTime::from_timestamp(1_760_670_000, 0).unwrap() + Duration::minutes(minute);
+        Snapshot::OrderList {
+            at,
+            symbol: "AAA".to_string(),
+            data: vec![VCIOderBook {
+                id: minute as u64,
+                timestamp: at.format("%H:%M:%S").to_string(),
+                price,
+                volume: 100,
+                match_type: "b".to_string(),
+            }],
+        }
+    }
+
+    fn day(index: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_759_276_800, 0).unwrap() + Duration::days(index)
+    }
+
+    /// Quote of AAA recorded `minute` minutes into the session, with the
+    /// close of each (day, close)
+    fn quote(minute: i64, closes: &[(i64, f64)]) -> Snapshot {
+        let bars: Vec<Candlestick> = closes
+            .iter()
+            .map(|&(index, close)| Candlestick {
+                timestamp: day(index),
+                open: close,
+                high: close,
+                low: close,
+                close,
+                volume: 100,
+            })
+            .collect();
+        Snapshot::Quote {
+            at: DateTime::from_timestamp(1_760_670_000, 0).unwrap() + Duration::minutes(minute),
+            time_frame: "ONE_DAY".to_string(),
+            data: vec![OHLCData::from_candlesticks("AAA", &bars)],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replayed_quotes_merge_in_the_range() {
+        let history: Vec<(i64, f64)> = (0..5).map(|i| (i, 10.0 + i as f64)).collect();
+        let live: SharedProvider = Arc::new(ReplayProvider::from_snapshots(
+            vec![quote(0, &[(-3, 7.0), (-2, 8.0), (-1, 9.0)])],
+            ReplayOptions::default(),
+        ));
+        let replay = ReplayProvider::from_snapshots(
+            // The history, then an update of the last candle and a new one
+            vec![quote(0, &history), quote(5, &[(4, 20.0), (5, 21.0)])],
+            ReplayOptions {
+                skip: Duration::minutes(10),
+                ..ReplayOptions::default()
+            },
+        )
+        .with_fallback(live);
+        let closes = |res: OHLCResponse| -> Vec<f64> {
+            res.0[0]
+                .to_candlesticks()
+                .iter()
+                .map(|bar| bar.close)
+                .collect()
+        };
+
+        let res = replay
+            .get_quote(&["AAA"], "ONE_DAY", Some(day(1)), Some(day(4)))
+            .await
+            .unwrap();
+        assert_eq!(closes(res), vec![11.0, 12.0, 13.0, 20.0]);
+
+        // The days before the session come from the fallback
+        let res = replay
+            .get_quote(&["AAA"], "ONE_DAY", Some(day(-2)), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            closes(res),
+            vec![8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 20.0, 21.0]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let path = std::env::temp_dir().join(format!("aim_recording_{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let source: SharedProvider = Arc::new(ReplayProvider::from_snapshots(
+            vec![order_list(0, 8600.0)],
+            ReplayOptions::default(),
+        ));
+        let recorder = RecordingProvider::new(source, &path).unwrap();
+        recorder.get_order_list("AAA", 10).await.unwrap();
+        recorder.get_order_list("AAA", 10).await.unwrap();
+
+        let replay = ReplayProvider::load(&path, ReplayOptions::default()).unwrap();
+        assert_eq!(replay.snapshots().len(), 2);
+        assert_eq!(
+            replay.get_order_list("AAA", 10).await.unwrap()[0].price,
+            8600.0
+        );
+        assert!(replay.get_order_list("VNM", 10).await.is_err());
+        assert!(!replay.follows_trading_hours());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_playback_skip() {
+        let replay = ReplayProvider::from_snapshots(
+            vec![order_list(10, 3.0), order_list(0, 1.0), order_list(5, 2.0)],
+            ReplayOptions {
+                skip: Duration::minutes(4),
+                ..ReplayOptions::default()
+            },
+        );
+        let price_after = |elapsed| {
+            let now = replay.playback_time_after(elapsed);
+            replay.latest(now, |s| match s {
+                Snapshot::OrderList { data, .. } => Some(data[0].price),
+                _ => None,
+            })
+        };
+        assert_eq!(price_after(Duration::zero()), Some(1.0));
+        assert_eq!(price_after(Duration::minutes(1)), Some(2.0));
+        assert_eq!(price_after(Duration::hours(1)), Some(3.0));
+    }
+}
@@ -0,0 +1,333 @@
+use std::fmt;
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveTime, TimeZone, Timelike, Utc};
+
+use crate::Candlestick;
+
+/// Vietnam time (UTC+7), used for the exchange sessions
+const VN_OFFSET_SECS: i32 = 7 * 3600;
+/// Morning session 9:00 - 11:30
+const MORNING_START_MIN: u32 = 9 * 60;
+const MORNING_LEN_MIN: u32 = 150;
+/// Afternoon session 13:00 - 15:00, continuous matching then ATC
+const AFTERNOON_START_MIN: u32 = 13 * 60;
+const AFTERNOON_LEN_MIN: u32 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeUnit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+/// A chart timeframe such as `15m`, `4H`, `2D` or `1M`
+///
+/// Bars are fetched at the base interval of the unit and aggregated with
+/// [`Timeframe::resample`]. Intraday buckets restart at the beginning of each
+/// session, so no bar spans the lunch break or the overnight gap. Day buckets
+/// never span two weeks (`2D` is Mon-Tue, Wed-Thu, Fri), week buckets are
+/// aligned on ISO weeks and month buckets on calendar months.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timeframe {
+    pub unit: TimeUnit,
+    pub count: u32,
+}
+
+impl Timeframe {
+    pub const DAY: Self = Self {
+        unit: TimeUnit::Day,
+        count: 1,
+    };
+
+    pub fn new(unit: TimeUnit, count: u32) -> Self {
+        Self {
+            unit,
+            count: count.max(1),
+        }
+    }
+
+    /// Parse the timeframe labels of the chart toolbar: `1m`, `15m`, `4H`, `3D`,
+    /// `2W`, `1M`... (`m` is minutes, `M` is months)
+    pub fn parse(label: &str) -> Option<Self> {
+        let label = label.trim();
+        let split = label.find(|c: char| !c.is_ascii_digit())?;
+        let (count, unit) = label.split_at(split);
+        let count = if count.is_empty() {
+            1
+        } else {
+            count.parse().ok().filter(|&n| n > 0)?
+        };
+        let unit = match unit {
+            "m" => TimeUnit::Minute,
+            "H" | "h" => TimeUnit::Hour,
+            "D" | "d" => TimeUnit::Day,
+            "W" | "w" => TimeUnit::Week,
+            "M" => TimeUnit::Month,
+            _ => return None,
+        };
+        Some(Self::new(unit, count))
+    }
+
+    /// Interval constant of the quote API the bars of this timeframe are built from
+    pub fn api_interval(&self) -> &'static str {
+        match (self.unit, self.count) {
+            (TimeUnit::Minute, _) => "ONE_MINUTE",
+            (TimeUnit::Hour, _) => "ONE_HOUR",
+            (TimeUnit::Week, 1) => "ONE_WEEK",
+            _ => "ONE_DAY",
+        }
+    }
+
+    /// Approximate length of one bar, used to extrapolate times past the data
+    pub fn bar_duration(&self) -> Duration {
+        let count = self.count as i64;
+        match self.unit {
+            TimeUnit::Minute => Duration::minutes(count),
+            TimeUnit::Hour => Duration::hours(count),
+            TimeUnit::Day => Duration::days(count),
+            TimeUnit::Week => Duration::weeks(count),
+            TimeUnit::Month => Duration::days(30 * count),
+        }
+    }
+
+    /// Calendar time covering at least `bars` bars of this timeframe, counting
+    /// the hours and days without trading
+    pub fn lookback(&self, bars: usize) -> Duration {
+        let bars = bars as u64;
+        let count = self.count as u64;
+        // Trading days needed, turned into calendar days over 5-day weeks
+        let calendar_days = |trading_days: u64| Duration::days((trading_days * 7 / 5 + 3) as i64);
+        let session_min = (MORNING_LEN_MIN + AFTERNOON_LEN_MIN) as u64;
+        match self.unit {
+            TimeUnit::Minute => calendar_days((bars * count).div_ceil(session_min)),
+            // Hour buckets restart with each session, so a day holds fewer of them
+            // than its trading hours suggest
+            TimeUnit::Hour => calendar_days(bars.div_ceil(session_min / 60 / count.min(3))),
+            TimeUnit::Day => calendar_days(bars * count),
+            TimeUnit::Week => Duration::weeks((bars * count + 1) as i64),
+            TimeUnit::Month => Duration::days((31 * (bars * count + 1)) as i64),
+        }
+    }
+
+    pub fn is_intraday(&self) -> bool {
+        matches!(self.unit, TimeUnit::Minute | TimeUnit::Hour)
+    }
+
+    /// Aggregate `bars` (sorted by time, at the base interval or finer) into bars
+    /// of this timeframe
+    ///
+    /// Intraday bars are stamped with the start of their bucket, longer ones with
+    /// the time of their first base bar so they keep the dates of the API.
+    pub fn resample(&self, bars: &[Candlestick]) -> Vec<Candlestick> {
+        let mut result: Vec<Candlestick> = Vec::new();
+        let mut current_key = None;
+
+        for bar in bars {
+            let key = self.bucket_key(bar.timestamp);
+            match result.last_mut() {
+                Some(last) if current_key == Some(key) => {
+                    last.high = last.high.max(bar.high);
+                    last.low = last.low.min(bar.low);
+                    last.close = bar.close;
+                    last.volume += bar.volume;
+                }
+                _ => {
+                    let timestamp = if self.is_intraday() {
+                        DateTime::from_timestamp(key, 0).unwrap_or(bar.timestamp)
+                    } else {
+                        bar.timestamp
+                    };
+                    result.push(Candlestick {
+                        timestamp,
+                        ..bar.clone()
+                    });
+                    current_key = Some(key);
+                }
+            }
+        }
+        result
+    }
+
+    /// Key shared by every bar of the same bucket. For intraday timeframes it is
+    /// the Unix timestamp of the bucket start.
+    fn bucket_key(&self, timestamp: DateTime<Utc>) -> i64 {
+        let vn = FixedOffset::east_opt(VN_OFFSET_SECS).expect("valid offset");
+        let local = timestamp.with_timezone(&vn);
+        let date = local.date_naive();
+
+        match self.unit {
+            TimeUnit::Minute | TimeUnit::Hour => {
+                let size = match self.unit {
+                    TimeUnit::Hour => self.count * 60,
+                    _ => self.count,
+                };
+                let minute = local.hour() * 60 + local.minute();
+                // Prints of the lunch break (e.g. 11:30) belong to the morning
+                // session, pre-open ones to its first bucket
+                let (start, len) = if minute < AFTERNOON_START_MIN {
+                    (MORNING_START_MIN, MORNING_LEN_MIN)
+                } else {
+                    (AFTERNOON_START_MIN, AFTERNOON_LEN_MIN)
+                };
+                let offset = minute.saturating_sub(start).min(len - 1);
+                let bucket_min = start + offset / size * size;
+                let bucket_time = NaiveTime::from_hms_opt(bucket_min / 60, bucket_min % 60, 0)
+                    .unwrap_or(NaiveTime::MIN);
+                vn.from_local_datetime(&date.and_time(bucket_time))
+                    .single()
+                    .map_or(timestamp.timestamp(), |t| t.timestamp())
+            }
+            TimeUnit::Day => {
+                let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                let chunk = date.weekday().num_days_from_monday() / self.count;
+                monday.num_days_from_ce() as i64 * 8 + chunk as i64
+            }
+            TimeUnit::Week => {
+                let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                (monday.num_days_from_ce() as i64 / 7).div_euclid(self.count as i64)
+            }
+            TimeUnit::Month => {
+                let months = date.year() as i64 * 12 + date.month0() as i64;
+                months.div_euclid(self.count as i64)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Timeframe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self.unit {
+            TimeUnit::Minute => "m",
+            TimeUnit::Hour => "H",
+            TimeUnit::Day => "D",
+            TimeUnit::Week => "W",
+            TimeUnit::Month => "M",
+        };
+        write!(f, "{}{unit}", self.count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bar at `hh:mm` Vietnam time on 2025-03-<day>
+    fn bar(day: u32, hh: u32, mm: u32, price: f64, volume: i64) -> Candlestick {
+        let vn = FixedOffset::east_opt(VN_OFFSET_SECS).unwrap();
+        Candlestick {
+            timestamp: vn
+                .with_ymd_and_hms(2025, 3, day, hh, mm, 0)
+                .unwrap()
+                .with_timezone(&Utc),
+            open: price,
+            high: price + 1.0,
+            low: price - 1.0,
+            close: price,
+            volume,
+        }
+    }
+
+    fn local_times(bars: &[Candlestick]) -> Vec<String> {
+        let vn = FixedOffset::east_opt(VN_OFFSET_SECS).unwrap();
+        bars.iter()
+            .map(|b| {
+                b.timestamp
+                    .with_timezone(&vn)
+                    .format("%d %H:%M")
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_labels() {
+        assert_eq!(
+            Timeframe::parse("15m"),
+            Some(Timeframe::new(TimeUnit::Minute, 15))
+        );
+        assert_eq!(Timeframe::parse("4H").unwrap().api_interval(), "ONE_HOUR");
+        assert_eq!(Timeframe::parse("1W").unwrap().api_interval(), "ONE_WEEK");
+        assert_eq!(Timeframe::parse("2W").unwrap().api_interval(), "ONE_DAY");
+        assert_eq!(Timeframe::parse("1M").unwrap().unit, TimeUnit::Month);
+        assert_eq!(Timeframe::parse("1M").unwrap().to_string(), "1M");
+        assert!(Timeframe::parse("0D").is_none());
+        assert!(Timeframe::parse("5x").is_none());
+    }
+
+    #[test]
+    fn test_lookback_covers_trading_time() {
+        let one_minute = Timeframe::parse("1m").unwrap();
+        // 270 trading minutes a day
+        assert_eq!(one_minute.lookback(540), Duration::days(5));
+        assert_eq!(Timeframe::DAY.lookback(5), Duration::days(10));
+        assert!(Timeframe::parse("1H").unwrap().lookback(5) >= Duration::days(1));
+        assert_eq!(
+            Timeframe::parse("1M").unwrap().lookback(2),
+            Duration::days(93)
+        );
+    }
+
+    #[test]
+    fn test_intraday_buckets_restart_after_lunch() {
+        let bars: Vec<_> = [
+            (10, 58),
+            (11, 0),
+            (11, 15),
+            (11, 29),
+            (11, 30),
+            (13, 0),
+            (13, 1),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, &(h, m))| bar(3, h, m, 10.0 + i as f64, 100))
+        .collect();
+
+        let tf = Timeframe::parse("2H").unwrap();
+        let resampled = tf.resample(&bars);
+        assert_eq!(
+            local_times(&resampled),
+            ["03 09:00", "03 11:00", "03 13:00"]
+        );
+        // 11:30 print closes the morning
+        assert_eq!(resampled[1].open, 11.0);
+        assert_eq!(resampled[1].close, 14.0);
+        assert_eq!(resampled[1].high, 15.0);
+        assert_eq!(resampled[1].low, 10.0);
+        assert_eq!(resampled[1].volume, 400);
+
+        let resampled = Timeframe::parse("30m").unwrap().resample(&bars);
+        assert_eq!(
+            local_times(&resampled),
+            ["03 10:30", "03 11:00", "03 13:00"]
+        );
+    }
+
+    #[test]
+    fn test_daily_buckets_stay_within_week() {
+        // Mon 3 .. Fri 7, Mon 10, Tue 11
+        let bars: Vec<_> = [3, 4, 5, 6, 7, 10, 11]
+            .iter()
+            .map(|&d| bar(d, 0, 0, d as f64, 1))
+            .collect();
+
+        let two_days = Timeframe::parse("2D").unwrap().resample(&bars);
+        assert_eq!(
+            two_days.iter().map(|b| b.volume).collect::<Vec<_>>(),
+            [2, 2, 1, 2]
+        );
+        assert_eq!(two_days[3].timestamp, bars[5].timestamp);
+
+        let three_days = Timeframe::parse("3D").unwrap().resample(&bars);
+        assert_eq!(
+            three_days.iter().map(|b| b.volume).collect::<Vec<_>>(),
+            [3, 2, 2]
+        );
+
+        let month = Timeframe::parse("1M").unwrap().resample(&bars);
+        assert_eq!(month.len(), 1);
+        assert_eq!((month[0].open, month[0].close), (3.0, 11.0));
+    }
+}
@@ -0,0 +1,620 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::error::{Error, Result};
+use crate::explorer::vci::ohlc::Candlestick;
+use crate::provider::MarketDataProvider;
+
+/// Header of a bar file, the last byte is the format version
+const BARS_MAGIC: &[u8; 8] = b"AIMBARS\x01";
+/// Header of a coverage file
+const RANGES_MAGIC: &[u8; 8] = b"AIMRNGS\x01";
+/// timestamp, open, high, low, close, volume
+const BAR_LEN: usize = 48;
+/// from, to
+const RANGE_LEN: usize = 16;
+/// A series is compacted once this many fetched ranges were appended to it
+const COMPACT_AFTER_RANGES: usize = 64;
+
+/// Local on-disk store of OHLCV history, one series per symbol and quote interval
+///
+/// Each series is kept in two append-only files under `root/<SYMBOL>/`:
+/// `<INTERVAL>.bars` holds fixed-size little-endian records, and
+/// `<INTERVAL>.ranges` the time ranges already fetched from the provider, so
+/// days without trading are not asked for again. A bar written twice (e.g. the
+/// candle still forming) is replaced by the latest copy when read back, and
+/// [`CandleStore::compact`] rewrites a series sorted and without duplicates.
+#[derive(Debug)]
+pub struct CandleStore {
+    root: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl CandleStore {
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root).map_err(|e| store_error(&root, e))?;
+        Ok(Self {
+            root,
+            lock: Mutex::new(()),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Record bars fetched for `from..=to`
+    ///
+    /// When the last bar may still be forming, the range is marked as fetched
+    /// only up to that bar, so it is asked for again on the next fetch.
+    pub fn insert(
+        &self,
+        symbol: &str,
+        interval: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        bars: &[Candlestick],
+    ) -> Result<()> {
+        let (bars_path, ranges_path) = self.paths(symbol, interval)?;
+        let _guard = self.lock.lock().unwrap();
+
+        if let Some(dir) = bars_path.parent() {
+            fs::create_dir_all(dir).map_err(|e| store_error(dir, e))?;
+        }
+        let mut bytes = Vec::with_capacity(bars.len() * BAR_LEN);
+        for bar in bars {
+            encode_bar(bar, &mut bytes);
+        }
+        append(&bars_path, BARS_MAGIC, BAR_LEN, &bytes)?;
+
+        let now = Utc::now();
+        let covered_to = match bars.iter().map(|bar| bar.timestamp).max() {
+            Some(last) if last + interval_duration(interval) > now => last - Duration::seconds(1),
+            _ => to.min(now),
+        };
+        if covered_to > from {
+            let mut range = Vec::with_capacity(RANGE_LEN);
+            range.extend_from_slice(&from.timestamp().to_le_bytes());
+            range.extend_from_slice(&covered_to.timestamp().to_le_bytes());
+            append(&ranges_path, RANGES_MAGIC, RANGE_LEN, &range)?;
+        }
+
+        let appended_ranges = file_len(&ranges_path).saturating_sub(RANGES_MAGIC.len()) / RANGE_LEN;
+        if appended_ranges > COMPACT_AFTER_RANGES {
+            compact_files(&bars_path, &ranges_path)?;
+        }
+        Ok(())
+    }
+
+    /// Stored bars between `from` and `to` (inclusive), sorted by time
+    pub fn range(
+        &self,
+        symbol: &str,
+        interval: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<Candlestick>> {
+        let (bars_path, _) = self.paths(symbol, interval)?;
+        let _guard = self.lock.lock().unwrap();
+        let (from, to) = (from.timestamp(), to.timestamp());
+        Ok(read_bars(&bars_path)?
+            .into_iter()
+            .filter(|bar| (from..=to).contains(&bar.timestamp.timestamp()))
+            .collect())
+    }
+
+    /// Parts of `from..=to` never fetched into the store, oldest first
+    ///
+    /// Nothing after the current time is reported missing.
+    pub fn gaps(
+        &self,
+        symbol: &str,
+        interval: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>> {
+        let (_, ranges_path) = self.paths(symbol, interval)?;
+        let _guard = self.lock.lock().unwrap();
+        let (from, to) = (from.timestamp(), to.min(Utc::now()).timestamp());
+
+        let mut gaps = Vec::new();
+        let mut cursor = from;
+        for (start, end) in read_ranges(&ranges_path)? {
+            if cursor > to {
+                break;
+            }
+            if end < cursor {
+                continue;
+            }
+            if start > cursor {
+                gaps.push((cursor, (start - 1).min(to)));
+            }
+            cursor = cursor.max(end + 1);
+        }
+        if cursor <= to {
+            gaps.push((cursor, to));
+        }
+        Ok(gaps
+            .into_iter()
+            .filter_map(|(start, end)| {
+                Some((
+                    DateTime::from_timestamp(start, 0)?,
+                    DateTime::from_timestamp(end, 0)?,
+                ))
+            })
+            .collect())
+    }
+
+    /// Bars between `from` and `to`, asking `provider` only for the gaps of the store
+    pub async fn fetch(
+        &self,
+        provider: &dyn MarketDataProvider,
+        symbol: &str,
+        interval: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<Candlestick>> {
+        for (start, end) in self.gaps(symbol, interval, from, to)? {
+            let response = provider
+                .get_quote(&[symbol], interval, Some(start), Some(end))
+                .await?;
+            let bars = response
+                .0
+                .iter()
+                .find(|data| data.symbol == symbol)
+                .map(|data| data.to_candlesticks())
+                .unwrap_or_default();
+            self.insert(symbol, interval, start, end, &bars)?;
+        }
+        self.range(symbol, interval, from, to)
+    }
+
+    /// Rewrite a series sorted by time, without duplicate bars or overlapping ranges
+    pub fn compact(&self, symbol: &str, interval: &str) -> Result<()> {
+        let (bars_path, ranges_path) = self.paths(symbol, interval)?;
+        let _guard = self.lock.lock().unwrap();
+        compact_files(&bars_path, &ranges_path)
+    }
+
+    /// Compact every series of the store, returns the number of series
+    pub fn compact_all(&self) -> Result<usize> {
+        let _guard = self.lock.lock().unwrap();
+        let mut count = 0;
+        for dir in read_dir(&self.root)? {
+            if !dir.is_dir() {
+                continue;
+            }
+            for file in read_dir(&dir)? {
+                if file.extension().is_some_and(|ext| ext == "bars") {
+                    compact_files(&file, &file.with_extension("ranges"))?;
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    fn paths(&self, symbol: &str, interval: &str) -> Result<(PathBuf, PathBuf)> {
+        let valid = |name: &str| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        };
+        if !valid(symbol) || !valid(interval) {
+            return Err(store_error(
+                &self.root,
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("invalid series {symbol}/{interval}"),
+                ),
+            ));
+        }
+        let dir = self.root.join(symbol.to_uppercase());
+        Ok((
+            dir.join(format!("{interval}.bars")),
+            dir.join(format!("{interval}.ranges")),
+        ))
+    }
+}
+
+static CANDLE_STORE: OnceLock<CandleStore> = OnceLock::new();
+
+/// Install the store used by `candle_store()`, returns false if one was already installed
+pub fn install_candle_store(store: CandleStore) -> bool {
+    CANDLE_STORE.set(store).is_ok()
+}
+
+/// Get the process-wide history store, kept in the temp directory if none was installed
+pub fn candle_store() -> &'static CandleStore {
+    CANDLE_STORE.get_or_init(|| {
+        CandleStore::open(std::env::temp_dir().join("aim-history"))
+            .expect("Failed to open the history store")
+    })
+}
+
+/// Time after which a bar of the quote `interval` is complete
+fn interval_duration(interval: &str) -> Duration {
+    match interval {
+        "ONE_MINUTE" => Duration::minutes(1),
+        "ONE_HOUR" => Duration::hours(1),
+        "ONE_WEEK" => Duration::weeks(1),
+        _ => Duration::days(1),
+    }
+}
+
+fn store_error(path: &Path, source: io::Error) -> Error {
+    Error::Store {
+        path: path.display().to_string(),
+        source,
+    }
+}
+
+fn file_len(path: &Path) -> usize {
+    fs::metadata(path).map_or(0, |meta| meta.len() as usize)
+}
+
+fn read_dir(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(dir).map_err(|e| store_error(dir, e))?;
+    Ok(entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .collect())
+}
+
+/// Append `bytes`, whole records of `record_len`, to `path`, writing `magic`
+/// first when the file is new
+///
+/// What an interrupted write left past the last whole record is cut off
+/// first, so the new records don't start in the middle of one. A header cut
+/// short is written again.
+fn append(path: &Path, magic: &[u8; 8], record_len: usize, bytes: &[u8]) -> Result<()> {
+    if bytes.is_empty() {
+        return Ok(());
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| store_error(path, e))?;
+    let len = file.metadata().map_err(|e| store_error(path, e))?.len() as usize;
+    let whole = if len < magic.len() {
+        0
+    } else {
+        magic.len() + (len - magic.len()) / record_len * record_len
+    };
+    let truncated = if whole < len {
+        file.set_len(whole as u64)
+    } else {
+        Ok(())
+    };
+    truncated
+        .and_then(|_| {
+            if whole == 0 {
+                file.write_all(magic)
+            } else {
+                Ok(())
+            }
+        })
+        .and_then(|_| file.write_all(bytes))
+        .map_err(|e| store_error(path, e))
+}
+
+/// Records of `path` after its header, a missing file is empty. A record cut
+/// short by an interrupted write is ignored.
+fn read_records(path: &Path, magic: &[u8; 8], record_len: usize) -> Result<Vec<u8>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(store_error(path, e)),
+    };
+    if bytes.len() < magic.len() {
+        return Ok(Vec::new());
+    }
+    if &bytes[..magic.len()] != magic {
+        return Err(store_error(
+            path,
+            io::Error::new(io::ErrorKind::InvalidData, "unknown file format"),
+        ));
+    }
+    let mut records = bytes[magic.len()..].to_vec();
+    records.truncate(records.len() / record_len * record_len);
+    Ok(records)
+}
+
+/// Bars of `path` sorted by time, the last copy of a bar wins
+fn read_bars(path: &Path) -> Result<Vec<Candlestick>> {
+    let records = read_records(path, BARS_MAGIC, BAR_LEN)?;
+    let mut bars: Vec<Candlestick> = records
+        .chunks_exact(BAR_LEN)
+        .filter_map(decode_bar)
+        .collect();
+    // Stable sort keeps the write order of copies, reversed so dedup keeps the newest
+    bars.reverse();
+    bars.sort_by_key(|bar| bar.timestamp);
+    bars.dedup_by_key(|bar| bar.timestamp);
+    Ok(bars)
+}
+
+/// Fetched ranges of `path`, sorted and merged
+fn read_ranges(path: &Path) -> Result<Vec<(i64, i64)>> {
+    let records = read_records(path, RANGES_MAGIC, RANGE_LEN)?;
+    let mut ranges: Vec<(i64, i64)> = records
+        .chunks_exact(RANGE_LEN)
+        .map(|chunk| (read_i64(chunk, 0), read_i64(chunk, 8)))
+        .filter(|(from, to)| from <= to)
+        .collect();
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(i64, i64)> = Vec::with_capacity(ranges.len());
+    for (from, to) in ranges {
+        match merged.last_mut() {
+            Some(last) if from <= last.1.saturating_add(1) => last.1 = last.1.max(to),
+            _ => merged.push((from, to)),
+        }
+    }
+    Ok(merged)
+}
+
+fn compact_files(bars_path: &Path, ranges_path: &Path) -> Result<()> {
+    let mut bars = Vec::new();
+    for bar in read_bars(bars_path)? {
+        encode_bar(&bar, &mut bars);
+    }
+    let mut ranges = Vec::new();
+    for (from, to) in read_ranges(ranges_path)? {
+        ranges.extend_from_slice(&from.to_le_bytes());
+        ranges.extend_from_slice(&to.to_le_bytes());
+    }
+    replace(bars_path, BARS_MAGIC, &bars)?;
+    replace(ranges_path, RANGES_MAGIC, &ranges)
+}
+
+/// Write a whole file next to `path` and move it in place
+fn replace(path: &Path, magic: &[u8; 8], records: &[u8]) -> Result<()> {
+    if records.is_empty() && !path.exists() {
+        return Ok(());
+    }
+    let tmp = path.with_extension("tmp");
+    File::create(&tmp)
+        .and_then(|mut file| {
+            file.write_all(magic)?;
+            file.write_all(records)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp, path))
+        .map_err(|e| store_error(path, e))
+}
+
+fn encode_bar(bar: &Candlestick, out: &mut Vec<u8>) {
+    out.extend_from_slice(&bar.timestamp.timestamp().to_le_bytes());
+    out.extend_from_slice(&bar.open.to_le_bytes());
+    out.extend_from_slice(&bar.high.to_le_bytes());
+    out.extend_from_slice(&bar.low.to_le_bytes());
+    out.extend_from_slice(&bar.close.to_le_bytes());
+    out.extend_from_slice(&bar.volume.to_le_bytes());
+}
+
+fn decode_bar(record: &[u8]) -> Option<Candlestick> {
+    let f64_at = |at| f64::from_bits(read_i64(record, at) as u64);
+    Some(Candlestick {
+        timestamp: DateTime::from_timestamp(read_i64(record, 0), 0)?,
+        open: f64_at(8),
+        high: f64_at(16),
+        low: f64_at(24),
+        close: f64_at(32),
+        volume: read_i64(record, 40),
+    })
+}
+
+fn read_i64(bytes: &[u8], at: usize) -> i64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(&bytes[at..at + 8]);
+    i64::from_le_bytes(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explorer::vci::OrderList;
+    use crate::explorer::vci::company_info::CompanyInfo;
+    use crate::explorer::vci::market_watch::MarketWatchResponse;
+    use crate::explorer::vci::ohlc::{OHLCData, OHLCResponse};
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn temp_store(name: &str) -> CandleStore {
+        let root = std::env::temp_dir().join(format!(
+            "aim_store_{name}_{}_{}",
+            std::process::id(),
+            Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        CandleStore::open(root).unwrap()
+    }
+
+    fn day(n: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_704_067_200, 0).unwrap() + Duration::days(n)
+    }
+
+    fn bar(n: i64, close: f64) -> Candlestick {
+        Candlestick {
+            timestamp: day(n),
+            open: close - 1.0,
+            high: close + 1.0,
+            low: close - 2.0,
+            close,
+            volume: 100 * n,
+        }
+    }
+
+    /// Daily bars for every requested day, counting the requests
+    struct DailyProvider {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl MarketDataProvider for DailyProvider {
+        async fn get_quote(
+            &self,
+            symbols: &[&str],
+            _time_frame: &str,
+            start_time: Option<DateTime<Utc>>,
+            end_time: Option<DateTime<Utc>>,
+        ) -> Result<OHLCResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let bars: Vec<_> = (0..30)
+                .map(|n| bar(n, 10.0 + n as f64))
+                .filter(|bar| Some(bar.timestamp) >= start_time && Some(bar.timestamp) <= end_time)
+                .collect();
+            Ok(OHLCResponse(vec![OHLCData::from_candlesticks(
+                symbols[0], &bars,
+            )]))
+        }
+
+        async fn get_market_watch(&self, _symbols: &[&str]) -> Result<MarketWatchResponse> {
+            Err(Error::Empty {
+                endpoint: "market watch".to_string(),
+            })
+        }
+
+        async fn get_company_info(&self, _symbol: &str, _period: &str) -> Result<CompanyInfo> {
+            Err(Error::Empty {
+                endpoint: "company info".to_string(),
+            })
+        }
+
+        async fn get_order_list(&self, _symbol: &str, _limit: u32) -> Result<OrderList> {
+            Err(Error::Empty {
+                endpoint: "order list".to_string(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_insert_range_and_gaps() {
+        let store = temp_store("gaps");
+        let bars: Vec<_> = (0..5).map(|n| bar(n, 10.0)).collect();
+        store
+            .insert("AAA", "ONE_DAY", day(0), day(4), &bars)
+            .unwrap();
+        // The last bar is written again with a new close
+        store
+            .insert("aaa", "ONE_DAY", day(4), day(9), &[bar(4, 12.0)])
+            .unwrap();
+
+        let stored = store.range("AAA", "ONE_DAY", day(1), day(9)).unwrap();
+        assert_eq!(stored.len(), 4);
+        assert_eq!(stored.last().unwrap().close, 12.0);
+
+        assert!(
+            store
+                .gaps("AAA", "ONE_DAY", day(2), day(9))
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            store.gaps("AAA", "ONE_DAY", day(-3), day(12)).unwrap(),
+            vec![
+                (day(-3), day(0) - Duration::seconds(1)),
+                (day(9) + Duration::seconds(1), day(12)),
+            ]
+        );
+        assert!(
+            store
+                .range("AAA", "ONE_HOUR", day(0), day(9))
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            store
+                .insert("../AAA", "ONE_DAY", day(0), day(1), &[])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_compact_removes_duplicates() {
+        let store = temp_store("compact");
+        for close in [10.0, 11.0, 12.0] {
+            let bars: Vec<_> = (0..3).map(|n| bar(n, close)).collect();
+            store
+                .insert("AAA", "ONE_DAY", day(0), day(2), &bars)
+                .unwrap();
+        }
+        let (bars_path, ranges_path) = store.paths("AAA", "ONE_DAY").unwrap();
+        assert_eq!(file_len(&bars_path), BARS_MAGIC.len() + 9 * BAR_LEN);
+
+        assert_eq!(store.compact_all().unwrap(), 1);
+        assert_eq!(file_len(&bars_path), BARS_MAGIC.len() + 3 * BAR_LEN);
+        assert_eq!(file_len(&ranges_path), RANGES_MAGIC.len() + RANGE_LEN);
+        let stored = store.range("AAA", "ONE_DAY", day(0), day(2)).unwrap();
+        assert!(stored.iter().all(|bar| bar.close == 12.0));
+    }
+
+    #[test]
+    fn test_append_after_an_interrupted_write() {
+        let store = temp_store("interrupted");
+        let (bars_path, ranges_path) = store.paths("AAA", "ONE_DAY").unwrap();
+        fs::create_dir_all(bars_path.parent().unwrap()).unwrap();
+        // A header cut short
+        fs::write(&bars_path, &BARS_MAGIC[..3]).unwrap();
+        let bars: Vec<_> = (0..3).map(|n| bar(n, 10.0)).collect();
+        store
+            .insert("AAA", "ONE_DAY", day(0), day(2), &bars)
+            .unwrap();
+        // Half a bar and half a range
+        let mut file = OpenOptions::new().append(true).open(&bars_path).unwrap();
+        file.write_all(&[7; BAR_LEN / 2]).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&ranges_path).unwrap();
+        file.write_all(&[7; RANGE_LEN / 2]).unwrap();
+
+        let bars: Vec<_> = (3..6).map(|n| bar(n, 11.0)).collect();
+        store
+            .insert("AAA", "ONE_DAY", day(3), day(5), &bars)
+            .unwrap();
+        assert_eq!(file_len(&bars_path), BARS_MAGIC.len() + 6 * BAR_LEN);
+        assert_eq!(file_len(&ranges_path), RANGES_MAGIC.len() + 2 * RANGE_LEN);
+        let stored = store.range("AAA", "ONE_DAY", day(0), day(5)).unwrap();
+        let stored: Vec<_> = stored
+            .iter()
+            .map(|bar| (bar.timestamp, bar.close))
+            .collect();
+        let expected: Vec<_> = (0..6)
+            .map(|n| (day(n), if n < 3 { 10.0 } else { 11.0 }))
+            .collect();
+        assert_eq!(stored, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_asks_only_for_gaps() {
+        let store = temp_store("fetch");
+        let provider = DailyProvider {
+            calls: AtomicUsize::new(0),
+        };
+        let first = store
+            .fetch(&provider, "AAA", "ONE_DAY", day(10), day(20))
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 11);
+
+        let again = store
+            .fetch(&provider, "AAA", "ONE_DAY", day(12), day(18))
+            .await
+            .unwrap();
+        assert_eq!(again.len(), 7);
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+
+        // Only the days before the stored range are requested
+        let wider = store
+            .fetch(&provider, "AAA", "ONE_DAY", day(5), day(20))
+            .await
+            .unwrap();
+        assert_eq!(wider.len(), 16);
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(wider[0].close, 15.0);
+    }
+}
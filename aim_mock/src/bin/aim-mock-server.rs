@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use aim_mock::{Fixtures, MockConfig, MockServer, Session};
+
+const USAGE: &str = "\
+Usage: aim-mock-server [OPTIONS]
+
+Options:
+  --addr <ADDR>        Address to listen on [default: 127.0.0.1:8787]
+  --fixtures <DIR>     Fixture directory [default: bundled fixtures]
+  --session <FILE>     Replay a recorded session (JSON lines of {offset_ms, path, body})
+  --speed <SPEED>      Session speed, 60 plays one minute per second [default: 1]
+  --loop               Start the session over when it ends
+  -h, --help           Print this help";
+
+fn parse_args() -> Result<MockConfig, String> {
+    let mut config = MockConfig::new(Fixtures::bundled());
+    config.addr = "127.0.0.1:8787".to_string();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{name} needs a value"));
+        match arg.as_str() {
+            "--addr" => config.addr = value("--addr")?,
+            "--fixtures" => config.fixtures = Fixtures::new(PathBuf::from(value("--fixtures")?)),
+            "--session" => {
+                let path = PathBuf::from(value("--session")?);
+                let session = Session::load(&path).map_err(|e| e.to_string())?;
+                config.session = Some(session);
+            }
+            "--speed" => {
+                config.speed = value("--speed")?
+                    .parse()
+                    .map_err(|_| "--speed must be a number".to_string())?
+            }
+            "--loop" => config.looped = true,
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            other => return Err(format!("unknown option {other}\n\n{USAGE}")),
+        }
+    }
+    Ok(config)
+}
+
+fn main() -> ExitCode {
+    let config = match parse_args() {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let fixtures = config.fixtures.root().display().to_string();
+    let server = match MockServer::start(config) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Failed to start the mock server: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let url = server.url();
+    println!("Serving {fixtures} on {url}");
+    println!("Point the app at it with:");
+    println!("  VCI_BASE_URL={url} AIM_SERVER_URL={url} AIM_MARKET_CAP_SERVER_URL={url}");
+    server.join();
+    ExitCode::SUCCESS
+}
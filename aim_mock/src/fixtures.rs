@@ -0,0 +1,94 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Extensions tried for every candidate path, with the content type served for them
+const EXTENSIONS: [(&str, &str); 2] = [("json", "application/json"), ("pdf", "application/pdf")];
+
+/// A fixture file ready to be served
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+/// Directory of recorded responses, laid out like the request paths
+///
+/// `balance-sheet/AAA/Y` is answered by the first file found among
+/// `balance-sheet/AAA/Y.json`, `balance-sheet/AAA.json` and `balance-sheet.json`,
+/// so one file can cover every symbol of an endpoint. A query string is matched
+/// first as a `__` suffix: `reports?source=9999` looks for `reports__source=9999.json`.
+#[derive(Debug, Clone)]
+pub struct Fixtures {
+    root: PathBuf,
+}
+
+impl Fixtures {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Fixtures shipped with this crate, recorded from the live endpoints
+    pub fn bundled() -> Self {
+        Self::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures"))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Find the fixture answering `path` (without leading `/`) and `query`
+    pub fn lookup(&self, path: &str, query: Option<&str>) -> Option<Fixture> {
+        let path = path.trim_matches('/');
+        if path.is_empty() || path.split('/').any(|segment| segment == "..") {
+            return None;
+        }
+
+        if let Some(query) = query.filter(|q| !q.is_empty())
+            && let Some(fixture) = self.read(&format!("{path}__{query}"))
+        {
+            return Some(fixture);
+        }
+
+        let mut candidate = path;
+        loop {
+            if let Some(fixture) = self.read(candidate) {
+                return Some(fixture);
+            }
+            candidate = &candidate[..candidate.rfind('/')?];
+        }
+    }
+
+    fn read(&self, candidate: &str) -> Option<Fixture> {
+        EXTENSIONS.iter().find_map(|(ext, content_type)| {
+            let file = self.root.join(format!("{candidate}.{ext}"));
+            fs::read(file)
+                .ok()
+                .map(|body| Fixture { content_type, body })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_falls_back_to_parent() {
+        let fixtures = Fixtures::bundled();
+        let specific = fixtures.lookup("balance-sheet/AAA/Y", None).unwrap();
+        assert_eq!(specific.content_type, "application/json");
+        assert_eq!(
+            specific.body,
+            fixtures.lookup("balance-sheet", None).unwrap().body
+        );
+
+        let strategy = fixtures.lookup("reports", Some("source=9999")).unwrap();
+        let reports = fixtures.lookup("reports", None).unwrap();
+        assert_ne!(strategy.body, reports.body);
+
+        let pdf = fixtures.lookup("report-file/AAA", None).unwrap();
+        assert_eq!(pdf.content_type, "application/pdf");
+        assert!(fixtures.lookup("unknown/path", None).is_none());
+        assert!(fixtures.lookup("../Cargo", None).is_none());
+    }
+}
@@ -0,0 +1,20 @@
+//! Local stand-in for the Vietcap trading API and the AIM backend
+//!
+//! Serves recorded JSON fixtures for every endpoint used by `aim_data` and can
+//! replay a recorded trading session at an adjustable speed, so the app and
+//! its tests run on a disconnected machine.
+//!
+//! ```no_run
+//! use aim_mock::{Fixtures, MockConfig, MockServer};
+//!
+//! let server = MockServer::start(MockConfig::new(Fixtures::bundled())).unwrap();
+//! println!("VCI_BASE_URL={}", server.url());
+//! ```
+
+mod fixtures;
+mod server;
+mod session;
+
+pub use fixtures::{Fixture, Fixtures};
+pub use server::{MockConfig, MockServer};
+pub use session::{Frame, ReplayClock, Session};
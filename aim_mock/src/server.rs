@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use serde_json::json;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::fixtures::{Fixture, Fixtures};
+use crate::session::{ReplayClock, Session};
+
+/// Settings of a mock server
+#[derive(Debug, Clone)]
+pub struct MockConfig {
+    /// Address to listen on, port 0 picks a free port
+    pub addr: String,
+    pub fixtures: Fixtures,
+    /// Recorded session answered before the fixtures for the paths it contains
+    pub session: Option<Session>,
+    /// Session time per wall-clock time, 60.0 plays one minute per second
+    pub speed: f64,
+    /// Start the session over once its last frame is reached
+    pub looped: bool,
+}
+
+impl MockConfig {
+    pub fn new(fixtures: Fixtures) -> Self {
+        Self {
+            addr: "127.0.0.1:0".to_string(),
+            fixtures,
+            session: None,
+            speed: 1.0,
+            looped: false,
+        }
+    }
+}
+
+struct State {
+    fixtures: Fixtures,
+    session: Option<Session>,
+    looped: bool,
+    clock: Mutex<ReplayClock>,
+    hits: Mutex<HashMap<String, usize>>,
+}
+
+/// Mock of the Vietcap and AIM endpoints running on a background thread
+///
+/// Besides the recorded endpoints it answers a few control routes:
+/// `GET /__mock/status`, `POST /__mock/speed/{speed}` and `POST /__mock/restart`.
+/// The server stops when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    server: Arc<Server>,
+    state: Arc<State>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    pub fn start(config: MockConfig) -> io::Result<Self> {
+        let server = Server::http(&config.addr).map_err(io::Error::other)?;
+        let addr = server
+            .server_addr()
+            .to_ip()
+            .ok_or_else(|| io::Error::other("mock server is not listening on TCP"))?;
+        let server = Arc::new(server);
+        let state = Arc::new(State {
+            fixtures: config.fixtures,
+            session: config.session,
+            looped: config.looped,
+            clock: Mutex::new(ReplayClock::new(config.speed)),
+            hits: Mutex::new(HashMap::new()),
+        });
+
+        let thread = {
+            let server = Arc::clone(&server);
+            let state = Arc::clone(&state);
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    state.handle(request);
+                }
+            })
+        };
+
+        Ok(Self {
+            addr,
+            server,
+            state,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Base URL to use in place of the Vietcap or AIM server URL
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn set_speed(&self, speed: f64) {
+        self.state.clock.lock().unwrap().set_speed(speed);
+    }
+
+    pub fn restart(&self) {
+        self.state.clock.lock().unwrap().restart();
+    }
+
+    /// Number of requests served for `path` (without leading `/`)
+    pub fn hits(&self, path: &str) -> usize {
+        self.state
+            .hits
+            .lock()
+            .unwrap()
+            .get(path.trim_matches('/'))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Block the calling thread until the server is stopped, used by the binary
+    pub fn join(mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl State {
+    fn handle(&self, request: Request) {
+        let url = request.url().to_string();
+        let (path, query) = match url.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (url.as_str(), None),
+        };
+        let path = path.trim_matches('/');
+
+        let response = if let Some(control) = path.strip_prefix("__mock/") {
+            self.control(request.method(), control)
+        } else {
+            *self
+                .hits
+                .lock()
+                .unwrap()
+                .entry(path.to_string())
+                .or_default() += 1;
+            match self
+                .session_frame(path)
+                .or_else(|| self.fixtures.lookup(path, query))
+            {
+                Some(fixture) => respond(200, fixture),
+                None => respond_json(404, json!({ "error": format!("no fixture for /{path}") })),
+            }
+        };
+
+        let _ = request.respond(response);
+    }
+
+    fn session_frame(&self, path: &str) -> Option<Fixture> {
+        let session = self.session.as_ref()?;
+        let mut offset = self.clock.lock().unwrap().offset_ms();
+        if self.looped && session.duration_ms() > 0 {
+            offset %= session.duration_ms() + 1;
+        }
+        let frame = session.frame_at(path, offset)?;
+        Some(Fixture {
+            content_type: "application/json",
+            body: serde_json::to_vec(&frame.body).ok()?,
+        })
+    }
+
+    fn control(&self, method: &Method, route: &str) -> Response<io::Cursor<Vec<u8>>> {
+        let mut clock = self.clock.lock().unwrap();
+        match (method, route.split_once('/').unwrap_or((route, ""))) {
+            (Method::Post, ("speed", speed)) => match speed.parse::<f64>() {
+                Ok(speed) if speed >= 0.0 => clock.set_speed(speed),
+                _ => return respond_json(400, json!({ "error": "invalid speed" })),
+            },
+            (Method::Post, ("restart", _)) => clock.restart(),
+            (Method::Get, ("status", _)) => {}
+            _ => return respond_json(404, json!({ "error": "unknown control route" })),
+        }
+        respond_json(
+            200,
+            json!({
+                "speed": clock.speed(),
+                "offset_ms": clock.offset_ms(),
+                "session_ms": self.session.as_ref().map(Session::duration_ms),
+                "looped": self.looped,
+            }),
+        )
+    }
+}
+
+fn respond(status: u16, fixture: Fixture) -> Response<io::Cursor<Vec<u8>>> {
+    let content_type =
+        Header::from_bytes("Content-Type", fixture.content_type).expect("static header is valid");
+    Response::from_data(fixture.body)
+        .with_status_code(status)
+        .with_header(content_type)
+}
+
+fn respond_json(status: u16, body: serde_json::Value) -> Response<io::Cursor<Vec<u8>>> {
+    respond(
+        status,
+        Fixture {
+            content_type: "application/json",
+            body: body.to_string().into_bytes(),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::Frame;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    fn get(server: &MockServer, method: &str, path: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        write!(
+            stream,
+            "{method} {path} HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response.split_once("\r\n\r\n").unwrap().1.to_string();
+        (status, body)
+    }
+
+    #[test]
+    fn test_serves_fixtures_and_session() {
+        let mut config = MockConfig::new(Fixtures::bundled());
+        config.session = Some(Session::from_frames(vec![Frame {
+            offset_ms: 0,
+            path: "api/price/symbols/getList".to_string(),
+            body: json!([]),
+        }]));
+        let server = MockServer::start(config).unwrap();
+
+        let (status, body) = get(&server, "GET", "/sjc-price");
+        assert_eq!(status, 200);
+        assert!(
+            serde_json::from_str::<serde_json::Value>(&body)
+                .unwrap()
+                .is_array()
+        );
+
+        assert_eq!(
+            get(&server, "POST", "/api/price/symbols/getList"),
+            (200, "[]".to_string())
+        );
+        assert_eq!(get(&server, "GET", "/nothing-here").0, 404);
+        assert_eq!(server.hits("sjc-price"), 1);
+
+        let (status, body) = get(&server, "POST", "/__mock/speed/4");
+        assert_eq!(status, 200);
+        assert!(body.contains("\"speed\":4.0"));
+    }
+}
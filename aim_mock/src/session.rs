@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// One recorded response, `offset_ms` after the start of the session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Frame {
+    pub offset_ms: u64,
+    /// Request path without leading `/`, e.g. `api/price/symbols/getList`
+    pub path: String,
+    pub body: Value,
+}
+
+/// Recorded trading session, one JSON `Frame` per line
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    frames: HashMap<String, Vec<Frame>>,
+    duration_ms: u64,
+}
+
+impl Session {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let mut frames = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let frame: Frame = serde_json::from_str(line).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}:{}: {e}", path.display(), i + 1),
+                )
+            })?;
+            frames.push(frame);
+        }
+        Ok(Self::from_frames(frames))
+    }
+
+    pub fn from_frames(frames: Vec<Frame>) -> Self {
+        let duration_ms = frames.iter().map(|f| f.offset_ms).max().unwrap_or(0);
+        let mut by_path: HashMap<String, Vec<Frame>> = HashMap::new();
+        for mut frame in frames {
+            frame.path = frame.path.trim_matches('/').to_string();
+            by_path.entry(frame.path.clone()).or_default().push(frame);
+        }
+        for frames in by_path.values_mut() {
+            frames.sort_by_key(|f| f.offset_ms);
+        }
+        Self {
+            frames: by_path,
+            duration_ms,
+        }
+    }
+
+    /// Offset of the last frame
+    pub fn duration_ms(&self) -> u64 {
+        self.duration_ms
+    }
+
+    /// Latest frame of `path` recorded at or before `offset_ms`, the first one
+    /// if the session has not reached it yet
+    pub fn frame_at(&self, path: &str, offset_ms: u64) -> Option<&Frame> {
+        let frames = self.frames.get(path.trim_matches('/'))?;
+        let next = frames.partition_point(|f| f.offset_ms <= offset_ms);
+        frames.get(next.saturating_sub(1))
+    }
+}
+
+/// Session time advancing `speed` times faster than the wall clock
+#[derive(Debug, Clone)]
+pub struct ReplayClock {
+    started_at: Instant,
+    started_offset_ms: f64,
+    speed: f64,
+}
+
+impl ReplayClock {
+    pub fn new(speed: f64) -> Self {
+        Self {
+            started_at: Instant::now(),
+            started_offset_ms: 0.0,
+            speed: speed.max(0.0),
+        }
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Session offset reached `elapsed` after the last speed change
+    pub fn offset_after(&self, elapsed: Duration) -> u64 {
+        (self.started_offset_ms + elapsed.as_secs_f64() * 1000.0 * self.speed) as u64
+    }
+
+    pub fn offset_ms(&self) -> u64 {
+        self.offset_after(self.started_at.elapsed())
+    }
+
+    /// Change the speed without jumping in the session
+    pub fn set_speed(&mut self, speed: f64) {
+        self.started_offset_ms = self.offset_ms() as f64;
+        self.started_at = Instant::now();
+        self.speed = speed.max(0.0);
+    }
+
+    /// Go back to the start of the session
+    pub fn restart(&mut self) {
+        self.started_offset_ms = 0.0;
+        self.started_at = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn frame(offset_ms: u64, price: f64) -> Frame {
+        Frame {
+            offset_ms,
+            path: "/api/price/symbols/getList".to_string(),
+            body: json!(price),
+        }
+    }
+
+    #[test]
+    fn test_frame_at_offset() {
+        let session =
+            Session::from_frames(vec![frame(1_000, 2.0), frame(0, 1.0), frame(5_000, 3.0)]);
+        let price = |offset| {
+            session
+                .frame_at("api/price/symbols/getList", offset)
+                .unwrap()
+                .body
+                .clone()
+        };
+        assert_eq!(price(0), json!(1.0));
+        assert_eq!(price(4_999), json!(2.0));
+        assert_eq!(price(60_000), json!(3.0));
+        assert_eq!(session.duration_ms(), 5_000);
+        assert!(session.frame_at("data-mt/graphql", 0).is_none());
+    }
+
+    #[test]
+    fn test_replay_clock_speed() {
+        let clock = ReplayClock::new(10.0);
+        assert_eq!(clock.offset_after(Duration::from_millis(1_500)), 15_000);
+    }
+}
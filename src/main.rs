@@ -7,12 +7,16 @@ mod tasks;
 use crate::{
     slint_generatedAppWindow::StockData as SlintStockData,
     tasks::{
-        build_stream_paths_for_range, sort_market_watch, sort_stocks, spawn_cache_storage_task,
-        ChartMetaData, ALL_STOCK_LIST,
+        build_stream_paths_for_range, register_alert_handlers, sort_market_watch, sort_stocks,
+        spawn_alert_task, spawn_cache_storage_task, spawn_connection_status_task, AlertBook,
+        ChartMetaData, SharedAlerts, ALL_STOCK_LIST,
     },
 };
 use aim_chart::Chart;
-use aim_data::{get_company_info, get_market_watch, get_quote};
+use aim_data::{
+    candle_store, install_candle_store, CandleStore, OHLCData, RecordingProvider, ReplayOptions,
+    ReplayProvider, SharedProvider,
+};
 use dirs_next::cache_dir;
 use slint::{Model, ModelRc, SharedString, VecModel};
 use std::{path::PathBuf, sync::Arc};
@@ -27,12 +31,13 @@ use tasks::{
     spawn_mini_chart_hnxindex_task, spawn_mini_chart_vn30_task, spawn_mini_chart_vnindex_task,
     spawn_overall_index_task, spawn_sjc_price_task, spawn_stock_influence_task,
     spawn_stock_update_task, spawn_trading_volume_task, spawn_ui_chart_task,
+    register_chart_grid_handlers, register_chart_compare_handlers,
     spawn_finance_report_task, spawn_finance_pdf_selected_task, render_pdf_to_png_paths,
     spawn_rsi_task, register_rsi_sort_handler, spawn_ma50_task, register_ma50_sort_handler,
     spawn_crypto_task, spawn_dominance_task, spawn_crypto_rsi_task, spawn_etf_flow_task, spawn_crypto_market_cap_task,
     spawn_top10_market_cap_task, spawn_icb_index_filtered_task
 };
-use aim_data::aim::fetch_finance_report_pdf;
+use aim_data::aim::{aim_client, install_aim_client, AimClient, AimConfig};
 // use crate::tasks::render_pdf_to_png_paths;
 
 
@@ -55,49 +60,71 @@ async fn main() {
     std::fs::create_dir_all(&app_cache_dir).unwrap();
     let cache_file: PathBuf = app_cache_dir.join("cache.bin");
     let user_list: PathBuf = app_cache_dir.join("user_list.json");
+    let aim_config_file: PathBuf = app_cache_dir.join("aim_config.json");
 
-    // Fetch initial chart data for default stock (AAA)
-    let chart_data = get_quote(&["AAA"], "ONE_DAY", None, None).await.unwrap();
-    let company_info = get_company_info("AAA").await.unwrap();
-    let company_info = aim_chart::CompanyInfo {
-        roe: company_info.data.company_financial_ratio.ratio[0]
-            .roe
-            .unwrap_or(0.0),
-        roa: company_info.data.company_financial_ratio.ratio[0]
-            .roa
-            .unwrap_or(0.0),
-        pe: company_info.data.company_financial_ratio.ratio[0]
-            .pe
-            .unwrap_or(0.0),
-        pb: company_info.data.company_financial_ratio.ratio[0]
-            .pb
-            .unwrap_or(0.0),
-        eps: company_info.data.company_financial_ratio.ratio[0]
-            .eps
-            .unwrap_or(0.0),
+    // Configure the AIM backend client (base URLs, token, TLS trust, timeouts)
+    let aim_config = if std::fs::metadata(&aim_config_file).is_ok() {
+        AimConfig::load(&aim_config_file).unwrap_or_else(|e| {
+            log::error!("Failed to load {}: {e}", aim_config_file.display());
+            AimConfig::from_env()
+        })
+    } else {
+        AimConfig::from_env()
+    };
+    match AimClient::new(aim_config) {
+        Ok(client) => {
+            install_aim_client(client);
+        }
+        Err(e) => log::error!("Failed to build AIM client: {e}"),
+    }
+
+    // Single market data provider shared by every data task
+    let provider: SharedProvider = market_data_provider();
+
+    // Local OHLCV history, charts and mini charts only fetch the bars it lacks.
+    // A replay serves the bars of its recording as of its playback time, they
+    // are kept apart from the live history and start over at each run
+    let history_dir = if provider.follows_trading_hours() {
+        app_cache_dir.join("history")
+    } else {
+        let dir = app_cache_dir.join("replay-history");
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::error!("Failed to clear {}: {e}", dir.display());
+            }
+        }
+        dir
     };
+    match CandleStore::open(history_dir) {
+        Ok(store) => {
+            if let Err(e) = store.compact_all() {
+                log::error!("Failed to compact the history store: {e}");
+            }
+            install_candle_store(store);
+        }
+        Err(e) => log::error!("Failed to open the history store: {e}"),
+    }
 
-    // Create a thread-safe chart container with initial chart
-    let chart_metadata = if std::fs::metadata(&cache_file).is_ok() {
+    // Create a thread-safe chart container with the charts of the last run,
+    // or the default stock (AAA) without them
+    let mut chart_metadata = if std::fs::metadata(&cache_file).is_ok() {
         ChartMetaData::load(&cache_file)
     } else {
-        ChartMetaData::new(vec![Chart::new_default(
-            "AAA".to_string(),
-            chart_data.0[0].clone(),
-            company_info,
-        )])
+        ChartMetaData::new(Vec::new())
     };
+    if chart_metadata.charts().next().is_none() {
+        if let Some(chart) = default_chart(&provider).await {
+            chart_metadata.insert(chart);
+        }
+    }
     let chart = Arc::new(Mutex::new(chart_metadata));
 
-    let init_data = match get_market_watch(&["AAA"]).await {
-        Ok(stock_list_data) => {
-            let stock_data: Vec<SlintStockData> = stock_list_data
-                .0
-                .iter()
-                .map(convert_to_stock_data)
-                .collect();
-            stock_data[0].clone()
-        }
+    let init_data = match provider.get_market_watch(&["AAA"]).await {
+        Ok(stock_list_data) => stock_list_data
+            .0
+            .first()
+            .map(convert_to_stock_data)
+            .unwrap_or_default(),
         Err(e) => {
             log::error!("Failed to fetch stock data: {e}.");
             SlintStockData::default()
@@ -411,7 +438,7 @@ async fn main() {
             });
 
             // Gọi API và render ngay ở đây (bỏ qua task nền)
-            match fetch_finance_report_pdf(&report_id).await {
+            match aim_client().fetch_finance_report_pdf(&report_id).await {
                 Ok(pdf) => {
                     log::info!("[LOI]📄 Đã tải PDF báo cáo cho report_id={} -> {}", report_id, pdf.file_path);
 
@@ -466,22 +493,48 @@ async fn main() {
     register_rsi_sort_handler(&ui);
     register_ma50_sort_handler(&ui);
 
+    // Price and drawing alerts, tested on the quotes of the stock board
+    let alert_book: SharedAlerts = Arc::new(Mutex::new(AlertBook::load(&app_cache_dir)));
+    register_alert_handlers(&ui, Arc::clone(&alert_book));
+    let (quote_tx, quote_rx) = tokio::sync::watch::channel(Vec::new());
+
+    // Grid of charts on the chart page, shown as it was saved
+    register_chart_grid_handlers(&ui, Arc::clone(&chart));
+    // Symbols compared with the chart
+    register_chart_compare_handlers(&ui, Arc::clone(&chart));
 
     // Spawn all the tasks
     let _ui_chart_handle = spawn_ui_chart_task(Arc::clone(&chart), &ui).await;
+    let _connection_status_handle = spawn_connection_status_task(&ui).await;
     // If you only want to read the chart data, you can pass a reference to the Arc<Mutex<ChartMetaData>>
     // Spawn cache storage task with task manager
     let _cache_handle =
         spawn_cache_storage_task(Arc::clone(&chart), Arc::clone(&symbol_list)).await;
-    let _stock_update_handles = spawn_stock_update_task(Arc::clone(&chart), &ui).await;
-    let _chart_update_handle = spawn_chart_update_task(Arc::clone(&chart)).await;
-    let _data_update_handle = spawn_data_update_task(&ui, Arc::clone(&symbol_list)).await;
+    let _stock_update_handles =
+        spawn_stock_update_task(Arc::clone(&chart), &ui, Arc::clone(&provider)).await;
+    let _chart_update_handle =
+        spawn_chart_update_task(Arc::clone(&chart), Arc::clone(&provider)).await;
+    let _data_update_handle = spawn_data_update_task(
+        &ui,
+        Arc::clone(&symbol_list),
+        Arc::clone(&provider),
+        quote_tx,
+    )
+    .await;
+    let _alert_handle = spawn_alert_task(
+        &ui,
+        alert_book,
+        Arc::clone(&chart),
+        Arc::clone(&provider),
+        quote_rx,
+    )
+    .await;
     let _balance_sheet_handles = spawn_balance_sheet_task(&ui).await;
     let _company_profile_handles = spawn_company_profile_task(&ui).await;
-    let _mini_vnindex_handle = spawn_mini_chart_vnindex_task(&ui).await;
-    let _mini_vn30_handle = spawn_mini_chart_vn30_task(&ui).await;
-    let _mini_hnx30_handle = spawn_mini_chart_hnx30_task(&ui).await;
-    let _mini_hnxindex_handle = spawn_mini_chart_hnxindex_task(&ui).await;
+    let _mini_vnindex_handle = spawn_mini_chart_vnindex_task(&ui, Arc::clone(&provider)).await;
+    let _mini_vn30_handle = spawn_mini_chart_vn30_task(&ui, Arc::clone(&provider)).await;
+    let _mini_hnx30_handle = spawn_mini_chart_hnx30_task(&ui, Arc::clone(&provider)).await;
+    let _mini_hnxindex_handle = spawn_mini_chart_hnxindex_task(&ui, Arc::clone(&provider)).await;
     // spawn_world_index_task(&ui);
     let _stock_influence_handle = spawn_stock_influence_task(&ui).await;
     let _overall_index_handle = spawn_overall_index_task(&ui).await; // update OverallIndex UI component
@@ -499,8 +552,8 @@ async fn main() {
     log::info!("Return matrix cache task spawned");
 
     // MP layout
-    let _rsi_task = spawn_rsi_task(&ui).await;
-    let _ma50_task = spawn_ma50_task(&ui).await;
+    let _rsi_task = spawn_rsi_task(&ui, Arc::clone(&provider)).await;
+    let _ma50_task = spawn_ma50_task(&ui, Arc::clone(&provider)).await;
 
     let _crypto_task = spawn_crypto_task(&ui).await;
     let _dominance_task = spawn_dominance_task(&ui).await;
@@ -562,7 +615,7 @@ async fn main() {
 
         // Spawn thread tính toán
         tokio::spawn(async move {
-            match aim_data::aim::fetch_correlation_matrix(&type_str).await {
+            match aim_client().fetch_correlation_matrix(&type_str).await {
                 Ok(correlation_data) => {
                     log::info!("Fetched correlation matrix data: {} entries", correlation_data.len());
 
@@ -644,7 +697,10 @@ async fn main() {
                     ui.set_is_loading(true);
                 });
 
-                match aim_data::aim::fetch_return_matrix(&period_str, &group_str).await {
+                match aim_client()
+                    .fetch_return_matrix(&period_str, &group_str)
+                    .await
+                {
                     Ok(return_data) => {
                         let (return_rows, return_columns) =
                             tasks::parse_return_matrix(&return_data, &period_str, &month_str);
@@ -703,21 +759,94 @@ async fn main() {
     ui.run().unwrap();
 }
 
+/// Live Vietcap provider, optionally recorded to or replayed from a session file
+///
+/// `AIM_REPLAY=<file>` plays a recorded session back (`AIM_REPLAY_SPEED`,
+/// `AIM_REPLAY_SKIP_MIN`, `AIM_REPLAY_LOOP=1`), `AIM_RECORD=<file>` appends every
+/// market watch, quote and order list response of the live session to the file.
+fn market_data_provider() -> SharedProvider {
+    let live = aim_data::default_provider();
+    let var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+
+    if let Some(path) = var("AIM_REPLAY") {
+        let options = ReplayOptions {
+            speed: var("AIM_REPLAY_SPEED")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1.0),
+            skip: chrono::Duration::minutes(
+                var("AIM_REPLAY_SKIP_MIN")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0),
+            ),
+            looped: var("AIM_REPLAY_LOOP").is_some_and(|v| v == "1" || v == "true"),
+        };
+        match ReplayProvider::load(std::path::Path::new(&path), options) {
+            Ok(replay) => return Arc::new(replay.with_fallback(live)),
+            Err(e) => log::error!("Failed to load replay {path}: {e}"),
+        }
+    } else if let Some(path) = var("AIM_RECORD") {
+        match RecordingProvider::new(Arc::clone(&live), std::path::Path::new(&path)) {
+            Ok(recorder) => return Arc::new(recorder),
+            Err(e) => log::error!("Failed to start recording to {path}: {e}"),
+        }
+    }
+    live
+}
+
+/// Daily chart of the default stock (AAA), `None` when it can't be fetched, e.g.
+/// offline; the chart task loads the chart shown once the quotes are back
+async fn default_chart(provider: &SharedProvider) -> Option<Chart> {
+    let now = chrono::Utc::now();
+    let bars = match candle_store()
+        .fetch(
+            provider.as_ref(),
+            "AAA",
+            "ONE_DAY",
+            Chart::history_start("1D", now),
+            now,
+        )
+        .await
+    {
+        Ok(bars) if !bars.is_empty() => bars,
+        Ok(_) => {
+            log::error!("No history for the default stock AAA");
+            return None;
+        }
+        Err(e) => {
+            log::error!("Failed to fetch the history of the default stock AAA: {e}");
+            return None;
+        }
+    };
+    let company_info = match provider.get_company_info("AAA", "Y").await {
+        Ok(info) => info
+            .latest_ratio()
+            .map(aim_chart::CompanyInfo::from)
+            .unwrap_or_default(),
+        Err(e) => {
+            log::error!("Failed to fetch company info for AAA: {e}");
+            aim_chart::CompanyInfo::default()
+        }
+    };
+    Some(Chart::new_default(
+        "AAA".to_string(),
+        "1D".to_string(),
+        OHLCData::from_candlesticks("AAA", &bars),
+        company_info,
+    ))
+}
+
 /// Converts user-friendly interval strings to API interval constants
 ///
 /// # Arguments
 /// * `interval` - The interval string (e.g., "1m", "5m", "1H", "1D")
 ///
 /// # Returns
-/// The corresponding API interval constant
+/// The API interval constant of the base bars, which the chart resamples
+/// to `interval` (e.g. "ONE_MINUTE" for "15m")
 fn interval_to_constant(interval: &str) -> &'static str {
-    match interval {
-        "1m" | "5m" | "15m" | "30m" => "ONE_MINUTE",
-        "1H" | "2H" | "4H" => "ONE_HOUR",
-        "1D" | "2D" | "3D" => "ONE_DAY",
-        "1W" | "2W" | "1M" => "ONE_WEEK",
-        _ => "ONE_DAY",
-    }
+    aim_data::Timeframe::parse(interval)
+        .unwrap_or(aim_data::Timeframe::DAY)
+        .api_interval()
 }
 
 #[derive(Clone)]
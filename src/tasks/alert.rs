@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use aim_chart::alert::{
+    drawing_levels, Alert, AlertEngine, AlertEvent, Condition, Quote, SymbolContext,
+    DEFAULT_EVENT_LIMIT,
+};
+use aim_chart::CandleData;
+use aim_data::SharedProvider;
+use chrono::{NaiveDate, Utc};
+use notify_rust::Notification;
+use slint::{ComponentHandle, ModelRc, SharedString, VecModel, Weak};
+use tokio::sync::{watch, Mutex};
+
+use crate::slint_generatedAppWindow::{
+    AlertEventItem, AlertItem, AppWindow, StockData as SlintStockData,
+};
+use crate::tasks::mp::daily_candles;
+use crate::tasks::task_manager::{register_task, TaskHandle, TaskStatus};
+use crate::tasks::ChartMetaData;
+
+pub type SharedAlerts = Arc<Mutex<AlertBook>>;
+
+/// Wait before loading the daily candles of a symbol again after a failed or
+/// empty load, doubled on every failure up to `MAX_HISTORY_RETRY`
+const HISTORY_RETRY: Duration = Duration::from_secs(30);
+const MAX_HISTORY_RETRY: Duration = Duration::from_secs(30 * 60);
+
+/// Alerts of the user and the events they fired, kept in the app cache dir
+pub struct AlertBook {
+    pub engine: AlertEngine,
+    /// Oldest first
+    pub events: Vec<AlertEvent>,
+    alerts_file: PathBuf,
+    events_file: PathBuf,
+}
+
+impl AlertBook {
+    pub fn load(app_cache_dir: &Path) -> Self {
+        let alerts_file = app_cache_dir.join("alerts.json");
+        let events_file = app_cache_dir.join("alert_history.json");
+        let alerts: Vec<Alert> = read_json(&alerts_file);
+        let events: Vec<AlertEvent> = read_json(&events_file);
+        log::info!(
+            "Loaded {} alerts and {} alert events",
+            alerts.len(),
+            events.len()
+        );
+        Self {
+            engine: AlertEngine::new(alerts),
+            events,
+            alerts_file,
+            events_file,
+        }
+    }
+
+    pub fn save_alerts(&self) {
+        write_json(&self.alerts_file, self.engine.alerts());
+    }
+
+    /// Add fired events to the history, forgetting the oldest ones past the limit
+    pub fn record(&mut self, events: &[AlertEvent]) {
+        self.events.extend_from_slice(events);
+        let excess = self.events.len().saturating_sub(DEFAULT_EVENT_LIMIT);
+        self.events.drain(..excess);
+        write_json(&self.events_file, &self.events);
+    }
+
+    pub fn clear_events(&mut self) {
+        self.events.clear();
+        write_json(&self.events_file, &self.events);
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned + Default>(path: &Path) -> T {
+    let Ok(json) = std::fs::read_to_string(path) else {
+        return T::default();
+    };
+    serde_json::from_str(&json).unwrap_or_else(|e| {
+        log::error!("Failed to parse {}: {e}", path.display());
+        T::default()
+    })
+}
+
+fn write_json<T: serde::Serialize + ?Sized>(path: &Path, value: &T) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(path, json) {
+                log::error!("Failed to write {}: {e}", path.display());
+            }
+        }
+        Err(e) => log::error!("Failed to serialize {}: {e}", path.display()),
+    }
+}
+
+/// Quote the alerts read from a row of the price board
+pub fn quote_of(stock: &SlintStockData) -> Quote {
+    Quote {
+        symbol: stock.symbol.to_string(),
+        price: stock.price,
+        change_percent: stock.change_percent,
+        volume: stock.volume as f64,
+    }
+}
+
+/// Condition of the alert form: its kind, its value and the extra field of
+/// the volume spike (sessions) and RSI (period) kinds
+fn condition_of(kind: i32, value: f32, extra: f32) -> Option<Condition> {
+    let sessions = (extra.round() as usize).max(1);
+    match kind {
+        0 => Some(Condition::PriceCross { level: value }),
+        1 => Some(Condition::ChangePercent { percent: value }),
+        2 => Some(Condition::VolumeSpike {
+            days: sessions,
+            ratio: value,
+        }),
+        3 => Some(Condition::TouchDrawing { tolerance: value }),
+        4 | 5 => Some(Condition::Rsi {
+            period: sessions,
+            threshold: value,
+            above: kind == 4,
+        }),
+        _ => None,
+    }
+}
+
+fn show_alerts(ui_handle: &Weak<AppWindow>, book: &AlertBook) {
+    let alerts: Vec<AlertItem> = book
+        .engine
+        .alerts()
+        .iter()
+        .map(|alert| AlertItem {
+            id: alert.id as i32,
+            symbol: alert.symbol.clone().into(),
+            condition: alert.condition.describe().into(),
+            enabled: alert.enabled,
+        })
+        .collect();
+    let events: Vec<AlertEventItem> = book
+        .events
+        .iter()
+        .rev()
+        .map(|event| AlertEventItem {
+            time: event
+                .time
+                .with_timezone(&chrono::Local)
+                .format("%d/%m %H:%M:%S")
+                .to_string()
+                .into(),
+            symbol: event.symbol.clone().into(),
+            message: event.message.clone().into(),
+        })
+        .collect();
+    let _ = ui_handle.upgrade_in_event_loop(move |ui| {
+        ui.set_alerts(ModelRc::new(VecModel::from(alerts)));
+        ui.set_alert_events(ModelRc::new(VecModel::from(events)));
+    });
+}
+
+/// Desktop notification of a fired alert, shown on a blocking thread as it
+/// waits for the notification daemon
+fn notify_desktop(event: &AlertEvent) {
+    let symbol = event.symbol.clone();
+    let message = event.message.clone();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = Notification::new()
+            .appname("Aim")
+            .summary(&format!("Cảnh báo {symbol}"))
+            .body(&message)
+            .show()
+        {
+            log::error!("Failed to show the desktop notification of {symbol}: {e}");
+        }
+    });
+}
+
+/// Set up the callbacks of the alert panel and show the saved alerts
+pub fn register_alert_handlers(ui: &AppWindow, book: SharedAlerts) {
+    let ui_handle = ui.as_weak();
+    let book_clone = Arc::clone(&book);
+    ui.on_add_alert(move |symbol: SharedString, kind, value, extra| {
+        let Some(condition) = condition_of(kind, value, extra) else {
+            log::error!("Unknown alert kind {kind}");
+            return;
+        };
+        let (book, ui_handle) = (Arc::clone(&book_clone), ui_handle.clone());
+        tokio::spawn(async move {
+            let mut book = book.lock().await;
+            let id = book.engine.add(&symbol, condition, Utc::now());
+            log::info!("Added alert #{id} on {symbol}");
+            book.save_alerts();
+            show_alerts(&ui_handle, &book);
+        });
+    });
+
+    let ui_handle = ui.as_weak();
+    let book_clone = Arc::clone(&book);
+    ui.on_remove_alert(move |id| {
+        let (book, ui_handle) = (Arc::clone(&book_clone), ui_handle.clone());
+        tokio::spawn(async move {
+            let mut book = book.lock().await;
+            if book.engine.remove(id as u64) {
+                book.save_alerts();
+            }
+            show_alerts(&ui_handle, &book);
+        });
+    });
+
+    let ui_handle = ui.as_weak();
+    let book_clone = Arc::clone(&book);
+    ui.on_toggle_alert(move |id, enabled| {
+        let (book, ui_handle) = (Arc::clone(&book_clone), ui_handle.clone());
+        tokio::spawn(async move {
+            let mut book = book.lock().await;
+            if book.engine.set_enabled(id as u64, enabled) {
+                book.save_alerts();
+            }
+            show_alerts(&ui_handle, &book);
+        });
+    });
+
+    let ui_handle = ui.as_weak();
+    let book_clone = Arc::clone(&book);
+    ui.on_clear_alert_events(move || {
+        let (book, ui_handle) = (Arc::clone(&book_clone), ui_handle.clone());
+        tokio::spawn(async move {
+            let mut book = book.lock().await;
+            book.clear_events();
+            show_alerts(&ui_handle, &book);
+        });
+    });
+
+    let ui_handle = ui.as_weak();
+    tokio::spawn(async move {
+        show_alerts(&ui_handle, &*book.lock().await);
+    });
+}
+
+/// Spawns a task that tests the alerts on every snapshot of the price board
+/// and notifies the user of the ones firing
+///
+/// `quotes` carries the board polled by the stock data task. The daily
+/// candles of the volume and RSI conditions are loaded once a day, and again
+/// with a growing delay while the load fails. The drawn levels are read from
+/// the charts on every snapshot.
+pub async fn spawn_alert_task(
+    ui: &AppWindow,
+    book: SharedAlerts,
+    chart: Arc<Mutex<ChartMetaData>>,
+    provider: SharedProvider,
+    mut quotes: watch::Receiver<Vec<Quote>>,
+) -> TaskHandle {
+    let ui_handle = ui.as_weak();
+    let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+    let task_handle =
+        register_task("system.alert".to_string(), tx, "Alert Engine".to_string()).await;
+
+    tokio::spawn(async move {
+        let mut task_status = TaskStatus::Running;
+        let mut daily: HashMap<String, (NaiveDate, Vec<CandleData>)> = HashMap::new();
+        // Next load of the symbols whose daily candles failed, and the delay after it
+        let mut retries: HashMap<String, (Instant, Duration)> = HashMap::new();
+        loop {
+            if let Ok(status) = rx.try_recv() {
+                if task_status != status {
+                    log::info!("Alert task status changed to: {:?}", status);
+                    task_status = status;
+                }
+            }
+            if task_status != TaskStatus::Running || !quotes.has_changed().unwrap_or(false) {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+            let board = quotes.borrow_and_update().clone();
+            if board.is_empty() {
+                continue;
+            }
+            let now = Utc::now();
+
+            let (history_symbols, drawing_symbols) = {
+                let book = book.lock().await;
+                let symbols = |needs: fn(&Condition) -> bool| -> Vec<String> {
+                    let mut symbols: Vec<String> = book
+                        .engine
+                        .active()
+                        .filter(|alert| needs(&alert.condition))
+                        .map(|alert| alert.symbol.clone())
+                        .collect();
+                    symbols.sort();
+                    symbols.dedup();
+                    symbols
+                };
+                (
+                    symbols(Condition::needs_history),
+                    symbols(Condition::needs_drawings),
+                )
+            };
+
+            let today = now.date_naive();
+            for symbol in &history_symbols {
+                if daily.get(symbol).map(|(day, _)| *day) == Some(today)
+                    || retries.get(symbol).is_some_and(|(at, _)| Instant::now() < *at)
+                {
+                    continue;
+                }
+                let candles = daily_candles(provider.as_ref(), symbol).await;
+                if candles.is_empty() {
+                    // Keep the candles of an earlier day until the load succeeds
+                    let delay = retries.get(symbol).map_or(HISTORY_RETRY, |(_, delay)| {
+                        (*delay * 2).min(MAX_HISTORY_RETRY)
+                    });
+                    log::warn!(
+                        "No daily candles of {symbol}, loading them again in {}s",
+                        delay.as_secs()
+                    );
+                    retries.insert(symbol.clone(), (Instant::now() + delay, delay));
+                } else {
+                    retries.remove(symbol);
+                    daily.insert(symbol.clone(), (today, candles));
+                }
+            }
+            let levels: HashMap<String, Vec<f32>> = {
+                let charts = chart.lock().await;
+                drawing_symbols
+                    .iter()
+                    .filter_map(|symbol| {
+                        let chart = charts.drawings_of(symbol)?;
+                        Some((symbol.clone(), drawing_levels(chart, now)))
+                    })
+                    .collect()
+            };
+            let mut contexts: HashMap<String, SymbolContext> = HashMap::new();
+            for symbol in history_symbols.iter().chain(&drawing_symbols) {
+                if contexts.contains_key(symbol) {
+                    continue;
+                }
+                let candles = daily
+                    .get(symbol)
+                    .map(|(_, candles)| candles.clone())
+                    .unwrap_or_default();
+                let drawn = levels.get(symbol).cloned().unwrap_or_default();
+                contexts.insert(symbol.clone(), SymbolContext::new(candles, drawn, now));
+            }
+
+            let mut book = book.lock().await;
+            let events = book.engine.evaluate(&board, &contexts, now);
+            if events.is_empty() {
+                continue;
+            }
+            for event in &events {
+                log::info!("Alert #{} fired: {}", event.alert_id, event.message);
+                notify_desktop(event);
+            }
+            book.record(&events);
+            show_alerts(&ui_handle, &book);
+            let toast = events
+                .iter()
+                .map(|event| event.message.as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let _ = ui_handle.upgrade_in_event_loop(move |ui| {
+                ui.set_alert_toast(toast.into());
+            });
+        }
+    });
+
+    task_handle
+}
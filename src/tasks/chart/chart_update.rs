@@ -1,11 +1,20 @@
 use crate::tasks::task_manager::{register_task, TaskHandle};
 use crate::tasks::{chart::is_trading_hours, ChartMetaData};
-use aim_data::get_quote;
-use std::{sync::Arc, time::Duration};
+use aim_chart::Chart;
+use aim_data::{candle_store, SharedProvider};
+use chrono::{DateTime, Utc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+    time::Duration,
+};
 use tokio::sync::Mutex;
 
 /// Spawns a task to handle real-time stock data updates
-pub async fn spawn_chart_update_task(chart: Arc<Mutex<ChartMetaData>>) -> TaskHandle {
+pub async fn spawn_chart_update_task(
+    chart: Arc<Mutex<ChartMetaData>>,
+    provider: SharedProvider,
+) -> TaskHandle {
     let chart_clone = Arc::clone(&chart);
     let (tx, mut rx) = tokio::sync::mpsc::channel(10);
     let task_handle = register_task(
@@ -17,7 +26,6 @@ pub async fn spawn_chart_update_task(chart: Arc<Mutex<ChartMetaData>>) -> TaskHa
 
     tokio::spawn(async move {
         let mut task_status = crate::tasks::task_manager::TaskStatus::Running;
-        let mut stock_names: Vec<String> = vec!["AAA".to_string()];
         loop {
             if let Ok(status) = rx.try_recv() {
                 if task_status != status {
@@ -29,31 +37,59 @@ pub async fn spawn_chart_update_task(chart: Arc<Mutex<ChartMetaData>>) -> TaskHa
                 tokio::time::sleep(std::time::Duration::from_millis(100)).await;
                 continue;
             }
-            if !is_trading_hours() {
+            if !is_trading_hours() && provider.follows_trading_hours() {
                 log::info!("Outside trading hours (9:00-15:00 Vietnam time), skipping data update");
                 tokio::time::sleep(Duration::from_millis(100)).await;
                 continue;
             }
+            // Group the tracked stocks by the base interval of their chart timeframe,
+            // asking each group only for bars from its oldest last candle on
+            let mut groups: BTreeMap<&'static str, (BTreeSet<String>, DateTime<Utc>)> =
+                BTreeMap::new();
+            let now = Utc::now();
+            for chart in chart_clone.lock().await.charts() {
+                let start = chart
+                    .next_fetch_start()
+                    .unwrap_or_else(|| Chart::history_start(&chart.time_frame, now));
+                let (names, group_start) = groups
+                    .entry(chart.timeframe().api_interval())
+                    .or_insert_with(|| (BTreeSet::new(), start));
+                names.insert(chart.stock_name.clone());
+                *group_start = (*group_start).min(start);
+            }
+
             // Fetch updated chart data for all tracked stocks
-            let stock_name_slices: Vec<&str> = stock_names.iter().map(|s| s.as_str()).collect();
-            if let Ok(chart_data_vec) = get_quote(&stock_name_slices, "ONE_DAY", None, None).await {
-                let mut charts = chart_clone.lock().await;
-                // Update list of tracked stocks
-                let new_stock_names: Vec<String> = charts
-                    .data
-                    .iter()
-                    .map(|chart| chart.stock_name.clone())
-                    .collect();
-                stock_names = new_stock_names.clone();
+            for (interval, (names, start)) in groups {
+                let stock_name_slices: Vec<&str> = names.iter().map(|s| s.as_str()).collect();
+                if let Ok(chart_data_vec) = provider
+                    .get_quote(&stock_name_slices, interval, Some(start), None)
+                    .await
+                {
+                    // Keep the new bars in the history store for the next fetches
+                    let now = Utc::now();
+                    for data in &chart_data_vec.0 {
+                        let bars = data.to_candlesticks();
+                        if let Err(e) =
+                            candle_store().insert(&data.symbol, interval, start, now, &bars)
+                        {
+                            log::error!("Failed to store {interval} bars of {}: {e}", data.symbol);
+                        }
+                    }
+
+                    let mut charts = chart_clone.lock().await;
 
-                // Update chart data for each tracked stock
-                for chart in charts.data.iter_mut() {
-                    if let Some(updated_data) = chart_data_vec
-                        .0
-                        .iter()
-                        .find(|data| data.symbol == chart.stock_name)
-                    {
-                        chart.update_candle_data(updated_data.clone());
+                    // Merge the new bars into each tracked chart
+                    for chart in charts.charts_mut() {
+                        if chart.timeframe().api_interval() != interval {
+                            continue;
+                        }
+                        if let Some(updated_data) = chart_data_vec
+                            .0
+                            .iter()
+                            .find(|data| data.symbol == chart.stock_name)
+                        {
+                            chart.merge_candle_data(updated_data.clone());
+                        }
                     }
                 }
             }
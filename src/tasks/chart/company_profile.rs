@@ -9,10 +9,8 @@ use crate::slint_generatedAppWindow::{
 };
 use crate::AppWindow;
 use aim_data::aim::{
-    fetch_insider_transactions_data, fetch_institution_data, fetch_officers_data,
-    fetch_sharedholder_data, fetch_subsidiaries_data, InsiderTransaction as ApiInsiderTransaction,
-    InstitutionData, Officer as ApiOfficer, SharedHolder as ApiSharedHolder,
-    Subsidiary as ApiSubsidiary,
+    aim_client, InsiderTransaction as ApiInsiderTransaction, InstitutionData, Officer as ApiOfficer,
+    SharedHolder as ApiSharedHolder, Subsidiary as ApiSubsidiary,
 };
 
 // Convert API data to UI data structures
@@ -213,7 +211,7 @@ async fn spawn_shareholders_task(ui: &AppWindow) -> TaskHandle {
                     log::info!("Fetching new shareholders data for {stock_name}");
 
                     // Fetch new data from API
-                    match fetch_sharedholder_data(&stock_name).await {
+                    match aim_client().fetch_sharedholder_data(&stock_name).await {
                         Ok(api_data) => {
                             shareholders_data = api_data;
 
@@ -322,7 +320,7 @@ async fn spawn_institution_task(ui: &AppWindow) -> TaskHandle {
                     log::info!("Fetching new institution data for {stock_name}");
 
                     // Fetch new data from API
-                    match fetch_institution_data(&stock_name).await {
+                    match aim_client().fetch_institution_data(&stock_name).await {
                         Ok(api_data) => {
                             institution_data = Some(api_data.clone());
 
@@ -442,7 +440,7 @@ async fn spawn_subsidiaries_task(ui: &AppWindow) -> TaskHandle {
                     log::info!("Fetching new subsidiaries data for {stock_name}");
 
                     // Fetch new data from API
-                    match fetch_subsidiaries_data(&stock_name).await {
+                    match aim_client().fetch_subsidiaries_data(&stock_name).await {
                         Ok(api_data) => {
                             subsidiaries_data = api_data;
 
@@ -544,7 +542,7 @@ async fn spawn_officers_task(ui: &AppWindow) -> TaskHandle {
                     log::info!("Fetching new officers data for {stock_name}");
 
                     // Fetch new data from API
-                    match fetch_officers_data(&stock_name).await {
+                    match aim_client().fetch_officers_data(&stock_name).await {
                         Ok(api_data) => {
                             officers_data = api_data;
 
@@ -645,7 +643,7 @@ async fn spawn_insider_transactions_task(ui: &AppWindow) -> TaskHandle {
                     log::info!("Fetching new insider transactions data for {stock_name}");
 
                     // Fetch new data from API
-                    match fetch_insider_transactions_data(&stock_name).await {
+                    match aim_client().fetch_insider_transactions_data(&stock_name).await {
                         Ok(api_data) => {
                             insider_transactions_data = api_data;
 
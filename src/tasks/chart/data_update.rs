@@ -4,6 +4,7 @@ use crate::slint_generatedAppWindow::{
     MarketWatchData as SlintMarketWatchData, OrderList as SlintOrderList,
     StockData as SlintStockData,
 };
+use crate::tasks::alert::quote_of;
 use crate::tasks::chart::create_sector_groups;
 use crate::tasks::chart::is_trading_hours;
 use crate::tasks::chart::sort_market_watch;
@@ -11,23 +12,32 @@ use crate::tasks::chart::ALL_STOCK_LIST;
 use crate::tasks::task_manager::TaskStatus;
 use crate::tasks::task_manager::{register_task, TaskHandle};
 use crate::tasks::DataUpdate;
+use aim_chart::alert::Quote;
 use aim_data::explorer::vci::VCIOderBook;
-use aim_data::get_market_watch;
-use aim_data::get_order_list;
+use aim_data::SharedProvider;
 use chrono::Timelike;
 use slint::Weak;
 use slint::{ComponentHandle, ModelRc};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::sync::watch;
 use tokio::sync::Mutex;
 
 use super::VN30_LIST;
 
+/// Pause before polling again after a failed fetch, the executor already retried it
+const FETCH_ERROR_DELAY: Duration = Duration::from_secs(2);
+
 /// Spawns a task to handle market watch data updates
+///
+/// Every new snapshot of the stock board is also published on `quotes`, for
+/// the alerts.
 pub async fn spawn_data_update_task(
     ui: &crate::slint_generatedAppWindow::AppWindow,
     custom_list: Arc<Mutex<Vec<String>>>,
+    provider: SharedProvider,
+    quotes: watch::Sender<Vec<Quote>>,
 ) -> Vec<TaskHandle> {
     let mut handles = Vec::new();
     let (tx_data_update, rx_data_update) = mpsc::channel::<DataUpdate>(10);
@@ -35,9 +45,14 @@ pub async fn spawn_data_update_task(
 
     // Create individual task handles for each sub-task
     handles.push(spawn_custom_list_polling_task(tx_data_update.clone(), custom_list).await);
-    handles.push(spawn_stock_data_polling_task(tx_data_update.clone()).await);
-    handles.push(spawn_market_watch_polling_task(tx_data_update.clone()).await);
-    handles.push(spawn_order_list_polling_task(tx_data_update.clone(), ui_handle.clone()).await);
+    handles.push(
+        spawn_stock_data_polling_task(tx_data_update.clone(), Arc::clone(&provider), quotes).await,
+    );
+    handles
+        .push(spawn_market_watch_polling_task(tx_data_update.clone(), Arc::clone(&provider)).await);
+    handles.push(
+        spawn_order_list_polling_task(tx_data_update.clone(), ui_handle.clone(), provider).await,
+    );
     handles.push(spawn_ui_update_task(ui_handle, rx_data_update).await);
 
     handles
@@ -63,7 +78,11 @@ async fn spawn_custom_list_polling_task(
     task_handle
 }
 
-async fn spawn_stock_data_polling_task(tx: mpsc::Sender<DataUpdate>) -> TaskHandle {
+async fn spawn_stock_data_polling_task(
+    tx: mpsc::Sender<DataUpdate>,
+    provider: SharedProvider,
+    quotes: watch::Sender<Vec<Quote>>,
+) -> TaskHandle {
     let (tx_task, rx) = tokio::sync::mpsc::channel(10);
     let task_handle = register_task(
         "chart.data_update.stock_data".to_string(),
@@ -73,13 +92,16 @@ async fn spawn_stock_data_polling_task(tx: mpsc::Sender<DataUpdate>) -> TaskHand
     .await;
 
     tokio::spawn(async move {
-        polling_all_stock_data(tx, rx).await;
+        polling_all_stock_data(tx, rx, provider, quotes).await;
     });
 
     task_handle
 }
 
-async fn spawn_market_watch_polling_task(tx: mpsc::Sender<DataUpdate>) -> TaskHandle {
+async fn spawn_market_watch_polling_task(
+    tx: mpsc::Sender<DataUpdate>,
+    provider: SharedProvider,
+) -> TaskHandle {
     let (tx_status, rx) = tokio::sync::mpsc::channel(10);
     let task_handle = register_task(
         "chart.data_update.market_watch".to_string(),
@@ -89,7 +111,7 @@ async fn spawn_market_watch_polling_task(tx: mpsc::Sender<DataUpdate>) -> TaskHa
     .await;
 
     tokio::spawn(async move {
-        polling_market_watch(tx, rx).await;
+        polling_market_watch(tx, rx, provider).await;
     });
 
     task_handle
@@ -98,6 +120,7 @@ async fn spawn_market_watch_polling_task(tx: mpsc::Sender<DataUpdate>) -> TaskHa
 async fn spawn_order_list_polling_task(
     tx: mpsc::Sender<DataUpdate>,
     ui_handle: Weak<crate::slint_generatedAppWindow::AppWindow>,
+    provider: SharedProvider,
 ) -> TaskHandle {
     let (tx_task, rx) = tokio::sync::mpsc::channel(10);
     let task_handle = register_task(
@@ -108,7 +131,7 @@ async fn spawn_order_list_polling_task(
     .await;
 
     tokio::spawn(async move {
-        polling_order_list(tx, rx, ui_handle).await;
+        polling_order_list(tx, rx, ui_handle, provider).await;
     });
 
     task_handle
@@ -187,6 +210,7 @@ async fn polling_order_list(
     tx: mpsc::Sender<DataUpdate>,
     mut rx: mpsc::Receiver<TaskStatus>,
     ui_handle: Weak<crate::slint_generatedAppWindow::AppWindow>,
+    provider: SharedProvider,
 ) {
     let current_stock = Arc::new(Mutex::new(String::from("AAA")));
     let mut previous_order_list: Option<Vec<VCIOderBook>> = None;
@@ -221,28 +245,30 @@ async fn polling_order_list(
         });
 
         log::info!("Polling order list for stock: {current_stock_str}");
-        let (order_changed, order_list) = match get_order_list(&current_stock_str).await {
-            Ok(order_list) => {
-                if let Some(previous) = &previous_order_list {
-                    if previous.is_empty()
-                        || order_list.is_empty()
-                        || previous[0].id != order_list[0].id
-                    {
+        let (order_changed, order_list) =
+            match provider.get_order_list(&current_stock_str, 30000).await {
+                Ok(order_list) => {
+                    if let Some(previous) = &previous_order_list {
+                        if previous.is_empty()
+                            || order_list.is_empty()
+                            || previous[0].id != order_list[0].id
+                        {
+                            previous_order_list = Some(order_list.clone());
+                            (true, order_list)
+                        } else {
+                            (false, previous.clone())
+                        }
+                    } else {
                         previous_order_list = Some(order_list.clone());
                         (true, order_list)
-                    } else {
-                        (false, previous.clone())
                     }
-                } else {
-                    previous_order_list = Some(order_list.clone());
-                    (true, order_list)
                 }
-            }
-            Err(e) => {
-                log::error!("Failed to fetch order list: {e}. try again ...");
-                continue;
-            }
-        };
+                Err(e) => {
+                    log::error!("Failed to fetch order list: {e}. try again ...");
+                    tokio::time::sleep(FETCH_ERROR_DELAY).await;
+                    continue;
+                }
+            };
 
         if order_changed {
             previous_order_list = Some(order_list.clone());
@@ -253,7 +279,12 @@ async fn polling_order_list(
     }
 }
 
-async fn polling_all_stock_data(tx: mpsc::Sender<DataUpdate>, mut rx: mpsc::Receiver<TaskStatus>) {
+async fn polling_all_stock_data(
+    tx: mpsc::Sender<DataUpdate>,
+    mut rx: mpsc::Receiver<TaskStatus>,
+    provider: SharedProvider,
+    quotes: watch::Sender<Vec<Quote>>,
+) {
     let mut previous_stock_data: Option<Vec<SlintStockData>> = None;
     let mut is_first_update = true; // Track if this is the first update
     let mut task_status = crate::tasks::task_manager::TaskStatus::Running;
@@ -273,17 +304,18 @@ async fn polling_all_stock_data(tx: mpsc::Sender<DataUpdate>, mut rx: mpsc::Rece
 
         // If not in trading hours and we've already done the first update,
         // only continue if custom list changed, otherwise skip data fetching
-        if !is_trading_hours && !is_first_update {
+        if !is_trading_hours && !is_first_update && provider.follows_trading_hours() {
             log::info!("Outside trading hours (9:00-15:00 Vietnam time), skipping data update");
             tokio::time::sleep(Duration::from_millis(100)).await;
             continue;
         }
 
         // Fetch market data
-        let market_watch_data = match get_market_watch(&ALL_STOCK_LIST).await {
+        let market_watch_data = match provider.get_market_watch(&ALL_STOCK_LIST).await {
             Ok(data) => data,
             Err(e) => {
                 log::error!("Failed to fetch market watch data: {e}. try again ...");
+                tokio::time::sleep(FETCH_ERROR_DELAY).await;
                 continue;
             }
         };
@@ -300,13 +332,18 @@ async fn polling_all_stock_data(tx: mpsc::Sender<DataUpdate>, mut rx: mpsc::Rece
         if market_watch_changed {
             is_first_update = false;
             previous_stock_data = Some(all_stock_data.clone());
+            quotes.send_replace(all_stock_data.iter().map(quote_of).collect());
             tx.send(DataUpdate::StockData(all_stock_data)).await.ok();
         }
         tokio::time::sleep(Duration::from_millis(100)).await;
     }
 }
 
-async fn polling_market_watch(tx: mpsc::Sender<DataUpdate>, mut rx: mpsc::Receiver<TaskStatus>) {
+async fn polling_market_watch(
+    tx: mpsc::Sender<DataUpdate>,
+    mut rx: mpsc::Receiver<TaskStatus>,
+    provider: SharedProvider,
+) {
     let mut previous_market_watch_data: Option<Vec<SlintMarketWatchData>> = None;
     let mut is_first_update = true; // Track if this is the first update
     let mut task_status = crate::tasks::task_manager::TaskStatus::Running;
@@ -326,17 +363,18 @@ async fn polling_market_watch(tx: mpsc::Sender<DataUpdate>, mut rx: mpsc::Receiv
 
         // If not in trading hours and we've already done the first update,
         // only continue if custom list changed, otherwise skip data fetching
-        if !is_trading_hours && !is_first_update {
+        if !is_trading_hours && !is_first_update && provider.follows_trading_hours() {
             log::info!("Outside trading hours (9:00-15:00 Vietnam time), skipping data update");
             tokio::time::sleep(Duration::from_millis(100)).await;
             continue;
         }
 
         // Fetch market data
-        let market_watch_data = match get_market_watch(&VN30_LIST).await {
+        let market_watch_data = match provider.get_market_watch(&VN30_LIST).await {
             Ok(data) => data,
             Err(e) => {
                 log::error!("Failed to fetch market watch data: {e}. try again ...");
+                tokio::time::sleep(FETCH_ERROR_DELAY).await;
                 continue;
             }
         };
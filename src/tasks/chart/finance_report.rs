@@ -6,8 +6,7 @@ use slint::{ComponentHandle, ModelRc, VecModel};
 use slint::Image;
 
 use aim_data::aim::{
-    fetch_finance_report_list, fetch_strategy_report_list, fetch_finance_report_pdf,
-    StockReport as ApiStockReport, StrategyReport as ApiStrategyReport, PdfReport,
+    aim_client, StockReport as ApiStockReport, StrategyReport as ApiStrategyReport, PdfReport,
 };
 
 use crate::slint_generatedAppWindow::{
@@ -85,7 +84,7 @@ async fn spawn_stock_report_task(ui: &AppWindow) -> TaskHandle {
 
             if data.is_empty() {
                 log::info!("📊 Fetching new Stock Reports...");
-                match fetch_finance_report_list().await {
+                match aim_client().fetch_finance_report_list().await {
                     Ok(api_data) => {
                         log::info!("✅ API trả về {} bản ghi gốc", api_data.len());
 
@@ -194,7 +193,7 @@ async fn spawn_strategy_report_task(ui: &AppWindow) -> TaskHandle {
 
             if data.is_empty() {
                 log::info!("Fetching new Strategy Reports...");
-                match fetch_strategy_report_list().await {
+                match aim_client().fetch_strategy_report_list().await {
                     Ok(api_data) => {
                         data = api_data.clone();
                         *cache.lock().await = api_data;
@@ -368,7 +367,7 @@ pub async fn spawn_finance_pdf_task(ui: &AppWindow) -> TaskHandle {
                 *prev = symbol.clone();
 
                 if pdf_data.is_none() {
-                    match fetch_finance_report_pdf(&symbol).await {
+                    match aim_client().fetch_finance_report_pdf(&symbol).await {
                         Ok(api_data) => {
                             pdf_data = Some(api_data.clone());
                             cache.lock().await.insert(symbol.clone(), api_data);
@@ -499,7 +498,7 @@ pub async fn spawn_finance_pdf_selected_task(ui: &AppWindow) -> TaskHandle {
 
             // 🧠 Nếu chưa có cache → fetch từ API
             if pdf_data.is_none() {
-                match fetch_finance_report_pdf(&report_id).await {
+                match aim_client().fetch_finance_report_pdf(&report_id).await {
                     Ok(api_data) => {
                         pdf_data = Some(api_data.clone());
                         cache.lock().await.insert(report_id.clone(), api_data);
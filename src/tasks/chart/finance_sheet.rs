@@ -6,11 +6,7 @@ use slint::ComponentHandle;
 use tokio::sync::Mutex;
 
 use crate::tasks::backend::{convert_financial_data_to_overview, FinanceListExt};
-use aim_data::explorer::aim::{
-    fetch_balance_sheet_data, fetch_cash_flow_gt_sheet_data, fetch_cash_flow_tt_sheet_data,
-    fetch_income_statement_sheet_data,
-};
-use aim_data::explorer::aim::{fetch_financial_data, FinanceSheetData};
+use aim_data::explorer::aim::{aim_client, FinanceSheetData};
 
 use crate::AppWindow;
 
@@ -118,7 +114,10 @@ macro_rules! create_finance_task {
                             && consecutive_empty_quarters < max_consecutive_empty
                         {
                             let period_str = current_period.to_period();
-                            match $fetch_fn(&stock_name, &period_str).await {
+                            match aim_data::aim::aim_client()
+                                .$fetch_fn(&stock_name, &period_str)
+                                .await
+                            {
                                 Ok(data) if !data.is_empty() => {
                                     data_vec.push(data);
                                     consecutive_empty_quarters = 0;
@@ -146,7 +145,10 @@ macro_rules! create_finance_task {
                         // Fallback to default periods if no data found
                         if data_vec.is_empty() {
                             for period in DEFAULT_PERIODS.iter() {
-                                if let Ok(data) = $fetch_fn(&stock_name, period).await {
+                                if let Ok(data) = aim_data::aim::aim_client()
+                                    .$fetch_fn(&stock_name, period)
+                                    .await
+                                {
                                     if !data.is_empty() {
                                         data_vec.push(data);
                                     }
@@ -355,7 +357,10 @@ async fn spawn_overview_task(ui: &AppWindow) -> TaskHandle {
                 log::info!("Fetching new overview data for {stock_name}");
 
                 // Fetch overview data
-                let overview_data_raw = fetch_financial_data(&stock_name).await.unwrap_or_default();
+                let overview_data_raw = aim_client()
+                    .fetch_financial_data(&stock_name)
+                    .await
+                    .unwrap_or_default();
                 let overview_data = convert_financial_data_to_overview(overview_data_raw);
 
                 // Store in cache
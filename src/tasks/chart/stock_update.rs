@@ -1,12 +1,15 @@
 use crate::interval_to_constant;
 use crate::slint_generatedAppWindow::AppWindow;
 use crate::tasks::task_manager::{register_task, TaskHandle};
-use crate::tasks::ChartMetaData;
+use crate::tasks::{ChartKey, ChartMetaData};
 use aim_chart::Chart;
 use aim_chart::CompanyInfo;
-use aim_data::get_company_info;
-use aim_data::get_quote;
+use aim_data::{candle_store, OHLCData, SharedProvider};
+use chrono::Utc;
 use slint::ComponentHandle;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 use std::{sync::Arc, time::Duration};
 use tokio::sync::Mutex;
 
@@ -14,18 +17,29 @@ use tokio::sync::Mutex;
 pub async fn spawn_stock_update_task(
     chart: Arc<Mutex<ChartMetaData>>,
     ui: &AppWindow,
+    provider: SharedProvider,
 ) -> Vec<TaskHandle> {
     let chart_clone = Arc::clone(&chart);
 
     let mut handles = Vec::new();
-    handles.push(spawn_current_stock_data_task(chart_clone.clone(), ui).await);
-    handles.push(spawn_new_stock_data_task(chart, ui).await);
+    handles
+        .push(spawn_current_stock_data_task(chart_clone.clone(), ui, Arc::clone(&provider)).await);
+    handles.push(spawn_new_stock_data_task(chart_clone.clone(), ui, Arc::clone(&provider)).await);
+    handles.push(spawn_grid_data_task(chart_clone.clone(), ui, Arc::clone(&provider)).await);
+    handles.push(spawn_comparison_data_task(chart, ui, provider).await);
     handles
 }
 
+/// How often the closes of the compared symbols are asked for again
+const COMPARISON_REFRESH: Duration = Duration::from_secs(10);
+
+/// Wait before asking again for a chart shown without candles
+const MISSING_CHART_RETRY: Duration = Duration::from_secs(10);
+
 pub async fn spawn_new_stock_data_task(
     chart: Arc<Mutex<ChartMetaData>>,
     ui: &AppWindow,
+    provider: SharedProvider,
 ) -> TaskHandle {
     let ui_handle = ui.as_weak();
     let chart_clone = Arc::clone(&chart);
@@ -52,6 +66,7 @@ pub async fn spawn_new_stock_data_task(
             }
 
             let chart_clone = Arc::clone(&chart_clone);
+            let provider = Arc::clone(&provider);
             let _ = ui_handle.upgrade_in_event_loop(move |ui| {
                 let mut ui_data = ui.get_ui_data();
 
@@ -60,7 +75,7 @@ pub async fn spawn_new_stock_data_task(
                     ui.set_is_chart_in_update(true);
                     ui_data.is_new_stock = false;
                     ui_data.is_new_time_frame = false;
-                    let time_frame = ui_data.clone().time_frame;
+                    let time_frame = ui_data.time_frame.to_string();
                     ui.set_ui_data(ui_data);
                     let stock = ui.get_current_stock().symbol;
 
@@ -68,69 +83,11 @@ pub async fn spawn_new_stock_data_task(
                     let chart_clone = Arc::clone(&chart_clone);
                     let ui_handle = ui.as_weak();
                     tokio::spawn(async move {
-                        // Check if chart with the same stock name already exists first
-                        let chart_exists = {
-                            let charts = chart_clone.lock().await;
-                            charts
-                                .data
-                                .iter()
-                                .any(|chart| chart.stock_name == stock.to_uppercase())
-                        };
-
-                        // If chart exists, just return without creating a new one
-                        if chart_exists {
-                            let _ = ui_handle.upgrade_in_event_loop(move |ui| {
-                                ui.set_is_chart_in_update(false);
-                            });
-                            return;
-                        }
-
-                        let stock_data = if let Ok(data) =
-                            get_quote(&[&stock], interval_to_constant(&time_frame), None, None)
-                                .await
-                        {
-                            data
-                        } else {
-                            log::error!("Failed to fetch stock data for {stock}");
-                            let _ = ui_handle.upgrade_in_event_loop(move |ui| {
-                                ui.set_is_chart_in_update(false);
-                            });
-                            return;
-                        };
-
-                        if !stock_data.0.is_empty() {
-                            let company_info =
-                                if let Ok(stock_info) = get_company_info(&stock).await {
-                                    CompanyInfo {
-                                        roe: stock_info.data.company_financial_ratio.ratio[0]
-                                            .roe
-                                            .unwrap_or(0.0),
-                                        roa: stock_info.data.company_financial_ratio.ratio[0]
-                                            .roa
-                                            .unwrap_or(0.0),
-                                        pe: stock_info.data.company_financial_ratio.ratio[0]
-                                            .pe
-                                            .unwrap_or(0.0),
-                                        pb: stock_info.data.company_financial_ratio.ratio[0]
-                                            .pb
-                                            .unwrap_or(0.0),
-                                        eps: stock_info.data.company_financial_ratio.ratio[0]
-                                            .eps
-                                            .unwrap_or(0.0),
-                                    }
-                                } else {
-                                    CompanyInfo::default()
-                                };
-                            let mut charts = chart_clone.lock().await;
-                            charts.data.push(Chart::new_default(
-                                stock.to_uppercase(),
-                                stock_data.0[0].clone(),
-                                company_info,
-                            ));
-                        }
-                        let _ = ui_handle.upgrade_in_event_loop(move |ui| {
-                            ui.set_is_chart_in_update(false);
-                        });
+                        // The active cell of the grid shows the new selection
+                        let key = ChartKey::new(&stock, &time_frame);
+                        chart_clone.lock().await.grid.show(&stock, &time_frame);
+                        load_chart(&chart_clone, &provider, &key).await;
+                        let _ = ui_handle.upgrade_in_event_loop(|ui| finish_chart_update(&ui));
                     });
                 }
             });
@@ -145,6 +102,7 @@ pub async fn spawn_new_stock_data_task(
 pub async fn spawn_current_stock_data_task(
     chart: Arc<Mutex<ChartMetaData>>,
     ui: &AppWindow,
+    provider: SharedProvider,
 ) -> TaskHandle {
     let ui_handle = ui.as_weak();
     let chart_clone = Arc::clone(&chart);
@@ -159,6 +117,10 @@ pub async fn spawn_current_stock_data_task(
     // Task 2: Update existing charts with latest data
     tokio::spawn(async move {
         let mut task_status = crate::tasks::task_manager::TaskStatus::Running;
+        // Only one page of older history is requested at a time
+        let loading_history = Arc::new(AtomicBool::new(false));
+        // The chart shown could not be loaded yet, e.g. at an offline start
+        let loading_chart = Arc::new(AtomicBool::new(false));
         loop {
             if let Ok(status) = rx.try_recv() {
                 if task_status != status {
@@ -177,65 +139,84 @@ pub async fn spawn_current_stock_data_task(
             //     continue;
             // }
             let chart_clone = Arc::clone(&chart_clone);
+            let provider = Arc::clone(&provider);
+            let loading_history = Arc::clone(&loading_history);
+            let loading_chart = Arc::clone(&loading_chart);
             let _ = ui_handle.upgrade_in_event_loop(move |ui| {
+                // A new selection is loaded by the new stock task
+                let in_update = ui.get_is_chart_in_update();
                 let stock = ui.get_current_stock().symbol;
+                let time_frame = ui.get_ui_data().time_frame.to_string();
+                let ui_handle = ui.as_weak();
 
                 tokio::spawn(async move {
-                    // Check if chart with the same stock name already exists
-                    let chart_exists = {
-                        let charts = chart_clone.lock().await;
-                        charts
-                            .data
-                            .iter()
-                            .any(|chart| chart.stock_name == stock.to_uppercase())
-                    };
-
-                    // If chart exists, update it with latest data
-                    if chart_exists {
-                        if let Ok(chart_data_vec) =
-                            get_quote(&[&stock], "ONE_DAY", None, None).await
+                    // If the displayed chart exists, fetch only the bars from its last
+                    // candle on
+                    let key = ChartKey::new(&stock, &time_frame);
+                    let interval = interval_to_constant(&time_frame);
+                    let ranges = chart_clone
+                        .lock()
+                        .await
+                        .get(&key)
+                        .map(|chart| (chart.next_fetch_start(), chart.older_history_range()));
+                    if let Some((next_start, older_range)) = ranges {
+                        let now = Utc::now();
+                        let start =
+                            next_start.unwrap_or_else(|| Chart::history_start(&time_frame, now));
+                        if let Ok(bars) = candle_store()
+                            .fetch(provider.as_ref(), &stock, interval, start, now)
+                            .await
                         {
                             let mut charts = chart_clone.lock().await;
+                            if let Some(chart) = charts.get_mut(&key) {
+                                chart.merge_candle_data(OHLCData::from_candlesticks(&stock, &bars));
+                            }
+                        }
 
-                            // Update chart data for each tracked stock
-                            for chart in charts.data.iter_mut() {
-                                if let Some(updated_data) = chart_data_vec
-                                    .0
-                                    .iter()
-                                    .find(|data| data.symbol == chart.stock_name)
+                        // Lazy-load older history once the user pans past the first candle
+                        if let Some((start, end)) = older_range {
+                            if !loading_history.swap(true, Ordering::AcqRel) {
+                                if let Ok(bars) = candle_store()
+                                    .fetch(provider.as_ref(), &stock, interval, start, end)
+                                    .await
                                 {
-                                    chart.update_candle_data(updated_data.clone());
+                                    if let Some(chart) = chart_clone.lock().await.get_mut(&key) {
+                                        if bars.is_empty() {
+                                            chart.history_exhausted = true;
+                                        } else {
+                                            chart.prepend_candle_data(OHLCData::from_candlesticks(
+                                                &stock, &bars,
+                                            ));
+                                        }
+                                    }
+                                    let _ =
+                                        ui_handle.upgrade_in_event_loop(|ui| request_render(&ui));
                                 }
+                                loading_history.store(false, Ordering::Release);
                             }
                         }
-                        if let Ok(stock_info) = get_company_info(&stock).await {
-                            let company_info = CompanyInfo {
-                                roe: stock_info.data.company_financial_ratio.ratio[0]
-                                    .roe
-                                    .unwrap_or(0.0),
-                                roa: stock_info.data.company_financial_ratio.ratio[0]
-                                    .roa
-                                    .unwrap_or(0.0),
-                                pe: stock_info.data.company_financial_ratio.ratio[0]
-                                    .pe
-                                    .unwrap_or(0.0),
-                                pb: stock_info.data.company_financial_ratio.ratio[0]
-                                    .pb
-                                    .unwrap_or(0.0),
-                                eps: stock_info.data.company_financial_ratio.ratio[0]
-                                    .eps
-                                    .unwrap_or(0.0),
-                            };
-
+                        if let Some(company_info) = provider
+                            .get_company_info(&stock, "Y")
+                            .await
+                            .ok()
+                            .and_then(|info| info.latest_ratio().map(CompanyInfo::from))
+                        {
                             let mut charts = chart_clone.lock().await;
 
-                            // Update chart data for each tracked stock
-                            for chart in charts.data.iter_mut() {
-                                if stock.to_uppercase() == chart.stock_name {
+                            // Every timeframe of the symbol shows the same company info
+                            for chart in charts.charts_mut() {
+                                if key.symbol == chart.stock_name {
                                     chart.update_company_info(company_info.clone());
                                 }
                             }
                         }
+                    } else if !in_update && !loading_chart.swap(true, Ordering::AcqRel) {
+                        if load_chart(&chart_clone, &provider, &key).await {
+                            let _ = ui_handle.upgrade_in_event_loop(|ui| request_render(&ui));
+                        } else {
+                            tokio::time::sleep(MISSING_CHART_RETRY).await;
+                        }
+                        loading_chart.store(false, Ordering::Release);
                     }
                 });
             });
@@ -245,3 +226,222 @@ pub async fn spawn_current_stock_data_task(
 
     task_handle
 }
+
+/// Spawns a task loading the charts of the grid cells the user is not on
+pub async fn spawn_grid_data_task(
+    chart: Arc<Mutex<ChartMetaData>>,
+    ui: &AppWindow,
+    provider: SharedProvider,
+) -> TaskHandle {
+    let ui_handle = ui.as_weak();
+    let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+    let task_handle = register_task(
+        "chart.stock_update.grid".to_string(),
+        tx,
+        "Chart Grid Data Task".to_string(),
+    )
+    .await;
+
+    tokio::spawn(async move {
+        let mut task_status = crate::tasks::task_manager::TaskStatus::Running;
+        // Cells whose symbol has no candles are not asked for again
+        let mut failed = HashSet::new();
+        loop {
+            if let Ok(status) = rx.try_recv() {
+                if task_status != status {
+                    log::info!("Chart grid task status changed to: {:?}", status);
+                    task_status = status;
+                }
+            }
+            if task_status != crate::tasks::task_manager::TaskStatus::Running {
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                continue;
+            }
+
+            // The active cell is loaded by the new stock task
+            let missing: Vec<ChartKey> = {
+                let charts = chart.lock().await;
+                let grid = &charts.grid;
+                grid.visible()
+                    .iter()
+                    .enumerate()
+                    .filter(|&(index, _)| index != grid.active)
+                    .map(|(_, cell)| ChartKey::new(&cell.symbol, &cell.time_frame))
+                    .filter(|key| !charts.contains(key) && !failed.contains(key))
+                    .collect()
+            };
+            let mut loaded = false;
+            for key in missing {
+                if load_chart(&chart, &provider, &key).await {
+                    loaded = true;
+                } else {
+                    failed.insert(key);
+                }
+            }
+            if loaded {
+                let _ = ui_handle.upgrade_in_event_loop(|ui| request_render(&ui));
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    });
+
+    task_handle
+}
+
+/// Spawns a task loading the closes of the symbols compared with the chart the
+/// user is on, through the quote path of the charts
+pub async fn spawn_comparison_data_task(
+    chart: Arc<Mutex<ChartMetaData>>,
+    ui: &AppWindow,
+    provider: SharedProvider,
+) -> TaskHandle {
+    let ui_handle = ui.as_weak();
+    let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+    let task_handle = register_task(
+        "chart.stock_update.compare".to_string(),
+        tx,
+        "Chart Comparison Data Task".to_string(),
+    )
+    .await;
+
+    tokio::spawn(async move {
+        let mut task_status = crate::tasks::task_manager::TaskStatus::Running;
+        // Last request for each compared symbol of each chart
+        let mut fetched: HashMap<(ChartKey, String), Instant> = HashMap::new();
+        loop {
+            if let Ok(status) = rx.try_recv() {
+                if task_status != status {
+                    log::info!("Chart comparison task status changed to: {:?}", status);
+                    task_status = status;
+                }
+            }
+            if task_status != crate::tasks::task_manager::TaskStatus::Running {
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                continue;
+            }
+
+            // Symbols added since the last round or not asked for in a while,
+            // from the first candle of the chart on
+            let due: Vec<(ChartKey, String, _)> = {
+                let charts = chart.lock().await;
+                let cell = charts.grid.active_cell();
+                let key = ChartKey::new(&cell.symbol, &cell.time_frame);
+                match charts
+                    .get(&key)
+                    .and_then(|c| Some((c, c.comparison_start()?)))
+                {
+                    Some((active, start)) => active
+                        .comparisons
+                        .iter()
+                        .map(|comparison| (key.clone(), comparison.symbol.clone(), start))
+                        .filter(|(key, symbol, _)| {
+                            fetched
+                                .get(&(key.clone(), symbol.clone()))
+                                .is_none_or(|at| at.elapsed() >= COMPARISON_REFRESH)
+                        })
+                        .collect(),
+                    None => Vec::new(),
+                }
+            };
+            let mut loaded = false;
+            for (key, symbol, start) in due {
+                fetched.insert((key.clone(), symbol.clone()), Instant::now());
+                let interval = interval_to_constant(&key.time_frame);
+                match candle_store()
+                    .fetch(provider.as_ref(), &symbol, interval, start, Utc::now())
+                    .await
+                {
+                    Ok(bars) if !bars.is_empty() => {
+                        if let Some(chart) = chart.lock().await.get_mut(&key) {
+                            chart.set_comparison(
+                                &symbol,
+                                OHLCData::from_candlesticks(&symbol, &bars),
+                            );
+                            loaded = true;
+                        }
+                    }
+                    Ok(_) => log::warn!("No bars of {symbol} to compare with {}", key.symbol),
+                    Err(e) => log::error!("Failed to fetch {symbol} to compare: {e}"),
+                }
+            }
+            if loaded {
+                let _ = ui_handle.upgrade_in_event_loop(|ui| request_render(&ui));
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    });
+
+    task_handle
+}
+
+/// Fetch the candles of `key` and add its chart unless it is loaded already,
+/// false if the chart is still missing
+async fn load_chart(
+    chart: &Arc<Mutex<ChartMetaData>>,
+    provider: &SharedProvider,
+    key: &ChartKey,
+) -> bool {
+    // Charts already loaded for this symbol and timeframe are shown straight
+    // from the cache
+    let cached_info = {
+        let charts = chart.lock().await;
+        if charts.contains(key) {
+            return true;
+        }
+        charts
+            .find_symbol(&key.symbol)
+            .map(|chart| chart.company_info.clone())
+    };
+
+    let now = Utc::now();
+    let bars = match candle_store()
+        .fetch(
+            provider.as_ref(),
+            &key.symbol,
+            interval_to_constant(&key.time_frame),
+            Chart::history_start(&key.time_frame, now),
+            now,
+        )
+        .await
+    {
+        Ok(bars) => bars,
+        Err(e) => {
+            log::error!("Failed to fetch stock data for {}: {e}", key.symbol);
+            return false;
+        }
+    };
+    if bars.is_empty() {
+        return false;
+    }
+
+    // Other timeframes of the symbol already have its company info
+    let company_info = match cached_info {
+        Some(info) => info,
+        None => provider
+            .get_company_info(&key.symbol, "Y")
+            .await
+            .ok()
+            .and_then(|info| info.latest_ratio().map(CompanyInfo::from))
+            .unwrap_or_default(),
+    };
+    chart.lock().await.insert(Chart::new_default(
+        key.symbol.clone(),
+        key.time_frame.clone(),
+        OHLCData::from_candlesticks(&key.symbol, &bars),
+        company_info,
+    ));
+    true
+}
+
+/// Clear the loading state and have the chart task render the new selection
+fn finish_chart_update(ui: &AppWindow) {
+    ui.set_is_chart_in_update(false);
+    request_render(ui);
+}
+
+/// Have the chart task render the displayed chart again
+fn request_render(ui: &AppWindow) {
+    let mut ui_data = ui.get_ui_data();
+    ui_data.is_in_update = true;
+    ui.set_ui_data(ui_data);
+}
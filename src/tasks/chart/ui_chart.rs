@@ -1,11 +1,18 @@
-use crate::slint_generatedAppWindow::AppWindow;
+use crate::slint_generatedAppWindow::{AppWindow, ChartCell};
 use crate::tasks::task_manager::{register_task, TaskHandle};
-use crate::tasks::ChartMetaData;
+use crate::tasks::{ChartKey, ChartMetaData};
+use aim_chart::grid::{ChartGrid, GridLayout, Links};
 use aim_chart::UiData;
-use slint::ComponentHandle;
+use aim_data::aim::aim_client;
+use chrono::{DateTime, Utc};
+use slint::{ComponentHandle, Model, ModelRc, SharedString, VecModel};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 
+/// How often the cells the mouse is not over show the latest candles
+const CELL_REFRESH: Duration = Duration::from_secs(1);
+
 /// Converts UI data from Slint to chart rendering format
 fn convert_ui_data_to_chart_data(ui_data: crate::slint_generatedAppWindow::UiData) -> UiData {
     // Map Slint's MouseType to aim_chart's MouseType explicitly to avoid requiring a From/Into impl.
@@ -24,6 +31,16 @@ fn convert_ui_data_to_chart_data(ui_data: crate::slint_generatedAppWindow::UiDat
         crate::slint_generatedAppWindow::MouseType::HorizontalLine => {
             aim_chart::MouseType::HorizontalLine
         }
+        crate::slint_generatedAppWindow::MouseType::FibRetracement => {
+            aim_chart::MouseType::FibRetracement
+        }
+        crate::slint_generatedAppWindow::MouseType::FibExtension => {
+            aim_chart::MouseType::FibExtension
+        }
+        crate::slint_generatedAppWindow::MouseType::Channel => aim_chart::MouseType::Channel,
+        crate::slint_generatedAppWindow::MouseType::Pitchfork => aim_chart::MouseType::Pitchfork,
+        crate::slint_generatedAppWindow::MouseType::PriceRange => aim_chart::MouseType::PriceRange,
+        crate::slint_generatedAppWindow::MouseType::DateRange => aim_chart::MouseType::DateRange,
     };
 
     UiData {
@@ -34,6 +51,7 @@ fn convert_ui_data_to_chart_data(ui_data: crate::slint_generatedAppWindow::UiDat
         is_in_object: ui_data.is_in_object,
         is_clean: ui_data.is_clean,
         is_undo: ui_data.is_undo,
+        is_redo: ui_data.is_redo,
         is_release: ui_data.is_release,
         move_x: ui_data.move_x,
         move_y: ui_data.move_y,
@@ -47,6 +65,101 @@ fn convert_ui_data_to_chart_data(ui_data: crate::slint_generatedAppWindow::UiDat
         is_new_time_frame: ui_data.is_new_time_frame,
         is_new_stock: ui_data.is_new_stock,
         color: ui_data.color,
+        text: ui_data.text.to_string(),
+        font_size: ui_data.font_size,
+        text_background: ui_data.text_background,
+        is_delete: ui_data.is_delete,
+        is_restyle: ui_data.is_restyle,
+        is_multi_select: ui_data.is_multi_select,
+        line_style: ui_data.line_style,
+        line_width: ui_data.line_width,
+        scale_mode: ui_data.scale_mode,
+        is_rescale: ui_data.is_rescale,
+        chart_style: ui_data.chart_style,
+        box_size: ui_data.box_size,
+        is_new_chart_style: ui_data.is_new_chart_style,
+    }
+}
+
+/// Cells of the grid the mouse is not over, as last rendered
+#[derive(Default)]
+struct GridFrame {
+    /// Grid, chart area and linked crosshair time they were rendered for
+    shown: Option<(ChartGrid, (i32, i32), Option<DateTime<Utc>>)>,
+    rendered_at: Option<Instant>,
+}
+
+impl GridFrame {
+    fn is_due(&self) -> bool {
+        self.rendered_at
+            .is_none_or(|rendered_at| rendered_at.elapsed() >= CELL_REFRESH)
+    }
+
+    /// Render the cells other than the active one if the grid, the chart area
+    /// or the linked time changed, if they are due for fresh candles, or if
+    /// `force`d. The active cell comes without an image, it shows the chart
+    /// image.
+    fn render(
+        &mut self,
+        charts: &mut ChartMetaData,
+        area: (f32, f32),
+        linked_time: Option<DateTime<Utc>>,
+        force: bool,
+    ) -> Option<Vec<ChartCell>> {
+        let grid = charts.grid.clone();
+        let shown = (grid.clone(), (area.0 as i32, area.1 as i32), linked_time);
+        if !force && !self.is_due() && self.shown.as_ref() == Some(&shown) {
+            return None;
+        }
+        let active = grid.active_cell();
+        let active_key = ChartKey::new(&active.symbol, &active.time_frame);
+        let cells = grid
+            .visible()
+            .iter()
+            .zip(grid.layout.rects())
+            .enumerate()
+            .map(|(index, (cell, (x, y, width, height)))| {
+                let is_active = index == grid.active;
+                let image = if is_active {
+                    slint::Image::default()
+                } else {
+                    let (cell_width, cell_height) = grid.layout.cell_size(index, area.0, area.1);
+                    let key = ChartKey::new(&cell.symbol, &cell.time_frame);
+                    charts
+                        .linked_cell(&key, &active_key)
+                        .map(|chart| chart.render_cell(cell_width, cell_height, linked_time))
+                        .unwrap_or_default()
+                };
+                ChartCell {
+                    symbol: cell.symbol.as_str().into(),
+                    time_frame: cell.time_frame.as_str().into(),
+                    image,
+                    active: is_active,
+                    x,
+                    y,
+                    width,
+                    height,
+                }
+            })
+            .collect();
+        self.shown = Some(shown);
+        self.rendered_at = Some(Instant::now());
+        Some(cells)
+    }
+}
+
+/// Show the cells of the grid, updating the rows in place so the active chart
+/// keeps its state while the other cells refresh
+fn set_chart_cells(ui: &AppWindow, cells: Vec<ChartCell>) {
+    let model = ui.get_chart_cells();
+    if model.row_count() != cells.len() {
+        ui.set_chart_cells(ModelRc::new(VecModel::from(cells)));
+        return;
+    }
+    for (index, cell) in cells.into_iter().enumerate() {
+        if model.row_data(index).as_ref() != Some(&cell) {
+            model.set_row_data(index, cell);
+        }
     }
 }
 
@@ -62,6 +175,7 @@ pub async fn spawn_ui_chart_task(chart: Arc<Mutex<ChartMetaData>>, ui: &AppWindo
     )
     .await;
 
+    let grid_frame = Arc::new(std::sync::Mutex::new(GridFrame::default()));
     tokio::task::spawn_blocking(move || {
         // This closure runs in a blocking context to avoid blocking the async runtime
         let mut task_status = crate::tasks::task_manager::TaskStatus::Running;
@@ -77,12 +191,29 @@ pub async fn spawn_ui_chart_task(chart: Arc<Mutex<ChartMetaData>>, ui: &AppWindo
                 continue;
             }
             let chart_clone = Arc::clone(&chart_clone);
+            let grid_frame = Arc::clone(&grid_frame);
             let _ = ui_handle.upgrade_in_event_loop(move |ui| {
                 let mut ui_data = ui.get_ui_data();
                 let previous_ui_data = ui.get_previous_ui_data();
                 let stock_name = ui.get_current_stock().symbol;
-                let height = ui.invoke_get_chart_height();
-                let width = ui.invoke_get_chart_width();
+                // The mouse works on the active cell of the grid
+                let area = (ui.invoke_get_chart_width(), ui.invoke_get_chart_height());
+                let cells = ui.get_chart_cells();
+                let (cell_width, cell_height) = cells
+                    .iter()
+                    .find(|cell| cell.active)
+                    .map_or((1.0, 1.0), |cell| (cell.width, cell.height));
+                let height = area.1 * cell_height;
+                let width = area.0 * cell_width;
+                // Drawings and candles of the other cells may change with those
+                // of the active one
+                let force_cells = ui_data.is_in_update
+                    || (ui_data.is_release && !previous_ui_data.is_release)
+                    || ui_data.is_clean
+                    || ui_data.is_undo
+                    || ui_data.is_redo
+                    || ui_data.is_delete
+                    || ui_data.is_restyle;
 
                 if previous_ui_data != ui_data
                     || stock_name != ui_data.ticker
@@ -94,31 +225,93 @@ pub async fn spawn_ui_chart_task(chart: Arc<Mutex<ChartMetaData>>, ui: &AppWindo
                     ui_data.width = width as i32;
 
                     // Render the chart plot
-                    let (image, is_in_object) = {
+                    let (image, is_in_object, edited_text, scale_mode, style, compared, cells) = {
                         ui_data.is_in_update = false;
                         log::info!("Rendering chart for {stock_name}");
                         tokio::task::block_in_place(|| {
                             let mut charts = chart_clone.blocking_lock();
-                            if let Some(chart) = charts
-                                .data
-                                .iter_mut()
-                                .find(|chart| stock_name == chart.stock_name)
-                            {
+                            let key = ChartKey::new(&stock_name, &ui_data.time_frame);
+                            let link_crosshair = charts.grid.links.crosshair;
+                            let (
+                                image,
+                                is_in_object,
+                                edited_text,
+                                scale_mode,
+                                style,
+                                compared,
+                                linked_time,
+                            ) = if let Some(chart) = charts.activate(&key) {
                                 log::info!("Found existing chart for {stock_name}, rendering...");
                                 ui_data.ticker = stock_name.clone();
                                 let chart_ui_data = convert_ui_data_to_chart_data(ui_data.clone());
-                                chart.render_plot(chart_ui_data)
+                                let (image, is_in_object) = chart.render_plot(chart_ui_data);
+                                let scale_mode = chart.scale_mode as i32;
+                                let style = (chart.style as i32, chart.box_size);
+                                let linked_time = chart.cursor_time().filter(|_| link_crosshair);
+                                let compared = chart
+                                    .comparisons
+                                    .iter()
+                                    .map(|comparison| comparison.label.clone())
+                                    .collect();
+                                (
+                                    image,
+                                    is_in_object,
+                                    chart.edited_text().cloned(),
+                                    scale_mode,
+                                    style,
+                                    compared,
+                                    linked_time,
+                                )
                             } else {
                                 log::warn!("No chart found for {stock_name}, using default");
-                                (slint::Image::default(), false)
-                            }
+                                (
+                                    slint::Image::default(),
+                                    false,
+                                    None,
+                                    ui_data.scale_mode,
+                                    (ui_data.chart_style, ui_data.box_size),
+                                    Vec::new(),
+                                    None,
+                                )
+                            };
+                            let cells = grid_frame.lock().unwrap().render(
+                                &mut charts,
+                                area,
+                                linked_time,
+                                force_cells,
+                            );
+                            (
+                                image,
+                                is_in_object,
+                                edited_text,
+                                scale_mode,
+                                style,
+                                compared,
+                                cells,
+                            )
                         })
                     };
+                    if let Some(cells) = cells {
+                        set_chart_cells(&ui, cells);
+                    }
 
                     // Update UI with new chart image
                     ui.set_candle_stick_image(image);
                     ui_data.is_in_object = is_in_object;
 
+                    // The text box follows the note being edited
+                    ui_data.is_edit_text = edited_text.is_some();
+                    if let Some(note) = edited_text {
+                        ui_data.text = note.text.into();
+                        ui_data.font_size = note.font_size as i32;
+                        ui_data.text_background = note.background.is_some();
+                    }
+
+                    // The scale and style pickers show those of the chart shown
+                    ui_data.scale_mode = scale_mode;
+                    (ui_data.chart_style, ui_data.box_size) = style;
+                    show_comparisons(&ui, compared);
+
                     // Reset UI state flags
                     if ui_data.is_clean {
                         ui_data.is_clean = false;
@@ -126,6 +319,13 @@ pub async fn spawn_ui_chart_task(chart: Arc<Mutex<ChartMetaData>>, ui: &AppWindo
                     if ui_data.is_undo {
                         ui_data.is_undo = false;
                     }
+                    if ui_data.is_redo {
+                        ui_data.is_redo = false;
+                    }
+                    ui_data.is_delete = false;
+                    ui_data.is_restyle = false;
+                    ui_data.is_rescale = false;
+                    ui_data.is_new_chart_style = false;
                     if ui_data.is_release {
                         ui_data.move_x = 0;
                         ui_data.move_y = 0;
@@ -133,6 +333,17 @@ pub async fn spawn_ui_chart_task(chart: Arc<Mutex<ChartMetaData>>, ui: &AppWindo
                     ui_data.zoom = 0;
                     ui.set_ui_data(ui_data.clone());
                     ui.set_previous_ui_data(ui_data);
+                } else if cells.row_count() > 1 && grid_frame.lock().unwrap().is_due() {
+                    // The other cells show the latest candles while the mouse rests
+                    let cells = tokio::task::block_in_place(|| {
+                        let mut charts = chart_clone.blocking_lock();
+                        let mut grid_frame = grid_frame.lock().unwrap();
+                        let linked_time = grid_frame.shown.as_ref().and_then(|shown| shown.2);
+                        grid_frame.render(&mut charts, area, linked_time, false)
+                    });
+                    if let Some(cells) = cells {
+                        set_chart_cells(&ui, cells);
+                    }
                 }
             });
             std::thread::sleep(std::time::Duration::from_millis(5));
@@ -141,3 +352,174 @@ pub async fn spawn_ui_chart_task(chart: Arc<Mutex<ChartMetaData>>, ui: &AppWindo
 
     task_handle
 }
+
+/// Set up the callbacks of the chart grid and show the saved grid
+pub fn register_chart_grid_handlers(ui: &AppWindow, chart: Arc<Mutex<ChartMetaData>>) {
+    let ui_handle = ui.as_weak();
+    let chart_clone = Arc::clone(&chart);
+    ui.on_set_chart_layout(move |layout| {
+        let Some(ui) = ui_handle.upgrade() else {
+            return;
+        };
+        let layout = GridLayout::from_u8(layout.clamp(0, u8::MAX as i32) as u8);
+        let grid = tokio::task::block_in_place(|| {
+            let mut charts = chart_clone.blocking_lock();
+            charts.grid.set_layout(layout);
+            charts.grid.clone()
+        });
+        show_grid(&ui, &grid);
+    });
+
+    let ui_handle = ui.as_weak();
+    let chart_clone = Arc::clone(&chart);
+    ui.on_select_chart_cell(move |index| {
+        let Some(ui) = ui_handle.upgrade() else {
+            return;
+        };
+        let grid = tokio::task::block_in_place(|| {
+            let mut charts = chart_clone.blocking_lock();
+            let index = usize::try_from(index).ok()?;
+            charts.grid.select(index).then(|| charts.grid.clone())
+        });
+        if let Some(grid) = grid {
+            show_grid(&ui, &grid);
+        }
+    });
+
+    let ui_handle = ui.as_weak();
+    let chart_clone = Arc::clone(&chart);
+    ui.on_set_chart_links(move |crosshair, symbol, drawings| {
+        let Some(ui) = ui_handle.upgrade() else {
+            return;
+        };
+        let grid = tokio::task::block_in_place(|| {
+            let mut charts = chart_clone.blocking_lock();
+            charts.grid.links = Links {
+                crosshair,
+                symbol,
+                drawings,
+            };
+            // Linking the symbols shows the symbol of the active cell everywhere
+            let cell = charts.grid.active_cell().clone();
+            charts.grid.show(&cell.symbol, &cell.time_frame);
+            charts.grid.clone()
+        });
+        show_grid(&ui, &grid);
+    });
+
+    let ui_handle = ui.as_weak();
+    tokio::spawn(async move {
+        let grid = chart.lock().await.grid.clone();
+        let _ = ui_handle.upgrade_in_event_loop(move |ui| show_grid(&ui, &grid));
+    });
+}
+
+/// Set up the callbacks adding and removing the symbols compared with the chart
+/// shown, and list the indices to compare with: VNINDEX, VN30 and the sector
+/// indices
+pub fn register_chart_compare_handlers(ui: &AppWindow, chart: Arc<Mutex<ChartMetaData>>) {
+    let ui_handle = ui.as_weak();
+    let chart_clone = Arc::clone(&chart);
+    ui.on_add_comparison(move |symbol, label| {
+        let Some(ui) = ui_handle.upgrade() else {
+            return;
+        };
+        let key = ChartKey::new(&ui.get_current_stock().symbol, &ui.get_ui_data().time_frame);
+        let added = tokio::task::block_in_place(|| {
+            let mut charts = chart_clone.blocking_lock();
+            charts
+                .get_mut(&key)
+                .is_some_and(|chart| chart.add_comparison(&symbol, &label))
+        });
+        if added {
+            let mut ui_data = ui.get_ui_data();
+            ui_data.is_in_update = true;
+            ui.set_ui_data(ui_data);
+        }
+    });
+
+    let ui_handle = ui.as_weak();
+    ui.on_remove_comparison(move |index| {
+        let Some(ui) = ui_handle.upgrade() else {
+            return;
+        };
+        let key = ChartKey::new(&ui.get_current_stock().symbol, &ui.get_ui_data().time_frame);
+        let removed = tokio::task::block_in_place(|| {
+            let mut charts = chart.blocking_lock();
+            let chart = charts.get_mut(&key)?;
+            let symbol = chart
+                .comparisons
+                .get(usize::try_from(index).ok()?)?
+                .symbol
+                .clone();
+            Some(chart.remove_comparison(&symbol))
+        });
+        if removed == Some(true) {
+            let mut ui_data = ui.get_ui_data();
+            ui_data.is_in_update = true;
+            ui.set_ui_data(ui_data);
+        }
+    });
+
+    let ui_handle = ui.as_weak();
+    tokio::spawn(async move {
+        // The first choice only asks to pick one
+        let mut choices = vec![
+            (String::new(), "So sánh".to_string()),
+            ("VNINDEX".to_string(), "VNINDEX".to_string()),
+            ("VN30".to_string(), "VN30".to_string()),
+        ];
+        match aim_client().fetch_icb_index_data().await {
+            Ok(indices) => choices.extend(
+                indices
+                    .into_iter()
+                    .map(|index| (index.icb_code, index.icb_name)),
+            ),
+            Err(e) => log::error!("Failed to fetch ICB indices to compare with: {e}"),
+        }
+        let _ = ui_handle.upgrade_in_event_loop(move |ui| {
+            let (symbols, labels): (Vec<SharedString>, Vec<SharedString>) = choices
+                .into_iter()
+                .map(|(symbol, label)| (symbol.into(), label.into()))
+                .unzip();
+            ui.set_compare_symbols(ModelRc::new(VecModel::from(symbols)));
+            ui.set_compare_choices(ModelRc::new(VecModel::from(labels)));
+        });
+    });
+}
+
+/// List the symbols compared with the chart shown, the model is kept while
+/// they stay the same
+fn show_comparisons(ui: &AppWindow, labels: Vec<String>) {
+    let shown = ui.get_comparisons();
+    if shown
+        .iter()
+        .map(|label| label.to_string())
+        .eq(labels.iter().cloned())
+    {
+        return;
+    }
+    let labels: Vec<SharedString> = labels.into_iter().map(SharedString::from).collect();
+    ui.set_comparisons(ModelRc::new(VecModel::from(labels)));
+}
+
+/// Show the layout and links of `grid` and have the chart page show its active
+/// cell
+fn show_grid(ui: &AppWindow, grid: &ChartGrid) {
+    ui.set_chart_layout(grid.layout as i32);
+    ui.set_link_crosshair(grid.links.crosshair);
+    ui.set_link_symbol(grid.links.symbol);
+    ui.set_link_drawings(grid.links.drawings);
+
+    let cell = grid.active_cell();
+    let mut stock = ui.get_current_stock();
+    let mut ui_data = ui.get_ui_data();
+    if stock.symbol != cell.symbol.as_str() || ui_data.time_frame != cell.time_frame.as_str() {
+        stock.symbol = cell.symbol.as_str().into();
+        ui.set_current_stock(stock);
+        ui_data.time_frame = cell.time_frame.as_str().into();
+        ui_data.is_new_stock = true;
+    }
+    ui_data.is_in_update = true;
+    ui.set_ui_data(ui_data);
+}
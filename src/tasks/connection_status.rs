@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use aim_data::{executor, CircuitState};
+use slint::{ComponentHandle, ModelRc, VecModel};
+
+use crate::slint_generatedAppWindow::{AppWindow, ConnectionStatus};
+use crate::tasks::task_manager::{register_task, TaskHandle, TaskStatus};
+
+/// Spawns a task that mirrors the circuit breaker state of every data host
+/// into the sidebar connection indicator
+pub async fn spawn_connection_status_task(ui: &AppWindow) -> TaskHandle {
+    let ui_handle = ui.as_weak();
+    let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+    let task_handle = register_task(
+        "system.connection_status".to_string(),
+        tx,
+        "Connection Status".to_string(),
+    )
+    .await;
+
+    tokio::spawn(async move {
+        let mut task_status = TaskStatus::Running;
+        let mut previous = Vec::new();
+        loop {
+            if let Ok(status) = rx.try_recv() {
+                if task_status != status {
+                    log::info!("Connection status task status changed to: {:?}", status);
+                    task_status = status;
+                }
+            }
+            if task_status != TaskStatus::Running {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+
+            let status: Vec<(String, CircuitState, u32, String)> = executor()
+                .health()
+                .into_iter()
+                .map(|h| {
+                    (
+                        h.host,
+                        h.state,
+                        h.consecutive_failures,
+                        h.last_error.unwrap_or_default(),
+                    )
+                })
+                .collect();
+
+            if status != previous {
+                previous = status.clone();
+                let _ = ui_handle.upgrade_in_event_loop(move |ui| {
+                    let rows: Vec<ConnectionStatus> = status
+                        .into_iter()
+                        .map(|(host, state, failures, last_error)| ConnectionStatus {
+                            host: host.into(),
+                            state: match state {
+                                CircuitState::Closed => "closed",
+                                CircuitState::HalfOpen => "half_open",
+                                CircuitState::Open => "open",
+                            }
+                            .into(),
+                            failures: failures as i32,
+                            last_error: last_error.into(),
+                        })
+                        .collect();
+                    ui.set_connection_status(ModelRc::new(VecModel::from(rows)));
+                });
+            }
+
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    });
+
+    task_handle
+}
@@ -1,6 +1,6 @@
 use crate::tasks::task_manager::{register_task, TaskStatus};
 use crate::AppWindow;
-use aim_data::aim::{CryptoRsiData, DominanceData, EtfFlowData, CryptoMarketCapData, fetch_crypto_data, fetch_crypto_market_cap_data, fetch_crypto_rsi_data, fetch_dominance_data, fetch_etf_flow_data };
+use aim_data::aim::{aim_client, CryptoRsiData, DominanceData, EtfFlowData, CryptoMarketCapData};
 // Removed fetch_crypto_data import because we will mock/implement the loop locally or assume a new function
 use slint::{ComponentHandle, Model, ModelRc, SharedString, VecModel, Image, SharedPixelBuffer, Rgb8Pixel};
 use crate::slint_generatedAppWindow::CryptoData as SlintCryptoData;
@@ -571,7 +571,7 @@ pub async fn spawn_crypto_task(ui: &AppWindow) -> crate::tasks::task_manager::Ta
 
             // 1. Fetch data for each symbol
             for symbol in &target_symbols {
-                let history_result = fetch_crypto_data(symbol).await;
+                let history_result = aim_client().fetch_crypto_data(symbol).await;
                 
                 if let Ok(history) = history_result {
                     if history.is_empty() {
@@ -663,7 +663,7 @@ pub async fn spawn_dominance_task(ui: &AppWindow) -> crate::tasks::task_manager:
             if let Ok(status) = rx.try_recv() { if task_status != status { task_status = status; } }
             if task_status != TaskStatus::Running { tokio::time::sleep(std::time::Duration::from_millis(100)).await; continue; }
 
-            let fetched_result = fetch_dominance_data().await;
+            let fetched_result = aim_client().fetch_dominance_data().await;
             let mut final_data = match fetched_result {
                 Ok(data) => if data.is_empty() { generate_raw_dominance_api_data() } else { data },
                 Err(_) => generate_raw_dominance_api_data()
@@ -726,7 +726,7 @@ pub async fn spawn_crypto_rsi_task(ui: &AppWindow) -> crate::tasks::task_manager
             if let Ok(status) = rx.try_recv() { if task_status != status { task_status = status; } }
             if task_status != TaskStatus::Running { tokio::time::sleep(std::time::Duration::from_millis(100)).await; continue; }
 
-            let fetched_result = fetch_crypto_rsi_data().await;
+            let fetched_result = aim_client().fetch_crypto_rsi_data().await;
             let final_data = match fetched_result {
                 Ok(data) => if data.is_empty() { vec![] } else { data },
                 Err(_) => { generate_dummy_rsi_data()} 
@@ -774,7 +774,7 @@ pub async fn spawn_etf_flow_task(ui: &AppWindow) -> crate::tasks::task_manager::
             if let Ok(status) = rx.try_recv() { if task_status != status { task_status = status; } }
             if task_status != TaskStatus::Running { tokio::time::sleep(std::time::Duration::from_millis(100)).await; continue; }
 
-            let fetched_result = fetch_etf_flow_data().await;
+            let fetched_result = aim_client().fetch_etf_flow_data().await;
             let final_data = match fetched_result { 
                 Ok(data) => if data.is_empty() { generate_dummy_etf_data() } else { data }, 
                 Err(_) => generate_dummy_etf_data() 
@@ -860,7 +860,7 @@ pub async fn spawn_crypto_market_cap_task(ui: &AppWindow) -> crate::tasks::task_
             if task_status != TaskStatus::Running { tokio::time::sleep(std::time::Duration::from_millis(100)).await; continue; }
 
             // 1. Fetch Data (mock if empty)
-            let fetched_result = fetch_crypto_market_cap_data().await;
+            let fetched_result = aim_client().fetch_crypto_market_cap_data().await;
             
             let mut final_data = match fetched_result {
                 Ok(data) => if data.is_empty() { generate_dummy_market_cap_data() } else { data },
@@ -1,6 +1,6 @@
 use crate::create_simple_task;
 use crate::slint_generatedAppWindow::AbnormalTradeData;
-use aim_data::aim::{fetch_abnormal_trade_data, AbnormalTrade};
+use aim_data::aim::AbnormalTrade;
 use slint::{ModelRc, VecModel};
 
 fn convert_abnormal_trade_to_ui_data(api_data: Vec<AbnormalTrade>) -> ModelRc<AbnormalTradeData> {
@@ -1,6 +1,6 @@
 use crate::slint_generatedAppWindow::{AppWindow, HeaderData};
 use crate::tasks::task_manager::{register_task, TaskHandle};
-use aim_data::aim::{fetch_stock_by_gics_data, StockByGics};
+use aim_data::aim::{aim_client, StockByGics};
 use slint::{Color, ComponentHandle, SharedString};
 use std::collections::HashMap;
 
@@ -79,7 +79,7 @@ pub async fn spawn_heat_map_task(ui: &AppWindow) -> TaskHandle {
                 tokio::time::sleep(std::time::Duration::from_millis(100)).await;
                 continue;
             }
-            match fetch_stock_by_gics_data().await {
+            match aim_client().fetch_stock_by_gics_data().await {
                 Ok(stocks) => {
                     let mut sector_map: HashMap<&str, Vec<HeaderData>> = HashMap::new();
                     for stock in stocks {
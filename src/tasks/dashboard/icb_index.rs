@@ -1,6 +1,6 @@
 use crate::create_simple_task;
 use crate::slint_generatedAppWindow::ICBRow;
-use aim_data::aim::{fetch_icb_index_data, IcbIndex};
+use aim_data::aim::IcbIndex;
 use slint::{ModelRc, VecModel};
 
 fn convert_icb_index_to_ui_row(api_data: Vec<IcbIndex>) -> ModelRc<ICBRow> {
@@ -1,6 +1,6 @@
 use crate::create_simple_task;
 use crate::slint_generatedAppWindow::IndexRow;
-use aim_data::aim::{fetch_exchange_index_data, ExchangeIndex};
+use aim_data::aim::ExchangeIndex;
 use slint::{ModelRc, VecModel};
 
 fn convert_exchange_index_to_index_row(api_data: Vec<ExchangeIndex>) -> ModelRc<IndexRow> {
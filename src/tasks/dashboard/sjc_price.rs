@@ -1,6 +1,6 @@
 use crate::create_simple_task;
 use crate::slint_generatedAppWindow::GoodsData;
-use aim_data::aim::{fetch_sjc_price_data, SjcPriceData};
+use aim_data::aim::SjcPriceData;
 use slint::{ModelRc, VecModel};
 
 fn convert_sjc_price_to_ui_data(api_data: Vec<SjcPriceData>) -> ModelRc<GoodsData> {
@@ -1,6 +1,6 @@
 use crate::create_simple_task;
 use crate::slint_generatedAppWindow::InfluenceData;
-use aim_data::aim::{fetch_top_stock_influencer_data, TopStockInfluencer};
+use aim_data::aim::TopStockInfluencer;
 use slint::{ModelRc, VecModel};
 
 // Convert API TopStockInfluencer data to UI InfluenceData format
@@ -1,5 +1,5 @@
 use crate::slint_generatedAppWindow::VolumeData;
-use aim_data::aim::{fetch_kqgd_nn_chart_data, fetch_kqgd_td_chart_data, PropTradingData};
+use aim_data::aim::PropTradingData;
 
 /// Dual-source task pattern macro for tasks that fetch from two different sources
 /// This macro generates a task function that:
@@ -51,7 +51,7 @@ macro_rules! create_dual_source_task {
                     }
 
                     // Fetch and update first data source
-                    match $fetch_fn1().await {
+                    match aim_data::aim::aim_client().$fetch_fn1().await {
                         Ok(data1) => {
                             let ui_data1 = $ui_conversion(&data1);
                             let ui_handle_clone = ui_handle.clone();
@@ -72,7 +72,7 @@ macro_rules! create_dual_source_task {
                     }
 
                     // Fetch and update second data source
-                    match $fetch_fn2().await {
+                    match aim_data::aim::aim_client().$fetch_fn2().await {
                         Ok(data2) => {
                             let ui_data2 = $ui_conversion(&data2);
                             let ui_handle_clone = ui_handle.clone();
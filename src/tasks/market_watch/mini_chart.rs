@@ -1,7 +1,7 @@
 use crate::slint_generatedAppWindow::AppWindow;
 use aim_chart::convert_candlesticks;
 use aim_chart::mini_chart::mini_chart_render;
-use aim_data::get_quote;
+use aim_data::{candle_store, MarketDataProvider, SharedProvider};
 use chrono::{Datelike, Duration, Local, NaiveDate, Utc, Weekday};
 use slint::{ComponentHandle, Model};
 
@@ -15,7 +15,10 @@ macro_rules! create_mini_chart_task {
         $symbol:literal,
         $ui_type:literal
     ) => {
-        pub async fn $task_fn(ui: &crate::AppWindow) -> crate::tasks::task_manager::TaskHandle {
+        pub async fn $task_fn(
+            ui: &crate::AppWindow,
+            provider: aim_data::SharedProvider,
+        ) -> crate::tasks::task_manager::TaskHandle {
             use crate::tasks::task_manager::register_task;
 
             let ui_handle = ui.as_weak();
@@ -26,7 +29,7 @@ macro_rules! create_mini_chart_task {
             tokio::spawn(async move {
                 println!("Mini chart {} task started!", $symbol);
                 crate::tasks::market_watch::mini_chart::spawn_chart_loop(
-                    ui_handle, $symbol, $ui_type, rx,
+                    ui_handle, provider, $symbol, $ui_type, rx,
                 )
                 .await;
             });
@@ -37,7 +40,11 @@ macro_rules! create_mini_chart_task {
 }
 // Unused imports removed
 
-async fn get_reference_price(symbol: &str, current_date: NaiveDate) -> f32 {
+async fn get_reference_price(
+    provider: &dyn MarketDataProvider,
+    symbol: &str,
+    current_date: NaiveDate,
+) -> f32 {
     // Try to get the previous trading day's close price
     let mut prev_date = current_date - Duration::days(1);
     let mut attempts = 0;
@@ -64,22 +71,21 @@ async fn get_reference_price(symbol: &str, current_date: NaiveDate) -> f32 {
                 symbol,
                 prev_date.format("%Y-%m-%d")
             );
-            match get_quote(&[symbol], "ONE_MINUTE", Some(day_start), Some(day_end)).await {
-                Ok(resp) => {
-                    if !resp.0.is_empty() {
-                        if let Some(ohlc) = resp.0.first() {
-                            let candlesticks = ohlc.to_candlesticks();
-                            if !candlesticks.is_empty() {
-                                let last_close = candlesticks.last().unwrap().close as f32; // Convert to display format
-                                println!(
-                                    "Mini chart {}: Got reference price {:.2} from {}",
-                                    symbol,
-                                    last_close,
-                                    prev_date.format("%Y-%m-%d")
-                                );
-                                return last_close;
-                            }
-                        }
+            // Past days are read from the history store once fetched
+            match candle_store()
+                .fetch(provider, symbol, "ONE_MINUTE", day_start, day_end)
+                .await
+            {
+                Ok(candlesticks) => {
+                    if let Some(last) = candlesticks.last() {
+                        let last_close = last.close as f32; // Convert to display format
+                        println!(
+                            "Mini chart {}: Got reference price {:.2} from {}",
+                            symbol,
+                            last_close,
+                            prev_date.format("%Y-%m-%d")
+                        );
+                        return last_close;
                     }
                 }
                 Err(e) => {
@@ -144,6 +150,7 @@ create_mini_chart_task!(
 
 pub async fn spawn_chart_loop(
     ui_handle: slint::Weak<AppWindow>,
+    provider: SharedProvider,
     symbol: &str,
     ui_type: &str,
     mut rx: tokio::sync::mpsc::Receiver<crate::tasks::task_manager::TaskStatus>,
@@ -190,91 +197,86 @@ pub async fn spawn_chart_loop(
                     symbol,
                     attempt_date.format("%Y-%m-%d")
                 );
-                match get_quote(&[symbol], "ONE_MINUTE", Some(day_start), Some(day_end)).await {
-                    Ok(resp) => {
+                // Only the minutes after the last stored bar are requested
+                match candle_store()
+                    .fetch(provider.as_ref(), symbol, "ONE_MINUTE", day_start, day_end)
+                    .await
+                {
+                    Ok(candlesticks) => {
                         println!(
-                            "Mini chart: Got response for {} with timeframe ONE_MINUTE - {} items",
-                            symbol,
-                            resp.0.len()
+                            "Mini chart: Got {} candlesticks for {}",
+                            candlesticks.len(),
+                            symbol
                         );
-                        if !resp.0.is_empty() {
-                            if let Some(ohlc) = resp.0.first() {
-                                let candlesticks = ohlc.to_candlesticks();
-                                println!(
-                                    "Mini chart: Got {} candlesticks for {}",
-                                    candlesticks.len(),
-                                    symbol
-                                );
-                                if !candlesticks.is_empty() {
-                                    let candle_data = convert_candlesticks(false, candlesticks);
+                        if !candlesticks.is_empty() {
+                            let candle_data = convert_candlesticks(false, candlesticks);
 
-                                    // Get reference price (previous day's close price)
-                                    let ref_price = get_reference_price(symbol, attempt_date).await;
+                            // Get reference price (previous day's close price)
+                            let ref_price =
+                                get_reference_price(provider.as_ref(), symbol, attempt_date).await;
 
-                                    let symbol_clone = symbol.to_string();
-                                    let ui_type_clone = ui_type.to_string();
-                                    let candle_data_clone = candle_data.clone();
-                                    let _ = ui_handle.upgrade_in_event_loop(move |ui| {
-                                        // Create the image inside the event loop to avoid thread safety issues
-                                        let image = mini_chart_render(ref_price, candle_data_clone.clone());
+                            let symbol_clone = symbol.to_string();
+                            let ui_type_clone = ui_type.to_string();
+                            let candle_data_clone = candle_data.clone();
+                            let _ = ui_handle.upgrade_in_event_loop(move |ui| {
+                                // Create the image inside the event loop to avoid thread safety issues
+                                let image = mini_chart_render(ref_price, candle_data_clone.clone());
 
-                                        // Calculate market data from candle data
-                                        let current_price = candle_data_clone.last().map(|c| c.close()).unwrap_or(0.0);
-                                        let change = current_price - ref_price;
-                                        let percentage = if ref_price > 0.0 { (change / ref_price) * 100.0 } else { 0.0 };
+                                // Calculate market data from candle data
+                                let current_price = candle_data_clone.last().map(|c| c.close()).unwrap_or(0.0);
+                                let change = current_price - ref_price;
+                                let percentage = if ref_price > 0.0 { (change / ref_price) * 100.0 } else { 0.0 };
 
-                                        // Calculate total volume from all candle data
-                                        let total_volume: i64 = candle_data_clone.iter()
-                                            .map(|c| c.volume() as i64)
-                                            .sum();
+                                // Calculate total volume from all candle data
+                                let total_volume: i64 = candle_data_clone.iter()
+                                    .map(|c| c.volume() as i64)
+                                    .sum();
 
-                                        // Format number (total volume count) with commas - this goes to the number field
-                                        let number_str = {
-                                            let num_str = total_volume.to_string();
-                                            let mut result = String::new();
-                                            let chars: Vec<char> = num_str.chars().rev().collect();
-                                            for (i, c) in chars.iter().enumerate() {
-                                                if i > 0 && i % 3 == 0 {
-                                                    result.push(',');
-                                                }
-                                                result.push(*c);
-                                            }
-                                            result.chars().rev().collect::<String>()
-                                        };
+                                // Format number (total volume count) with commas - this goes to the number field
+                                let number_str = {
+                                    let num_str = total_volume.to_string();
+                                    let mut result = String::new();
+                                    let chars: Vec<char> = num_str.chars().rev().collect();
+                                    for (i, c) in chars.iter().enumerate() {
+                                        if i > 0 && i % 3 == 0 {
+                                            result.push(',');
+                                        }
+                                        result.push(*c);
+                                    }
+                                    result.chars().rev().collect::<String>()
+                                };
 
-                                        // Format volume string (for display) - this goes to the volume field
-                                        let volume_str = if total_volume > 1_000_000 {
-                                            format!("{:.1} TỶ", total_volume as f64 / 1_000_000.0)
-                                        } else if total_volume > 1_000 {
-                                            format!("{:.1}K", total_volume as f64 / 1_000.0)
-                                        } else {
-                                            total_volume.to_string()
-                                        };
+                                // Format volume string (for display) - this goes to the volume field
+                                let volume_str = if total_volume > 1_000_000 {
+                                    format!("{:.1} TỶ", total_volume as f64 / 1_000_000.0)
+                                } else if total_volume > 1_000 {
+                                    format!("{:.1}K", total_volume as f64 / 1_000.0)
+                                } else {
+                                    total_volume.to_string()
+                                };
 
-                                        println!("Mini chart: Updated {symbol_clone} - Price: {current_price:.2}, Change: {change:.2} ({percentage:.2}%), Total Volume: {number_str}, Volume Display: {volume_str}");
+                                println!("Mini chart: Updated {symbol_clone} - Price: {current_price:.2}, Change: {change:.2} ({percentage:.2}%), Total Volume: {number_str}, Volume Display: {volume_str}");
 
-                                        // Update the index_data array with complete market data
-                                        let index_data = ui.get_index_data();
-                                        for i in 0..index_data.row_count() {
-                                            if let Some(mut row) = index_data.row_data(i) {
-                                                if row.symbol.as_str().to_lowercase() == ui_type_clone.as_str() {
-                                                    row.image = image;
-                                                    row.number = number_str.into(); // Total volume count
-                                                    row.volume = volume_str.into();  // Formatted volume display
-                                                    row.price = current_price;
-                                                    row.change = change;
-                                                    row.percentage = percentage;
-                                                    index_data.set_row_data(i, row);
-                                                    break;
-                                                }
-                                            }
+                                // Update the index_data array with complete market data
+                                let index_data = ui.get_index_data();
+                                for i in 0..index_data.row_count() {
+                                    if let Some(mut row) = index_data.row_data(i) {
+                                        if row.symbol.as_str().to_lowercase() == ui_type_clone.as_str() {
+                                            row.image = image;
+                                            row.number = number_str.into(); // Total volume count
+                                            row.volume = volume_str.into();  // Formatted volume display
+                                            row.price = current_price;
+                                            row.change = change;
+                                            row.percentage = percentage;
+                                            index_data.set_row_data(i, row);
+                                            break;
                                         }
-                                        ui.set_index_data(index_data);
-                                    });
-                                    data_found = true;
-                                    break;
+                                    }
                                 }
-                            }
+                                ui.set_index_data(index_data);
+                            });
+                            data_found = true;
+                            break;
                         }
                     }
                     Err(e) => {
@@ -1,7 +1,10 @@
 use crate::slint_generatedAppWindow;
+use aim_chart::grid::{ChartGrid, GridLayout};
 use aim_chart::Chart;
 use aim_data::explorer::vci::OrderList;
+pub use alert::{register_alert_handlers, spawn_alert_task, AlertBook, SharedAlerts};
 pub use cache_storage::spawn_cache_storage_task;
+pub use connection_status::spawn_connection_status_task;
 pub use chart::*;
 pub use dashboard::*;
 pub use market_watch::*;
@@ -12,14 +15,16 @@ use slint_generatedAppWindow::{
 use std::{fs::File, io::Write, path::PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 // MP layout
 pub use mp::{spawn_rsi_task,register_rsi_sort_handler, spawn_ma50_task, register_ma50_sort_handler};
 pub use crypto::{spawn_crypto_task, spawn_dominance_task, spawn_crypto_rsi_task, spawn_etf_flow_task, spawn_crypto_market_cap_task };
 
+pub mod alert;
 pub mod backend;
 pub mod cache_storage;
 pub mod chart;
+pub mod connection_status;
 pub mod dashboard;
 pub mod market_watch;
 pub mod quantitative;
@@ -73,7 +78,7 @@ macro_rules! create_simple_task {
                     }
 
                     // Fetch and update data
-                    match $fetch_fn().await {
+                    match aim_data::aim::aim_client().$fetch_fn().await {
                         Ok(data) => {
                             let ui_handle_clone = ui_handle.clone();
                             let _ = ui_handle_clone.upgrade_in_event_loop(move |ui| {
@@ -96,25 +101,139 @@ macro_rules! create_simple_task {
     };
 }
 
+/// Key of a chart: upper-case symbol and timeframe label (e.g. "15m")
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct ChartKey {
+    pub symbol: String,
+    pub time_frame: String,
+}
+
+impl ChartKey {
+    pub fn new(symbol: &str, time_frame: &str) -> Self {
+        Self {
+            symbol: symbol.to_uppercase(),
+            time_frame: time_frame.to_string(),
+        }
+    }
+
+    fn of(chart: &Chart) -> Self {
+        Self::new(&chart.stock_name, &chart.time_frame)
+    }
+}
+
+/// Charts of every (symbol, timeframe) pair opened by the user
+///
+/// Each pair keeps its own candles and viewport so switching back to a
+/// timeframe shows the cached chart at once. Drawings belong to the symbol:
+/// they follow the user, with their undo history, to whichever timeframe of it
+/// is displayed. In a grid of charts they do so only when the cells link
+/// their drawings, otherwise each cell keeps its own.
 #[repr(C)]
 pub struct ChartMetaData {
-    data: Vec<Chart>,
+    data: BTreeMap<ChartKey, Chart>,
+    /// Chart of each symbol shown last, the one holding its current drawings
+    shown: BTreeMap<String, ChartKey>,
+    /// Cells of the chart page and the symbol and timeframe of each
+    pub grid: ChartGrid,
 }
 
 impl ChartMetaData {
     pub fn new(data: Vec<Chart>) -> Self {
-        Self { data }
+        let grid = data.first().map_or_else(
+            || ChartGrid::new("AAA", "1D"),
+            |chart| ChartGrid::new(&chart.stock_name, &chart.time_frame),
+        );
+        Self {
+            data: data
+                .into_iter()
+                .map(|chart| (ChartKey::of(&chart), chart))
+                .collect(),
+            shown: BTreeMap::new(),
+            grid,
+        }
     }
 
-    pub fn save(&self, mut file: File) {
-        const VERSION: u32 = 1;
+    pub fn contains(&self, key: &ChartKey) -> bool {
+        self.data.contains_key(key)
+    }
+
+    pub fn get(&self, key: &ChartKey) -> Option<&Chart> {
+        self.data.get(key)
+    }
 
+    pub fn get_mut(&mut self, key: &ChartKey) -> Option<&mut Chart> {
+        self.data.get_mut(key)
+    }
+
+    /// Add a chart, replacing the one of the same symbol and timeframe
+    pub fn insert(&mut self, chart: Chart) {
+        self.data.insert(ChartKey::of(&chart), chart);
+    }
+
+    pub fn charts(&self) -> impl Iterator<Item = &Chart> {
+        self.data.values()
+    }
+
+    pub fn charts_mut(&mut self) -> impl Iterator<Item = &mut Chart> {
+        self.data.values_mut()
+    }
+
+    /// Any chart of `symbol`, whatever its timeframe
+    pub fn find_symbol(&self, symbol: &str) -> Option<&Chart> {
+        let symbol = symbol.to_uppercase();
+        self.data.values().find(|chart| chart.stock_name == symbol)
+    }
+
+    /// Chart with the current drawings of `symbol`: the one shown last, or any
+    /// chart of it if none was shown since the start
+    pub fn drawings_of(&self, symbol: &str) -> Option<&Chart> {
+        self.shown
+            .get(&symbol.to_uppercase())
+            .and_then(|key| self.data.get(key))
+            .or_else(|| self.find_symbol(symbol))
+    }
+
+    /// Get the chart to display for `key`, carrying the drawings over when the
+    /// user last saw the symbol on another timeframe
+    pub fn activate(&mut self, key: &ChartKey) -> Option<&mut Chart> {
+        if !self.data.contains_key(key) {
+            return None;
+        }
+        let previous = self.shown.insert(key.symbol.clone(), key.clone());
+        let carry = self.grid.layout == GridLayout::Single || self.grid.links.drawings;
+        let source = previous
+            .filter(|previous| carry && previous != key)
+            .and_then(|previous| Some((self.data.remove(&previous)?, previous)));
+        if let Some((source, previous)) = source {
+            if let Some(chart) = self.data.get_mut(key) {
+                chart.adopt_drawings(&source);
+            }
+            self.data.insert(previous, source);
+        }
+        self.data.get_mut(key)
+    }
+
+    /// Get the chart of a grid cell the mouse is not over; with the drawings
+    /// linked it shows those of the `active` chart when it has the same symbol
+    pub fn linked_cell(&mut self, key: &ChartKey, active: &ChartKey) -> Option<&mut Chart> {
+        if self.grid.links.drawings && key.symbol == active.symbol && key != active {
+            if let Some(source) = self.data.remove(active) {
+                if let Some(chart) = self.data.get_mut(key) {
+                    chart.adopt_drawings(&source);
+                }
+                self.data.insert(active.clone(), source);
+            }
+        }
+        self.data.get_mut(key)
+    }
+
+    pub fn save(&self, mut file: File) {
         let mut bytes = Vec::new();
         // Write version header
-        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&aim_chart::CACHE_VERSION.to_le_bytes());
         let count = self.data.len() as u32;
         bytes.extend_from_slice(&count.to_le_bytes());
-        for (i, chart) in self.data.iter().enumerate() {
+        for (i, chart) in self.data.values().enumerate() {
             let start_len = bytes.len();
             chart.write_to_bytes(&mut bytes);
             let written = bytes.len() - start_len;
@@ -122,6 +241,8 @@ impl ChartMetaData {
                 log::error!("Chart #{} failed to serialize: {}", i, chart.stock_name);
             }
         }
+        // The grid of the chart page follows the charts
+        self.grid.write_to_bytes(&mut bytes);
         if let Err(e) = file.write_all(&bytes) {
             log::error!("Failed to write chart data to cache file: {e}");
         }
@@ -130,18 +251,28 @@ impl ChartMetaData {
     // Load charts from a file (manual deserialization, no external crate)
     pub fn load(path: &PathBuf) -> Self {
         let mut data = Vec::new();
+        let mut grid = None;
         match std::fs::read(path) {
             Ok(bytes) => {
                 let mut pos = 0;
                 if bytes.len() < 8 {
                     log::error!("File too small to contain version and chart count: {path:?}");
-                    return Self { data };
+                    return Self::new(data);
                 }
                 let version = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
                 pos += 4;
-                if version != 1 {
+                if version == 0 || version > aim_chart::CACHE_VERSION {
                     log::error!("Unsupported chart cache version {version} in {path:?}");
-                    return Self { data };
+                    Self::keep_copy(path, &format!(".v{version}"));
+                    return Self::new(data);
+                }
+                if version < aim_chart::CACHE_VERSION {
+                    // Read as it is, the next save writes the current version
+                    log::info!(
+                        "Upgrading chart cache from version {version} to {}",
+                        aim_chart::CACHE_VERSION
+                    );
+                    Self::keep_copy(path, &format!(".v{version}"));
                 }
                 let count = u32::from_le_bytes([
                     bytes[pos],
@@ -152,7 +283,7 @@ impl ChartMetaData {
                 pos += 4;
                 for i in 0..count {
                     let chart_bytes = &bytes[pos..];
-                    match Chart::read_from_bytes(chart_bytes) {
+                    match Chart::read_version_from_bytes(chart_bytes, version) {
                         Some((chart, used)) => {
                             if used == 0 {
                                 log::error!(
@@ -167,10 +298,15 @@ impl ChartMetaData {
                             log::error!(
                                 "Failed to deserialize chart #{i} at pos {pos} in {path:?}"
                             );
+                            Self::keep_copy(path, ".unreadable");
                             break;
                         }
                     }
                 }
+                // The grid follows the charts since version 9
+                if data.len() == count && version >= 9 {
+                    grid = ChartGrid::read_from_bytes(&bytes[pos..]).map(|(grid, _)| grid);
+                }
                 log::info!("Loaded {} charts from {}", data.len(), path.display());
                 for chart in &data {
                     log::info!("Chart loaded: {}", chart.stock_name);
@@ -189,15 +325,32 @@ impl ChartMetaData {
         for chart in &data {
             log::info!("Chart loaded: {}", chart.stock_name);
         }
-        Self { data }
+        let mut charts = Self::new(data);
+        if let Some(grid) = grid {
+            charts.grid = grid;
+        }
+        charts
+    }
+
+    /// Copy the cache at `path` next to it, with `suffix` after its name, so
+    /// what this version can't read survives the next save
+    fn keep_copy(path: &PathBuf, suffix: &str) {
+        let mut copy = path.clone().into_os_string();
+        copy.push(suffix);
+        let copy = PathBuf::from(copy);
+        match std::fs::copy(path, &copy) {
+            Ok(_) => log::warn!("Kept a copy of {} at {}", path.display(), copy.display()),
+            Err(e) => log::error!("Failed to copy {} to {}: {e}", path.display(), copy.display()),
+        }
     }
 
     // Get a simple hash of the chart data (no external crate)
     pub fn get_md5(&self) -> String {
         // Use a simple FNV-1a hash for demonstration
         let mut hash: u64 = 0xcbf29ce484222325;
-        for chart in &self.data {
-            let bytes = chart.to_bytes();
+        let mut grid = Vec::new();
+        self.grid.write_to_bytes(&mut grid);
+        for bytes in self.data.values().map(Chart::to_bytes).chain([grid]) {
             for b in bytes {
                 hash ^= b as u64;
                 hash = hash.wrapping_mul(0x100000001b3);
@@ -253,51 +406,39 @@ pub async fn spawn_return_matrix_cache_task() -> ReturnMatrixCacheMap {
 
         log::info!("Starting return matrix cache preload...");
 
+        // One request per (period, group), the daily months are all parsed from the
+        // same response. The shared executor paces the requests per host.
         for period in &periods {
             for group in &groups {
-                if *period == "daily" {
-                    // For daily, load all months
-                    for month in &months {
-                        let key = ReturnMatrixKey {
-                            period: period.to_string(),
-                            group: group.to_string(),
-                            month: month.to_string(),
-                        };
-
-                        match aim_data::aim::fetch_return_matrix(period, group).await {
-                            Ok(data) => {
-                                let (rows, columns) = parse_return_matrix(&data, period, month);
-                                let cache_value = ReturnMatrixCache { rows, columns };
-                                cache_clone.write().await.insert(key.clone(), cache_value);
-                                log::info!("Cached return matrix: {}_{} ({})", period, group, month);
-                            }
-                            Err(e) => {
-                                log::error!("Failed to cache return matrix {}_{} ({}): {:?}", period, group, month, e);
-                            }
+                let data = match aim_data::aim::aim_client().fetch_return_matrix(period, group).await {
+                    Ok(data) => data,
+                    Err(e) => {
+                        log::error!("Failed to cache return matrix {}_{}: {}", period, group, e);
+                        if matches!(e, aim_data::Error::CircuitOpen { .. }) {
+                            log::error!("AIM server unavailable, aborting return matrix preload");
+                            return;
                         }
-                        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                        continue;
                     }
+                };
+
+                let cache_months: Vec<&str> = if *period == "daily" {
+                    months.clone()
                 } else {
                     // For non-daily periods, use empty month
+                    vec![""]
+                };
+                for month in cache_months {
                     let key = ReturnMatrixKey {
                         period: period.to_string(),
                         group: group.to_string(),
-                        month: String::new(),
+                        month: month.to_string(),
                     };
-
-                    match aim_data::aim::fetch_return_matrix(period, group).await {
-                        Ok(data) => {
-                            let (rows, columns) = parse_return_matrix(&data, period, "");
-                            let cache_value = ReturnMatrixCache { rows, columns };
-                            cache_clone.write().await.insert(key.clone(), cache_value);
-                            log::info!("Cached return matrix: {}_{}", period, group);
-                        }
-                        Err(e) => {
-                            log::error!("Failed to cache return matrix {}_{}: {:?}", period, group, e);
-                        }
-                    }
-                    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                    let (rows, columns) = parse_return_matrix(&data, period, month);
+                    let cache_value = ReturnMatrixCache { rows, columns };
+                    cache_clone.write().await.insert(key, cache_value);
                 }
+                log::info!("Cached return matrix: {}_{}", period, group);
             }
         }
 
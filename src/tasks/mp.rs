@@ -3,7 +3,10 @@ use std::rc::Rc;
 
 use crate::tasks::task_manager::{register_task, TaskStatus};
 use crate::AppWindow;
-use aim_data::aim::{fetch_rsi14_data, RsiData, fetch_ma50_data, MaData}; // Import RsiData explicitly
+use aim_chart::indicator::{run, Bollinger, Rsi};
+use aim_chart::{convert_candlesticks, CandleData, Chart};
+use aim_data::aim::{aim_client, RsiData, MaData}; // Import RsiData explicitly
+use aim_data::{candle_store, MarketDataProvider, SharedProvider};
 use slint::{ComponentHandle, Model, ModelRc, VecModel};
 use crate::slint_generatedAppWindow::{
     RsiData as SlintRsiData, 
@@ -58,7 +61,49 @@ fn generate_dummy_ma_data() -> Vec<MaData> {
     }).collect()
 }
 
-pub async fn spawn_rsi_task(ui: &AppWindow) -> crate::tasks::task_manager::TaskHandle {
+/// Daily candles of a watchlist stock, read through the local history store
+pub(crate) async fn daily_candles(provider: &dyn MarketDataProvider, symbol: &str) -> Vec<CandleData> {
+    let now = chrono::Utc::now();
+    let start = Chart::history_start("1D", now);
+    match candle_store().fetch(provider, symbol, "ONE_DAY", start, now).await {
+        Ok(bars) => convert_candlesticks(true, bars),
+        Err(e) => {
+            log::error!("Failed to load daily bars of {}: {}", symbol, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Compute RSI14 from daily bars for the rows the server sent without a value
+async fn fill_missing_rsi(provider: &dyn MarketDataProvider, mut data: Vec<RsiData>) -> Vec<RsiData> {
+    for row in data.iter_mut().filter(|row| row.value.is_none()) {
+        let candles = daily_candles(provider, &row.symbol).await;
+        row.value = run(&mut Rsi::new(14), &candles)
+            .last()
+            .and_then(|values| values[0])
+            .map(f64::from);
+    }
+    data
+}
+
+/// Compute the MA50 bands (50 candles, 2 sigma) from daily bars for the rows
+/// the server sent without them
+async fn fill_missing_ma50(provider: &dyn MarketDataProvider, mut data: Vec<MaData>) -> Vec<MaData> {
+    for row in data.iter_mut().filter(|row| row.middle.is_none() || row.upper.is_none()) {
+        let candles = daily_candles(provider, &row.symbol).await;
+        let (Some(bands), Some(last)) = (run(&mut Bollinger::new(50, 2.0), &candles).pop(), candles.last()) else {
+            continue;
+        };
+        row.middle = bands[0].map(f64::from);
+        row.upper = bands[1].map(f64::from);
+        row.lower = bands[2].map(f64::from);
+        // The score compares the price with the bands, keep both on the same scale
+        row.price = Some(f64::from(last.close()));
+    }
+    data
+}
+
+pub async fn spawn_rsi_task(ui: &AppWindow, provider: SharedProvider) -> crate::tasks::task_manager::TaskHandle {
     let ui_handle = ui.as_weak();
     let (tx, mut rx) = tokio::sync::mpsc::channel(10);
     let task_handle =
@@ -81,7 +126,7 @@ pub async fn spawn_rsi_task(ui: &AppWindow) -> crate::tasks::task_manager::TaskH
             }
 
             // Fetch data from API
-            let fetched_result = fetch_rsi14_data().await;
+            let fetched_result = aim_client().fetch_rsi14_data().await;
             
             // Logic: If API returns empty (or fails), use dummy data for Debugging
             let final_data = match fetched_result {
@@ -99,6 +144,7 @@ pub async fn spawn_rsi_task(ui: &AppWindow) -> crate::tasks::task_manager::TaskH
                     generate_dummy_data()
                 }
             };
+            let final_data = fill_missing_rsi(provider.as_ref(), final_data).await;
 
             // --- DATA PROCESSING ---
 
@@ -183,7 +229,7 @@ pub async fn spawn_rsi_task(ui: &AppWindow) -> crate::tasks::task_manager::TaskH
     task_handle
 }
 
-pub async fn spawn_ma50_task(ui: &AppWindow) -> crate::tasks::task_manager::TaskHandle {
+pub async fn spawn_ma50_task(ui: &AppWindow, provider: SharedProvider) -> crate::tasks::task_manager::TaskHandle {
     let ui_handle = ui.as_weak();
     let (tx, mut rx) = tokio::sync::mpsc::channel(10);
     let task_handle =
@@ -206,7 +252,7 @@ pub async fn spawn_ma50_task(ui: &AppWindow) -> crate::tasks::task_manager::Task
             }
 
             // Fetch data from API
-            let fetched_result = fetch_ma50_data().await;
+            let fetched_result = aim_client().fetch_ma50_data().await;
             
             let final_data = match fetched_result {
                 Ok(data) => {
@@ -223,6 +269,7 @@ pub async fn spawn_ma50_task(ui: &AppWindow) -> crate::tasks::task_manager::Task
                     generate_dummy_ma_data()
                 }
             };
+            let final_data = fill_missing_ma50(provider.as_ref(), final_data).await;
 
             // --- DATA PROCESSING FOR MA50 ---
             
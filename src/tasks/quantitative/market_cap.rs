@@ -1,5 +1,5 @@
 use crate::create_simple_task;
-use aim_data::explorer::aim::{fetch_icb_index_data_filtered, fetch_top_10_market_cap_data, IcbIndex, Top10MarketCap};
+use aim_data::explorer::aim::{aim_client, IcbIndex, Top10MarketCap};
 use slint::ComponentHandle;
 use crate::tasks::task_manager::{register_task, TaskStatus};
 
@@ -42,7 +42,7 @@ pub async fn spawn_top10_market_cap_task(ui: &crate::AppWindow) -> crate::tasks:
             }
 
             // Fetch and log Top 10 Market Cap data
-            match fetch_top_10_market_cap_data().await {
+            match aim_client().fetch_top_10_market_cap_data().await {
                 Ok(data) => {
                     log::info!("✅ Fetched Top 10 Market Cap data: {} items", data.len());
                     for item in &data {
@@ -99,7 +99,7 @@ pub async fn spawn_icb_index_filtered_task(ui: &crate::AppWindow) -> crate::task
             }
 
             // Fetch and calculate totals
-            match fetch_icb_index_data_filtered().await {
+            match aim_client().fetch_icb_index_data_filtered().await {
                 Ok(data) => {
                     let total_market_cap: i64 = data.iter().map(|x| x.market_cap).sum();
                     let total_volume: i64 = data.iter().map(|x| x.volume).sum();
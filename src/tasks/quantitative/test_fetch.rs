@@ -2,7 +2,7 @@
 /// Run with: cargo run --bin test_fetch (after adding bin target in Cargo.toml)
 /// Or run directly: cargo test --test test_fetch
 
-use aim_data::explorer::aim::{fetch_icb_index_data_filtered, fetch_top_10_market_cap_data};
+use aim_data::explorer::aim::aim_client;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -16,7 +16,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Test 1: Fetch Top 10 Market Cap
     println!("TEST 1: Fetching Top 10 Market Cap from http://103.48.84.52:4040/top-10-market-cap");
     
-    match fetch_top_10_market_cap_data().await {
+    match aim_client().fetch_top_10_market_cap_data().await {
         Ok(data) => {
             println!("Success! Fetched {} items\n", data.len());
             println!("{:<10} {:<15} {:<12} {:<10}", "Mã CK", "Vốn Hóa (tỷ)", "Giá", "% Thay đổi");
@@ -42,7 +42,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Test 2: Fetch ICB Index Filtered (industry_code 2 digits)
     println!("TEST 2: Fetching ICB Index Filtered (industry_code 2 digits) from https://103.48.84.52:4443/icb-index");
     
-    match fetch_icb_index_data_filtered().await {
+    match aim_client().fetch_icb_index_data_filtered().await {
         Ok(data) => {
             let total_market_cap: i64 = data.iter().map(|x| x.market_cap).sum();
             let total_volume: i64 = data.iter().map(|x| x.volume).sum();